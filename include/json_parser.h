@@ -0,0 +1,38 @@
+#ifndef JSON_PARSER_H
+#define JSON_PARSER_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct JsonHandle JsonHandle;
+
+typedef enum JsonType {
+    JSON_TYPE_INVALID = 0,
+    JSON_TYPE_NULL = 1,
+    JSON_TYPE_BOOL = 2,
+    JSON_TYPE_INTEGER = 3,
+    JSON_TYPE_FLOAT = 4,
+    JSON_TYPE_STRING = 5,
+    JSON_TYPE_ARRAY = 6,
+    JSON_TYPE_OBJECT = 7,
+} JsonType;
+
+/* Returns NULL if the input is not valid json. */
+JsonHandle *json_parse(const char *json);
+/* Every returned handle is owned by the caller and must be released with json_free. */
+JsonHandle *json_get(const JsonHandle *handle, const char *key);
+JsonHandle *json_get_index(const JsonHandle *handle, size_t index);
+JsonType json_type(const JsonHandle *handle);
+/* The returned string must be released with json_string_free. */
+char *json_to_string(const JsonHandle *handle);
+void json_string_free(char *s);
+void json_free(JsonHandle *handle);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,280 @@
+use crate::filter::compare;
+use crate::json::{AnyJson, Array, Json, JsonError, Object, Value};
+use crate::parser::{JsonData, Shared};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+// Which records `join` keeps besides the ones that match, like the joins of SQL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinKind {
+    Inner,
+    Left,
+    Right,
+    Outer,
+}
+
+impl Json<Array> {
+    // The sum of an array of integers, an `Overflow` error when it doesn't fit in an `i64`
+    pub fn sum_i64(&self) -> Result<i64, JsonError> {
+        (0..self.len()?).try_fold(0i64, |sum, i| {
+            sum.checked_add(self.get_value(i)?.get_i64()?)
+                .ok_or_else(|| JsonError::Overflow {
+                    path: self.path.clone(),
+                })
+        })
+    }
+
+    // The mean of an array of numbers, integers read as floats. `None` for an empty array.
+    pub fn avg_f64(&self) -> Result<Option<f64>, JsonError> {
+        let len = self.len()?;
+        let sum = (0..len).try_fold(0.0, |sum, i| {
+            Ok::<_, JsonError>(sum + self.get_value(i)?.get_f64_lossy()?)
+        })?;
+        Ok((len > 0).then(|| sum / len as f64))
+    }
+
+    // The first object of an array of objects with the smallest value at `key`. Values are
+    // compared like `sort` does, so numbers by value and strings by their text.
+    pub fn min_by_key(&self, key: &str) -> Result<Option<Json<Object>>, JsonError> {
+        self.extreme_by_key(key, Ordering::Less)
+    }
+
+    // The last object with the largest value at `key`, see `min_by_key`
+    pub fn max_by_key(&self, key: &str) -> Result<Option<Json<Object>>, JsonError> {
+        self.extreme_by_key(key, Ordering::Greater)
+    }
+
+    fn extreme_by_key(
+        &self,
+        key: &str,
+        wanted: Ordering,
+    ) -> Result<Option<Json<Object>>, JsonError> {
+        let mut found: Option<(Json<Object>, AnyJson)> = None;
+        for i in 0..self.len()? {
+            let record = self.get_object(i)?;
+            let value = AnyJson::from(record.get_value(key)?);
+            let replaces = match &found {
+                Some((_, best)) => {
+                    let ordering = compare(value.data(), best.data());
+                    ordering == wanted || (ordering.is_eq() && wanted.is_gt())
+                }
+                None => true,
+            };
+            if replaces {
+                found = Some((record, value));
+            }
+        }
+        Ok(found.map(|(record, _)| record))
+    }
+
+    // The objects of an array of objects grouped by their value at `key`, in the order
+    // the groups first show up. Strings are used as they are and other values as their
+    // json text, so `1` and `"1"` end up in the same group.
+    pub fn group_by(&self, key: &str) -> Result<Json<Object>, JsonError> {
+        let mut groups: Vec<(String, Vec<AnyJson>)> = Vec::new();
+        let mut indexes: HashMap<String, usize> = HashMap::new();
+        for i in 0..self.len()? {
+            let record = self.get_object(i)?;
+            let value = record.get_value(key)?;
+            let name = group_name(&value);
+            let index = *indexes.entry(name.clone()).or_insert_with(|| {
+                groups.push((name, Vec::new()));
+                groups.len() - 1
+            });
+            groups[index].1.push(AnyJson::from(record));
+        }
+        Ok(groups
+            .into_iter()
+            .map(|(name, records)| (name, records.into_iter().collect::<Json<Array>>()))
+            .collect())
+    }
+}
+
+// Strings as they are and other values as their json text
+fn group_name(value: &Json<Value>) -> String {
+    value
+        .get_unescaped_string()
+        .unwrap_or_else(|_| value.to_string())
+}
+
+// What `join` matches records on, numbers by value and strings apart from other values
+fn join_key(value: &Json<Value>) -> (bool, String) {
+    match value.get_number() {
+        Ok(number) => (false, number.to_string()),
+        Err(_) => (value.get_string().is_ok(), group_name(value)),
+    }
+}
+
+// The members of `left` and then those of `right`, which win when both have a key
+fn merge_records(left: &Json<Object>, right: &Json<Object>) -> Shared<JsonData> {
+    let (JsonData::Object(left), JsonData::Object(right)) =
+        (left.data.as_ref(), right.data.as_ref())
+    else {
+        unreachable!()
+    };
+    let mut map = left.clone();
+    for (key, value) in right.iter() {
+        map.insert(key.clone(), Shared::clone(value));
+    }
+    Shared::new(JsonData::Object(map))
+}
+
+// Joins two arrays of objects on their values at the key `on`, every pair of records that
+// have the same value becomes one record with the members of both. Records of `left` come
+// first in their order, then with `Right` and `Outer` those of `right` that matched nothing.
+pub fn join(
+    left: &Json<Array>,
+    right: &Json<Array>,
+    on: &str,
+    kind: JoinKind,
+) -> Result<Json<Array>, JsonError> {
+    let mut right_records = Vec::new();
+    let mut by_key: HashMap<(bool, String), Vec<usize>> = HashMap::new();
+    for i in 0..right.len()? {
+        let record = right.get_object(i)?;
+        let key = join_key(&record.get_value(on)?);
+        by_key.entry(key).or_default().push(i);
+        right_records.push(record);
+    }
+    let mut matched = vec![false; right_records.len()];
+    let mut records = Vec::new();
+    for i in 0..left.len()? {
+        let record = left.get_object(i)?;
+        let key = join_key(&record.get_value(on)?);
+        match by_key.get(&key) {
+            Some(indexes) => {
+                for &index in indexes {
+                    matched[index] = true;
+                    records.push(merge_records(&record, &right_records[index]));
+                }
+            }
+            None if matches!(kind, JoinKind::Left | JoinKind::Outer) => {
+                records.push(Shared::clone(&record.data))
+            }
+            None => {}
+        }
+    }
+    if matches!(kind, JoinKind::Right | JoinKind::Outer) {
+        for (record, matched) in right_records.iter().zip(matched) {
+            if !matched {
+                records.push(Shared::clone(&record.data));
+            }
+        }
+    }
+    Ok(Json::from_data(JsonData::Array(records)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn products() -> Json<Array> {
+        Json::new(
+            "[{\"name\": \"a\", \"category\": \"tools\", \"price\": 5, \"stock\": 2},
+              {\"name\": \"b\", \"category\": \"toys\", \"price\": 2.5, \"stock\": 0},
+              {\"name\": \"c\", \"category\": \"tools\", \"price\": 7, \"stock\": 1},
+              {\"name\": \"d\", \"category\": 1, \"price\": 2.5, \"stock\": 4}]",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn sums_and_averages() {
+        let ints = Json::<Array>::new("[1, 2, 3]").unwrap();
+        assert_eq!(Ok(6), ints.sum_i64());
+        assert_eq!(Ok(Some(2.0)), ints.avg_f64());
+        assert_eq!(
+            Ok(Some(2.0)),
+            Json::<Array>::new("[1, 3.0]").unwrap().avg_f64()
+        );
+        assert_eq!(Ok(None), Json::<Array>::default().avg_f64());
+        assert_eq!(Ok(0), Json::<Array>::default().sum_i64());
+        let big = Json::<Array>::new("[9223372036854775807, 1]").unwrap();
+        assert!(matches!(big.sum_i64(), Err(JsonError::Overflow { .. })));
+        assert!(matches!(
+            Json::<Array>::new("[1, 2.5]").unwrap().sum_i64(),
+            Err(JsonError::IncorrectType { path, .. }) if path == "/1"
+        ));
+        assert!(Json::<Array>::new("[1, \"2\"]").unwrap().avg_f64().is_err());
+    }
+
+    #[test]
+    fn min_and_max() {
+        let products = products();
+        let name = |record: Option<Json<Object>>| {
+            record
+                .unwrap()
+                .get_value("name")
+                .unwrap()
+                .get_unescaped_string()
+                .unwrap()
+        };
+        assert_eq!("b", name(products.min_by_key("price").unwrap()));
+        assert_eq!("c", name(products.max_by_key("price").unwrap()));
+        assert_eq!("d", name(products.max_by_key("stock").unwrap()));
+        assert_eq!(
+            Ok(None),
+            Json::<Array>::default()
+                .min_by_key("price")
+                .map(|r| r.map(|_| ()))
+        );
+        assert!(matches!(
+            products.min_by_key("weight"),
+            Err(JsonError::KeyNotFound { path }) if path == "/0/weight"
+        ));
+    }
+
+    #[test]
+    fn groups() {
+        let groups = products().group_by("category").unwrap();
+        assert_eq!(
+            Json::<Object>::new(
+                "{\"tools\": [{\"name\": \"a\", \"category\": \"tools\", \"price\": 5, \"stock\": 2},
+                              {\"name\": \"c\", \"category\": \"tools\", \"price\": 7, \"stock\": 1}],
+                  \"toys\": [{\"name\": \"b\", \"category\": \"toys\", \"price\": 2.5, \"stock\": 0}],
+                  \"1\": [{\"name\": \"d\", \"category\": 1, \"price\": 2.5, \"stock\": 4}]}"
+            )
+            .unwrap(),
+            groups
+        );
+        let nested = Json::<Array>::new("[{\"k\": [1]}]").unwrap();
+        assert!(matches!(
+            nested.group_by("k"),
+            Err(JsonError::IncorrectType { .. })
+        ));
+    }
+
+    #[test]
+    fn joins() {
+        let users = Json::<Array>::new(
+            "[{\"id\": 1, \"name\": \"a\"}, {\"id\": 2, \"name\": \"b\"}, {\"id\": \"1\", \"name\": \"c\"}]",
+        )
+        .unwrap();
+        let orders = Json::<Array>::new(
+            "[{\"id\": 1.0, \"total\": 5}, {\"id\": 3, \"total\": 7}, {\"id\": 1, \"total\": 9, \"name\": \"x\"}]",
+        )
+        .unwrap();
+        let joined = |kind| join(&users, &orders, "id", kind).unwrap();
+        assert_eq!(
+            Json::<Array>::new(
+                "[{\"id\": 1.0, \"name\": \"a\", \"total\": 5}, {\"id\": 1, \"name\": \"x\", \"total\": 9}]"
+            )
+            .unwrap(),
+            joined(JoinKind::Inner)
+        );
+        assert_eq!(4, joined(JoinKind::Left).len().unwrap());
+        assert_eq!(
+            Json::<Array>::new(
+                "[{\"id\": 1.0, \"name\": \"a\", \"total\": 5}, {\"id\": 1, \"name\": \"x\", \"total\": 9},
+                  {\"id\": 3, \"total\": 7}]"
+            )
+            .unwrap(),
+            joined(JoinKind::Right)
+        );
+        assert_eq!(5, joined(JoinKind::Outer).len().unwrap());
+        assert!(matches!(
+            join(&users, &Json::<Array>::new("[{}]").unwrap(), "id", JoinKind::Inner),
+            Err(JsonError::KeyNotFound { path }) if path == "/0/id"
+        ));
+    }
+}
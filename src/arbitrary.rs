@@ -0,0 +1,103 @@
+use crate::json::{AnyJson, Array, Json, Object, Value};
+use crate::parser::JsonData;
+use proptest::prelude::*;
+
+fn arb_scalar() -> impl Strategy<Value = JsonData> {
+    prop_oneof![
+        Just(JsonData::Null),
+        any::<bool>().prop_map(JsonData::Bool),
+        any::<i64>().prop_map(JsonData::Integer),
+        // normal floats cover both the plain and the exponent notation of `FloatFormat`
+        prop_oneof![prop::num::f64::NORMAL, (-1000.0..1000.0)].prop_map(JsonData::Float),
+        any::<String>().prop_map(|s| JsonData::Str(s.into()))
+    ]
+}
+
+pub(crate) fn arb_data() -> impl Strategy<Value = JsonData> {
+    // https://altsysrq.github.io/proptest-book/proptest/tutorial/recursive.html
+    arb_scalar().prop_recursive(4, 64, 8, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..12).prop_map(JsonData::array),
+            prop::collection::hash_map(any::<String>(), inner, 0..12).prop_map(JsonData::object),
+        ]
+    })
+}
+
+// Random scalars, `Json<Value>` is never an object or an array
+pub fn arb_json() -> impl Strategy<Value = Json<Value>> {
+    arb_scalar().prop_map(Json::from_data)
+}
+
+// Random documents up to 4 levels deep, for property tests of code that handles json
+pub fn arb_any_json() -> impl Strategy<Value = AnyJson> {
+    arb_data().prop_map(AnyJson::from_data)
+}
+
+pub fn arb_object() -> impl Strategy<Value = Json<Object>> {
+    prop::collection::hash_map(any::<String>(), arb_data(), 0..12)
+        .prop_map(|members| Json::from_data(JsonData::object(members)))
+}
+
+pub fn arb_array() -> impl Strategy<Value = Json<Array>> {
+    prop::collection::vec(arb_data(), 0..12)
+        .prop_map(|items| Json::from_data(JsonData::array(items)))
+}
+
+impl Arbitrary for Json<Value> {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        arb_json().boxed()
+    }
+}
+
+impl Arbitrary for Json<Object> {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        arb_object().boxed()
+    }
+}
+
+impl Arbitrary for Json<Array> {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        arb_array().boxed()
+    }
+}
+
+impl Arbitrary for AnyJson {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        arb_any_json().boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn arbitrary_json_round_trips(json in any::<AnyJson>(), value in any::<Json<Value>>()) {
+            let parsed = Json::parse(json.to_string()).unwrap();
+            prop_assert_eq!(json.data(), parsed.data());
+            prop_assert_eq!(value.clone(), Json::<Value>::new(value.to_string()).unwrap());
+        }
+
+        #[test]
+        fn arbitrary_containers_round_trip(
+            object in any::<Json<Object>>(),
+            array in any::<Json<Array>>(),
+        ) {
+            prop_assert_eq!(&object, &Json::<Object>::new(object.to_string()).unwrap());
+            prop_assert_eq!(&array, &Json::<Array>::new(array.to_string()).unwrap());
+        }
+    }
+}
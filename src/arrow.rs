@@ -0,0 +1,176 @@
+use crate::columns::{Column, Columns, Typed};
+use crate::json::{Array, Json, JsonError};
+use crate::parser::{float_or_null, JsonData};
+use arrow_array::cast::AsArray;
+use arrow_array::types::{
+    Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type, UInt16Type, UInt32Type,
+    UInt64Type, UInt8Type,
+};
+use arrow_array::{
+    Array as _, ArrayRef, BooleanArray, Float64Array, Int64Array, NullArray, RecordBatch,
+    RecordBatchOptions, StringArray,
+};
+use arrow_schema::{DataType, Field, Schema};
+use std::sync::Arc;
+
+impl Columns {
+    // Every column is nullable and gets the arrow type of its values, a column without any
+    // values is of the `Null` type
+    pub fn to_record_batch(&self) -> Result<RecordBatch, JsonError> {
+        let (fields, arrays): (Vec<Field>, Vec<ArrayRef>) = self
+            .columns()
+            .map(|(key, column)| {
+                let array = to_array(column);
+                (Field::new(key, array.data_type().clone(), true), array)
+            })
+            .unzip();
+        let options = RecordBatchOptions::new().with_row_count(Some(self.rows()));
+        RecordBatch::try_new_with_options(Arc::new(Schema::new(fields)), arrays, &options)
+            .map_err(|error| JsonError::InvalidArrow(error.to_string()))
+    }
+}
+
+fn to_array(column: &Column) -> ArrayRef {
+    fn options<T: Clone>(column: &Typed<T>) -> impl Iterator<Item = Option<T>> + '_ {
+        column
+            .values
+            .iter()
+            .zip(&column.valid)
+            .map(|(value, valid)| valid.then(|| value.clone()))
+    }
+    match column {
+        Column::Null(len) => Arc::new(NullArray::new(*len)),
+        Column::Bool(column) => Arc::new(options(column).collect::<BooleanArray>()),
+        Column::Integer(column) => Arc::new(options(column).collect::<Int64Array>()),
+        Column::Float(column) => Arc::new(options(column).collect::<Float64Array>()),
+        Column::String(column) => Arc::new(options(column).collect::<StringArray>()),
+    }
+}
+
+impl Json<Array> {
+    // `to_columns` straight into a record batch
+    pub fn to_record_batch(&self, keys: &[&str]) -> Result<RecordBatch, JsonError> {
+        self.to_columns(keys)?.to_record_batch()
+    }
+
+    // An object for every row with a member for every column, nulls included. Booleans,
+    // integers, floats and strings can be read, other arrow types are an error.
+    pub fn from_record_batch(batch: &RecordBatch) -> Result<Self, JsonError> {
+        let schema = batch.schema();
+        let mut rows = Vec::with_capacity(batch.num_rows());
+        for row in 0..batch.num_rows() {
+            let mut members = Vec::with_capacity(batch.num_columns());
+            for (field, array) in schema.fields().iter().zip(batch.columns()) {
+                members.push((field.name().as_str(), value_at(array, row)?));
+            }
+            rows.push(JsonData::object(members));
+        }
+        Ok(Self::from_data(JsonData::array(rows)))
+    }
+}
+
+fn value_at(array: &ArrayRef, row: usize) -> Result<JsonData, JsonError> {
+    if array.is_null(row) {
+        return Ok(JsonData::Null);
+    }
+    Ok(match array.data_type() {
+        DataType::Null => JsonData::Null,
+        DataType::Boolean => JsonData::Bool(array.as_boolean().value(row)),
+        DataType::Int8 => JsonData::Integer(array.as_primitive::<Int8Type>().value(row).into()),
+        DataType::Int16 => JsonData::Integer(array.as_primitive::<Int16Type>().value(row).into()),
+        DataType::Int32 => JsonData::Integer(array.as_primitive::<Int32Type>().value(row).into()),
+        DataType::Int64 => JsonData::Integer(array.as_primitive::<Int64Type>().value(row)),
+        DataType::UInt8 => JsonData::Integer(array.as_primitive::<UInt8Type>().value(row).into()),
+        DataType::UInt16 => JsonData::Integer(array.as_primitive::<UInt16Type>().value(row).into()),
+        DataType::UInt32 => JsonData::Integer(array.as_primitive::<UInt32Type>().value(row).into()),
+        DataType::UInt64 => unsigned(array.as_primitive::<UInt64Type>().value(row)),
+        DataType::Float32 => float_or_null(array.as_primitive::<Float32Type>().value(row).into()),
+        DataType::Float64 => float_or_null(array.as_primitive::<Float64Type>().value(row)),
+        DataType::Utf8 => JsonData::Str(array.as_string::<i32>().value(row).into()),
+        DataType::LargeUtf8 => JsonData::Str(array.as_string::<i64>().value(row).into()),
+        DataType::Utf8View => JsonData::Str(array.as_string_view().value(row).into()),
+        other => {
+            return Err(JsonError::InvalidArrow(format!(
+                "Arrow type {} can't be read as json",
+                other
+            )))
+        }
+    })
+}
+
+fn unsigned(n: u64) -> JsonData {
+    if let Ok(i) = i64::try_from(n) {
+        return JsonData::Integer(i);
+    }
+    #[cfg(feature = "bigint")]
+    return JsonData::BigInt(n.into());
+    #[cfg(not(feature = "bigint"))]
+    JsonData::Float(n as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::{Int32Array, ListArray};
+
+    #[test]
+    fn record_batch_round_trip() {
+        let records: Json<Array> = Json::new(
+            "[{\"id\": 1, \"price\": 2.5, \"name\": \"a\\nb\"},
+              {\"id\": 2, \"name\": null, \"other\": {}}]",
+        )
+        .unwrap();
+        let batch = records
+            .to_record_batch(&["id", "price", "name", "ts"])
+            .unwrap();
+        assert_eq!(2, batch.num_rows());
+        let schema = batch.schema();
+        let types: Vec<_> = schema.fields().iter().map(|f| f.data_type()).collect();
+        assert_eq!(
+            vec![
+                &DataType::Int64,
+                &DataType::Float64,
+                &DataType::Utf8,
+                &DataType::Null
+            ],
+            types
+        );
+        assert_eq!("a\nb", batch.column(2).as_string::<i32>().value(0));
+        assert!(batch.column(1).is_null(1));
+        assert_eq!(
+            Json::<Array>::new(
+                "[{\"id\": 1, \"price\": 2.5, \"name\": \"a\\nb\", \"ts\": null},
+                  {\"id\": 2, \"price\": null, \"name\": null, \"ts\": null}]"
+            )
+            .unwrap(),
+            Json::from_record_batch(&batch).unwrap()
+        );
+    }
+
+    #[test]
+    fn read_other_arrow_types() {
+        let narrow: ArrayRef = Arc::new(Int32Array::from(vec![Some(7), None]));
+        let list: ArrayRef = Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+            Some(vec![Some(1)]),
+            None,
+        ]));
+        let batch = RecordBatch::try_from_iter([("n", narrow.clone())]).unwrap();
+        assert_eq!(
+            Json::<Array>::new("[{\"n\": 7}, {\"n\": null}]").unwrap(),
+            Json::from_record_batch(&batch).unwrap()
+        );
+        let batch = RecordBatch::try_from_iter([("n", narrow), ("l", list)]).unwrap();
+        assert!(matches!(
+            Json::<Array>::from_record_batch(&batch),
+            Err(JsonError::InvalidArrow(_))
+        ));
+        assert_eq!(
+            0,
+            Json::<Array>::new("[]")
+                .unwrap()
+                .to_record_batch(&[])
+                .unwrap()
+                .num_rows()
+        );
+    }
+}
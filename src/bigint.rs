@@ -0,0 +1,78 @@
+use crate::json::{Json, JsonError, JsonType, Value};
+use crate::parser::JsonData;
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+
+// Rounds to the nearest float, or to infinity when out of range
+pub(crate) fn bigint_to_f64(i: &BigInt) -> f64 {
+    i.to_f64().expect("THIS SHOULD NEVER PANIC")
+}
+
+impl Json<Value> {
+    // Any integer, also ones beyond what `get_i64` can return
+    pub fn get_bigint(&self) -> Result<BigInt, JsonError> {
+        match self.data.as_ref().unraw() {
+            JsonData::Integer(i) => Ok(BigInt::from(*i)),
+            JsonData::BigInt(i) => Ok(i.clone()),
+            _ => Err(self.incorrect_type(&[JsonType::Integer])),
+        }
+    }
+}
+
+// Stored as a normal integer when it fits in an `i64`
+impl From<BigInt> for Json<Value> {
+    fn from(i: BigInt) -> Self {
+        Json::from_data(match i.to_i64() {
+            Some(i) => JsonData::Integer(i),
+            None => JsonData::BigInt(i),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::FormatOptions;
+    use crate::json::Array;
+    use crate::json::JsonType;
+
+    #[test]
+    fn bigint_parse_and_write() {
+        let text = "[123456789012345678901234567890,-9223372036854775809,9223372036854775807,1.5]";
+        let json: Json<Array> = Json::new(text).unwrap();
+        assert_eq!(text, json.to_formatted_string(&FormatOptions::minified()));
+        let big = json.get_value(0).unwrap();
+        assert_eq!(
+            Ok("123456789012345678901234567890".parse::<BigInt>().unwrap()),
+            big.get_bigint()
+        );
+        assert_eq!(
+            Err(JsonError::IncorrectType {
+                path: "/0".to_string(),
+                expected: vec![JsonType::Integer],
+                found: JsonType::Integer,
+            }),
+            big.get_i64()
+        );
+        assert_eq!(
+            Ok(BigInt::from(i64::MAX)),
+            json.get_value(2).unwrap().get_bigint()
+        );
+        assert_eq!(Ok(i64::MAX), json.get_value(2).unwrap().get_i64());
+        assert_eq!(
+            Err(JsonError::IncorrectType {
+                path: "/3".to_string(),
+                expected: vec![JsonType::Integer],
+                found: JsonType::Float,
+            }),
+            json.get_value(3).unwrap().get_bigint()
+        );
+        let small = Json::<Value>::from(BigInt::from(-5));
+        assert_eq!(Ok(-5), small.get_i64());
+        let twice = Json::<Value>::from(big.get_bigint().unwrap() * 2);
+        assert_eq!(
+            "246913578024691357802469135780",
+            twice.to_formatted_string(&FormatOptions::minified())
+        );
+    }
+}
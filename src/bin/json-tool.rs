@@ -0,0 +1,540 @@
+use json_parser::edit::Case;
+use json_parser::filter::Filter;
+use json_parser::format::{reformat, FormatOptions};
+use json_parser::json::{AnyJson, Json, JsonError, JsonState};
+use json_parser::lint::{lint, LintOptions};
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, Read, Write};
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: json-tool <COMMAND> [OPTIONS] [FILE...]
+       json-tool get|select <QUERY> [FILE...]
+       json-tool filter <FILTER> [FILE...]
+
+Reads stdin when no file (or `-`) is given.
+
+Commands:
+  validate          Exit with 1 if any input is not valid json
+  fmt               Pretty print the input
+  minify            Remove all insignificant whitespace
+  get <QUERY>       Print the matched values, strings without quotes
+  select <QUERY>    Print the matched values as json, one per line
+  filter <FILTER>   Print the output of a jq like filter as json, one per line
+  lint              Print duplicate keys, numbers that lose precision and unusual
+                    escapes, exit with 1 if there are any
+
+A query is either a json pointer (`/items/0/id`) or a JSONPath (`$.items[*].id`).
+A filter looks like `.items[] | select(.price > 10) | .name`.
+
+Options:
+  --indent <N>      Spaces per indentation level for `fmt`, or `tab` (default 2)
+  --inline-width <N>
+                    Keep arrays and objects of at most N chars on one line for `fmt`
+  --check           Don't write anything, exit with 1 if an input isn't already formatted
+  --max-depth <N>   Also lint arrays and objects nested deeper than N levels
+  --key-case <CASE> Also lint keys not in CASE: camel, pascal, snake, kebab or
+                    screaming-snake
+  -h, --help        Print this help
+";
+
+const EXIT_OK: u8 = 0;
+const EXIT_FAILURE: u8 = 1;
+const EXIT_USAGE: u8 = 2;
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Validate,
+    Format(FormatOptions),
+    Get(String),
+    Select(String),
+    Filter(Filter),
+    Lint(LintOptions),
+}
+
+#[derive(Debug, PartialEq)]
+struct Args {
+    command: Command,
+    check: bool,
+    files: Vec<String>,
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Args, String> {
+    let command = args.next().ok_or("Missing command")?;
+    let command_name = command.clone();
+    let query = match command.as_str() {
+        "get" | "select" | "filter" => Some(args.next().ok_or("Missing query")?),
+        _ => None,
+    };
+    // `None` for tabs
+    let mut indent = Some(2);
+    let mut inline_width = None;
+    let mut check = false;
+    let mut lint_options = LintOptions::new();
+    // The first option only `lint` takes
+    let mut lint_option = None;
+    let mut files = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--indent" => {
+                indent = match args.next().as_deref() {
+                    Some("tab") => None,
+                    n => Some(
+                        n.and_then(|n| n.parse().ok())
+                            .ok_or("`--indent` expects a number or `tab`")?,
+                    ),
+                };
+            }
+            "--inline-width" => {
+                inline_width = Some(
+                    args.next()
+                        .and_then(|n| n.parse().ok())
+                        .ok_or("`--inline-width` expects a number")?,
+                );
+            }
+            "--check" => check = true,
+            "--max-depth" => {
+                let depth = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .ok_or("`--max-depth` expects a number")?;
+                lint_options = lint_options.max_depth(depth);
+                lint_option.get_or_insert(arg);
+            }
+            "--key-case" => {
+                let case = match args.next().as_deref() {
+                    Some("camel") => Case::CamelCase,
+                    Some("pascal") => Case::PascalCase,
+                    Some("snake") => Case::SnakeCase,
+                    Some("kebab") => Case::KebabCase,
+                    Some("screaming-snake") => Case::ScreamingSnakeCase,
+                    _ => return Err(String::from("`--key-case` expects a case")),
+                };
+                lint_options = lint_options.key_case(case);
+                lint_option.get_or_insert(arg);
+            }
+            _ if arg.starts_with("--") => return Err(format!("Unknown option `{}`", arg)),
+            _ => files.push(arg),
+        }
+    }
+    let command = match (command.as_str(), query) {
+        ("validate", _) => Command::Validate,
+        ("fmt", _) => {
+            let options = indent.map_or_else(FormatOptions::tabs, FormatOptions::pretty);
+            Command::Format(match inline_width {
+                Some(width) => options.inline_width(width),
+                None => options,
+            })
+        }
+        ("minify", _) => Command::Format(FormatOptions::minified()),
+        ("get", Some(query)) => Command::Get(query),
+        ("select", Some(query)) => Command::Select(query),
+        ("filter", Some(filter)) => {
+            Command::Filter(Filter::compile(&filter).map_err(|e| error_message(&e))?)
+        }
+        ("lint", _) => Command::Lint(lint_options),
+        _ => return Err(format!("Unknown command `{}`", command)),
+    };
+    if check && !matches!(command, Command::Format(_)) {
+        return Err(format!("`--check` can't be used with `{}`", command_name));
+    }
+    match lint_option {
+        Some(option) if !matches!(command, Command::Lint(_)) => {
+            return Err(format!(
+                "`{}` can't be used with `{}`",
+                option, command_name
+            ));
+        }
+        _ => {}
+    }
+    if files.is_empty() {
+        files.push(String::from("-"));
+    }
+    Ok(Args {
+        command,
+        check,
+        files,
+    })
+}
+
+fn error_message(error: &JsonError) -> String {
+    match error {
+        JsonError::InvalidJsonSyntax(msg)
+        | JsonError::InvalidPath(msg)
+        | JsonError::InvalidFilter(msg) => msg.to_owned(),
+        error => error.to_string(),
+    }
+}
+
+// Compares everything written to it against `original`
+struct CheckWriter<R: Read> {
+    original: BufReader<R>,
+    matches: bool,
+}
+
+impl<R: Read> Write for CheckWriter<R> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.matches {
+            let mut expected = vec![0; buf.len()];
+            self.matches = self.original.read_exact(&mut expected).is_ok() && expected == buf;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<R: Read> CheckWriter<R> {
+    fn is_exhausted(&mut self) -> bool {
+        matches!(self.original.read(&mut [0]), Ok(0))
+    }
+}
+
+// Opens `name` a second time when `--check` needs the original to compare against,
+// stdin can only be read once so it's buffered in that case
+fn open<'a>(
+    name: &str,
+    stdin: &'a mut dyn Read,
+    twice: bool,
+) -> io::Result<(Box<dyn Read + 'a>, Box<dyn Read + 'a>)> {
+    match (name, twice) {
+        ("-", false) => Ok((Box::new(stdin), Box::new(io::empty()))),
+        ("-", true) => {
+            let mut buf = Vec::new();
+            stdin.read_to_end(&mut buf)?;
+            Ok((
+                Box::new(io::Cursor::new(buf.clone())),
+                Box::new(io::Cursor::new(buf)),
+            ))
+        }
+        (_, false) => Ok((Box::new(File::open(name)?), Box::new(io::empty()))),
+        (_, true) => Ok((Box::new(File::open(name)?), Box::new(File::open(name)?))),
+    }
+}
+
+fn query<S: JsonState>(json: &Json<S>, command: &Command) -> Result<Vec<AnyJson>, JsonError> {
+    let query = match command {
+        Command::Get(query) | Command::Select(query) => query,
+        Command::Filter(filter) => return filter.eval(json),
+        _ => unreachable!(),
+    };
+    if query.starts_with('$') {
+        json.select(query)
+    } else {
+        match json.pointer(query) {
+            Ok(found) => Ok(vec![found]),
+            Err(
+                JsonError::KeyNotFound { .. }
+                | JsonError::IndexNotFound { .. }
+                | JsonError::IncorrectType { .. },
+            ) => Ok(Vec::new()),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+fn write_matches(
+    input: &mut dyn Read,
+    stdout: &mut dyn Write,
+    command: &Command,
+) -> Result<bool, JsonError> {
+    let raw = matches!(command, Command::Get(_));
+    let mut text = String::new();
+    input
+        .read_to_string(&mut text)
+        .map_err(|e| JsonError::FileError(e.kind()))?;
+    let found = match Json::parse(text)? {
+        AnyJson::Object(json) => query(&json, command)?,
+        AnyJson::Array(json) => query(&json, command)?,
+        AnyJson::Value(json) => query(&json, command)?,
+    };
+    for json in &found {
+        let line = match json {
+            AnyJson::Value(value) if raw && value.get_string().is_ok() => {
+                value.get_unescaped_string()?
+            }
+            json => json.to_formatted_string(&FormatOptions::minified()),
+        };
+        writeln!(stdout, "{}", line).map_err(|e| JsonError::FileError(e.kind()))?;
+    }
+    Ok(!raw || !found.is_empty())
+}
+
+// `Ok(false)` when the input is fine but the command failed for it
+fn run_file(
+    args: &Args,
+    name: &str,
+    stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+) -> Result<bool, JsonError> {
+    let (mut input, original) =
+        open(name, stdin, args.check).map_err(|e| JsonError::FileError(e.kind()))?;
+    match &args.command {
+        Command::Validate => reformat(input, io::sink(), &FormatOptions::minified()).map(|_| true),
+        Command::Get(_) | Command::Select(_) | Command::Filter(_) => {
+            write_matches(&mut input, stdout, &args.command)
+        }
+        Command::Lint(options) => {
+            let mut text = String::new();
+            input
+                .read_to_string(&mut text)
+                .map_err(|e| JsonError::FileError(e.kind()))?;
+            let findings = lint(text, options)?;
+            for finding in &findings {
+                writeln!(
+                    stdout,
+                    "{}:{}:{}: {} [{}]",
+                    name,
+                    finding.loc.row,
+                    finding.loc.col,
+                    finding.message,
+                    finding.rule.name()
+                )
+                .map_err(|e| JsonError::FileError(e.kind()))?;
+            }
+            Ok(findings.is_empty())
+        }
+        Command::Format(options) if args.check => {
+            let mut check = CheckWriter {
+                original: BufReader::new(original),
+                matches: true,
+            };
+            reformat(input, &mut check, options)?;
+            check.write_all(b"\n").expect("THIS SHOULD NEVER PANIC");
+            Ok(check.matches && check.is_exhausted())
+        }
+        Command::Format(options) => {
+            reformat(input, &mut *stdout, options)?;
+            stdout
+                .write_all(b"\n")
+                .map_err(|e| JsonError::FileError(e.kind()))?;
+            Ok(true)
+        }
+    }
+}
+
+fn run<I: Iterator<Item = String>>(
+    args: I,
+    stdin: &mut dyn Read,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> u8 {
+    let mut args = args.peekable();
+    if matches!(args.peek().map(String::as_str), Some("-h" | "--help")) {
+        let _ = write!(stdout, "{}", USAGE);
+        return EXIT_OK;
+    }
+    let args = match parse_args(args) {
+        Ok(args) => args,
+        Err(msg) => {
+            let _ = write!(stderr, "error: {}\n\n{}", msg, USAGE);
+            return EXIT_USAGE;
+        }
+    };
+    let mut code = EXIT_OK;
+    for name in &args.files {
+        match run_file(&args, name, stdin, stdout) {
+            Ok(true) => {}
+            Ok(false) => {
+                let msg = match args.command {
+                    Command::Get(_) => "no match",
+                    Command::Lint(_) => "lint failed",
+                    _ => "not formatted",
+                };
+                let _ = writeln!(stderr, "{}: {}", name, msg);
+                code = EXIT_FAILURE;
+            }
+            Err(JsonError::FileError(kind)) => {
+                let _ = writeln!(stderr, "{}: {}", name, io::Error::from(kind));
+                code = code.max(EXIT_USAGE);
+            }
+            Err(error) => {
+                let _ = writeln!(stderr, "{}: {}", name, error_message(&error));
+                code = code.max(EXIT_FAILURE);
+            }
+        }
+    }
+    code
+}
+
+fn main() -> ExitCode {
+    ExitCode::from(run(
+        std::env::args().skip(1),
+        &mut io::stdin().lock(),
+        &mut io::stdout().lock(),
+        &mut io::stderr(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_str(args: &[&str], stdin: &str) -> (u8, String, String) {
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let code = run(
+            args.iter().map(|s| s.to_string()),
+            &mut stdin.as_bytes(),
+            &mut stdout,
+            &mut stderr,
+        );
+        (
+            code,
+            String::from_utf8(stdout).unwrap(),
+            String::from_utf8(stderr).unwrap(),
+        )
+    }
+
+    #[test]
+    fn cli_validate() {
+        assert_eq!(EXIT_OK, run_str(&["validate"], "{\"a\": [1]}").0);
+        let (code, _, stderr) = run_str(&["validate", "-"], "{\"a\": [1,]}");
+        assert_eq!(EXIT_FAILURE, code);
+        assert!(stderr.starts_with("-: Invalid Json Syntax"), "{stderr}");
+        assert_eq!(
+            EXIT_OK,
+            run_str(&["validate", "src/__test_data__/test_data8.json"], "").0
+        );
+        assert_eq!(
+            EXIT_USAGE,
+            run_str(&["validate", "does/not/exist.json"], "").0
+        );
+        for empty in ["", "  \n"] {
+            let (code, _, stderr) = run_str(&["validate"], empty);
+            assert_eq!(EXIT_FAILURE, code);
+            assert!(stderr.contains("Empty input"), "{stderr}");
+        }
+    }
+
+    #[test]
+    fn cli_fmt_and_minify() {
+        assert_eq!(
+            (
+                EXIT_OK,
+                String::from("{\n    \"a\": [\n        1\n    ]\n}\n"),
+                String::new()
+            ),
+            run_str(&["fmt", "--indent", "4"], "{\"a\":[1]}")
+        );
+        assert_eq!(
+            (
+                EXIT_OK,
+                String::from("{\n\t\"a\": [1],\n\t\"b\": {\n\t\t\"c\": 2\n\t}\n}\n"),
+                String::new()
+            ),
+            run_str(
+                &["fmt", "--indent", "tab", "--inline-width", "5"],
+                "{\"a\":[1],\"b\":{\"c\":2}}"
+            )
+        );
+        assert_eq!(
+            (EXIT_OK, String::from("{\"a\":[1]}\n"), String::new()),
+            run_str(&["minify"], "{ \"a\" : [ 1 ] }")
+        );
+    }
+
+    #[test]
+    fn cli_check() {
+        assert_eq!(EXIT_OK, run_str(&["minify", "--check"], "{\"a\":[1]}\n").0);
+        assert_eq!(
+            EXIT_FAILURE,
+            run_str(&["minify", "--check"], "{\"a\": [1]}\n").0
+        );
+        assert_eq!(
+            EXIT_FAILURE,
+            run_str(&["minify", "--check"], "{\"a\":[1]}").0
+        );
+        assert_eq!(
+            EXIT_OK,
+            run_str(&["fmt", "--check"], "{\n  \"a\": 1\n}\n").0
+        );
+        assert_eq!(EXIT_FAILURE, run_str(&["fmt", "--check"], "{\"a\":1}\n").0);
+    }
+
+    #[test]
+    fn cli_get_and_select() {
+        let json = "{\"items\": [{\"id\": \"a\\\"b\"}, {\"id\": [1, 2]}]}";
+        assert_eq!(
+            (EXIT_OK, String::from("a\"b\n"), String::new()),
+            run_str(&["get", "/items/0/id"], json)
+        );
+        assert_eq!(
+            (EXIT_OK, String::from("a\"b\n[1,2]\n"), String::new()),
+            run_str(&["get", "$.items[*].id"], json)
+        );
+        assert_eq!(
+            (EXIT_OK, String::from("\"a\\\"b\"\n[1,2]\n"), String::new()),
+            run_str(&["select", "$.items[*].id"], json)
+        );
+        assert_eq!(
+            (EXIT_OK, String::from("2\n"), String::new()),
+            run_str(&["select", "/items/1/id/1"], json)
+        );
+        assert_eq!(
+            (EXIT_OK, String::new(), String::new()),
+            run_str(&["select", "$.nothing"], json)
+        );
+        let (code, _, stderr) = run_str(&["get", "/items/2"], json);
+        assert_eq!((EXIT_FAILURE, "-: no match\n"), (code, stderr.as_str()));
+        assert_eq!(EXIT_FAILURE, run_str(&["get", "$.items["], json).0);
+    }
+
+    #[test]
+    fn cli_filter() {
+        let json =
+            "{\"items\": [{\"name\": \"a\", \"price\": 5}, {\"name\": \"b\", \"price\": 12}]}";
+        assert_eq!(
+            (EXIT_OK, String::from("\"b\"\n"), String::new()),
+            run_str(&["filter", ".items[] | select(.price > 10) | .name"], json)
+        );
+        assert_eq!(EXIT_FAILURE, run_str(&["filter", ".items.name"], json).0);
+        let (code, _, stderr) = run_str(&["filter", ".items[", "-"], json);
+        assert_eq!(EXIT_USAGE, code);
+        assert!(stderr.starts_with("error: Expected"), "{stderr}");
+    }
+
+    #[test]
+    fn cli_lint() {
+        assert_eq!(
+            (EXIT_OK, String::new(), String::new()),
+            run_str(&["lint"], "{\"a\": [1]}")
+        );
+        assert_eq!(
+            (
+                EXIT_FAILURE,
+                String::from(
+                    "-:1:10: Duplicate key `a`, only the last value is kept [duplicate-keys]\n\
+                     -:1:16: Key `b_c` is not camelCase, it would be `bC` [key-case]\n"
+                ),
+                String::from("-: lint failed\n")
+            ),
+            run_str(
+                &["lint", "--key-case", "camel"],
+                "{\"a\": 1, \"a\": {\"b_c\": 2}}"
+            )
+        );
+        assert_eq!(
+            EXIT_FAILURE,
+            run_str(&["lint", "--max-depth", "1"], "[[]]").0
+        );
+        assert_eq!(EXIT_FAILURE, run_str(&["lint"], "[1,]").0);
+    }
+
+    #[test]
+    fn cli_usage_errors() {
+        assert_eq!(EXIT_USAGE, run_str(&[], "").0);
+        assert_eq!(EXIT_USAGE, run_str(&["frobnicate"], "").0);
+        assert_eq!(EXIT_USAGE, run_str(&["fmt", "--max-depth", "2"], "").0);
+        assert_eq!(EXIT_USAGE, run_str(&["lint", "--key-case", "title"], "").0);
+        assert_eq!(EXIT_USAGE, run_str(&["fmt", "--indent"], "").0);
+        assert_eq!(EXIT_USAGE, run_str(&["fmt", "--inline-width", "x"], "").0);
+        assert_eq!(EXIT_USAGE, run_str(&["validate", "--check"], "").0);
+        assert_eq!(EXIT_USAGE, run_str(&["get"], "").0);
+        assert_eq!(EXIT_USAGE, run_str(&["select", "$", "--check"], "").0);
+        assert_eq!(EXIT_OK, run_str(&["--help"], "").0);
+    }
+}
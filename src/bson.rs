@@ -0,0 +1,521 @@
+use crate::json::{Json, JsonError, Object, MAX_DEPTH};
+use crate::parser::{float_or_null, JsonData, Map, Shared};
+use crate::pointer::pointer_token;
+use crate::small::SmallString;
+
+const DOUBLE: u8 = 0x01;
+const STRING: u8 = 0x02;
+const DOCUMENT: u8 = 0x03;
+const ARRAY: u8 = 0x04;
+const BINARY: u8 = 0x05;
+const UNDEFINED: u8 = 0x06;
+const OBJECT_ID: u8 = 0x07;
+const BOOL: u8 = 0x08;
+const DATETIME: u8 = 0x09;
+const NULL: u8 = 0x0a;
+const INT32: u8 = 0x10;
+const TIMESTAMP: u8 = 0x11;
+const INT64: u8 = 0x12;
+
+// Bson types json has no value for are read as the objects of MongoDB's canonical extended
+// json, `{"$binary": {"base64": "...", "subType": "00"}}`, `{"$date": {"$numberLong": "..."}}`,
+// `{"$oid": "..."}` and `{"$timestamp": {"t": 0, "i": 0}}`, and these objects are written as
+// the bson types again.
+impl Json<Object> {
+    pub fn to_bson(&self) -> Result<Vec<u8>, JsonError> {
+        let mut out = Vec::new();
+        match self.data.as_ref() {
+            JsonData::Object(map) => encode_document(map.iter(), "", &mut out)?,
+            _ => unreachable!(),
+        }
+        Ok(out)
+    }
+
+    pub fn from_bson(bson: &[u8]) -> Result<Self, JsonError> {
+        let mut decoder = Decoder { bson, pos: 0 };
+        let data = decoder.document(false, 1)?;
+        if decoder.pos != bson.len() {
+            return Err(decoder.error("Trailing bytes after the document"));
+        }
+        Ok(Self::from_data(data))
+    }
+}
+
+fn unrepresentable(msg: &str, path: &str) -> JsonError {
+    JsonError::UnrepresentableInBson(format!("{} at `{}`", msg, path))
+}
+
+fn encode_document<'a, K: AsRef<str> + 'a, I>(
+    members: I,
+    path: &str,
+    out: &mut Vec<u8>,
+) -> Result<(), JsonError>
+where
+    I: Iterator<Item = (K, &'a Shared<JsonData>)>,
+{
+    let start = out.len();
+    out.extend_from_slice(&[0; 4]);
+    for (key, value) in members {
+        let key = key.as_ref();
+        let path = format!("{}/{}", path, pointer_token(key));
+        let kind = out.len();
+        out.push(0);
+        if key.contains('\0') {
+            return Err(unrepresentable("Keys can't contain a nul", &path));
+        }
+        out.extend_from_slice(key.as_bytes());
+        out.push(0);
+        let value = encode(value, &path, out)?;
+        out[kind] = value;
+    }
+    out.push(0);
+    let len = (out.len() - start) as i32;
+    out[start..start + 4].copy_from_slice(&len.to_le_bytes());
+    Ok(())
+}
+
+fn encode_i64(i: i64, out: &mut Vec<u8>) -> u8 {
+    match i32::try_from(i) {
+        Ok(i) => {
+            out.extend_from_slice(&i.to_le_bytes());
+            INT32
+        }
+        Err(_) => {
+            out.extend_from_slice(&i.to_le_bytes());
+            INT64
+        }
+    }
+}
+
+fn encode_f64(f: f64, out: &mut Vec<u8>) -> u8 {
+    out.extend_from_slice(&f.to_le_bytes());
+    DOUBLE
+}
+
+// Writes the value of an element and returns its type
+fn encode(data: &JsonData, path: &str, out: &mut Vec<u8>) -> Result<u8, JsonError> {
+    Ok(match data {
+        JsonData::Null => NULL,
+        JsonData::Bool(b) => {
+            out.push(*b as u8);
+            BOOL
+        }
+        JsonData::Integer(i) => encode_i64(*i, out),
+        // integers that don't fit in 64 bits and decimals lose precision like in cbor
+        #[cfg(feature = "bigint")]
+        JsonData::BigInt(i) => match i64::try_from(i) {
+            Ok(i) => encode_i64(i, out),
+            Err(_) => encode_f64(data.as_f64().expect("THIS SHOULD NEVER PANIC"), out),
+        },
+        JsonData::Float(f) => encode_f64(*f, out),
+        #[cfg(feature = "decimal")]
+        JsonData::Decimal(_) => encode_f64(data.as_f64().expect("THIS SHOULD NEVER PANIC"), out),
+        #[cfg(feature = "raw_numbers")]
+        JsonData::RawNumber(raw) => encode(&raw.data, path, out)?,
+        JsonData::Str(s) => {
+            out.extend_from_slice(&(s.len() as i32 + 1).to_le_bytes());
+            out.extend_from_slice(s.as_bytes());
+            out.push(0);
+            STRING
+        }
+        JsonData::Array(v) => {
+            let keys = (0..v.len()).map(|i| i.to_string());
+            encode_document(keys.zip(v.iter()), path, out)?;
+            ARRAY
+        }
+        JsonData::Object(m) => match extension(m) {
+            Some((kind, bytes)) => {
+                out.extend_from_slice(&bytes);
+                kind
+            }
+            None => {
+                encode_document(m.iter(), path, out)?;
+                DOCUMENT
+            }
+        },
+    })
+}
+
+fn member<'a>(map: &'a Map<SmallString, Shared<JsonData>>, key: &str) -> Option<&'a JsonData> {
+    map.get(key).map(|data| data.as_ref())
+}
+
+fn string(data: Option<&JsonData>) -> Option<String> {
+    match data {
+        Some(JsonData::Str(s)) => Some(s.to_string()),
+        _ => None,
+    }
+}
+
+fn u32_member(map: &Map<SmallString, Shared<JsonData>>, key: &str) -> Option<u32> {
+    match member(map, key).map(JsonData::unraw) {
+        Some(JsonData::Integer(i)) => u32::try_from(*i).ok(),
+        _ => None,
+    }
+}
+
+// The bson type and bytes of an extended json object, `None` for any other object
+fn extension(map: &Map<SmallString, Shared<JsonData>>) -> Option<(u8, Vec<u8>)> {
+    let (key, value) = map.iter().next().filter(|_| map.len() == 1)?;
+    match (key.as_str(), value.as_ref()) {
+        ("$binary", JsonData::Object(binary)) if binary.len() == 2 => {
+            let bytes = base64_decode(&string(member(binary, "base64"))?)?;
+            let subtype = u8::from_str_radix(&string(member(binary, "subType"))?, 16).ok()?;
+            let mut out = (bytes.len() as i32).to_le_bytes().to_vec();
+            out.push(subtype);
+            out.extend_from_slice(&bytes);
+            Some((BINARY, out))
+        }
+        ("$date", JsonData::Object(date)) if date.len() == 1 => {
+            let millis: i64 = string(member(date, "$numberLong"))?.parse().ok()?;
+            Some((DATETIME, millis.to_le_bytes().to_vec()))
+        }
+        ("$oid", JsonData::Str(oid)) if oid.len() == 24 => {
+            let bytes = (0..24)
+                .step_by(2)
+                .map(|i| u8::from_str_radix(oid.get(i..i + 2)?, 16).ok())
+                .collect::<Option<Vec<u8>>>()?;
+            Some((OBJECT_ID, bytes))
+        }
+        ("$timestamp", JsonData::Object(timestamp)) if timestamp.len() == 2 => {
+            let (t, i) = (u32_member(timestamp, "t")?, u32_member(timestamp, "i")?);
+            let mut out = i.to_le_bytes().to_vec();
+            out.extend_from_slice(&t.to_le_bytes());
+            Some((TIMESTAMP, out))
+        }
+        _ => None,
+    }
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(4) {
+        return None;
+    }
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    for chunk in text.as_bytes().chunks(4) {
+        let padding = chunk.iter().rev().take_while(|c| **c == b'=').count();
+        if padding > 2 {
+            return None;
+        }
+        let mut n = 0u32;
+        for c in &chunk[..4 - padding] {
+            n = n << 6 | BASE64.iter().position(|b| b == c)? as u32;
+        }
+        n <<= 6 * padding;
+        out.extend_from_slice(&n.to_be_bytes()[1..4 - padding]);
+    }
+    Some(out)
+}
+
+struct Decoder<'a> {
+    bson: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn error(&self, msg: &str) -> JsonError {
+        JsonError::InvalidBson(format!("{} at byte {}", msg, self.pos))
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], JsonError> {
+        if self.bson.len() - self.pos < n {
+            return Err(self.error("Unexpected end of input"));
+        }
+        let bytes = &self.bson[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn i32(&mut self) -> Result<i32, JsonError> {
+        Ok(i32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, JsonError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn i64(&mut self) -> Result<i64, JsonError> {
+        Ok(i64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn length(&mut self) -> Result<usize, JsonError> {
+        match usize::try_from(self.i32()?) {
+            Ok(len) if len <= self.bson.len() - self.pos => Ok(len),
+            _ => Err(self.error("Invalid length")),
+        }
+    }
+
+    fn utf8(&self, bytes: &[u8]) -> Result<String, JsonError> {
+        String::from_utf8(bytes.to_vec()).map_err(|_| self.error("Text is not valid utf-8"))
+    }
+
+    fn cstring(&mut self) -> Result<String, JsonError> {
+        let len = self.bson[self.pos..]
+            .iter()
+            .position(|b| *b == 0)
+            .ok_or_else(|| self.error("Unterminated key"))?;
+        let bytes = self.take(len + 1)?;
+        self.utf8(&bytes[..len])
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        let len = self.length()?;
+        let bytes = self.take(len)?;
+        match bytes.split_last() {
+            Some((0, text)) => self.utf8(text),
+            _ => Err(self.error("String is not terminated by a nul")),
+        }
+    }
+
+    // A document or, with `array`, the values of a document without its keys. `depth` counts
+    // the documents around its values, past `MAX_DEPTH` it is an error so untrusted input can
+    // not overflow the stack
+    fn document(&mut self, array: bool, depth: usize) -> Result<JsonData, JsonError> {
+        let start = self.pos;
+        // the length of a document counts the bytes of the length itself
+        let end = match usize::try_from(self.i32()?) {
+            Ok(len) if len >= 5 && len <= self.bson.len() - start => start + len,
+            _ => return Err(self.error("Invalid length")),
+        };
+        let (mut members, mut items) = (Map::default(), Vec::new());
+        loop {
+            let kind = self.take(1)?[0];
+            if kind == 0 {
+                break;
+            }
+            let key = self.cstring()?;
+            let value = self.decode(kind, depth)?;
+            if array {
+                items.push(value);
+            } else {
+                members.insert(key, value);
+            }
+        }
+        if self.pos != end {
+            self.pos = start;
+            return Err(self.error("Document length does not match its content"));
+        }
+        Ok(if array {
+            JsonData::array(items)
+        } else {
+            JsonData::object(members)
+        })
+    }
+
+    fn decode(&mut self, kind: u8, depth: usize) -> Result<JsonData, JsonError> {
+        if !matches!(kind, DOCUMENT | ARRAY) {
+            return self.scalar(kind);
+        }
+        if depth >= MAX_DEPTH {
+            return Err(self.error(&format!("Nested deeper than {} levels", MAX_DEPTH)));
+        }
+        self.document(kind == ARRAY, depth + 1)
+    }
+
+    fn scalar(&mut self, kind: u8) -> Result<JsonData, JsonError> {
+        Ok(match kind {
+            DOUBLE => float_or_null(f64::from_le_bytes(self.take(8)?.try_into().unwrap())),
+            STRING => JsonData::Str(self.string()?.into()),
+            BINARY => {
+                let len = self.length()?;
+                let subtype = self.take(1)?[0];
+                let bytes = self.take(len)?;
+                JsonData::object([(
+                    "$binary",
+                    JsonData::object([
+                        ("base64", JsonData::Str(base64_encode(bytes).into())),
+                        ("subType", JsonData::Str(format!("{:02x}", subtype).into())),
+                    ]),
+                )])
+            }
+            UNDEFINED | NULL => JsonData::Null,
+            OBJECT_ID => {
+                let hex: String = self
+                    .take(12)?
+                    .iter()
+                    .map(|b| format!("{:02x}", b))
+                    .collect();
+                JsonData::object([("$oid", JsonData::Str(hex.into()))])
+            }
+            BOOL => match self.take(1)?[0] {
+                0 => JsonData::Bool(false),
+                1 => JsonData::Bool(true),
+                _ => return Err(self.error("Invalid boolean")),
+            },
+            DATETIME => {
+                let millis = self.i64()?;
+                JsonData::object([(
+                    "$date",
+                    JsonData::object([("$numberLong", JsonData::Str(millis.to_string().into()))]),
+                )])
+            }
+            INT32 => JsonData::Integer(self.i32()?.into()),
+            TIMESTAMP => {
+                let (i, t) = (self.u32()?, self.u32()?);
+                JsonData::object([(
+                    "$timestamp",
+                    JsonData::object([
+                        ("t", JsonData::Integer(t.into())),
+                        ("i", JsonData::Integer(i.into())),
+                    ]),
+                )])
+            }
+            INT64 => JsonData::Integer(self.i64()?),
+            _ => {
+                self.pos -= 1;
+                return Err(self.error(&format!("Unsupported bson type 0x{:02x}", kind)));
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn encode_known_documents() {
+        // the examples from bsonspec.org
+        assert_eq!(
+            hex("160000000268656c6c6f0006000000776f726c640000"),
+            Json::<Object>::new("{\"hello\": \"world\"}")
+                .unwrap()
+                .to_bson()
+                .unwrap()
+        );
+        assert_eq!(
+            hex("310000000442534f4e002600000002300008000000617765736f6d65000131003333333333331440103200c20700000000"),
+            Json::<Object>::new("{\"BSON\": [\"awesome\", 5.05, 1986]}")
+                .unwrap()
+                .to_bson()
+                .unwrap()
+        );
+        assert_eq!(
+            hex("1b0000000462001300000012300000000000010000000a31000000"),
+            Json::<Object>::new("{\"b\": [4294967296, null]}")
+                .unwrap()
+                .to_bson()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn extended_json_round_trip() {
+        let json = Json::<Object>::new(
+            "{\"bin\": {\"$binary\": {\"base64\": \"AQID/w==\", \"subType\": \"80\"}},
+              \"date\": {\"$date\": {\"$numberLong\": \"-1000\"}},
+              \"id\": {\"$oid\": \"5f1a2b3c4d5e6f7a8b9c0d1e\"},
+              \"ts\": {\"$timestamp\": {\"t\": 12, \"i\": 3}},
+              \"not\": {\"$oid\": 1}}",
+        )
+        .unwrap();
+        let bson = json.to_bson().unwrap();
+        assert_eq!(json, Json::from_bson(&bson).unwrap());
+        let mut kinds = Vec::new();
+        let mut decoder = Decoder {
+            bson: &bson,
+            pos: 4,
+        };
+        while decoder.bson[decoder.pos] != 0 {
+            let kind = decoder.take(1).unwrap()[0];
+            decoder.cstring().unwrap();
+            decoder.decode(kind, 1).unwrap();
+            kinds.push(kind);
+        }
+        kinds.sort();
+        assert_eq!(
+            vec![DOCUMENT, BINARY, OBJECT_ID, DATETIME, TIMESTAMP],
+            kinds
+        );
+        assert_eq!(Some(vec![1, 2, 3, 255]), base64_decode("AQID/w=="));
+        assert_eq!("AQID/w==", base64_encode(&[1, 2, 3, 255]));
+        assert_eq!("YWI=", base64_encode(b"ab"));
+        assert_eq!(None, base64_decode("A==="));
+    }
+
+    #[test]
+    fn roundtrip_test_data() {
+        let json = Json::<Object>::from_file("src/__test_data__/test_data2.json").unwrap();
+        let decoded = Json::<Object>::from_bson(&json.to_bson().unwrap()).unwrap();
+        assert_eq!(json.data, decoded.data);
+    }
+
+    #[test]
+    fn invalid_bson() {
+        assert!(matches!(
+            Json::<Object>::from_bson(&hex("0500")),
+            Err(JsonError::InvalidBson(_))
+        ));
+        assert!(matches!(
+            Json::<Object>::from_bson(&hex("0c000000136100000000000000")),
+            Err(JsonError::InvalidBson(_))
+        ));
+        assert!(matches!(
+            Json::<Object>::from_bson(&hex("0500000000ff")),
+            Err(JsonError::InvalidBson(_))
+        ));
+        assert_eq!(
+            Err(JsonError::UnrepresentableInBson(
+                "Keys can't contain a nul at `/a/b\0`".to_string()
+            )),
+            Json::<Object>::new("{\"a\": {\"b\\u0000\": 1}}")
+                .unwrap()
+                .to_bson()
+        );
+    }
+
+    // `depth` documents, each one the only value of the one around it
+    fn nested(depth: usize) -> Vec<u8> {
+        let mut bson = Vec::new();
+        for level in (1..=depth).rev() {
+            let len = 5 + 8 * (level as i32 - 1);
+            bson.extend_from_slice(&len.to_le_bytes());
+            if level > 1 {
+                bson.extend_from_slice(b"\x03a\0");
+            }
+        }
+        bson.extend(std::iter::repeat_n(0, depth));
+        bson
+    }
+
+    #[test]
+    fn too_deep() {
+        assert!(matches!(
+            Json::<Object>::from_bson(&nested(20_000)),
+            Err(JsonError::InvalidBson(msg)) if msg.starts_with("Nested deeper than 512 levels")
+        ));
+        let deepest = Json::<Object>::from_bson(&nested(MAX_DEPTH)).unwrap();
+        assert_eq!(
+            deepest,
+            Json::from_bson(&deepest.to_bson().unwrap()).unwrap()
+        );
+        assert!(Json::<Object>::from_bson(&nested(MAX_DEPTH + 1)).is_err());
+    }
+}
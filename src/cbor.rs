@@ -0,0 +1,407 @@
+use crate::json::{Json, JsonError, JsonState, MAX_DEPTH};
+use crate::parser::{float_or_null, JsonData, Map};
+
+const UNSIGNED: u8 = 0;
+const NEGATIVE: u8 = 1;
+const BYTES: u8 = 2;
+const TEXT: u8 = 3;
+const ARRAY: u8 = 4;
+const MAP: u8 = 5;
+const TAG: u8 = 6;
+const SIMPLE: u8 = 7;
+
+const INDEFINITE: u8 = 31;
+const BREAK: u8 = 0xff;
+
+impl<S: JsonState> Json<S> {
+    pub fn to_cbor(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        encode(&self.data, &mut out);
+        out
+    }
+
+    pub fn from_cbor(cbor: &[u8]) -> Result<Self, JsonError> {
+        let mut decoder = Decoder { cbor, pos: 0 };
+        let data = decoder.decode(0)?;
+        if decoder.pos != cbor.len() {
+            return Err(decoder.error("Trailing bytes after the first item"));
+        }
+        Self::checked(data)
+    }
+}
+
+fn encode_head(major: u8, n: u64, out: &mut Vec<u8>) {
+    let major = major << 5;
+    if n < 24 {
+        out.push(major | n as u8);
+    } else if n <= u8::MAX as u64 {
+        out.push(major | 24);
+        out.push(n as u8);
+    } else if n <= u16::MAX as u64 {
+        out.push(major | 25);
+        out.extend_from_slice(&(n as u16).to_be_bytes());
+    } else if n <= u32::MAX as u64 {
+        out.push(major | 26);
+        out.extend_from_slice(&(n as u32).to_be_bytes());
+    } else {
+        out.push(major | 27);
+        out.extend_from_slice(&n.to_be_bytes());
+    }
+}
+
+fn encode_text(text: &str, out: &mut Vec<u8>) {
+    encode_head(TEXT, text.len() as u64, out);
+    out.extend_from_slice(text.as_bytes());
+}
+
+fn encode(data: &JsonData, out: &mut Vec<u8>) {
+    match data {
+        JsonData::Null => out.push(0xf6),
+        JsonData::Bool(false) => out.push(0xf4),
+        JsonData::Bool(true) => out.push(0xf5),
+        JsonData::Integer(i) if *i >= 0 => encode_head(UNSIGNED, *i as u64, out),
+        JsonData::Integer(i) => encode_head(NEGATIVE, !(*i) as u64, out),
+        JsonData::Float(f) => {
+            out.push(0xfb);
+            out.extend_from_slice(&f.to_be_bytes());
+        }
+        // cbor has decimal fractions but they are not widely supported
+        // only integers that fit in the 64 bits of an argument stay exact
+        #[cfg(feature = "bigint")]
+        JsonData::BigInt(i) => match (u64::try_from(i), u64::try_from(-1 - i)) {
+            (Ok(n), _) => encode_head(UNSIGNED, n, out),
+            (_, Ok(n)) => encode_head(NEGATIVE, n, out),
+            _ => encode(
+                &JsonData::Float(data.as_f64().expect("THIS SHOULD NEVER PANIC")),
+                out,
+            ),
+        },
+        #[cfg(feature = "decimal")]
+        JsonData::Decimal(_) => encode(
+            &JsonData::Float(data.as_f64().expect("THIS SHOULD NEVER PANIC")),
+            out,
+        ),
+        #[cfg(feature = "raw_numbers")]
+        JsonData::RawNumber(raw) => encode(&raw.data, out),
+        JsonData::Str(s) => encode_text(s, out),
+        JsonData::Array(v) => {
+            encode_head(ARRAY, v.len() as u64, out);
+            for item in v {
+                encode(item, out);
+            }
+        }
+        JsonData::Object(m) => {
+            encode_head(MAP, m.len() as u64, out);
+            for (key, value) in m.iter() {
+                encode_text(key, out);
+                encode(value, out);
+            }
+        }
+    }
+}
+
+struct Decoder<'a> {
+    cbor: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn error(&self, msg: &str) -> JsonError {
+        JsonError::InvalidCbor(format!("{} at byte {}", msg, self.pos))
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], JsonError> {
+        if self.cbor.len() - self.pos < n {
+            return Err(self.error("Unexpected end of input"));
+        }
+        let bytes = &self.cbor[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn peek_break(&mut self) -> Result<bool, JsonError> {
+        match self.cbor.get(self.pos) {
+            Some(&BREAK) => {
+                self.pos += 1;
+                Ok(true)
+            }
+            Some(_) => Ok(false),
+            None => Err(self.error("Unexpected end of input")),
+        }
+    }
+
+    // Reads the argument of a head, `None` means indefinite length
+    fn argument(&mut self, info: u8) -> Result<Option<u64>, JsonError> {
+        Ok(Some(match info {
+            0..=23 => info as u64,
+            24 => self.take(1)?[0] as u64,
+            25 => u16::from_be_bytes(self.take(2)?.try_into().unwrap()) as u64,
+            26 => u32::from_be_bytes(self.take(4)?.try_into().unwrap()) as u64,
+            27 => u64::from_be_bytes(self.take(8)?.try_into().unwrap()),
+            INDEFINITE => return Ok(None),
+            _ => return Err(self.error("Reserved additional information")),
+        }))
+    }
+
+    fn length(&mut self, info: u8) -> Result<Option<usize>, JsonError> {
+        match self.argument(info)? {
+            Some(n) if n > (self.cbor.len() - self.pos) as u64 => {
+                Err(self.error("Length is longer than the input"))
+            }
+            n => Ok(n.map(|n| n as usize)),
+        }
+    }
+
+    fn text(&mut self, major: u8, info: u8) -> Result<String, JsonError> {
+        let mut bytes = Vec::new();
+        if let Some(len) = self.length(info)? {
+            bytes.extend_from_slice(self.take(len)?);
+        } else {
+            while !self.peek_break()? {
+                let head = self.take(1)?[0];
+                if head >> 5 != major || head & 0x1f == INDEFINITE {
+                    return Err(self.error("Invalid chunk in indefinite length string"));
+                }
+                let len = self.length(head & 0x1f)?.unwrap_or_default();
+                bytes.extend_from_slice(self.take(len)?);
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| self.error("Text is not valid utf-8"))
+    }
+
+    // `depth` counts the arrays, maps and tags around the item, past `MAX_DEPTH` it is an
+    // error so untrusted input can not overflow the stack
+    fn decode(&mut self, depth: usize) -> Result<JsonData, JsonError> {
+        let head = self.take(1)?[0];
+        let (major, info) = (head >> 5, head & 0x1f);
+        if !matches!(major, ARRAY | MAP | TAG) {
+            return self.scalar(major, info);
+        }
+        if depth >= MAX_DEPTH {
+            return Err(self.too_deep());
+        }
+        match major {
+            ARRAY => self.array(info, depth + 1),
+            MAP => self.map(info, depth + 1),
+            // Tags carry no meaning in json so only the tagged item is kept
+            _ => {
+                self.argument(info)?
+                    .ok_or_else(|| self.error("Invalid tag"))?;
+                self.decode(depth + 1)
+            }
+        }
+    }
+
+    fn too_deep(&self) -> JsonError {
+        self.error(&format!("Nested deeper than {} levels", MAX_DEPTH))
+    }
+
+    fn array(&mut self, info: u8, depth: usize) -> Result<JsonData, JsonError> {
+        let mut arr = Vec::new();
+        if let Some(len) = self.length(info)? {
+            for _ in 0..len {
+                arr.push(self.decode(depth)?);
+            }
+        } else {
+            while !self.peek_break()? {
+                arr.push(self.decode(depth)?);
+            }
+        }
+        Ok(JsonData::array(arr))
+    }
+
+    fn map(&mut self, info: u8, depth: usize) -> Result<JsonData, JsonError> {
+        let mut map = Map::default();
+        let len = self.length(info)?;
+        let mut count = 0;
+        loop {
+            let done = match len {
+                Some(len) => count == len,
+                None => self.peek_break()?,
+            };
+            if done {
+                break;
+            }
+            let key = self.take(1)?[0];
+            if key >> 5 != TEXT {
+                return Err(self.error("Map keys must be text"));
+            }
+            let key = self.text(TEXT, key & 0x1f)?;
+            map.insert(key, self.decode(depth)?);
+            count += 1;
+        }
+        Ok(JsonData::object(map))
+    }
+
+    // The items that hold no other item
+    fn scalar(&mut self, major: u8, info: u8) -> Result<JsonData, JsonError> {
+        match major {
+            UNSIGNED => {
+                let n = self
+                    .argument(info)?
+                    .ok_or_else(|| self.error("Invalid integer"))?;
+                Ok(i64::try_from(n)
+                    .map(JsonData::Integer)
+                    .unwrap_or_else(|_| large_integer(n as i128)))
+            }
+            NEGATIVE => {
+                let n = self
+                    .argument(info)?
+                    .ok_or_else(|| self.error("Invalid integer"))?;
+                Ok(i64::try_from(n)
+                    .map(|n| JsonData::Integer(-1 - n))
+                    .unwrap_or_else(|_| large_integer(-1 - n as i128)))
+            }
+            BYTES => Err(self.error("Byte strings have no json representation")),
+            TEXT => Ok(JsonData::Str(self.text(TEXT, info)?.into())),
+            SIMPLE => match info {
+                20 => Ok(JsonData::Bool(false)),
+                21 => Ok(JsonData::Bool(true)),
+                22 | 23 => Ok(JsonData::Null),
+                25 => Ok(float_or_null(f16_to_f64(u16::from_be_bytes(
+                    self.take(2)?.try_into().unwrap(),
+                )))),
+                26 => Ok(float_or_null(
+                    f32::from_be_bytes(self.take(4)?.try_into().unwrap()) as f64,
+                )),
+                27 => Ok(float_or_null(f64::from_be_bytes(
+                    self.take(8)?.try_into().unwrap(),
+                ))),
+                _ => Err(self.error("Unsupported simple value")),
+            },
+            _ => unreachable!(),
+        }
+    }
+}
+
+// An integer outside of the range of `i64`, a float without the `bigint` feature
+fn large_integer(n: i128) -> JsonData {
+    #[cfg(feature = "bigint")]
+    return JsonData::BigInt(n.into());
+    #[cfg(not(feature = "bigint"))]
+    JsonData::Float(n as f64)
+}
+
+fn f16_to_f64(half: u16) -> f64 {
+    let exp = (half >> 10) & 0x1f;
+    let mant = (half & 0x3ff) as f64;
+    let value = match exp {
+        0 => mant * 2f64.powi(-24),
+        31 if mant == 0.0 => f64::INFINITY,
+        31 => f64::NAN,
+        exp => (mant + 1024.0) * 2f64.powi(exp as i32 - 25),
+    };
+    if half & 0x8000 != 0 {
+        -value
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json::{Array, Json, JsonError, Object, Value, MAX_DEPTH};
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn encode_known_values() {
+        assert_eq!(hex("00"), Json::<Value>::new("0").unwrap().to_cbor());
+        assert_eq!(hex("1864"), Json::<Value>::new("100").unwrap().to_cbor());
+        assert_eq!(
+            hex("3903e7"),
+            Json::<Value>::new("-1000").unwrap().to_cbor()
+        );
+        assert_eq!(
+            hex("fb3ff199999999999a"),
+            Json::<Value>::new("1.1").unwrap().to_cbor()
+        );
+        assert_eq!(hex("f6"), Json::<Value>::new("null").unwrap().to_cbor());
+        assert_eq!(hex("f5"), Json::<Value>::new("true").unwrap().to_cbor());
+        assert_eq!(hex("6161"), Json::<Value>::new("\"a\"").unwrap().to_cbor());
+        assert_eq!(
+            hex("83010203"),
+            Json::<Array>::new("[1, 2, 3]").unwrap().to_cbor()
+        );
+        assert_eq!(
+            hex("a1616101"),
+            Json::<Object>::new("{\"a\": 1}").unwrap().to_cbor()
+        );
+    }
+
+    #[test]
+    fn decode_known_values() {
+        assert_eq!(
+            Ok(-1000),
+            Json::<Value>::from_cbor(&hex("3903e7")).unwrap().get_i64()
+        );
+        assert_eq!(
+            Ok(1.5),
+            Json::<Value>::from_cbor(&hex("f93e00")).unwrap().get_f64()
+        );
+        assert_eq!(
+            Ok("streaming"),
+            Json::<Value>::from_cbor(&hex("7f657374726561646d696e67ff"))
+                .unwrap()
+                .get_string()
+        );
+        let arr = Json::<Array>::from_cbor(&hex("9f018202039f0405ffff")).unwrap();
+        assert_eq!(Ok(3), arr.len());
+        assert_eq!(
+            Ok(5),
+            arr.get_array(2).unwrap().get_value(1).unwrap().get_i64()
+        );
+        // tag 1 (epoch time) is dropped
+        assert_eq!(
+            Ok(1363896240),
+            Json::<Value>::from_cbor(&hex("c11a514b67b0"))
+                .unwrap()
+                .get_i64()
+        );
+    }
+
+    #[test]
+    fn roundtrip_test_data() {
+        let json = Json::<Object>::from_file("src/__test_data__/test_data2.json").unwrap();
+        let decoded = Json::<Object>::from_cbor(&json.to_cbor()).unwrap();
+        assert_eq!(json.data, decoded.data);
+        let json = Json::<Object>::from_file("src/__test_data__/test_data_my_1.json").unwrap();
+        let decoded = Json::<Object>::from_cbor(&json.to_cbor()).unwrap();
+        assert_eq!(json.data, decoded.data);
+    }
+
+    #[test]
+    fn invalid_cbor() {
+        assert!(matches!(
+            Json::<Value>::from_cbor(&hex("1a0000")),
+            Err(JsonError::InvalidCbor(_))
+        ));
+        assert!(matches!(
+            Json::<Value>::from_cbor(&hex("0000")),
+            Err(JsonError::InvalidCbor(_))
+        ));
+        assert!(matches!(
+            Json::<Object>::from_cbor(&hex("a10101")),
+            Err(JsonError::InvalidCbor(_))
+        ));
+        assert!(matches!(
+            Json::<Value>::from_cbor(&hex("4100")),
+            Err(JsonError::InvalidCbor(_))
+        ));
+        let mut nested = vec![0x81; 1_000_000];
+        nested.push(0x00);
+        assert!(matches!(
+            Json::<Array>::from_cbor(&nested),
+            Err(JsonError::InvalidCbor(msg)) if msg.starts_with("Nested deeper than 512 levels")
+        ));
+        assert!(Json::<Value>::from_cbor(&[0xc0; 100_000]).is_err());
+        let mut deepest = vec![0x81; MAX_DEPTH];
+        deepest.push(0x00);
+        assert!(Json::<Array>::from_cbor(&deepest).is_ok());
+    }
+}
@@ -0,0 +1,165 @@
+use crate::format::{write_data, write_str, FormatOptions};
+use crate::json::{AnyJson, Json, JsonState};
+use crate::parser::JsonData;
+
+// The ANSI escape sequences put in front of each kind of token, an empty string leaves
+// that kind uncolored
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorScheme {
+    pub key: String,
+    pub string: String,
+    pub number: String,
+    pub literal: String,
+    pub punctuation: String,
+}
+
+const RESET: &str = "\x1b[0m";
+
+impl Default for ColorScheme {
+    fn default() -> Self {
+        Self {
+            key: "\x1b[1;34m".to_string(),
+            string: "\x1b[32m".to_string(),
+            number: "\x1b[33m".to_string(),
+            literal: "\x1b[35m".to_string(),
+            punctuation: "\x1b[2m".to_string(),
+        }
+    }
+}
+
+impl ColorScheme {
+    // A scheme without any colors, the output is the same as `FormatOptions::pretty(2)`
+    pub fn plain() -> Self {
+        Self {
+            key: String::new(),
+            string: String::new(),
+            number: String::new(),
+            literal: String::new(),
+            punctuation: String::new(),
+        }
+    }
+}
+
+fn push_colored(out: &mut String, color: &str, text: &str) {
+    if color.is_empty() {
+        out.push_str(text);
+    } else {
+        out.push_str(color);
+        out.push_str(text);
+        out.push_str(RESET);
+    }
+}
+
+fn newline(out: &mut String, depth: usize) {
+    out.push('\n');
+    for _ in 0..depth {
+        out.push_str("  ");
+    }
+}
+
+fn write_colored(data: &JsonData, out: &mut String, depth: usize, scheme: &ColorScheme) {
+    let options = FormatOptions::pretty(2);
+    match data {
+        JsonData::Array(arr) if arr.is_empty() => push_colored(out, &scheme.punctuation, "[]"),
+        JsonData::Object(map) if map.is_empty() => push_colored(out, &scheme.punctuation, "{}"),
+        JsonData::Array(arr) => {
+            push_colored(out, &scheme.punctuation, "[");
+            for (i, data) in arr.iter().enumerate() {
+                if i > 0 {
+                    push_colored(out, &scheme.punctuation, ",");
+                }
+                newline(out, depth + 1);
+                write_colored(data, out, depth + 1, scheme);
+            }
+            newline(out, depth);
+            push_colored(out, &scheme.punctuation, "]");
+        }
+        JsonData::Object(map) => {
+            push_colored(out, &scheme.punctuation, "{");
+            for (i, (key, data)) in map.iter().enumerate() {
+                if i > 0 {
+                    push_colored(out, &scheme.punctuation, ",");
+                }
+                newline(out, depth + 1);
+                let mut text = String::from('"');
+                write_str(key, &mut text, &options);
+                text.push('"');
+                push_colored(out, &scheme.key, &text);
+                push_colored(out, &scheme.punctuation, ":");
+                out.push(' ');
+                write_colored(data, out, depth + 1, scheme);
+            }
+            newline(out, depth);
+            push_colored(out, &scheme.punctuation, "}");
+        }
+        scalar => {
+            let color = match scalar {
+                JsonData::Str(_) => &scheme.string,
+                JsonData::Null | JsonData::Bool(_) => &scheme.literal,
+                // Non-finite floats are written as `null`
+                JsonData::Float(f) if !f.is_finite() => &scheme.literal,
+                _ => &scheme.number,
+            };
+            let mut text = String::new();
+            write_data(scalar, &mut text, depth, &options);
+            push_colored(out, color, &text);
+        }
+    }
+}
+
+impl<S: JsonState> Json<S> {
+    // Pretty-prints with two spaces of indentation, each token wrapped in the color
+    // `scheme` gives its kind, for printing to a terminal
+    pub fn to_string_colored(&self, scheme: &ColorScheme) -> String {
+        let mut out = String::new();
+        write_colored(&self.data, &mut out, 0, scheme);
+        out
+    }
+}
+
+impl AnyJson {
+    pub fn to_string_colored(&self, scheme: &ColorScheme) -> String {
+        let mut out = String::new();
+        write_colored(self.data(), &mut out, 0, scheme);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::FormatOptions;
+    use crate::json::{Array, Object, Value};
+
+    #[test]
+    fn colored_tokens() {
+        let scheme = ColorScheme {
+            key: "<k>".to_string(),
+            string: "<s>".to_string(),
+            number: "<n>".to_string(),
+            literal: "<l>".to_string(),
+            punctuation: String::new(),
+        };
+        let json = Json::<Object>::new("{\"a\": [1, 2.5, \"x\", true, null, {}]}").unwrap();
+        let expected = "{\n  <k>\"a\"\x1b[0m: [\n    <n>1\x1b[0m,\n    <n>2.5\x1b[0m,\n    <s>\"x\"\x1b[0m,\n    <l>true\x1b[0m,\n    <l>null\x1b[0m,\n    {}\n  ]\n}";
+        assert_eq!(expected, json.to_string_colored(&scheme));
+        assert_eq!(
+            "<s>\"\\n\"\x1b[0m",
+            Json::<Value>::new("\"\\n\"")
+                .unwrap()
+                .to_string_colored(&scheme)
+        );
+    }
+
+    #[test]
+    fn plain_scheme_is_pretty() {
+        let json = Json::<Array>::new("[{\"a\": [1, {\"b\": null}]}, \"c\", []]").unwrap();
+        assert_eq!(
+            json.to_formatted_string(&FormatOptions::pretty(2)),
+            json.to_string_colored(&ColorScheme::plain())
+        );
+        assert!(json
+            .to_string_colored(&ColorScheme::default())
+            .contains("\x1b[32m\"c\"\x1b[0m"));
+    }
+}
@@ -0,0 +1,299 @@
+use crate::json::{Array, Json, JsonError, JsonType, Object};
+use crate::keyset::KeySet;
+use crate::parser::{JsonData, Map, Shared};
+use crate::pointer::{parse_pointer, resolve};
+use crate::small::SmallString;
+
+// The values of one key in every record and which of them were there. A missing or null
+// value is the default of `T` and is not `valid`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Typed<T> {
+    pub values: Vec<T>,
+    pub valid: Vec<bool>,
+}
+
+impl<T: Default> Typed<T> {
+    fn nulls(len: usize) -> Self {
+        Self {
+            values: (0..len).map(|_| T::default()).collect(),
+            valid: vec![false; len],
+        }
+    }
+
+    fn push(&mut self, value: Option<T>) {
+        self.valid.push(value.is_some());
+        self.values.push(value.unwrap_or_default());
+    }
+}
+
+// A column of the type its values have. Integers and floats mixed make a float column and a
+// column with only missing and null values is `Null`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Column {
+    Null(usize),
+    Bool(Typed<bool>),
+    Integer(Typed<i64>),
+    Float(Typed<f64>),
+    String(Typed<String>),
+}
+
+impl Column {
+    pub fn len(&self) -> usize {
+        match self {
+            Column::Null(len) => *len,
+            Column::Bool(column) => column.valid.len(),
+            Column::Integer(column) => column.valid.len(),
+            Column::Float(column) => column.valid.len(),
+            Column::String(column) => column.valid.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn push_null(&mut self) {
+        match self {
+            Column::Null(len) => *len += 1,
+            Column::Bool(column) => column.push(None),
+            Column::Integer(column) => column.push(None),
+            Column::Float(column) => column.push(None),
+            Column::String(column) => column.push(None),
+        }
+    }
+
+    // `data` is the value of the column in the next record, at `path`
+    fn push(&mut self, data: &JsonData, path: &str) -> Result<(), JsonError> {
+        let data = data.unraw();
+        if let Column::Null(len) = *self {
+            *self = match JsonType::of(data) {
+                JsonType::Null => Column::Null(len),
+                JsonType::Bool => Column::Bool(Typed::nulls(len)),
+                JsonType::Integer if matches!(data, JsonData::Integer(_)) => {
+                    Column::Integer(Typed::nulls(len))
+                }
+                JsonType::Integer | JsonType::Float => Column::Float(Typed::nulls(len)),
+                _ => Column::String(Typed::nulls(len)),
+            };
+        }
+        if let Column::Integer(column) = self {
+            if data.as_f64().is_some() && !matches!(data, JsonData::Integer(_)) {
+                *self = Column::Float(Typed {
+                    values: column.values.iter().map(|i| *i as f64).collect(),
+                    valid: std::mem::take(&mut column.valid),
+                });
+            }
+        }
+        match (&mut *self, data) {
+            (_, JsonData::Null) => self.push_null(),
+            (Column::Bool(column), JsonData::Bool(b)) => column.push(Some(*b)),
+            (Column::Integer(column), JsonData::Integer(i)) => column.push(Some(*i)),
+            (Column::Float(column), data) if data.as_f64().is_some() => column.push(data.as_f64()),
+            (Column::String(column), JsonData::Str(s)) => column.push(Some(s.to_string())),
+            (Column::Bool(_), _) => {
+                return Err(JsonError::incorrect_type(path, &[JsonType::Bool], data))
+            }
+            (Column::Integer(_), _) | (Column::Float(_), _) => {
+                let expected = [JsonType::Integer, JsonType::Float];
+                return Err(JsonError::incorrect_type(path, &expected, data));
+            }
+            (Column::String(_), _) | (Column::Null(_), _) => {
+                return Err(JsonError::incorrect_type(path, &[JsonType::String], data))
+            }
+        }
+        Ok(())
+    }
+}
+
+// The columns of `keys` in an array of records, in the order of `keys`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Columns {
+    keys: KeySet,
+    columns: Vec<Column>,
+}
+
+impl Columns {
+    pub fn get(&self, key: &str) -> Option<&Column> {
+        self.keys.index(key).map(|index| &self.columns[index])
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = (&str, &Column)> {
+        self.keys.keys().zip(&self.columns)
+    }
+
+    // The number of records
+    pub fn rows(&self) -> usize {
+        self.columns.first().map_or(0, Column::len)
+    }
+}
+
+impl Json<Array> {
+    // Reads `keys` out of every object in the array in one pass. Each column gets the type
+    // of its values, a value of another type or an element that is not an object is an
+    // error.
+    pub fn to_columns(&self, keys: &[&str]) -> Result<Columns, JsonError> {
+        let keys = KeySet::new(keys.iter().copied());
+        let mut columns = vec![Column::Null(0); keys.len()];
+        let len = self.len()?;
+        for i in 0..len {
+            let record: Json<Object> = self.get_object(i)?;
+            for (column, value) in columns.iter_mut().zip(keys.values(&record)) {
+                match value {
+                    Some(value) => column.push(value.data(), value.path())?,
+                    None => column.push_null(),
+                }
+            }
+        }
+        Ok(Columns { keys, columns })
+    }
+
+    // A copy of an array of objects with only the members `fields` name, like SQL's
+    // `SELECT`. A field that starts with `/` is a json pointer and its value is put at the
+    // same place in the copy, so `/user/name` keeps `{"user": {"name": ...}}`. Values that
+    // are not there are left out.
+    pub fn project(&self, fields: &[&str]) -> Result<Json<Array>, JsonError> {
+        let fields = fields
+            .iter()
+            .map(|field| {
+                if field.starts_with('/') {
+                    parse_pointer(field)
+                } else {
+                    Ok(vec![field.to_string()])
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let len = self.len()?;
+        let mut records = Vec::with_capacity(len);
+        for i in 0..len {
+            let record: Json<Object> = self.get_object(i)?;
+            let mut map = Box::<Map<SmallString, Shared<JsonData>>>::default();
+            for tokens in &fields {
+                if let Ok(value) = resolve(&record.data, tokens) {
+                    insert_at(&mut map, tokens, Shared::clone(value));
+                }
+            }
+            records.push(Shared::new(JsonData::Object(map)));
+        }
+        Ok(Json::from_data(JsonData::Array(records)))
+    }
+}
+
+// Puts `value` at `tokens` in `map`, with the objects on the way
+fn insert_at(
+    map: &mut Map<SmallString, Shared<JsonData>>,
+    tokens: &[String],
+    value: Shared<JsonData>,
+) {
+    let (first, rest) = tokens.split_first().expect("THIS SHOULD NEVER PANIC");
+    let key = SmallString::from(first);
+    if rest.is_empty() {
+        map.insert(key, value);
+        return;
+    }
+    let inner = map
+        .entry(key)
+        .or_insert_with(|| Shared::new(JsonData::Object(Default::default())));
+    if let JsonData::Object(inner) = Shared::make_mut(inner) {
+        insert_at(inner, rest, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typed_columns_with_nulls() {
+        let records: Json<Array> = Json::new(
+            "[{\"id\": 1, \"price\": 2, \"name\": \"a\\\"b\", \"ok\": true},
+              {\"id\": 2, \"price\": 2.5, \"name\": null},
+              {\"id\": 3, \"ok\": false, \"extra\": [1]}]",
+        )
+        .unwrap();
+        let columns = records
+            .to_columns(&["id", "price", "name", "ok", "ts"])
+            .unwrap();
+        assert_eq!(3, columns.rows());
+        assert_eq!(
+            Some(&Column::Integer(Typed {
+                values: vec![1, 2, 3],
+                valid: vec![true, true, true]
+            })),
+            columns.get("id")
+        );
+        assert_eq!(
+            Some(&Column::Float(Typed {
+                values: vec![2.0, 2.5, 0.0],
+                valid: vec![true, true, false]
+            })),
+            columns.get("price")
+        );
+        assert_eq!(
+            Some(&Column::String(Typed {
+                values: vec!["a\"b".to_string(), String::new(), String::new()],
+                valid: vec![true, false, false]
+            })),
+            columns.get("name")
+        );
+        assert_eq!(
+            Some(&Column::Bool(Typed {
+                values: vec![true, false, false],
+                valid: vec![true, false, true]
+            })),
+            columns.get("ok")
+        );
+        assert_eq!(Some(&Column::Null(3)), columns.get("ts"));
+        assert_eq!(
+            vec!["id", "price", "name", "ok", "ts"],
+            columns.columns().map(|(key, _)| key).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn mismatched_types() {
+        let records: Json<Array> = Json::new("[{\"id\": 1}, {\"id\": \"2\"}, 3]").unwrap();
+        assert_eq!(
+            Err(JsonError::IncorrectType {
+                path: "/1/id".to_string(),
+                expected: vec![JsonType::Integer, JsonType::Float],
+                found: JsonType::String
+            }),
+            records.to_columns(&["id"]).map(|_| ())
+        );
+        let records: Json<Array> = Json::new("[{\"id\": 1}, 3]").unwrap();
+        assert_eq!(
+            Err(JsonError::IncorrectType {
+                path: "/1".to_string(),
+                expected: vec![JsonType::Object],
+                found: JsonType::Integer
+            }),
+            records.to_columns(&["id"]).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn project_fields() {
+        let records: Json<Array> = Json::new(
+            "[{\"id\": 1, \"name\": \"a\", \"user\": {\"name\": \"x\", \"age\": 3}, \"a/b\": 1},
+              {\"id\": 2, \"user\": 5, \"extra\": true}]",
+        )
+        .unwrap();
+        assert_eq!(
+            Json::<Array>::new(
+                "[{\"id\": 1, \"name\": \"a\", \"user\": {\"name\": \"x\"}, \"a/b\": 1}, {\"id\": 2}]"
+            )
+            .unwrap(),
+            records
+                .project(&["id", "name", "/user/name", "/a~1b"])
+                .unwrap()
+        );
+        assert_eq!(
+            Json::<Array>::new("[{}, {}]").unwrap(),
+            records.project(&[]).unwrap()
+        );
+        assert!(matches!(
+            Json::<Array>::new("[1]").unwrap().project(&["id"]),
+            Err(JsonError::IncorrectType { .. })
+        ));
+    }
+}
@@ -0,0 +1,753 @@
+use crate::escape::unquote;
+use crate::filter::compare;
+use crate::format::{event_error, syntax_error, write_data, FormatOptions};
+use crate::json::{AnyJson, Json, JsonError, JsonState, MAX_DEPTH};
+use crate::lexer::Token;
+use crate::parser::{JsonData, ParseError};
+use crate::pointer::pointer_token;
+use crate::stream::{scalar_data, DataBuilder, Event, Events, ReadChars};
+use std::cell::Cell;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::io::{self, Read};
+use std::rc::Rc;
+
+// Where a value is, as keys and array indexes separated by `.`. A `*` stands for any one
+// key or index and `**` for any number of them, so `**.timestamp` is every `timestamp` and
+// `items.*.id` the `id` of every item. Keys with a `.` in them can't be matched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathPattern {
+    segments: Vec<String>,
+}
+
+impl PathPattern {
+    pub fn new(pattern: &str) -> Self {
+        Self {
+            segments: pattern.split('.').map(String::from).collect(),
+        }
+    }
+
+    // Whether the value at `path`, its unescaped keys and indexes from the root, matches
+    pub fn matches<T: AsRef<str>>(&self, path: &[T]) -> bool {
+        fn matches<T: AsRef<str>>(segments: &[String], path: &[T]) -> bool {
+            match segments.split_first() {
+                None => path.is_empty(),
+                Some((first, rest)) if first == "**" => {
+                    (0..=path.len()).any(|skipped| matches(rest, &path[skipped..]))
+                }
+                Some((first, rest)) => match path.split_first() {
+                    Some((step, path)) => {
+                        (first == "*" || first == step.as_ref()) && matches(rest, path)
+                    }
+                    None => false,
+                },
+            }
+        }
+        matches(&self.segments, path)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EqOptions {
+    ignore_key_order: bool,
+    max_buffered_members: usize,
+    ignore: Vec<PathPattern>,
+}
+
+impl Default for EqOptions {
+    fn default() -> Self {
+        Self {
+            ignore_key_order: true,
+            max_buffered_members: 100_000,
+            ignore: Vec::new(),
+        }
+    }
+}
+
+impl EqOptions {
+    // Objects are only equal when their members come in the same order
+    pub fn ordered_keys(mut self) -> Self {
+        self.ignore_key_order = false;
+        self
+    }
+
+    // How many members of an object that came in a different order on the two sides can be
+    // kept in memory before the comparison gives up
+    pub fn max_buffered_members(mut self, members: usize) -> Self {
+        self.max_buffered_members = members;
+        self
+    }
+
+    // Values at these paths are left out of the comparison, members of objects are equal
+    // whether they are on both sides or only one. Array items are still counted.
+    pub fn ignore(mut self, patterns: &[PathPattern]) -> Self {
+        self.ignore.extend_from_slice(patterns);
+        self
+    }
+
+    fn ignores(&self, path: &[String]) -> bool {
+        self.ignore.iter().any(|pattern| pattern.matches(path))
+    }
+}
+
+struct Side<R: Read> {
+    events: Events<ReadChars<R>>,
+    read_error: Rc<Cell<Option<io::ErrorKind>>>,
+}
+
+impl<R: Read> Side<R> {
+    fn new(reader: R) -> Self {
+        let chars = ReadChars::new(reader);
+        let read_error = chars.error();
+        Self {
+            events: Events::new(chars),
+            read_error,
+        }
+    }
+
+    fn next(&mut self) -> Result<Option<Event>, JsonError> {
+        let event = self
+            .events
+            .next()
+            .transpose()
+            .map_err(|error| event_error(error, self.read_error.get()))?;
+        if let Some(kind) = self.read_error.get() {
+            return Err(JsonError::FileError(kind));
+        }
+        // Nested deeper than `MAX_DEPTH` is an error like for `Json::new`
+        if let Some(Event::StartArray(open) | Event::StartObject(open)) = &event {
+            if self.events.depth() > MAX_DEPTH {
+                return Err(syntax_error(ParseError::TooDeep(open.clone())));
+            }
+        }
+        Ok(event)
+    }
+
+    // Only called where the events say a value has to follow
+    fn value_event(&mut self) -> Result<Event, JsonError> {
+        Ok(self.next()?.expect("THIS SHOULD NEVER PANIC"))
+    }
+
+    fn skip(&mut self, first: &Event) -> Result<(), JsonError> {
+        self.events
+            .skip_value(first)
+            .map_err(|error| event_error(error, self.read_error.get()))
+    }
+
+    // The key of the next member that isn't ignored, `None` at the end of the object
+    fn next_key(
+        &mut self,
+        path: &mut Vec<String>,
+        options: &EqOptions,
+    ) -> Result<Option<String>, JsonError> {
+        loop {
+            let Some(key) = key(self.value_event()?) else {
+                return Ok(None);
+            };
+            path.push(key);
+            let ignored = options.ignores(path);
+            let key = path.pop().expect("THIS SHOULD NEVER PANIC");
+            if !ignored {
+                return Ok(Some(key));
+            }
+            let first = self.value_event()?;
+            self.skip(&first)?;
+        }
+    }
+
+    fn build(&mut self, first: Event) -> Result<JsonData, JsonError> {
+        let mut builder = DataBuilder::default();
+        let mut event = first;
+        loop {
+            let built = builder
+                .push(event)
+                .map_err(|error| event_error(error, self.read_error.get()))?;
+            if let Some(data) = built {
+                return Ok(data);
+            }
+            event = self.value_event()?;
+        }
+    }
+}
+
+fn key(event: Event) -> Option<String> {
+    match event {
+        Event::Key(token) => Some(unquote(token.text)),
+        _ => None,
+    }
+}
+
+fn scalar(token: Token) -> Result<JsonData, JsonError> {
+    scalar_data(token).map_err(|error| event_error(error, None))
+}
+
+// Compares built values like `values_eq`, for the members that had to be kept
+fn data_eq<'a>(
+    a: &'a JsonData,
+    b: &'a JsonData,
+    path: &mut Vec<String>,
+    options: &EqOptions,
+) -> bool {
+    if options.ignore.is_empty() {
+        return compare(a, b).is_eq();
+    }
+    let at = |path: &mut Vec<String>, step: String, a: Option<&JsonData>, b| {
+        path.push(step);
+        let equal = options.ignores(path)
+            || matches!((a, b), (Some(a), Some(b)) if data_eq(a, b, path, options));
+        path.pop();
+        equal
+    };
+    match (a, b) {
+        (JsonData::Array(arr_a), JsonData::Array(arr_b)) => {
+            arr_a.len() == arr_b.len()
+                && arr_a
+                    .iter()
+                    .zip(arr_b.iter())
+                    .enumerate()
+                    .all(|(i, (a, b))| at(path, i.to_string(), Some(a), Some(b)))
+        }
+        (JsonData::Object(map_a), JsonData::Object(map_b)) => {
+            let keys: HashSet<&str> = map_a
+                .keys()
+                .chain(map_b.keys())
+                .map(|key| key.as_str())
+                .collect();
+            keys.into_iter().all(|key| {
+                at(
+                    path,
+                    key.to_string(),
+                    map_a.get(key).map(|data| data.as_ref()),
+                    map_b.get(key).map(|data| data.as_ref()),
+                )
+            })
+        }
+        _ => compare(a, b).is_eq(),
+    }
+}
+
+// What comes next when comparing two documents as they are read
+enum Next {
+    // The first events of the next two values to compare
+    Values(Event, Event),
+    // The array or object that was being read on both sides ended, with whether it was
+    // the same on both sides
+    Done(bool),
+}
+
+// An array or object both sides are in, with the index of the next item or the members
+// that are not compared yet
+enum Open {
+    Array(usize),
+    Object(Members),
+}
+
+// Compares the values `first_a` and `first_b` start as they are read. The arrays and
+// objects they are in are kept on a stack instead of recursing, with the key or index of
+// each value inside them in `path`.
+fn values_eq<A: Read, B: Read>(
+    a: &mut Side<A>,
+    b: &mut Side<B>,
+    first_a: Event,
+    first_b: Event,
+    options: &EqOptions,
+) -> Result<bool, JsonError> {
+    let mut path = Vec::new();
+    let mut open = Vec::new();
+    let mut next = Next::Values(first_a, first_b);
+    loop {
+        let (event_a, event_b) = match next {
+            Next::Values(event_a, event_b) => (event_a, event_b),
+            Next::Done(equal) => return Ok(equal),
+        };
+        let opened = if options.ignores(&path) {
+            a.skip(&event_a)?;
+            b.skip(&event_b)?;
+            false
+        } else {
+            match (event_a, event_b) {
+                (Event::Scalar(token_a), Event::Scalar(token_b)) => {
+                    if compare(&scalar(token_a)?, &scalar(token_b)?).is_ne() {
+                        return Ok(false);
+                    }
+                    false
+                }
+                (Event::StartArray(_), Event::StartArray(_)) => {
+                    open.push(Open::Array(0));
+                    true
+                }
+                (Event::StartObject(_), Event::StartObject(_)) => {
+                    open.push(Open::Object(Members::default()));
+                    true
+                }
+                _ => return Ok(false),
+            }
+        };
+        if !opened && !open.is_empty() {
+            path.pop();
+        }
+        next = next_values(a, b, &mut open, &mut path, options)?;
+    }
+}
+
+// The next values to compare after the last one was read, the arrays and objects that end
+// on both sides before them are popped off `open`
+fn next_values<A: Read, B: Read>(
+    a: &mut Side<A>,
+    b: &mut Side<B>,
+    open: &mut Vec<Open>,
+    path: &mut Vec<String>,
+    options: &EqOptions,
+) -> Result<Next, JsonError> {
+    loop {
+        let next = match open.last_mut() {
+            None => return Ok(Next::Done(true)),
+            Some(Open::Array(index)) => next_item(a, b, index, path)?,
+            Some(Open::Object(members)) => members.next(a, b, path, options)?,
+        };
+        match next {
+            Next::Done(true) => {
+                open.pop();
+                if !open.is_empty() {
+                    path.pop();
+                }
+            }
+            next => return Ok(next),
+        }
+    }
+}
+
+fn next_item<A: Read, B: Read>(
+    a: &mut Side<A>,
+    b: &mut Side<B>,
+    index: &mut usize,
+    path: &mut Vec<String>,
+) -> Result<Next, JsonError> {
+    let (item_a, item_b) = (a.value_event()?, b.value_event()?);
+    let (end_a, end_b) = (
+        matches!(item_a, Event::EndArray(_)),
+        matches!(item_b, Event::EndArray(_)),
+    );
+    if end_a || end_b {
+        return Ok(Next::Done(end_a && end_b));
+    }
+    path.push(index.to_string());
+    *index += 1;
+    Ok(Next::Values(item_a, item_b))
+}
+
+// The members of an object on both sides. Members that come in the same order are
+// compared as they are read, the others are built and kept until the member with the same
+// key shows up on the other side.
+#[derive(Default)]
+struct Members {
+    pending_a: HashMap<String, JsonData>,
+    pending_b: HashMap<String, JsonData>,
+    done_a: bool,
+    done_b: bool,
+}
+
+impl Members {
+    fn next<A: Read, B: Read>(
+        &mut self,
+        a: &mut Side<A>,
+        b: &mut Side<B>,
+        path: &mut Vec<String>,
+        options: &EqOptions,
+    ) -> Result<Next, JsonError> {
+        loop {
+            let key_a = if self.done_a {
+                None
+            } else {
+                a.next_key(path, options)?
+            };
+            let key_b = if self.done_b {
+                None
+            } else {
+                b.next_key(path, options)?
+            };
+            self.done_a = key_a.is_none();
+            self.done_b = key_b.is_none();
+            match (key_a, key_b) {
+                (None, None) => {
+                    return Ok(Next::Done(
+                        self.pending_a.is_empty() && self.pending_b.is_empty(),
+                    ))
+                }
+                (Some(key_a), Some(key_b))
+                    if key_a == key_b
+                        && !self.pending_a.contains_key(&key_a)
+                        && !self.pending_b.contains_key(&key_b) =>
+                {
+                    let (first_a, first_b) = (a.value_event()?, b.value_event()?);
+                    path.push(key_a);
+                    return Ok(Next::Values(first_a, first_b));
+                }
+                _ if !options.ignore_key_order => return Ok(Next::Done(false)),
+                (key_a, key_b) => {
+                    if !self.buffer(a, b, key_a, key_b, path, options)? {
+                        return Ok(Next::Done(false));
+                    }
+                }
+            }
+        }
+    }
+
+    // Builds the values of `key_a` and `key_b`, false when one of them is not the same as
+    // the member with that key that was kept from the other side
+    fn buffer<A: Read, B: Read>(
+        &mut self,
+        a: &mut Side<A>,
+        b: &mut Side<B>,
+        key_a: Option<String>,
+        key_b: Option<String>,
+        path: &mut Vec<String>,
+        options: &EqOptions,
+    ) -> Result<bool, JsonError> {
+        if let Some(key) = key_a {
+            let first = a.value_event()?;
+            let data = a.build(first)?;
+            match self.pending_b.remove(&key) {
+                Some(other) if !member_eq(&data, &other, &key, path, options) => return Ok(false),
+                Some(_) => {}
+                None => {
+                    self.pending_a.insert(key, data);
+                }
+            }
+        }
+        if let Some(key) = key_b {
+            let first = b.value_event()?;
+            let data = b.build(first)?;
+            match self.pending_a.remove(&key) {
+                Some(other) if !member_eq(&data, &other, &key, path, options) => return Ok(false),
+                Some(_) => {}
+                None => {
+                    self.pending_b.insert(key, data);
+                }
+            }
+        }
+        if self.pending_a.len() + self.pending_b.len() > options.max_buffered_members {
+            return Err(JsonError::LimitExceeded(format!(
+                "More than {} members of objects are in a different order on the two sides",
+                options.max_buffered_members
+            )));
+        }
+        Ok(true)
+    }
+}
+
+fn member_eq(
+    a: &JsonData,
+    b: &JsonData,
+    key: &str,
+    path: &mut Vec<String>,
+    options: &EqOptions,
+) -> bool {
+    path.push(key.to_string());
+    let equal = data_eq(a, b, path, options);
+    path.pop();
+    equal
+}
+
+// Compares two json documents as they are read, without building either of them.
+// Numbers are compared by value and strings after unescaping them. Only the members of an
+// object that are in a different order on the two sides are kept in memory, more than
+// `max_buffered_members` of them is a `LimitExceeded` error. Empty inputs and arrays and
+// objects nested deeper than `MAX_DEPTH` are syntax errors like for `Json::new`.
+pub fn streaming_eq<A: Read, B: Read>(a: A, b: B, options: &EqOptions) -> Result<bool, JsonError> {
+    let mut a = Side::new(a);
+    let mut b = Side::new(b);
+    let equal = match (a.next()?, b.next()?) {
+        (Some(first_a), Some(first_b)) => values_eq(&mut a, &mut b, first_a, first_b, options)?,
+        _ => return Err(syntax_error(ParseError::EmptyInput)),
+    };
+    // Reads the rest so trailing garbage is still an error
+    if equal {
+        a.next()?;
+        b.next()?;
+    }
+    Ok(equal)
+}
+
+// A value that is not the same in two jsons, by its json pointer. `actual` and `expected`
+// are the minified values, `None` where a member is missing on that side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Difference {
+    pub pointer: String,
+    pub actual: Option<String>,
+    pub expected: Option<String>,
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pointer = if self.pointer.is_empty() {
+            "(root)"
+        } else {
+            &self.pointer
+        };
+        match (&self.actual, &self.expected) {
+            (Some(actual), Some(expected)) => {
+                write!(f, "{}: expected {}, found {}", pointer, expected, actual)
+            }
+            (None, Some(expected)) => write!(f, "{}: missing, expected {}", pointer, expected),
+            (Some(actual), None) => write!(f, "{}: unexpected {}", pointer, actual),
+            (None, None) => write!(f, "{}", pointer),
+        }
+    }
+}
+
+fn minified(data: &JsonData) -> String {
+    let mut out = String::new();
+    write_data(data, &mut out, 0, &FormatOptions::minified());
+    out
+}
+
+// The differences between the values at `pointer`, members of objects in key order
+fn diff_data(actual: &JsonData, expected: &JsonData, pointer: &str, out: &mut Vec<Difference>) {
+    match (actual, expected) {
+        (JsonData::Array(arr_a), JsonData::Array(arr_e)) if arr_a.len() == arr_e.len() => {
+            for (i, (a, e)) in arr_a.iter().zip(arr_e.iter()).enumerate() {
+                diff_data(a, e, &format!("{}/{}", pointer, i), out);
+            }
+        }
+        (JsonData::Object(map_a), JsonData::Object(map_e)) => {
+            let keys: BTreeSet<&str> = map_a
+                .keys()
+                .chain(map_e.keys())
+                .map(|key| key.as_str())
+                .collect();
+            for key in keys {
+                let pointer = format!("{}/{}", pointer, pointer_token(key));
+                match (map_a.get(key), map_e.get(key)) {
+                    (Some(a), Some(e)) => diff_data(a, e, &pointer, out),
+                    (a, e) => out.push(Difference {
+                        pointer,
+                        actual: a.map(|a| minified(a)),
+                        expected: e.map(|e| minified(e)),
+                    }),
+                }
+            }
+        }
+        _ if compare(actual, expected).is_eq() => {}
+        _ => out.push(Difference {
+            pointer: pointer.to_string(),
+            actual: Some(minified(actual)),
+            expected: Some(minified(expected)),
+        }),
+    }
+}
+
+impl<S: JsonState> Json<S> {
+    // Like `==` but the values at the paths of `ignore` are left out, see `EqOptions::ignore`
+    pub fn eq_ignoring<T: JsonState>(&self, other: &Json<T>, ignore: &[PathPattern]) -> bool {
+        let options = EqOptions::default().ignore(ignore);
+        data_eq(&self.data, &other.data, &mut Vec::new(), &options)
+    }
+
+    // Where this json is not the same as `expected`, compared like `streaming_eq`. Arrays of
+    // different lengths are one difference.
+    pub fn differences<T: JsonState>(&self, expected: &Json<T>) -> Vec<Difference> {
+        let mut out = Vec::new();
+        diff_data(&self.data, &expected.data, "", &mut out);
+        out
+    }
+}
+
+// Used by `assert_json_eq!`
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_json_eq(actual: &str, expected: &str) {
+    let parse = |side: &str, text: &str| {
+        AnyJson::parse(text)
+            .unwrap_or_else(|error| panic!("The {} json is not valid: {}\n{}", side, error, text))
+    };
+    let (actual, expected) = (parse("actual", actual), parse("expected", expected));
+    let mut differences = Vec::new();
+    diff_data(actual.data(), expected.data(), "", &mut differences);
+    if differences.is_empty() {
+        return;
+    }
+    let mut message = String::from("The jsons are not the same:");
+    for difference in differences {
+        message.push_str(&format!("\n  {}", difference));
+    }
+    panic!("{}", message);
+}
+
+// Panics unless `actual` and `expected` are the same json whatever the order of the
+// members of their objects, listing the values that differ by their json pointer. Both
+// are anything that displays as json, like a `Json` or a `&str`.
+#[macro_export]
+macro_rules! assert_json_eq {
+    ($actual:expr, $expected:expr $(,)?) => {
+        $crate::compare::assert_json_eq(&$actual.to_string(), &$expected.to_string())
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{Array, Object};
+
+    fn eq(a: &str, b: &str) -> Result<bool, JsonError> {
+        streaming_eq(a.as_bytes(), b.as_bytes(), &EqOptions::default())
+    }
+
+    #[test]
+    fn equal_documents() {
+        assert_eq!(
+            Ok(true),
+            eq("{\"a\": [1, 2.0, \"x\"]}", "{\"a\":[1.0,2,\"\\u0078\"]}")
+        );
+        assert_eq!(
+            Ok(true),
+            eq(
+                "{\"a\": 1, \"b\": {\"c\": [true], \"d\": null}, \"e\": 3}",
+                "{\"e\": 3, \"b\": {\"d\": null, \"c\": [true]}, \"a\": 1}"
+            )
+        );
+        assert_eq!(Ok(false), eq("{\"a\": 1}", "{\"a\": 1, \"b\": 2}"));
+        assert_eq!(
+            Ok(false),
+            eq("{\"a\": 1, \"b\": 2}", "{\"b\": 2, \"a\": 3}")
+        );
+        assert_eq!(Ok(false), eq("[1, 2]", "[1, 2, 3]"));
+        assert_eq!(Ok(false), eq("[1]", "{}"));
+        assert_eq!(Ok(false), eq("\"1\"", "1"));
+    }
+
+    #[test]
+    fn options_and_errors() {
+        let a = "{\"a\": 1, \"b\": 2}";
+        let b = "{\"b\": 2, \"a\": 1}";
+        assert_eq!(
+            Ok(false),
+            streaming_eq(
+                a.as_bytes(),
+                b.as_bytes(),
+                &EqOptions::default().ordered_keys()
+            )
+        );
+        let error = streaming_eq(
+            a.as_bytes(),
+            b.as_bytes(),
+            &EqOptions::default().max_buffered_members(1),
+        )
+        .unwrap_err();
+        assert_eq!(
+            JsonError::LimitExceeded(String::from(
+                "More than 1 members of objects are in a different order on the two sides"
+            )),
+            error
+        );
+        assert_eq!("E0021", error.code());
+        assert!(matches!(
+            eq("[1,", "[1,"),
+            Err(JsonError::InvalidJsonSyntax(_))
+        ));
+        assert!(matches!(
+            eq("[1] x", "[1]"),
+            Err(JsonError::InvalidJsonSyntax(_))
+        ));
+        let empty = Err(JsonError::InvalidJsonSyntax(String::from("Empty input")));
+        assert_eq!(empty, eq("", "  "));
+        assert_eq!(empty, eq("1", ""));
+        assert_eq!(empty, eq(" ", "1"));
+    }
+
+    #[test]
+    fn nesting_limit() {
+        let arrays = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        let objects = |depth: usize| format!("{}1{}", "{\"a\":".repeat(depth), "}".repeat(depth));
+        assert_eq!(Ok(true), eq(&arrays(MAX_DEPTH), &arrays(MAX_DEPTH)));
+        assert_eq!(Ok(true), eq(&objects(MAX_DEPTH), &objects(MAX_DEPTH)));
+        assert!(eq(&arrays(MAX_DEPTH + 1), &arrays(MAX_DEPTH + 1)).is_err());
+        assert!(eq(&objects(200_000), &objects(200_000)).is_err());
+    }
+
+    #[test]
+    fn ignored_paths() {
+        let a = "{\"id\": 1, \"meta\": {\"timestamp\": 5, \"request_id\": \"x\"}, \"items\": [{\"timestamp\": [1], \"v\": 1}]}";
+        let b =
+            "{\"items\": [{\"v\": 1, \"timestamp\": {}}], \"meta\": {\"timestamp\": 6}, \"id\": 1}";
+        let ignore = [
+            PathPattern::new("**.timestamp"),
+            PathPattern::new("meta.request_id"),
+        ];
+        let options = EqOptions::default().ignore(&ignore);
+        assert_eq!(Ok(false), eq(a, b));
+        assert_eq!(Ok(true), streaming_eq(a.as_bytes(), b.as_bytes(), &options));
+        let json_a = Json::<Object>::new(a).unwrap();
+        let json_b = Json::<Object>::new(b).unwrap();
+        assert!(json_a != json_b);
+        assert!(json_a.eq_ignoring(&json_b, &ignore));
+        assert!(!json_a.eq_ignoring(&json_b, &ignore[..1]));
+        let items = [PathPattern::new("*.*.v")];
+        assert!(Json::<Array>::new("[[{\"v\": 1}], {\"b\": 2}]")
+            .unwrap()
+            .eq_ignoring(
+                &Json::<Array>::new("[[{\"v\": 2}], {\"b\": 2}]").unwrap(),
+                &items
+            ));
+        assert!(!Json::<Array>::new("[1, 2]").unwrap().eq_ignoring(
+            &Json::<Array>::new("[1]").unwrap(),
+            &[PathPattern::new("1")]
+        ));
+        assert_eq!(
+            Ok(true),
+            streaming_eq(
+                "[1, 2]".as_bytes(),
+                "{\"a\": 1}".as_bytes(),
+                &EqOptions::default().ignore(&[PathPattern::new("**")])
+            )
+        );
+    }
+
+    #[test]
+    fn differences() {
+        let actual = Json::<Object>::new(
+            "{\"a\": [1, 2.0, {\"b/c\": true}], \"d\": \"x\", \"e\": [1], \"f\": null}",
+        )
+        .unwrap();
+        let expected = Json::<Object>::new(
+            "{\"f\": null, \"a\": [1, 2, {\"b/c\": false}], \"e\": [], \"g\": {}}",
+        )
+        .unwrap();
+        let differences: Vec<String> = actual
+            .differences(&expected)
+            .iter()
+            .map(|d| d.to_string())
+            .collect();
+        assert_eq!(
+            vec![
+                "/a/2/b~1c: expected false, found true",
+                "/d: unexpected \"x\"",
+                "/e: expected [], found [1]",
+                "/g: missing, expected {}",
+            ],
+            differences
+        );
+        assert!(expected.differences(&expected).is_empty());
+        crate::assert_json_eq!(
+            actual,
+            "{\"f\": null, \"e\": [1], \"d\": \"x\", \"a\": [1, 2, {\"b/c\": true}]}"
+        );
+        crate::assert_json_eq!("[1, 2]", String::from("[1.0,2]"));
+    }
+
+    #[test]
+    #[should_panic(expected = "The jsons are not the same:\n  (root): expected 2, found 1")]
+    fn assert_json_eq_panics() {
+        crate::assert_json_eq!("1", "2");
+    }
+
+    #[test]
+    fn path_patterns() {
+        let pattern = PathPattern::new("a.**.b.*");
+        assert!(pattern.matches(&["a", "b", "0"]));
+        assert!(pattern.matches(&["a", "x", "y", "b", "c"]));
+        assert!(!pattern.matches(&["a", "b"]));
+        assert!(!pattern.matches(&["x", "b", "0"]));
+        assert!(PathPattern::new("**").matches::<&str>(&[]));
+    }
+}
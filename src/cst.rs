@@ -0,0 +1,686 @@
+use crate::escape::unescape_str;
+use crate::format::syntax_error;
+use crate::json::{AnyJson, Json, JsonError, JsonState, JsonType, MAX_DEPTH};
+use crate::lexer::{Lexer, Token, TokenKind};
+use crate::parser::{parse_json, ParseError};
+use crate::pointer::{array_index, parse_pointer, pointer_token, to_pointer};
+use crate::tokens::{self, locate, trivia, DEFAULT_TAB_WIDTH};
+use std::fmt;
+use std::ops::Range;
+
+// A token with the whitespace and comments written in front of it
+#[derive(Debug, Clone)]
+struct Piece {
+    trivia: String,
+    text: String,
+}
+
+impl Piece {
+    fn new(text: &str) -> Self {
+        Self {
+            trivia: String::new(),
+            text: text.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    trivia: String,
+    kind: NodeKind,
+}
+
+#[derive(Debug, Clone)]
+enum NodeKind {
+    Scalar(String),
+    Array { items: Vec<Item>, close: Piece },
+    Object { members: Vec<Member>, close: Piece },
+}
+
+#[derive(Debug, Clone)]
+struct Item {
+    value: Node,
+    comma: Option<Piece>,
+}
+
+#[derive(Debug, Clone)]
+struct Member {
+    key: Piece,
+    colon: Piece,
+    value: Node,
+    comma: Option<Piece>,
+}
+
+// A lossless parse of a json document. Whitespace and `//` or `/* */` comments are kept
+// with the tokens they come before, so writing it back with `to_string` gives the input
+// unchanged, apart from the values that were edited.
+#[derive(Debug, Clone)]
+pub struct Cst {
+    root: Node,
+    end: String,
+}
+
+struct Scanner<'a> {
+    src: &'a str,
+    pos: usize,
+    // Where the arrays and objects that are being scanned start
+    open: Vec<usize>,
+}
+
+impl<'a> Scanner<'a> {
+    fn error(&self, text: &str) -> JsonError {
+        self.error_at(self.pos, text)
+    }
+
+    fn token(&self, pos: usize, kind: TokenKind, text: &str) -> Token {
+        Token {
+            kind,
+            text: text.to_string(),
+            loc: locate(self.src, pos, DEFAULT_TAB_WIDTH),
+        }
+    }
+
+    fn error_at(&self, pos: usize, text: &str) -> JsonError {
+        if !text.is_empty() {
+            let found = self.token(pos, TokenKind::Invalid, text);
+            return syntax_error(ParseError::malformed(found));
+        }
+        syntax_error(match self.open.last() {
+            Some(&open) if self.src[open..].starts_with('{') => {
+                ParseError::Unclosed(self.token(open, TokenKind::OpenCurly, "{"))
+            }
+            Some(&open) => ParseError::Unclosed(self.token(open, TokenKind::OpenBracket, "[")),
+            None => ParseError::UnexpectedEof,
+        })
+    }
+
+    fn trivia(&mut self) -> Result<String, JsonError> {
+        let start = self.pos;
+        while let Some((kind, len)) = trivia(&self.src[self.pos..]) {
+            if kind == tokens::TokenKind::Invalid {
+                return Err(self.error("/*"));
+            }
+            self.pos += len;
+        }
+        Ok(self.src[start..self.pos].to_string())
+    }
+
+    fn next(&mut self) -> Result<(TokenKind, Piece), JsonError> {
+        let trivia = self.trivia()?;
+        let token = Lexer::new(self.src[self.pos..].chars())
+            .next()
+            .expect("THIS SHOULD NEVER PANIC");
+        if token.kind == TokenKind::Invalid {
+            return Err(self.error(&token.text));
+        }
+        self.pos += token.text.len();
+        Ok((
+            token.kind,
+            Piece {
+                trivia,
+                text: token.text,
+            },
+        ))
+    }
+
+    // Errors at the token that was just read
+    fn unexpected(&self, piece: &Piece) -> JsonError {
+        self.error_at(self.pos - piece.text.len(), &piece.text)
+    }
+
+    // Scans a whole value without recursing, the arrays and objects it is in are kept on a
+    // stack with the trivia in front of their opening tokens
+    fn value(&mut self) -> Result<Node, JsonError> {
+        let mut stack: Vec<(String, Partial)> = Vec::new();
+        let (mut kind, mut piece) = self.next()?;
+        loop {
+            let mut node = match kind {
+                TokenKind::OpenBracket | TokenKind::OpenCurly => {
+                    self.open_at(kind, &piece)?;
+                    let (next_kind, next_piece) = self.next()?;
+                    let empty = match (kind, next_kind) {
+                        (TokenKind::OpenBracket, TokenKind::CloseBracket) => NodeKind::Array {
+                            items: Vec::new(),
+                            close: next_piece,
+                        },
+                        (TokenKind::OpenCurly, TokenKind::CloseCurly) => NodeKind::Object {
+                            members: Vec::new(),
+                            close: next_piece,
+                        },
+                        (TokenKind::OpenBracket, _) => {
+                            stack.push((piece.trivia, Partial::Array(Vec::new())));
+                            (kind, piece) = (next_kind, next_piece);
+                            continue;
+                        }
+                        _ => {
+                            let key = self.key(next_kind, next_piece)?;
+                            stack.push((piece.trivia, Partial::Object(Vec::new(), key)));
+                            (kind, piece) = self.next()?;
+                            continue;
+                        }
+                    };
+                    self.open.pop();
+                    Node {
+                        trivia: piece.trivia,
+                        kind: empty,
+                    }
+                }
+                TokenKind::Str
+                | TokenKind::Integer
+                | TokenKind::Float
+                | TokenKind::Null
+                | TokenKind::True
+                | TokenKind::False => Node {
+                    trivia: piece.trivia,
+                    kind: NodeKind::Scalar(piece.text),
+                },
+                _ => return Err(self.unexpected(&piece)),
+            };
+            // Puts `node` in the array or object it is in, and the array or object in its
+            // own parent when `node` was its last element
+            loop {
+                let close = match stack.last() {
+                    None => return Ok(node),
+                    Some((_, Partial::Array(_))) => TokenKind::CloseBracket,
+                    Some((_, Partial::Object(..))) => TokenKind::CloseCurly,
+                };
+                let (next_kind, next_piece) = self.next()?;
+                if next_kind == close {
+                    let (trivia, partial) = stack.pop().expect("THIS SHOULD NEVER PANIC");
+                    self.open.pop();
+                    node = Node {
+                        trivia,
+                        kind: partial.close(node, next_piece),
+                    };
+                    continue;
+                }
+                if next_kind != TokenKind::Comma {
+                    return Err(self.unexpected(&next_piece));
+                }
+                match stack.last_mut().expect("THIS SHOULD NEVER PANIC") {
+                    (_, Partial::Array(items)) => items.push(Item {
+                        value: node,
+                        comma: Some(next_piece),
+                    }),
+                    (_, Partial::Object(members, key)) => {
+                        let (key_kind, key_piece) = self.next()?;
+                        let (key, colon) = std::mem::replace(key, self.key(key_kind, key_piece)?);
+                        members.push(Member {
+                            key,
+                            colon,
+                            value: node,
+                            comma: Some(next_piece),
+                        });
+                    }
+                }
+                (kind, piece) = self.next()?;
+                break;
+            }
+        }
+    }
+
+    // Past `MAX_DEPTH` it is an error like for `Json::new`, writing and dropping the nodes
+    // recurses
+    fn open_at(&mut self, kind: TokenKind, piece: &Piece) -> Result<(), JsonError> {
+        let start = self.pos - piece.text.len();
+        if self.open.len() >= MAX_DEPTH {
+            let token = self.token(start, kind, &piece.text);
+            return Err(syntax_error(ParseError::TooDeep(token)));
+        }
+        self.open.push(start);
+        Ok(())
+    }
+
+    // The key `piece` and the `:` after it
+    fn key(&mut self, kind: TokenKind, piece: Piece) -> Result<(Piece, Piece), JsonError> {
+        if kind != TokenKind::Str {
+            return Err(self.unexpected(&piece));
+        }
+        let (colon_kind, colon) = self.next()?;
+        if colon_kind != TokenKind::Colon {
+            return Err(self.unexpected(&colon));
+        }
+        Ok((piece, colon))
+    }
+}
+
+// An array or object of which the closing token was not scanned yet
+enum Partial {
+    Array(Vec<Item>),
+    // The members so far and the key and `:` of the member whose value comes next
+    Object(Vec<Member>, (Piece, Piece)),
+}
+
+impl Partial {
+    fn close(self, last: Node, close: Piece) -> NodeKind {
+        match self {
+            Partial::Array(mut items) => {
+                items.push(Item {
+                    value: last,
+                    comma: None,
+                });
+                NodeKind::Array { items, close }
+            }
+            Partial::Object(mut members, (key, colon)) => {
+                members.push(Member {
+                    key,
+                    colon,
+                    value: last,
+                    comma: None,
+                });
+                NodeKind::Object { members, close }
+            }
+        }
+    }
+}
+
+fn write_piece(piece: &Piece, out: &mut String, trivia: bool) {
+    if trivia {
+        out.push_str(&piece.trivia);
+    }
+    out.push_str(&piece.text);
+}
+
+// Writes `node` back, without any whitespace or comments when `trivia` is false
+fn write_node(node: &Node, out: &mut String, trivia: bool) {
+    if trivia {
+        out.push_str(&node.trivia);
+    }
+    match &node.kind {
+        NodeKind::Scalar(text) => out.push_str(text),
+        NodeKind::Array { items, close } => {
+            out.push('[');
+            for item in items {
+                write_node(&item.value, out, trivia);
+                if let Some(comma) = &item.comma {
+                    write_piece(comma, out, trivia);
+                }
+            }
+            write_piece(close, out, trivia);
+        }
+        NodeKind::Object { members, close } => {
+            out.push('{');
+            for member in members {
+                write_piece(&member.key, out, trivia);
+                write_piece(&member.colon, out, trivia);
+                write_node(&member.value, out, trivia);
+                if let Some(comma) = &member.comma {
+                    write_piece(comma, out, trivia);
+                }
+            }
+            write_piece(close, out, trivia);
+        }
+    }
+}
+
+fn key_matches(key: &Piece, token: &str) -> bool {
+    unescape_str(&key.text[1..key.text.len() - 1]) == token
+}
+
+// The trivia for the element after a removed one, a comment on the line of the removed
+// element is removed with it
+fn joined_trivia(removed: &str, next: &str) -> String {
+    match next.split_once('\n') {
+        Some((_, rest)) => format!("{}\n{}", &removed[..removed.rfind('\n').unwrap_or(0)], rest),
+        None => removed.to_string(),
+    }
+}
+
+fn to_json(node: &Node) -> AnyJson {
+    let mut text = String::new();
+    write_node(node, &mut text, false);
+    AnyJson::from_data(parse_json(text).expect("THIS SHOULD NEVER PANIC"))
+}
+
+// The node for a value, the same as the node of its minified text
+fn node_of<S: JsonState>(value: &Json<S>) -> Result<Node, JsonError> {
+    Cst::parse(value.to_string()).map(|cst| cst.root)
+}
+
+fn piece_len(piece: &Piece) -> usize {
+    piece.trivia.len() + piece.text.len()
+}
+
+// Moves `pos` past `node` and keeps the byte range of the innermost value that contains
+// `range`, the values inside a node are visited before the node itself
+fn find_span(node: &Node, pos: &mut usize, range: &Range<usize>, span: &mut Option<Range<usize>>) {
+    *pos += node.trivia.len();
+    let start = *pos;
+    match &node.kind {
+        NodeKind::Scalar(text) => *pos += text.len(),
+        NodeKind::Array { items, close } => {
+            *pos += 1;
+            for item in items {
+                find_span(&item.value, pos, range, span);
+                *pos += item.comma.as_ref().map_or(0, piece_len);
+            }
+            *pos += piece_len(close);
+        }
+        NodeKind::Object { members, close } => {
+            *pos += 1;
+            for member in members {
+                *pos += piece_len(&member.key) + piece_len(&member.colon);
+                find_span(&member.value, pos, range, span);
+                *pos += member.comma.as_ref().map_or(0, piece_len);
+            }
+            *pos += piece_len(close);
+        }
+    }
+    if span.is_none() && start <= range.start && range.end <= *pos {
+        *span = Some(start..*pos);
+    }
+}
+
+// Looking into the scalar `text` at `path` as if it was an array or object
+fn not_a_container(path: String, text: &str) -> JsonError {
+    let data = parse_json(text).expect("THIS SHOULD NEVER PANIC");
+    JsonError::incorrect_type(path, &[JsonType::Object, JsonType::Array], &data)
+}
+
+impl Cst {
+    pub fn parse<R: AsRef<str>>(json: R) -> Result<Self, JsonError> {
+        let mut scanner = Scanner {
+            src: json.as_ref(),
+            pos: 0,
+            open: Vec::new(),
+        };
+        let root = scanner.value()?;
+        let end = scanner.trivia()?;
+        if scanner.pos < scanner.src.len() {
+            let (_, piece) = scanner.next()?;
+            return Err(scanner.unexpected(&piece));
+        }
+        Ok(Self { root, end })
+    }
+
+    // The byte range of the smallest value that contains all of `range`, the whole root
+    // value when `range` reaches outside of it
+    pub(crate) fn value_span(&self, range: &Range<usize>) -> Range<usize> {
+        let mut span = None;
+        let mut pos = 0;
+        find_span(&self.root, &mut pos, range, &mut span);
+        span.unwrap_or(self.root.trivia.len()..pos)
+    }
+
+    // The document without any whitespace or comments
+    pub fn to_json(&self) -> AnyJson {
+        to_json(&self.root)
+    }
+
+    fn node(&self, tokens: &[String]) -> Result<&Node, JsonError> {
+        let path = |len: usize| to_pointer(&tokens[..len]);
+        tokens
+            .iter()
+            .enumerate()
+            .try_fold(&self.root, |node, (i, token)| match &node.kind {
+                // The last of a repeated key wins, like `Json::new`
+                NodeKind::Object { members, .. } => members
+                    .iter()
+                    .rev()
+                    .find(|member| key_matches(&member.key, token))
+                    .map(|member| &member.value)
+                    .ok_or_else(|| JsonError::key_not_found(path(i + 1))),
+                NodeKind::Array { items, .. } => array_index(token)
+                    .and_then(|index| items.get(index))
+                    .map(|item| &item.value)
+                    .ok_or_else(|| JsonError::index_not_found(path(i + 1))),
+                NodeKind::Scalar(text) => Err(not_a_container(path(i), text)),
+            })
+    }
+
+    fn node_mut(&mut self, tokens: &[String]) -> Result<&mut Node, JsonError> {
+        let path = |len: usize| to_pointer(&tokens[..len]);
+        tokens
+            .iter()
+            .enumerate()
+            .try_fold(&mut self.root, |node, (i, token)| match &mut node.kind {
+                NodeKind::Object { members, .. } => members
+                    .iter_mut()
+                    .rev()
+                    .find(|member| key_matches(&member.key, token))
+                    .map(|member| &mut member.value)
+                    .ok_or_else(|| JsonError::key_not_found(path(i + 1))),
+                NodeKind::Array { items, .. } => array_index(token)
+                    .and_then(|index| items.get_mut(index))
+                    .map(|item| &mut item.value)
+                    .ok_or_else(|| JsonError::index_not_found(path(i + 1))),
+                NodeKind::Scalar(text) => Err(not_a_container(path(i), text)),
+            })
+    }
+
+    // Looks up a json pointer like `Json::pointer`
+    pub fn get(&self, pointer: &str) -> Result<AnyJson, JsonError> {
+        self.node(&parse_pointer(pointer)?).map(to_json)
+    }
+
+    // Replaces the value at `pointer`, keeping the whitespace and comments in front of it.
+    // A missing key is added at the end of its object indented like the member before it
+    // and `-` or the length of an array appends to it. New values are written minified.
+    pub fn set<S: JsonState>(&mut self, pointer: &str, value: Json<S>) -> Result<(), JsonError> {
+        let mut tokens = parse_pointer(pointer)?;
+        let mut new = node_of(&value)?;
+        let last = match tokens.pop() {
+            Some(last) => last,
+            None => {
+                new.trivia = std::mem::take(&mut self.root.trivia);
+                self.root = new;
+                return Ok(());
+            }
+        };
+        let parent = to_pointer(&tokens);
+        let target = format!("{}/{}", parent, pointer_token(&last));
+        match &mut self.node_mut(&tokens)?.kind {
+            NodeKind::Object { members, .. } => {
+                if let Some(member) = members
+                    .iter_mut()
+                    .rev()
+                    .find(|member| key_matches(&member.key, &last))
+                {
+                    new.trivia = std::mem::take(&mut member.value.trivia);
+                    member.value = new;
+                    return Ok(());
+                }
+                let (key_trivia, value_trivia) = match members.last_mut() {
+                    Some(previous) => {
+                        previous.comma = Some(Piece::new(","));
+                        (previous.key.trivia.clone(), previous.value.trivia.clone())
+                    }
+                    None => (String::new(), String::new()),
+                };
+                new.trivia = value_trivia;
+                members.push(Member {
+                    key: Piece {
+                        trivia: key_trivia,
+                        text: Json::from(last.as_str()).to_string(),
+                    },
+                    colon: Piece::new(":"),
+                    value: new,
+                    comma: None,
+                });
+            }
+            NodeKind::Array { items, .. } => {
+                if last == "-" || array_index(&last) == Some(items.len()) {
+                    new.trivia = match items.last_mut() {
+                        Some(previous) => {
+                            previous.comma = Some(Piece::new(","));
+                            previous.value.trivia.clone()
+                        }
+                        None => String::new(),
+                    };
+                    items.push(Item {
+                        value: new,
+                        comma: None,
+                    });
+                } else {
+                    let item = array_index(&last)
+                        .and_then(|index| items.get_mut(index))
+                        .ok_or(JsonError::index_not_found(target))?;
+                    new.trivia = std::mem::take(&mut item.value.trivia);
+                    item.value = new;
+                }
+            }
+            NodeKind::Scalar(text) => return Err(not_a_container(parent, text)),
+        }
+        Ok(())
+    }
+
+    // Removes the value at `pointer` together with its key and separating comma
+    pub fn remove(&mut self, pointer: &str) -> Result<AnyJson, JsonError> {
+        let mut tokens = parse_pointer(pointer)?;
+        let last = tokens.pop().ok_or_else(|| {
+            JsonError::InvalidPath("The root of a document can not be removed".to_string())
+        })?;
+        let parent = to_pointer(&tokens);
+        let target = format!("{}/{}", parent, pointer_token(&last));
+        let removed = match &mut self.node_mut(&tokens)?.kind {
+            NodeKind::Object { members, .. } => {
+                let index = members
+                    .iter()
+                    .rposition(|member| key_matches(&member.key, &last))
+                    .ok_or(JsonError::key_not_found(target))?;
+                let member = members.remove(index);
+                match members.get_mut(index) {
+                    Some(next) => {
+                        next.key.trivia = joined_trivia(&member.key.trivia, &next.key.trivia)
+                    }
+                    None => {
+                        if let Some(previous) = members.last_mut() {
+                            previous.comma = None;
+                        }
+                    }
+                }
+                member.value
+            }
+            NodeKind::Array { items, .. } => {
+                let index = array_index(&last)
+                    .filter(|index| *index < items.len())
+                    .ok_or(JsonError::index_not_found(target))?;
+                let item = items.remove(index);
+                match items.get_mut(index) {
+                    Some(next) => {
+                        next.value.trivia = joined_trivia(&item.value.trivia, &next.value.trivia)
+                    }
+                    None => {
+                        if let Some(previous) = items.last_mut() {
+                            previous.comma = None;
+                        }
+                    }
+                }
+                item.value
+            }
+            NodeKind::Scalar(text) => return Err(not_a_container(parent, text)),
+        };
+        Ok(to_json(&removed))
+    }
+}
+
+impl fmt::Display for Cst {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = String::new();
+        write_node(&self.root, &mut out, true);
+        out.push_str(&self.end);
+        f.write_str(&out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::Value;
+
+    const CONFIG: &str = "// settings\n{\n  \"name\": \"app\", // the name\n  /* ports */\n  \"ports\" : [ 80,\n    443 ],\n  \"debug\": false\n}\n";
+
+    #[test]
+    fn nesting_limit() {
+        let nested = |depth: usize| format!("{}{}", "[ ".repeat(depth), "]".repeat(depth));
+        assert_eq!(
+            nested(MAX_DEPTH),
+            Cst::parse(nested(MAX_DEPTH)).unwrap().to_string()
+        );
+        assert_eq!(
+            Some(JsonError::InvalidJsonSyntax(
+                "Arrays and objects nested deeper than 512 levels, this array starts at 1:1025"
+                    .to_string()
+            )),
+            Cst::parse(nested(MAX_DEPTH + 1)).err()
+        );
+        assert!(Cst::parse("{\"a\":".repeat(200_000)).is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        assert_eq!(CONFIG, Cst::parse(CONFIG).unwrap().to_string());
+        for text in ["  1 ", "[]", "{ }", "[ [ ] , { \"a\" :{}} ]", "\"\\u0041\""] {
+            assert_eq!(text, Cst::parse(text).unwrap().to_string());
+        }
+        let cst = Cst::parse(CONFIG).unwrap();
+        assert_eq!("[80,443]", cst.get("/ports").unwrap().to_string());
+        assert_eq!(
+            Json::<Value>::new("443").unwrap().to_string(),
+            cst.get("/ports/1").unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn edits_keep_formatting() {
+        let mut cst = Cst::parse(CONFIG).unwrap();
+        cst.set("/debug", Json::from(true)).unwrap();
+        cst.set("/ports/1", Json::from(8443i64)).unwrap();
+        cst.set("/ports/-", Json::from(9000i64)).unwrap();
+        cst.set("/extra", Json::from("x")).unwrap();
+        assert_eq!(
+            "// settings\n{\n  \"name\": \"app\", // the name\n  /* ports */\n  \"ports\" : [ 80,\n    8443,\n    9000 ],\n  \"debug\": true,\n  \"extra\": \"x\"\n}\n",
+            cst.to_string()
+        );
+        assert_eq!("\"app\"", cst.remove("/name").unwrap().to_string());
+        assert_eq!("\"x\"", cst.remove("/extra").unwrap().to_string());
+        assert_eq!(
+            "// settings\n{\n  /* ports */\n  \"ports\" : [ 80,\n    8443,\n    9000 ],\n  \"debug\": true\n}\n",
+            cst.to_string()
+        );
+        cst.set("", Json::from(1i64)).unwrap();
+        assert_eq!("// settings\n1\n", cst.to_string());
+    }
+
+    #[test]
+    fn remove_from_arrays() {
+        let mut cst = Cst::parse("[1, 2, 3]").unwrap();
+        cst.remove("/0").unwrap();
+        assert_eq!("[2, 3]", cst.to_string());
+        cst.remove("/1").unwrap();
+        assert_eq!("[2]", cst.to_string());
+        cst.remove("/0").unwrap();
+        assert_eq!("[]", cst.to_string());
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(
+            Cst::parse("{\n  \"a\": 1,\n}"),
+            Err(JsonError::InvalidJsonSyntax(e)) if e.contains("at 3:1")
+        ));
+        assert!(Cst::parse("[1] 2").is_err());
+        assert!(Cst::parse("[1 /* open").is_err());
+        assert!(Cst::parse("").is_err());
+        assert_eq!(
+            Err(JsonError::InvalidJsonSyntax(
+                "Unclosed array started at 2:8".to_string()
+            )),
+            Cst::parse("{\"a\": {\n  \"b\": [1, // c\n").map(|cst| cst.to_string())
+        );
+        let mut cst = Cst::parse("{\"a\": [1]}").unwrap();
+        assert_eq!(
+            JsonError::KeyNotFound {
+                path: "/b".to_string()
+            },
+            cst.remove("/b").unwrap_err()
+        );
+        assert_eq!(
+            JsonError::IndexNotFound {
+                path: "/a/5".to_string()
+            },
+            cst.set("/a/5", Json::from(1i64)).unwrap_err()
+        );
+        assert!(matches!(cst.remove(""), Err(JsonError::InvalidPath(_))));
+    }
+}
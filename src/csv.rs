@@ -0,0 +1,237 @@
+use crate::json::{Array, Json, JsonError};
+use crate::parser::JsonData;
+use std::io::Read;
+
+struct Field {
+    text: String,
+    quoted: bool,
+}
+
+impl Json<Array> {
+    // Reads csv where the first row is the header, every following row becomes an object.
+    // Unquoted fields are inferred as null (empty or `null`), bool, integer or float,
+    // quoted fields are always strings.
+    pub fn from_csv<R: Read>(mut reader: R) -> Result<Json<Array>, JsonError> {
+        let mut csv = String::new();
+        if let Err(e) = reader.read_to_string(&mut csv) {
+            return Err(JsonError::FileError(e.kind()));
+        }
+        let mut rows = parse_rows(&csv)?.into_iter();
+        let header = match rows.next() {
+            Some(header) => header,
+            None => return Ok(Json::from_data(JsonData::Array(vec![]))),
+        };
+        let mut keys: Vec<String> = Vec::with_capacity(header.len());
+        for field in header {
+            let key = field.text;
+            if keys.contains(&key) {
+                return Err(JsonError::InvalidCsvSyntax(format!(
+                    "Duplicate header `{}`",
+                    key
+                )));
+            }
+            keys.push(key);
+        }
+        let mut arr = Vec::new();
+        for (row_count, row) in rows.enumerate() {
+            if row.len() != keys.len() {
+                return Err(JsonError::InvalidCsvSyntax(format!(
+                    "Row {} has {} fields but the header has {}",
+                    row_count + 2,
+                    row.len(),
+                    keys.len()
+                )));
+            }
+            arr.push(JsonData::object(
+                keys.iter().cloned().zip(row.into_iter().map(infer_type)),
+            ));
+        }
+        Ok(Json::from_data(JsonData::array(arr)))
+    }
+}
+
+fn infer_type(field: Field) -> JsonData {
+    if field.quoted {
+        return JsonData::Str(field.text.into());
+    }
+    match field.text.as_str() {
+        "" | "null" => JsonData::Null,
+        "true" => JsonData::Bool(true),
+        "false" => JsonData::Bool(false),
+        text => {
+            // zip codes, ids and phone numbers like `02134` keep their leading zeros
+            let digits = text.strip_prefix('-').unwrap_or(text).as_bytes();
+            let leading_zero = digits.len() > 1 && digits[0] == b'0' && digits[1].is_ascii_digit();
+            let is_number = text.starts_with(|c: char| c == '-' || c.is_ascii_digit())
+                && !text.ends_with('.')
+                && !leading_zero;
+            if let (true, Ok(i)) = (is_number, text.parse::<i64>()) {
+                JsonData::Integer(i)
+            } else if let (true, Ok(f)) = (is_number, text.parse::<f64>()) {
+                if f.is_finite() {
+                    JsonData::Float(f)
+                } else {
+                    JsonData::Str(text.into())
+                }
+            } else {
+                JsonData::Str(text.into())
+            }
+        }
+    }
+}
+
+fn parse_rows(csv: &str) -> Result<Vec<Vec<Field>>, JsonError> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = Field {
+        text: String::new(),
+        quoted: false,
+    };
+    let mut in_quotes = false;
+    let mut row_count = 1;
+    let mut chars = csv.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.text.push('"');
+                }
+                '"' => in_quotes = false,
+                c => field.text.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.text.is_empty() && !field.quoted => {
+                field.quoted = true;
+                in_quotes = true;
+            }
+            '"' => {
+                return Err(JsonError::InvalidCsvSyntax(format!(
+                    "Unexpected `\"` on row {}",
+                    row_count
+                )))
+            }
+            ',' => row.push(std::mem::replace(
+                &mut field,
+                Field {
+                    text: String::new(),
+                    quoted: false,
+                },
+            )),
+            '\r' if chars.peek() == Some(&'\n') => continue,
+            '\n' => {
+                row.push(std::mem::replace(
+                    &mut field,
+                    Field {
+                        text: String::new(),
+                        quoted: false,
+                    },
+                ));
+                rows.push(std::mem::take(&mut row));
+                row_count += 1;
+            }
+            c if field.quoted => {
+                return Err(JsonError::InvalidCsvSyntax(format!(
+                    "Unexpected `{}` after closing quote on row {}",
+                    c, row_count
+                )))
+            }
+            c => field.text.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(JsonError::InvalidCsvSyntax(format!(
+            "Unterminated quoted field on row {}",
+            row_count
+        )));
+    }
+    if !row.is_empty() || !field.text.is_empty() || field.quoted {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json::{Array, Json, JsonError};
+
+    #[test]
+    fn csv_type_inference() {
+        let json = Json::<Array>::from_csv(
+            "id,name,price,active,note\n1,apple,1.5,true,\n2,\"pear, green\",-3,false,null\n"
+                .as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(Ok(2), json.len());
+        let first = json.get_object(0).unwrap();
+        assert_eq!(Ok(1), first.get_value("id").unwrap().get_i64());
+        assert_eq!(Ok("apple"), first.get_value("name").unwrap().get_string());
+        assert_eq!(Ok(1.5), first.get_value("price").unwrap().get_f64());
+        assert_eq!(Ok(true), first.get_value("active").unwrap().get_bool());
+        assert!(first.get_value("note").unwrap().is_null());
+        let second = json.get_object(1).unwrap();
+        assert_eq!(
+            Ok("pear, green"),
+            second.get_value("name").unwrap().get_string()
+        );
+        assert_eq!(Ok(-3), second.get_value("price").unwrap().get_i64());
+        assert!(second.get_value("note").unwrap().is_null());
+    }
+
+    #[test]
+    fn csv_quoted_fields_are_strings() {
+        let json =
+            Json::<Array>::from_csv("a,b\r\n\"12\",\"say \"\"hi\"\"\nbye\"".as_bytes()).unwrap();
+        let row = json.get_object(0).unwrap();
+        assert_eq!(Ok("12"), row.get_value("a").unwrap().get_string());
+        assert_eq!(
+            Ok("say \"hi\"\nbye"),
+            row.get_value("b").unwrap().get_string()
+        );
+    }
+
+    #[test]
+    fn csv_leading_zeros_are_strings() {
+        let json = Json::<Array>::from_csv(
+            "zip,id,phone,zero,frac,neg\n02134,007,0701234567,0,0.5,-05\n".as_bytes(),
+        )
+        .unwrap();
+        let row = json.get_object(0).unwrap();
+        let value = |key| row.get_value(key).unwrap();
+        assert_eq!(Ok("02134"), value("zip").get_string());
+        assert_eq!(Ok("007"), value("id").get_string());
+        assert_eq!(Ok("0701234567"), value("phone").get_string());
+        assert_eq!(Ok(0), value("zero").get_i64());
+        assert_eq!(Ok(0.5), value("frac").get_f64());
+        assert_eq!(Ok("-05"), value("neg").get_string());
+    }
+
+    #[test]
+    fn csv_only_header() {
+        assert_eq!(
+            Ok(true),
+            Json::<Array>::from_csv("a,b\n".as_bytes())
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn csv_invalid_row_length() {
+        assert!(matches!(
+            Json::<Array>::from_csv("a,b\n1\n".as_bytes()),
+            Err(JsonError::InvalidCsvSyntax(_))
+        ));
+    }
+
+    #[test]
+    fn csv_unterminated_quote() {
+        assert!(matches!(
+            Json::<Array>::from_csv("a\n\"1\n".as_bytes()),
+            Err(JsonError::InvalidCsvSyntax(_))
+        ));
+    }
+}
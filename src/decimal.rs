@@ -0,0 +1,99 @@
+use crate::json::{Json, JsonError, JsonType, Value};
+use crate::parser::JsonData;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+
+// The exact value of a float token if parsing it as `f64` would change it, like
+// `0.1000000000000000055511` or `12345678901234567.89`
+pub(crate) fn inexact_float(text: &str, f: f64) -> Option<Decimal> {
+    let exact = if text.contains(['e', 'E']) {
+        Decimal::from_scientific(text)
+    } else {
+        Decimal::from_str_exact(text)
+    }
+    .ok()?;
+    // `f64` displays as the shortest text that parses back to the same float
+    let rounded = Decimal::from_str_exact(&f.to_string()).ok();
+    (rounded != Some(exact)).then_some(exact)
+}
+
+pub(crate) fn decimal_to_f64(decimal: &Decimal) -> f64 {
+    decimal.to_f64().expect("THIS SHOULD NEVER PANIC")
+}
+
+impl Json<Value> {
+    // The number as a decimal. Floats are read from their shortest form so `0.1` gives
+    // exactly `0.1`, numbers with more digits than a `f64` holds are kept exactly when parsed.
+    pub fn get_decimal(&self) -> Result<Decimal, JsonError> {
+        match self.data.as_ref().unraw() {
+            JsonData::Decimal(d) => Ok(*d),
+            JsonData::Integer(i) => Ok(Decimal::from(*i)),
+            JsonData::Float(f) => Decimal::from_str_exact(&f.to_string())
+                .map_err(|_| JsonError::InvalidDecimal(format!("{} does not fit in a decimal", f))),
+            _ => Err(self.incorrect_type(&[JsonType::Integer, JsonType::Float])),
+        }
+    }
+}
+
+// Written exactly, including trailing zeros like in `1.50`
+impl From<Decimal> for Json<Value> {
+    fn from(decimal: Decimal) -> Self {
+        Json::from_data(JsonData::Decimal(decimal))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::FormatOptions;
+    use crate::json::Array;
+    use crate::json::JsonType;
+    use std::str::FromStr;
+
+    #[test]
+    fn decimal_parse_and_write() {
+        let json: Json<Array> = Json::new(
+            "[0.1, 12345678901234567.89, 1.5e-20, 0.30000000000000000001, 7, \"1\", 1e30]",
+        )
+        .unwrap();
+        let decimal = |i| json.get_value(i).unwrap().get_decimal();
+        assert_eq!(Ok(Decimal::from_str("0.1").unwrap()), decimal(0));
+        assert_eq!(
+            Ok(Decimal::from_str("12345678901234567.89").unwrap()),
+            decimal(1)
+        );
+        assert_eq!(
+            Ok(Decimal::from_str("0.000000000000000000015").unwrap()),
+            decimal(2)
+        );
+        assert_eq!(
+            Ok(Decimal::from_str("0.30000000000000000001").unwrap()),
+            decimal(3)
+        );
+        assert_eq!(Ok(Decimal::from(7)), decimal(4));
+        assert_eq!(
+            Err(JsonError::IncorrectType {
+                path: "/5".to_string(),
+                expected: vec![JsonType::Integer, JsonType::Float],
+                found: JsonType::String,
+            }),
+            decimal(5)
+        );
+        assert!(matches!(decimal(6), Err(JsonError::InvalidDecimal(_))));
+        // only the numbers a float would change are kept as decimals
+        assert_eq!(Ok(0.1), json.get_value(0).unwrap().get_f64());
+        assert_eq!(Ok(0.3), json.get_value(3).unwrap().get_f64());
+        let exact = "[0.1,12345678901234567.89,0.30000000000000000001,-1.0]";
+        assert_eq!(
+            exact,
+            Json::<Array>::new(exact)
+                .unwrap()
+                .to_formatted_string(&FormatOptions::minified())
+        );
+        let price = Json::<Value>::from(Decimal::from_str("1.50").unwrap());
+        assert_eq!(
+            "1.50",
+            price.to_formatted_string(&FormatOptions::minified())
+        );
+    }
+}
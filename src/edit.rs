@@ -0,0 +1,786 @@
+use crate::filter::compare;
+use crate::json::{AnyJson, Array, Json, JsonError, JsonState, JsonType, Object};
+use crate::parser::{remove_member, JsonData, Map, Shared};
+use crate::pointer::{array_index, parse_pointer, pointer_token, resolve, resolve_mut, to_pointer};
+use crate::small::SmallString;
+use std::cmp::Ordering;
+use std::ops::{Bound, RangeBounds};
+
+// The members of the object, copied first if they are shared with another view
+pub(crate) fn object_mut(json: &mut Json<Object>) -> &mut Map<SmallString, Shared<JsonData>> {
+    match Shared::make_mut(&mut json.data) {
+        JsonData::Object(map) => map,
+        _ => panic!("Json<Object> does not hold an object"),
+    }
+}
+
+// The elements of the array, copied first if they are shared with another view
+pub(crate) fn array_mut(json: &mut Json<Array>) -> &mut Vec<Shared<JsonData>> {
+    match Shared::make_mut(&mut json.data) {
+        JsonData::Array(arr) => arr,
+        _ => panic!("Json<Array> does not hold an array"),
+    }
+}
+
+// What `prune_empty` removes from objects, and from arrays when `in_arrays` is set.
+// The default removes nulls, `[]` and `{}` everywhere but keeps empty strings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PruneOptions {
+    pub nulls: bool,
+    pub empty_arrays: bool,
+    pub empty_objects: bool,
+    pub empty_strings: bool,
+    pub in_arrays: bool,
+}
+
+impl Default for PruneOptions {
+    fn default() -> Self {
+        Self {
+            nulls: true,
+            empty_arrays: true,
+            empty_objects: true,
+            empty_strings: false,
+            in_arrays: true,
+        }
+    }
+}
+
+impl PruneOptions {
+    fn removes(&self, data: &JsonData) -> bool {
+        match data {
+            JsonData::Null => self.nulls,
+            JsonData::Array(arr) => self.empty_arrays && arr.is_empty(),
+            JsonData::Object(map) => self.empty_objects && map.is_empty(),
+            JsonData::Str(s) => self.empty_strings && s.is_empty(),
+            _ => false,
+        }
+    }
+
+    fn finds_any(&self, data: &JsonData) -> bool {
+        match data {
+            JsonData::Array(arr) => arr
+                .iter()
+                .any(|data| (self.in_arrays && self.removes(data)) || self.finds_any(data)),
+            JsonData::Object(map) => map
+                .values()
+                .any(|data| self.removes(data) || self.finds_any(data)),
+            _ => false,
+        }
+    }
+}
+
+// Only copies the containers that something is removed from, or below
+fn prune(data: &mut Shared<JsonData>, options: &PruneOptions) {
+    if !options.finds_any(data) {
+        return;
+    }
+    match Shared::make_mut(data) {
+        JsonData::Array(arr) => {
+            arr.iter_mut().for_each(|data| prune(data, options));
+            if options.in_arrays {
+                arr.retain(|data| !options.removes(data));
+            }
+        }
+        JsonData::Object(map) => {
+            map.values_mut().for_each(|data| prune(data, options));
+            map.retain(|_, data| !options.removes(data));
+        }
+        _ => {}
+    }
+}
+
+impl<S: JsonState> Json<S> {
+    // Removes every null member and element, recursively
+    pub fn strip_nulls(&mut self) {
+        self.prune_empty(&PruneOptions {
+            nulls: true,
+            empty_arrays: false,
+            empty_objects: false,
+            empty_strings: false,
+            in_arrays: true,
+        });
+    }
+
+    // Removes what `options` asks for recursively, a container that becomes empty by it is
+    // removed too. The json itself is never removed, only emptied.
+    pub fn prune_empty(&mut self, options: &PruneOptions) {
+        prune(&mut self.data, options);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Case {
+    // snake_case
+    SnakeCase,
+    // camelCase
+    CamelCase,
+    // PascalCase
+    PascalCase,
+    // kebab-case
+    KebabCase,
+    // SCREAMING_SNAKE_CASE
+    ScreamingSnakeCase,
+}
+
+// Splits a key into lowercase words at `_`, `-`, spaces and changes of case, so both
+// `userId` and `user_id` give `user` and `id`, and `HTTPServer` gives `http` and `server`
+fn words(key: &str) -> Vec<String> {
+    let chars: Vec<char> = key.chars().collect();
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if matches!(c, '_' | '-' | ' ') {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+        if c.is_uppercase() && !word.is_empty() {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|c| c.is_lowercase());
+            if prev.is_lowercase() || prev.is_numeric() || (prev.is_uppercase() && next_is_lower) {
+                words.push(std::mem::take(&mut word));
+            }
+        }
+        word.extend(c.to_lowercase());
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+impl Case {
+    // `key` in this case, e.g. `user_id` becomes `userId` in `Case::CamelCase`
+    pub fn convert(&self, key: &str) -> String {
+        let words = words(key);
+        match self {
+            Case::SnakeCase => words.join("_"),
+            Case::KebabCase => words.join("-"),
+            Case::ScreamingSnakeCase => words.join("_").to_uppercase(),
+            Case::PascalCase => words.iter().map(|word| capitalize(word)).collect(),
+            Case::CamelCase => words
+                .iter()
+                .enumerate()
+                .map(|(i, word)| {
+                    if i == 0 {
+                        word.clone()
+                    } else {
+                        capitalize(word)
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+fn rename_keys(data: &mut Shared<JsonData>, case: Case) {
+    if !matches!(data.as_ref(), JsonData::Array(_) | JsonData::Object(_)) {
+        return;
+    }
+    match Shared::make_mut(data) {
+        JsonData::Array(arr) => arr.iter_mut().for_each(|data| rename_keys(data, case)),
+        JsonData::Object(map) => {
+            **map = std::mem::take(&mut **map)
+                .into_iter()
+                .map(|(key, mut data)| {
+                    rename_keys(&mut data, case);
+                    (case.convert(&key).into(), data)
+                })
+                .collect();
+        }
+        _ => {}
+    }
+}
+
+impl<S: JsonState> Json<S> {
+    // Converts every key to `case`, recursively. If two keys of an object end up the same
+    // only one of them is kept, which one is not specified.
+    pub fn rename_keys(&mut self, case: Case) {
+        rename_keys(&mut self.data, case);
+    }
+
+    pub fn to_camel_case_keys(&mut self) {
+        self.rename_keys(Case::CamelCase);
+    }
+
+    pub fn to_snake_case_keys(&mut self) {
+        self.rename_keys(Case::SnakeCase);
+    }
+}
+
+// Removes the value at `tokens`, the root can not be removed
+fn remove(data: &mut Shared<JsonData>, tokens: &[String]) -> Result<Shared<JsonData>, JsonError> {
+    let (last, parent) = tokens.split_last().expect("THIS SHOULD NEVER PANIC");
+    match Shared::make_mut(resolve_mut(data, parent)?) {
+        JsonData::Object(map) => {
+            remove_member(map, last).ok_or_else(|| JsonError::key_not_found(to_pointer(tokens)))
+        }
+        JsonData::Array(arr) => match array_index(last) {
+            Some(index) if index < arr.len() => Ok(arr.remove(index)),
+            _ => Err(JsonError::index_not_found(to_pointer(tokens))),
+        },
+        data => Err(JsonError::incorrect_type(
+            to_pointer(parent),
+            &[JsonType::Object, JsonType::Array],
+            data,
+        )),
+    }
+}
+
+// Adds `value` at `tokens` the way JSON Patch does, `-` appends to an array
+fn add(
+    data: &mut Shared<JsonData>,
+    tokens: &[String],
+    value: Shared<JsonData>,
+) -> Result<(), JsonError> {
+    let Some((last, parent)) = tokens.split_last() else {
+        *data = value;
+        return Ok(());
+    };
+    match Shared::make_mut(resolve_mut(data, parent)?) {
+        JsonData::Object(map) => {
+            map.insert(last.into(), value);
+        }
+        JsonData::Array(arr) => match array_index(last) {
+            _ if last == "-" => arr.push(value),
+            Some(index) if index <= arr.len() => arr.insert(index, value),
+            _ => return Err(JsonError::index_not_found(to_pointer(tokens))),
+        },
+        data => {
+            return Err(JsonError::incorrect_type(
+                to_pointer(parent),
+                &[JsonType::Object, JsonType::Array],
+                data,
+            ))
+        }
+    }
+    Ok(())
+}
+
+impl<S: JsonState> Json<S> {
+    // Renames the member `old` of the object at `pointer` to `new`, a member that is already
+    // called `new` is replaced
+    pub fn rename_key(&mut self, pointer: &str, old: &str, new: &str) -> Result<(), JsonError> {
+        let tokens = parse_pointer(pointer)?;
+        let under = |error: JsonError| error.under(&self.path);
+        match resolve(&self.data, &tokens).map_err(under)?.as_ref() {
+            JsonData::Object(map) => map.get(old).ok_or_else(|| {
+                let path = format!("{}/{}", to_pointer(&tokens), pointer_token(old));
+                under(JsonError::key_not_found(path))
+            })?,
+            data => {
+                let path = to_pointer(&tokens);
+                return Err(under(JsonError::incorrect_type(
+                    path,
+                    &[JsonType::Object],
+                    data,
+                )));
+            }
+        };
+        if old == new {
+            return Ok(());
+        }
+        if let JsonData::Object(map) = Shared::make_mut(resolve_mut(&mut self.data, &tokens)?) {
+            let value = remove_member(map, old).expect("THIS SHOULD NEVER PANIC");
+            map.insert(new.into(), value);
+        }
+        Ok(())
+    }
+
+    // Moves the value at the pointer `from` to the pointer `to` like the `move` operation of
+    // JSON Patch (RFC 6902). It is removed first and then added at `to`, where `-` appends to
+    // an array and a member that is already there is replaced. Nothing changes on an error.
+    pub fn move_value(&mut self, from: &str, to: &str) -> Result<(), JsonError> {
+        let from_tokens = parse_pointer(from)?;
+        let to_tokens = parse_pointer(to)?;
+        let under = |error: JsonError| error.under(&self.path);
+        if from_tokens == to_tokens {
+            return resolve(&self.data, &from_tokens).map(|_| ()).map_err(under);
+        }
+        if to_tokens.starts_with(&from_tokens) {
+            return Err(JsonError::InvalidPath(format!(
+                "Can not move `{}` into itself at `{}`",
+                from, to
+            )));
+        }
+        // the changes are made to a copy of the root, only the containers on the two paths
+        // are copied
+        let mut data = Shared::clone(&self.data);
+        let value = remove(&mut data, &from_tokens).map_err(under)?;
+        add(&mut data, &to_tokens, value).map_err(under)?;
+        self.data = data;
+        Ok(())
+    }
+}
+
+impl Json<Object> {
+    // Moves the member out of the object and removes its key. The object is copied first if
+    // it is shared with another view, the value never is.
+    pub fn take(&mut self, key: &str) -> Result<AnyJson, JsonError> {
+        let path = format!("{}/{}", self.path, pointer_token(key));
+        let map = object_mut(self);
+        match remove_member(map, key) {
+            Some(data) => Ok(AnyJson::from_shared(data).at(path)),
+            None => Err(JsonError::key_not_found(path)),
+        }
+    }
+
+    // Keeps only the members for which `keep` returns true, keys are given unescaped
+    pub fn retain<F: FnMut(&str, &AnyJson) -> bool>(&mut self, mut keep: F) {
+        object_mut(self).retain(|key, data| keep(key, &AnyJson::from_shared(Shared::clone(data))));
+    }
+}
+
+// Keys are given unescaped and a key that is already there gets the new value
+impl<K: AsRef<str>, V: Into<AnyJson>> Extend<(K, V)> for Json<Object> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, members: I) {
+        let map = object_mut(self);
+        for (key, value) in members {
+            map.insert(key.as_ref().into(), value.into().into_shared());
+        }
+    }
+}
+
+impl<K: AsRef<str>, V: Into<AnyJson>> FromIterator<(K, V)> for Json<Object> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(members: I) -> Self {
+        let mut json = Json::<Object>::default();
+        json.extend(members);
+        json
+    }
+}
+
+impl<V: Into<AnyJson>> Extend<V> for Json<Array> {
+    fn extend<I: IntoIterator<Item = V>>(&mut self, items: I) {
+        array_mut(self).extend(items.into_iter().map(|value| value.into().into_shared()));
+    }
+}
+
+impl<V: Into<AnyJson>> FromIterator<V> for Json<Array> {
+    fn from_iter<I: IntoIterator<Item = V>>(items: I) -> Self {
+        let mut json = Json::<Array>::default();
+        json.extend(items);
+        json
+    }
+}
+
+// The start and end of `range` in a sequence of `len` items, an unbounded end is `len`.
+// Bounds at `usize::MAX` saturate instead of overflowing, they are past any end anyway.
+pub(crate) fn range_bounds<R: RangeBounds<usize>>(range: &R, len: usize) -> (usize, usize) {
+    let start = match range.start_bound() {
+        Bound::Included(&i) => i,
+        Bound::Excluded(&i) => i.saturating_add(1),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&i) => i.saturating_add(1),
+        Bound::Excluded(&i) => i,
+        Bound::Unbounded => len,
+    };
+    (start, end)
+}
+
+impl Json<Array> {
+    // Keeps only the elements for which `keep` returns true, in order
+    pub fn retain<F: FnMut(&AnyJson) -> bool>(&mut self, mut keep: F) {
+        array_mut(self).retain(|data| keep(&AnyJson::from_shared(Shared::clone(data))));
+    }
+
+    // Replaces every element with what `f` returns for it, dropping those it returns None for
+    pub fn filter_map<F: FnMut(AnyJson) -> Option<AnyJson>>(&mut self, mut f: F) {
+        let arr = array_mut(self);
+        *arr = arr
+            .drain(..)
+            .filter_map(|data| f(AnyJson::from_shared(data)).map(AnyJson::into_shared))
+            .collect();
+    }
+
+    // Moves the element out of the array and leaves `null` in its place, so the indices of
+    // the other elements stay the same
+    pub fn take_index(&mut self, index: usize) -> Result<AnyJson, JsonError> {
+        let path = format!("{}/{}", self.path, index);
+        match array_mut(self).get_mut(index) {
+            Some(data) => {
+                let data = std::mem::replace(data, Shared::new(JsonData::Null));
+                Ok(AnyJson::from_shared(data).at(path))
+            }
+            None => Err(JsonError::index_not_found(path)),
+        }
+    }
+
+    pub fn insert<S: JsonState>(&mut self, index: usize, value: Json<S>) -> Result<(), JsonError> {
+        let path = format!("{}/{}", self.path, index);
+        let arr = array_mut(self);
+        if index > arr.len() {
+            return Err(JsonError::index_not_found(path));
+        }
+        arr.insert(index, value.data);
+        Ok(())
+    }
+
+    // Replaces the elements in `range` with `replace_with` and returns the removed ones
+    pub fn splice<R, I>(&mut self, range: R, replace_with: I) -> Result<Vec<AnyJson>, JsonError>
+    where
+        R: RangeBounds<usize>,
+        I: IntoIterator<Item = AnyJson>,
+    {
+        let path = self.path.clone();
+        let arr = array_mut(self);
+        let (start, end) = range_bounds(&range, arr.len());
+        if start > end || end > arr.len() {
+            let missing = if end > arr.len() { arr.len() } else { start };
+            return Err(JsonError::index_not_found(format!("{}/{}", path, missing)));
+        }
+        Ok(arr
+            .splice(
+                start..end,
+                replace_with.into_iter().map(AnyJson::into_shared),
+            )
+            .map(AnyJson::from_shared)
+            .collect())
+    }
+
+    // Removes consecutive elements that are equal, so `1` and `1.0` count as the same
+    pub fn dedup(&mut self) {
+        array_mut(self).dedup_by(|a, b| compare(a, b).is_eq());
+    }
+
+    // Sorts the elements with the same ordering as comparisons in filters:
+    // null < false < true < numbers < strings < arrays < objects. Strings are compared by
+    // their unescaped text, arrays element by element and objects by their sorted keys first
+    // and then by the values under them. The sort is stable.
+    pub fn sort(&mut self) {
+        array_mut(self).sort_by(|a, b| compare(a, b));
+    }
+
+    pub fn sort_by<F: FnMut(&AnyJson, &AnyJson) -> Ordering>(&mut self, mut compare: F) {
+        array_mut(self).sort_by(|a, b| {
+            compare(
+                &AnyJson::from_shared(Shared::clone(a)),
+                &AnyJson::from_shared(Shared::clone(b)),
+            )
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::escape::escape_str;
+    use crate::format::{write_data, FormatOptions};
+    use crate::json::JsonType;
+    use crate::json::Value;
+
+    // Minified with the members of objects sorted, as their order is not kept
+    fn sorted<S: JsonState>(json: &Json<S>) -> String {
+        fn sort(data: &JsonData, out: &mut String) {
+            match data {
+                JsonData::Object(map) => {
+                    let mut keys: Vec<_> = map.keys().collect();
+                    keys.sort();
+                    out.push('{');
+                    for (i, key) in keys.into_iter().enumerate() {
+                        if i > 0 {
+                            out.push(',');
+                        }
+                        out.push_str(&format!("\"{}\":", escape_str(key)));
+                        sort(&map[key], out);
+                    }
+                    out.push('}');
+                }
+                JsonData::Array(arr) => {
+                    out.push('[');
+                    for (i, data) in arr.iter().enumerate() {
+                        if i > 0 {
+                            out.push(',');
+                        }
+                        sort(data, out);
+                    }
+                    out.push(']');
+                }
+                data => write_data(data, out, 0, &FormatOptions::minified()),
+            }
+        }
+        let mut out = String::new();
+        sort(&json.data, &mut out);
+        out
+    }
+
+    #[test]
+    fn object_retain() {
+        let original: Json<Object> =
+            Json::new("{\"id\": 1, \"_internal\": true, \"a\\\"_b\": 2, \"name\": null}").unwrap();
+        let mut json: Json<Object> = Json::from_shared(Shared::clone(&original.data));
+        json.retain(|key, value| {
+            !key.starts_with('_')
+                && !key.contains('"')
+                && !matches!(value, AnyJson::Value(v) if v.is_null())
+        });
+        assert_eq!(
+            "{\"id\":1}",
+            json.to_formatted_string(&FormatOptions::minified())
+        );
+        assert!(original.get_value("_internal").is_ok());
+    }
+
+    #[test]
+    fn array_retain_and_filter_map() {
+        let mut json: Json<Array> = Json::new("[1, \"a\", 2, [3], 4]").unwrap();
+        json.retain(|value| !matches!(value, AnyJson::Array(_)));
+        assert_eq!(Ok(4), json.len());
+        json.filter_map(|value| match value {
+            AnyJson::Value(v) => v.get_i64().ok().map(|i| Json::<Value>::from(i * 10).into()),
+            _ => None,
+        });
+        assert_eq!(Ok(3), json.len());
+        assert_eq!(Ok(40), json.get_value(2).unwrap().get_i64());
+    }
+
+    #[test]
+    fn array_insert_and_splice() {
+        let mut json: Json<Array> = Json::new("[1, 2, 3]").unwrap();
+        json.insert(0, Json::<Value>::from("a")).unwrap();
+        json.insert(4, Json::<Array>::new("[]").unwrap()).unwrap();
+        assert_eq!(
+            Err(JsonError::IndexNotFound {
+                path: "/6".to_string()
+            }),
+            json.insert(6, Json::from(true))
+        );
+        let removed = json.splice(1..3, [Json::<Value>::from(9).into()]).unwrap();
+        assert_eq!(2, removed.len());
+        assert_eq!(
+            "[\"a\",9,3,[]]",
+            json.to_formatted_string(&FormatOptions::minified())
+        );
+        assert!(json.splice(3..=4, []).is_err());
+        assert!(matches!(
+            json.splice(..=usize::MAX, []),
+            Err(JsonError::IndexNotFound { .. })
+        ));
+        assert!(json
+            .splice((Bound::Excluded(usize::MAX), Bound::Unbounded), [])
+            .is_err());
+        assert_eq!(1, json.splice(..1, []).unwrap().len());
+        assert_eq!(Ok(3), json.len());
+    }
+
+    #[test]
+    fn array_dedup_and_sort() {
+        let mut json: Json<Array> =
+            Json::new("[{\"b\": 1}, \"b\", [1, 2], 2, 1.0, 1, true, null, [1], {\"a\": 2}]")
+                .unwrap();
+        json.sort();
+        assert_eq!(
+            "[null,true,1.0,1,2,\"b\",[1],[1,2],{\"a\":2},{\"b\":1}]",
+            json.to_formatted_string(&FormatOptions::minified())
+        );
+        json.dedup();
+        assert_eq!(Ok(9), json.len());
+        json.sort_by(|a, b| b.to_string().len().cmp(&a.to_string().len()));
+        assert!(matches!(json.get_value(8), Ok(value) if value.get_i64() == Ok(2)));
+    }
+
+    #[test]
+    fn strip_nulls_and_prune() {
+        let original: Json<Object> = Json::new(
+            "{\"a\": null, \"b\": [null, 1, {}], \"c\": {\"d\": [null], \"e\": \"\"}, \"f\": {\"g\": 2}}",
+        )
+        .unwrap();
+        let mut json: Json<Object> = Json::from_shared(Shared::clone(&original.data));
+        json.strip_nulls();
+        assert_eq!(
+            "{\"b\":[1,{}],\"c\":{\"d\":[],\"e\":\"\"},\"f\":{\"g\":2}}",
+            sorted(&json)
+        );
+        let mut json: Json<Object> = Json::from_shared(Shared::clone(&original.data));
+        json.prune_empty(&PruneOptions::default());
+        assert_eq!(
+            "{\"b\":[1],\"c\":{\"e\":\"\"},\"f\":{\"g\":2}}",
+            sorted(&json)
+        );
+        // untouched parts are still shared with the original
+        assert!(Shared::ptr_eq(
+            &original.get_object("f").unwrap().data,
+            &json.get_object("f").unwrap().data
+        ));
+        let mut json: Json<Object> = Json::from_shared(Shared::clone(&original.data));
+        json.prune_empty(&PruneOptions {
+            empty_strings: true,
+            in_arrays: false,
+            ..PruneOptions::default()
+        });
+        assert_eq!(
+            "{\"b\":[null,1,{}],\"c\":{\"d\":[null]},\"f\":{\"g\":2}}",
+            sorted(&json)
+        );
+        let mut empty: Json<Array> = Json::new("[[], [null]]").unwrap();
+        empty.prune_empty(&PruneOptions::default());
+        assert_eq!(Ok(true), empty.is_empty());
+    }
+
+    #[test]
+    fn case_conversion() {
+        for (key, snake, camel) in [
+            ("user_id", "user_id", "userId"),
+            ("userId", "user_id", "userId"),
+            ("UserID", "user_id", "userId"),
+            ("HTTPServer-port 2", "http_server_port_2", "httpServerPort2"),
+            ("__a__b", "a_b", "aB"),
+            ("", "", ""),
+        ] {
+            assert_eq!(snake, Case::SnakeCase.convert(key), "{key}");
+            assert_eq!(camel, Case::CamelCase.convert(key), "{key}");
+        }
+        assert_eq!("UserId", Case::PascalCase.convert("user_id"));
+        assert_eq!("user-id", Case::KebabCase.convert("userId"));
+        assert_eq!("USER_ID", Case::ScreamingSnakeCase.convert("userId"));
+    }
+
+    #[test]
+    fn rename_keys_recursively() {
+        let mut json: Json<Array> =
+            Json::new("[{\"user_id\": {\"first_name\": \"a_b\"}}, [{\"is_\\\"x\\\"\": 1}]]")
+                .unwrap();
+        json.to_camel_case_keys();
+        assert_eq!(
+            "[{\"userId\":{\"firstName\":\"a_b\"}},[{\"is\\\"x\\\"\":1}]]",
+            sorted(&json)
+        );
+        json.to_snake_case_keys();
+        assert_eq!(
+            "[{\"user_id\":{\"first_name\":\"a_b\"}},[{\"is\\\"x\\\"\":1}]]",
+            sorted(&json)
+        );
+    }
+
+    #[test]
+    fn rename_key_by_pointer() {
+        let mut json: Json<Object> =
+            Json::new("{\"a\": {\"old\": 1, \"new\": 2, \"x\": []}, \"b\": [{\"c\\\"\": 3}]}")
+                .unwrap();
+        json.rename_key("/a", "old", "new").unwrap();
+        json.rename_key("/b/0", "c\"", "d/e").unwrap();
+        json.rename_key("", "b", "b").unwrap();
+        assert_eq!(
+            "{\"a\":{\"new\":1,\"x\":[]},\"b\":[{\"d/e\":3}]}",
+            sorted(&json)
+        );
+        assert_eq!(
+            Err(JsonError::KeyNotFound {
+                path: "/a/old".to_string()
+            }),
+            json.rename_key("/a", "old", "y")
+        );
+        assert_eq!(
+            Err(JsonError::IncorrectType {
+                path: "/a/x".to_string(),
+                expected: vec![JsonType::Object],
+                found: JsonType::Array,
+            }),
+            json.rename_key("/a/x", "0", "y")
+        );
+        assert_eq!(
+            Err(JsonError::KeyNotFound {
+                path: "/z".to_string()
+            }),
+            json.rename_key("/z", "a", "y")
+        );
+    }
+
+    #[test]
+    fn move_value_by_pointer() {
+        let original: Json<Object> = Json::new("{\"a\": {\"b\": [1, 2, 3]}, \"c\": {}}").unwrap();
+        let mut json: Json<Object> = Json::from_shared(Shared::clone(&original.data));
+        json.move_value("/a/b/0", "/c/first").unwrap();
+        json.move_value("/a/b/1", "/a/b/0").unwrap();
+        json.move_value("/c/first", "/a/b/-").unwrap();
+        json.move_value("/a/b", "/a/b").unwrap();
+        assert_eq!("{\"a\":{\"b\":[3,2,1]},\"c\":{}}", sorted(&json));
+        assert_eq!("{\"a\":{\"b\":[1,2,3]},\"c\":{}}", sorted(&original));
+        assert!(matches!(
+            json.move_value("/a", "/a/b/0"),
+            Err(JsonError::InvalidPath(_))
+        ));
+        assert_eq!(
+            Err(JsonError::IndexNotFound {
+                path: "/a/b/5".to_string()
+            }),
+            json.move_value("/a/b/0", "/a/b/5")
+        );
+        assert_eq!(
+            Err(JsonError::KeyNotFound {
+                path: "/x".to_string()
+            }),
+            json.move_value("/x", "/c/x")
+        );
+        assert_eq!(
+            Err(JsonError::KeyNotFound {
+                path: "/x".to_string()
+            }),
+            json.move_value("/a/b", "/x/y")
+        );
+        assert_eq!("{\"a\":{\"b\":[3,2,1]},\"c\":{}}", sorted(&json));
+    }
+
+    #[test]
+    fn take_values_out() {
+        let mut json: Json<Object> = Json::new("{\"a\": [1, {\"b\": 2}], \"c\\\"d\": 3}").unwrap();
+        let copy = json.clone();
+        let AnyJson::Array(mut a) = json.take("a").unwrap() else {
+            panic!("not an array")
+        };
+        assert_eq!("/a", a.path());
+        let b = a.take_index(1).unwrap();
+        assert_eq!("/a/1", b.path());
+        assert_eq!("{\"b\":2}", b.to_string());
+        assert_eq!("[1,null]", a.to_string());
+        assert_eq!("3", json.take("c\"d").unwrap().to_string());
+        assert_eq!("{}", json.to_string());
+        assert_eq!(
+            Err(JsonError::KeyNotFound {
+                path: "/a".to_string()
+            }),
+            json.take("a").map(|_| ())
+        );
+        assert_eq!(
+            Err(JsonError::IndexNotFound {
+                path: "/a/2".to_string()
+            }),
+            a.take_index(2).map(|_| ())
+        );
+        assert!(copy.get_array("a").is_ok());
+        let mut entry = json.entry("e").or_insert(Json::<Value>::from(true));
+        assert!(!entry.is_null());
+        entry = json.entry("e").or_insert(Json::<Value>::from(false));
+        assert_eq!("true", entry.into_value().to_string());
+        assert_eq!("{\"e\":null}", json.to_string());
+    }
+
+    #[test]
+    fn collect_and_extend() {
+        let squares: Json<Array> = (1..4).map(|i: i64| i * i).collect();
+        assert_eq!("[1,4,9]", squares.to_string());
+        let mut object: Json<Object> = [("a\"b", AnyJson::from(squares)), ("c", "d".into())]
+            .into_iter()
+            .collect();
+        assert_eq!(Ok(3), object.get_array("a\"b").unwrap().len());
+        object.extend([("c", true), ("e", false)]);
+        assert_eq!(Ok(true), object.get_value("c").unwrap().get_bool());
+        assert_eq!(
+            "{\"a\\\"b\":[1,4,9],\"c\":true,\"e\":false}",
+            sorted(&object)
+        );
+        let mut mixed: Json<Array> = vec![Json::from(1.5)].into_iter().collect();
+        mixed.extend([AnyJson::from("x"), AnyJson::from(Json::<Object>::default())]);
+        assert_eq!("[1.5,\"x\",{}]", mixed.to_string());
+    }
+}
@@ -0,0 +1,94 @@
+use crate::edit::object_mut;
+use crate::json::{Json, JsonMut, JsonState, Object};
+use crate::parser::{JsonData, MapEntry, Shared};
+use crate::pointer::pointer_token;
+use crate::small::SmallString;
+
+// A key of an object that may or may not be there yet, see `Json::<Object>::entry`
+pub struct Entry<'a> {
+    inner: MapEntry<'a, SmallString, Shared<JsonData>>,
+    path: String,
+}
+
+impl<'a> Entry<'a> {
+    pub fn or_insert<S: JsonState>(self, default: Json<S>) -> JsonMut<'a> {
+        JsonMut {
+            data: self.inner.or_insert(default.data),
+            path: self.path,
+        }
+    }
+
+    pub fn or_insert_with<S: JsonState, F: FnOnce() -> Json<S>>(self, default: F) -> JsonMut<'a> {
+        JsonMut {
+            data: self.inner.or_insert_with(|| default().data),
+            path: self.path,
+        }
+    }
+
+    pub fn and_modify<F: FnOnce(&mut JsonMut)>(self, f: F) -> Self {
+        let path = self.path.clone();
+        Self {
+            inner: self.inner.and_modify(|data| f(&mut JsonMut { data, path })),
+            path: self.path,
+        }
+    }
+
+    pub fn key(&self) -> String {
+        self.inner.key().to_string()
+    }
+}
+
+impl Json<Object> {
+    // Like `HashMap::entry`, for in place updates of a key. The object is copied first if it
+    // is shared with another view.
+    pub fn entry(&mut self, key: &str) -> Entry<'_> {
+        let path = format!("{}/{}", self.path, pointer_token(key));
+        let map = object_mut(self);
+        Entry {
+            inner: map.entry(key.into()),
+            path,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json::{Json, Object, Value};
+    use crate::parser::Shared;
+
+    #[test]
+    fn entry_counters() {
+        let mut counts: Json<Object> = Json::new("{\"a\": 1}").unwrap();
+        for word in ["a", "b", "a", "c\"d"] {
+            counts
+                .entry(word)
+                .and_modify(|count| {
+                    let n = count.get_i64().unwrap();
+                    count.set(Json::from(n + 1));
+                })
+                .or_insert(Json::from(1));
+        }
+        assert_eq!(Ok(3), counts.get_value("a").unwrap().get_i64());
+        assert_eq!(Ok(1), counts.get_value("b").unwrap().get_i64());
+        assert_eq!(Ok(1), counts.get_value("c\"d").unwrap().get_i64());
+        assert_eq!("c\"d", counts.entry("c\"d").key());
+    }
+
+    #[test]
+    fn entry_or_insert_with() {
+        let original: Json<Object> = Json::new("{\"list\": [1]}").unwrap();
+        let mut json: Json<Object> = Json::from_shared(Shared::clone(&original.data));
+        let mut calls = 0;
+        let value = json.entry("list").or_insert_with(|| {
+            calls += 1;
+            Json::<Value>::from("unused")
+        });
+        assert!(value.get_string().is_err());
+        assert_eq!(0, calls);
+        json.entry("name")
+            .or_insert_with(|| Json::<Value>::from("x"))
+            .set(Json::<Value>::from(true));
+        assert_eq!(Ok(true), json.get_value("name").unwrap().get_bool());
+        assert!(original.get_value("name").is_err());
+    }
+}
@@ -0,0 +1,138 @@
+use std::borrow::Cow;
+use std::fmt::Write;
+
+// `JsonData::Str` and the keys of objects hold plain text. A lexed string is unescaped once
+// by `unquote` when it is parsed and `escape_str` puts the escapes back when it is written.
+// Borrows `text` when it has nothing to escape.
+pub(crate) fn escape_str(text: &str) -> Cow<'_, str> {
+    if !text.contains(|c: char| c == '"' || c == '\\' || (c as u32) < 0x20) {
+        return Cow::Borrowed(text);
+    }
+    let mut escaped = String::with_capacity(text.len() + 2);
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '\u{8}' => escaped.push_str("\\b"),
+            '\u{c}' => escaped.push_str("\\f"),
+            c if (c as u32) < 0x20 => {
+                write!(escaped, "\\u{:04x}", c as u32).expect("THIS SHOULD NEVER PANIC")
+            }
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+// The text of a lexed `Str` token without its quotes and escapes. The quotes are taken off
+// in place and only a string with escapes in it is copied.
+pub(crate) fn unquote(mut text: String) -> String {
+    text.pop();
+    text.remove(0);
+    match unescape_str(&text) {
+        Cow::Borrowed(_) => text,
+        Cow::Owned(unescaped) => unescaped,
+    }
+}
+
+// Borrows `text` when it has no `\` in it, which most strings don't
+pub(crate) fn unescape_str(text: &str) -> Cow<'_, str> {
+    if !text.contains('\\') {
+        return Cow::Borrowed(text);
+    }
+    let mut chars = text.chars();
+    let mut unescaped = String::with_capacity(text.len());
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some('t') => unescaped.push('\t'),
+            Some('b') => unescaped.push('\u{8}'),
+            Some('f') => unescaped.push('\u{c}'),
+            Some('u') => {
+                let high = read_hex4(&mut chars);
+                let c = match high {
+                    Some(high @ 0xD800..=0xDBFF) => {
+                        let mut lookahead = chars.clone();
+                        let low = if lookahead.next() == Some('\\') && lookahead.next() == Some('u')
+                        {
+                            read_hex4(&mut lookahead)
+                        } else {
+                            None
+                        };
+                        match low {
+                            Some(low @ 0xDC00..=0xDFFF) => {
+                                chars = lookahead;
+                                char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+                            }
+                            _ => None,
+                        }
+                    }
+                    Some(code) => char::from_u32(code),
+                    None => None,
+                };
+                unescaped.push(c.unwrap_or(char::REPLACEMENT_CHARACTER));
+            }
+            // `\"`, `\\` and `\/` and any unknown escape keeps the escaped char
+            Some(c) => unescaped.push(c),
+            None => unescaped.push('\\'),
+        }
+    }
+    Cow::Owned(unescaped)
+}
+
+fn read_hex4(chars: &mut (impl Iterator<Item = char> + Clone)) -> Option<u32> {
+    let mut code = 0;
+    for _ in 0..4 {
+        code = code * 16 + chars.next()?.to_digit(16)?;
+    }
+    Some(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn escape_unescape_roundtrip(ref s in r"\PC*") {
+            prop_assert_eq!(s, &unescape_str(&escape_str(s)).into_owned());
+            prop_assert_eq!(s, &unquote(format!("\"{}\"", escape_str(s))));
+        }
+    }
+
+    #[test]
+    fn escape_control_chars() {
+        assert_eq!(r#"a\"b\\c\n\u0001"#, escape_str("a\"b\\c\n\u{1}"));
+    }
+
+    #[test]
+    fn unescape_surrogate_pair() {
+        assert_eq!("\u{1F600}", unescape_str(r"\ud83d\ude00"));
+    }
+
+    #[test]
+    fn unescape_borrows_without_escapes() {
+        assert!(matches!(unescape_str("plain"), Cow::Borrowed("plain")));
+        assert!(matches!(unescape_str(r"a\nb"), Cow::Owned(s) if s == "a\nb"));
+    }
+
+    #[test]
+    fn escape_borrows_without_escapes() {
+        assert!(matches!(escape_str("plain é"), Cow::Borrowed("plain é")));
+        assert_eq!(r#"\"\u001f"#, escape_str("\"\u{1f}"));
+    }
+
+    #[test]
+    fn unescape_lone_surrogate() {
+        assert_eq!("\u{FFFD}x", unescape_str(r"\ud83dx"));
+    }
+}
@@ -0,0 +1,409 @@
+use crate::json::{AnyJson, Json, JsonError, JsonState};
+use crate::parser::{JsonData, Shared};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// What `expand` substitutes. By default `${NAME}` is looked up in the environment and
+// includes are read relative to the current directory.
+#[derive(Debug, Clone, Default)]
+pub struct ExpandOptions {
+    variables: Option<HashMap<String, String>>,
+    base_dir: PathBuf,
+    skip_includes: bool,
+}
+
+impl ExpandOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Looks variables up in `variables` instead of the environment
+    pub fn variables(mut self, variables: HashMap<String, String>) -> Self {
+        self.variables = Some(variables);
+        self
+    }
+
+    // The directory relative `$include` paths are resolved against
+    pub fn base_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.base_dir = dir.as_ref().to_path_buf();
+        self
+    }
+
+    // Leaves `{"$include": ...}` objects as they are
+    pub fn without_includes(mut self) -> Self {
+        self.skip_includes = true;
+        self
+    }
+
+    fn variable(&self, name: &str) -> Option<String> {
+        match &self.variables {
+            Some(variables) => variables.get(name).cloned(),
+            None => std::env::var(name).ok(),
+        }
+    }
+}
+
+// Replaces every `${NAME}` in `text`, `${NAME:-default}` uses `default` for an unset
+// variable and `$${` is a literal `${`
+fn substitute(text: &str, options: &ExpandOptions) -> Result<String, JsonError> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('$') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if let Some(after) = rest.strip_prefix("$${") {
+            out.push_str("${");
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("${") {
+            let end = after.find('}').ok_or_else(|| {
+                JsonError::InvalidExpansion(format!("Unclosed `${{` in `{}`", text))
+            })?;
+            let (name, default) = match after[..end].split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (&after[..end], None),
+            };
+            match options
+                .variable(name)
+                .or_else(|| default.map(str::to_string))
+            {
+                Some(value) => out.push_str(&value),
+                None => {
+                    return Err(JsonError::InvalidExpansion(format!(
+                        "Variable `{}` is not set",
+                        name
+                    )))
+                }
+            }
+            rest = &after[end + 1..];
+        } else {
+            out.push('$');
+            rest = &rest[1..];
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+// The path of an object that is only `{"$include": "path"}`
+fn include_path(data: &JsonData) -> Option<&Shared<JsonData>> {
+    match data {
+        JsonData::Object(map) if map.len() == 1 => map.get("$include"),
+        _ => None,
+    }
+}
+
+// `data` with everything expanded, the parts that did not change are shared with it.
+// `including` holds the files being included, innermost last, to catch include cycles.
+fn expand_data(
+    data: &Shared<JsonData>,
+    options: &ExpandOptions,
+    including: &mut Vec<PathBuf>,
+) -> Result<Shared<JsonData>, JsonError> {
+    match data.as_ref() {
+        JsonData::Str(s) if s.contains('$') => {
+            let expanded = substitute(s, options)?;
+            Ok(Shared::new(JsonData::Str(expanded.into())))
+        }
+        JsonData::Object(_) if !options.skip_includes && include_path(data).is_some() => {
+            let path = include_path(data).expect("THIS SHOULD NEVER PANIC");
+            let path = match expand_data(path, options, including)?.as_ref() {
+                JsonData::Str(path) => path.to_string(),
+                _ => {
+                    return Err(JsonError::InvalidExpansion(
+                        "`$include` has to be a string".to_string(),
+                    ))
+                }
+            };
+            include(&options.base_dir.join(path), options, including)
+        }
+        _ => map_values(data, |value| expand_data(value, options, including)),
+    }
+}
+
+// `data` with `f` applied to the values of an object or array, shared with it when none
+// of them changed
+fn map_values<F>(data: &Shared<JsonData>, mut f: F) -> Result<Shared<JsonData>, JsonError>
+where
+    F: FnMut(&Shared<JsonData>) -> Result<Shared<JsonData>, JsonError>,
+{
+    match data.as_ref() {
+        JsonData::Object(map) => {
+            let mut mapped = map.clone();
+            let mut changed = false;
+            for value in mapped.values_mut() {
+                let new = f(value)?;
+                changed |= !Shared::ptr_eq(value, &new);
+                *value = new;
+            }
+            Ok(if changed {
+                Shared::new(JsonData::Object(mapped))
+            } else {
+                Shared::clone(data)
+            })
+        }
+        JsonData::Array(arr) => {
+            let mapped = arr.iter().map(&mut f).collect::<Result<Vec<_>, _>>()?;
+            let changed = arr.iter().zip(&mapped).any(|(a, b)| !Shared::ptr_eq(a, b));
+            Ok(if changed {
+                Shared::new(JsonData::Array(mapped))
+            } else {
+                Shared::clone(data)
+            })
+        }
+        _ => Ok(Shared::clone(data)),
+    }
+}
+
+// Reads and expands the json in `path`, its own includes are relative to its directory
+fn include(
+    path: &Path,
+    options: &ExpandOptions,
+    including: &mut Vec<PathBuf>,
+) -> Result<Shared<JsonData>, JsonError> {
+    let canonical = fs::canonicalize(path).map_err(|e| JsonError::FileError(e.kind()))?;
+    if including.contains(&canonical) {
+        return Err(JsonError::InvalidExpansion(format!(
+            "`{}` includes itself",
+            path.display()
+        )));
+    }
+    let json = AnyJson::from_file(&canonical)?;
+    let options = ExpandOptions {
+        base_dir: canonical
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default(),
+        ..options.clone()
+    };
+    including.push(canonical);
+    let expanded = expand_data(&json.into_shared(), &options, including);
+    including.pop();
+    expanded
+}
+
+fn unbound(name: &str) -> JsonError {
+    JsonError::InvalidExpansion(format!("No value for `{{{{{}}}}}`", name))
+}
+
+// The name of a placeholder that is the whole of `text`, `{{name}}` or `{{ name }}`
+fn placeholder(text: &str) -> Option<&str> {
+    let name = text.strip_prefix("{{")?.strip_suffix("}}")?.trim();
+    (!name.contains(['{', '}'])).then_some(name)
+}
+
+// Replaces the `{{name}}` in the middle of `text` with the text of the values
+fn bind_text(text: &str, values: &HashMap<&str, AnyJson>) -> Result<String, JsonError> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        let name = rest[start + 2..start + end].trim();
+        let value = values.get(name).ok_or_else(|| unbound(name))?;
+        match value.data() {
+            JsonData::Str(s) => out.push_str(s),
+            JsonData::Array(_) | JsonData::Object(_) => {
+                return Err(JsonError::InvalidExpansion(format!(
+                    "`{{{{{}}}}}` is inside a string but its value is not a scalar",
+                    name
+                )))
+            }
+            _ => out.push_str(&value.to_string()),
+        }
+        rest = &rest[start + end + 2..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+fn bind_data(
+    data: &Shared<JsonData>,
+    values: &HashMap<&str, AnyJson>,
+) -> Result<Shared<JsonData>, JsonError> {
+    let bound = |name: &str| {
+        let value = values.get(name).ok_or_else(|| unbound(name))?;
+        Ok(Shared::new(value.data().clone()))
+    };
+    match data.as_ref() {
+        JsonData::Str(s) if s.contains("{{") => match placeholder(s) {
+            Some(name) => bound(name),
+            None => Ok(Shared::new(JsonData::Str(bind_text(s, values)?.into()))),
+        },
+        JsonData::Object(map) if map.len() == 1 && map.contains_key("$var") => {
+            match map.get("$var").map(|name| name.as_ref()) {
+                Some(JsonData::Str(name)) => bound(name),
+                _ => Err(JsonError::InvalidExpansion(
+                    "`$var` has to be a string".to_string(),
+                )),
+            }
+        }
+        _ => map_values(data, |value| bind_data(value, values)),
+    }
+}
+
+impl<S: JsonState> Json<S> {
+    // Fills in a template: strings that are only `{{name}}` and objects that are only
+    // `{"$var": "name"}` are replaced by the value of `name` whatever its type, so numbers
+    // stay numbers. A `{{name}}` inside a longer string gets the text of a scalar value. A
+    // name without a value is an `InvalidExpansion` error and nothing is changed.
+    pub fn bind(&mut self, values: &HashMap<&str, AnyJson>) -> Result<(), JsonError> {
+        let data = bind_data(&self.data, values)?;
+        self.data = Self::checked(data)?.data;
+        Ok(())
+    }
+
+    // Substitutes `${NAME}` placeholders in strings and replaces objects that are only
+    // `{"$include": "other.json"}` with the expanded json of that file. Nothing is changed
+    // when it fails.
+    pub fn expand(&mut self, options: &ExpandOptions) -> Result<(), JsonError> {
+        self.data = expand_data(&self.data, options, &mut Vec::new())?;
+        Ok(())
+    }
+
+    // Reads and expands a config file, with includes relative to the directory of `file`
+    pub fn from_file_expanded<P: AsRef<Path>>(
+        file: P,
+        options: &ExpandOptions,
+    ) -> Result<Self, JsonError> {
+        let data = include(file.as_ref(), options, &mut Vec::new())?;
+        Self::checked(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{Array, Object, Value};
+
+    fn options() -> ExpandOptions {
+        ExpandOptions::new().variables(HashMap::from([
+            ("HOME".to_string(), "/home/me".to_string()),
+            ("QUOTE".to_string(), "say \"hi\"".to_string()),
+        ]))
+    }
+
+    fn dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("json-expand-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn substitute_variables() {
+        let mut json = Json::<Object>::new(
+            "{\"a\": \"${HOME}/x\", \"b\": [\"${QUOTE}\", \"${USER:-nobody}\", \"$${HOME} $5\"], \"c\": 1}",
+        )
+        .unwrap();
+        json.expand(&options()).unwrap();
+        assert_eq!("/home/me/x", json.get_string_or("a", ""));
+        let b = json.get_array("b").unwrap();
+        assert_eq!(
+            "say \"hi\"",
+            b.get_value(0).unwrap().get_unescaped_string().unwrap()
+        );
+        assert_eq!(
+            "nobody",
+            b.get_value(1).unwrap().get_unescaped_string().unwrap()
+        );
+        assert_eq!(
+            "${HOME} $5",
+            b.get_value(2).unwrap().get_unescaped_string().unwrap()
+        );
+
+        let mut json = Json::<Value>::new("\"${MISSING}\"").unwrap();
+        assert!(matches!(
+            json.expand(&options()),
+            Err(JsonError::InvalidExpansion(_))
+        ));
+        assert_eq!("\"${MISSING}\"", json.to_string());
+        assert!(Json::<Value>::new("\"${OPEN\"")
+            .unwrap()
+            .expand(&options())
+            .is_err());
+    }
+
+    #[test]
+    fn includes() {
+        let dir = dir("includes");
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(
+            dir.join("main.json"),
+            "{\"db\": {\"$include\": \"sub/db.json\"}, \"home\": \"${HOME}\"}",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("sub/db.json"),
+            "{\"port\": 5432, \"user\": {\"$include\": \"user.json\"}}",
+        )
+        .unwrap();
+        fs::write(dir.join("sub/user.json"), "\"${HOME}\"").unwrap();
+        let json = Json::<Object>::from_file_expanded(dir.join("main.json"), &options()).unwrap();
+        let db = json.get_object("db").unwrap();
+        assert_eq!(5432, db.get_i64_or("port", 0));
+        assert_eq!("/home/me", db.get_string_or("user", ""));
+
+        let mut json = Json::<Object>::new("{\"x\": {\"$include\": \"sub/user.json\"}}").unwrap();
+        json.expand(&options().base_dir(&dir).without_includes())
+            .unwrap();
+        assert!(json.get_object("x").is_ok());
+        json.expand(&options().base_dir(&dir)).unwrap();
+        assert_eq!("/home/me", json.get_string_or("x", ""));
+
+        fs::write(dir.join("loop.json"), "[{\"$include\": \"loop.json\"}]").unwrap();
+        assert!(matches!(
+            Json::<Array>::from_file_expanded(dir.join("loop.json"), &options()),
+            Err(JsonError::InvalidExpansion(_))
+        ));
+        assert!(matches!(
+            Json::<Object>::new("{\"$include\": \"missing.json\"}")
+                .unwrap()
+                .expand(&options().base_dir(&dir)),
+            Err(JsonError::FileError(_))
+        ));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn bind_templates() {
+        let mut body = Json::<Object>::new(
+            "{\"user\": \"{{ name }}\", \"age\": {\"$var\": \"age\"}, \"tags\": [\"{{tags}}\", \"id-{{id}}: {{name}}\"],
+              \"raw\": \"{{ not closed\", \"n\": 1}",
+        )
+        .unwrap();
+        let values = HashMap::from([
+            ("name", AnyJson::from("a \"b\"")),
+            ("age", AnyJson::from(30)),
+            ("id", AnyJson::from(7)),
+            ("tags", AnyJson::parse("[\"x\", null]").unwrap()),
+        ]);
+        body.bind(&values).unwrap();
+        assert_eq!(
+            Json::<Object>::new(
+                "{\"user\": \"a \\\"b\\\"\", \"age\": 30, \"tags\": [[\"x\", null], \"id-7: a \\\"b\\\"\"],
+                  \"raw\": \"{{ not closed\", \"n\": 1}"
+            )
+            .unwrap(),
+            body
+        );
+
+        let mut template = Json::<Object>::new("{\"a\": \"{{missing}}\"}").unwrap();
+        assert!(matches!(
+            template.bind(&values),
+            Err(JsonError::InvalidExpansion(message)) if message == "No value for `{{missing}}`"
+        ));
+        let mut template = Json::<Object>::new("{\"a\": \"tags: {{tags}}\"}").unwrap();
+        assert!(template.bind(&values).is_err());
+        let mut root = Json::<Value>::new("\"{{tags}}\"").unwrap();
+        assert!(matches!(
+            root.bind(&values),
+            Err(JsonError::IncorrectType { .. })
+        ));
+        assert_eq!("\"{{tags}}\"", root.to_string());
+        let mut root = Json::<Value>::new("\"{{age}}\"").unwrap();
+        root.bind(&values).unwrap();
+        assert_eq!(Ok(30), root.get_i64());
+    }
+}
@@ -0,0 +1,197 @@
+// C interface, build a linkable library with
+// `cargo rustc --release --features ffi --crate-type staticlib` (or `cdylib`)
+// and include `include/json_parser.h`.
+use crate::parser::{parse_json, JsonData, Shared};
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+
+pub struct JsonHandle {
+    data: Shared<JsonData>,
+}
+
+#[repr(C)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum JsonType {
+    Invalid = 0,
+    Null = 1,
+    Bool = 2,
+    Integer = 3,
+    Float = 4,
+    String = 5,
+    Array = 6,
+    Object = 7,
+}
+
+fn into_handle(data: Shared<JsonData>) -> *mut JsonHandle {
+    Box::into_raw(Box::new(JsonHandle { data }))
+}
+
+/// Parses a nul terminated utf-8 string, returns null if the json is invalid or has
+/// arrays and objects nested deeper than 512 levels.
+///
+/// # Safety
+/// `json` must be null or a valid nul terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn json_parse(json: *const c_char) -> *mut JsonHandle {
+    if json.is_null() {
+        return ptr::null_mut();
+    }
+    match CStr::from_ptr(json).to_str().map(parse_json) {
+        Ok(Ok(data)) => into_handle(Shared::new(data)),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Returns a new handle to the value at `key` or null if `handle` is not an object
+/// or the key is missing.
+///
+/// # Safety
+/// `handle` must be null or a live handle and `key` null or a valid nul terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn json_get(
+    handle: *const JsonHandle,
+    key: *const c_char,
+) -> *mut JsonHandle {
+    if handle.is_null() || key.is_null() {
+        return ptr::null_mut();
+    }
+    let key = match CStr::from_ptr(key).to_str() {
+        Ok(key) => key,
+        Err(_) => return ptr::null_mut(),
+    };
+    match (*handle).data.as_ref() {
+        JsonData::Object(m) => match m.get(key) {
+            Some(data) => into_handle(Shared::clone(data)),
+            None => ptr::null_mut(),
+        },
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Returns a new handle to the element at `index` or null if `handle` is not an array
+/// or the index is out of bounds.
+///
+/// # Safety
+/// `handle` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn json_get_index(
+    handle: *const JsonHandle,
+    index: usize,
+) -> *mut JsonHandle {
+    if handle.is_null() {
+        return ptr::null_mut();
+    }
+    match (*handle).data.as_ref() {
+        JsonData::Array(v) => match v.get(index) {
+            Some(data) => into_handle(Shared::clone(data)),
+            None => ptr::null_mut(),
+        },
+        _ => ptr::null_mut(),
+    }
+}
+
+/// # Safety
+/// `handle` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn json_type(handle: *const JsonHandle) -> JsonType {
+    if handle.is_null() {
+        return JsonType::Invalid;
+    }
+    match (*handle).data.as_ref() {
+        JsonData::Null => JsonType::Null,
+        JsonData::Bool(_) => JsonType::Bool,
+        JsonData::Integer(_) => JsonType::Integer,
+        #[cfg(feature = "bigint")]
+        JsonData::BigInt(_) => JsonType::Integer,
+        JsonData::Float(_) => JsonType::Float,
+        #[cfg(feature = "decimal")]
+        JsonData::Decimal(_) => JsonType::Float,
+        #[cfg(feature = "raw_numbers")]
+        JsonData::RawNumber(raw) => match crate::json::JsonType::of(&raw.data) {
+            crate::json::JsonType::Integer => JsonType::Integer,
+            _ => JsonType::Float,
+        },
+        JsonData::Str(_) => JsonType::String,
+        JsonData::Array(_) => JsonType::Array,
+        JsonData::Object(_) => JsonType::Object,
+    }
+}
+
+/// Serializes the value, the returned string must be released with `json_string_free`.
+///
+/// # Safety
+/// `handle` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn json_to_string(handle: *const JsonHandle) -> *mut c_char {
+    if handle.is_null() {
+        return ptr::null_mut();
+    }
+    match CString::new((*handle).data.to_string()) {
+        Ok(s) => s.into_raw(),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// # Safety
+/// `s` must be null or a string returned by `json_to_string` that is not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn json_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// # Safety
+/// `handle` must be null or a handle that is not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn json_free(handle: *mut JsonHandle) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ffi_parse_get_free() {
+        let json = CString::new("{\"a\": [1, \"two\", {\"b\": null}]}").unwrap();
+        let key = CString::new("a").unwrap();
+        unsafe {
+            let root = json_parse(json.as_ptr());
+            assert_eq!(JsonType::Object, json_type(root));
+            let arr = json_get(root, key.as_ptr());
+            assert_eq!(JsonType::Array, json_type(arr));
+            let first = json_get_index(arr, 0);
+            assert_eq!(JsonType::Integer, json_type(first));
+            let second = json_get_index(arr, 1);
+            let s = json_to_string(second);
+            assert_eq!("\"two\"", CStr::from_ptr(s).to_str().unwrap());
+            json_string_free(s);
+            assert!(json_get_index(arr, 3).is_null());
+            assert!(json_get(arr, key.as_ptr()).is_null());
+            json_free(second);
+            json_free(first);
+            json_free(arr);
+            json_free(root);
+        }
+    }
+
+    #[test]
+    fn ffi_invalid_input() {
+        let json = CString::new("{\"a\": tru}").unwrap();
+        unsafe {
+            assert!(json_parse(json.as_ptr()).is_null());
+            assert!(json_parse(ptr::null()).is_null());
+            let deep = CString::new("[".repeat(100_000)).unwrap();
+            assert!(json_parse(deep.as_ptr()).is_null());
+            let deep = CString::new(format!("{}{}", "[".repeat(513), "]".repeat(513))).unwrap();
+            assert!(json_parse(deep.as_ptr()).is_null());
+            assert_eq!(JsonType::Invalid, json_type(ptr::null()));
+            assert!(json_to_string(ptr::null()).is_null());
+            json_free(ptr::null_mut());
+            json_string_free(ptr::null_mut());
+        }
+    }
+}
@@ -0,0 +1,662 @@
+// A small jq like filter language, e.g. `.items[] | select(.price > 10) | .name`.
+//
+// Supported are `.`, `.name`, `."name"`, `.[0]`, `.[-1]`, `.["name"]`, `.[]`, pipes `|`,
+// `,` for producing several outputs, literals, parentheses, `==`, `!=`, `<`, `<=`, `>`,
+// `>=`, `and`, `or` and the functions `select(f)`, `map(f)`, `not`, `length` and `keys`.
+use crate::escape::unescape_str;
+use crate::json::{AnyJson, Json, JsonError, JsonState, JsonType};
+use crate::parser::{JsonData, Shared};
+use crate::small::SmallString;
+use std::cmp::Ordering;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Expr {
+    Identity,
+    Field(Box<Expr>, String),
+    Index(Box<Expr>, i64),
+    Iterate(Box<Expr>),
+    Literal(JsonData),
+    Pipe(Box<Expr>, Box<Expr>),
+    Comma(Box<Expr>, Box<Expr>),
+    Compare(Box<Expr>, Op, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Select(Box<Expr>),
+    Map(Box<Expr>),
+    Not,
+    Length,
+    Keys,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Filter {
+    expr: Expr,
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+struct Parser<'a> {
+    filter: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.filter[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn error(&self, msg: &str) -> JsonError {
+        JsonError::InvalidFilter(format!(
+            "{} at position {} in filter `{}`",
+            msg, self.pos, self.filter
+        ))
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, s: &str) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(s) {
+            self.pos += s.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        self.skip_whitespace();
+        let rest = self.rest();
+        if rest.starts_with(keyword) && !rest[keyword.len()..].starts_with(is_ident) {
+            self.pos += keyword.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, s: &str) -> Result<(), JsonError> {
+        if self.eat(s) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("Expected `{}`", s)))
+        }
+    }
+
+    fn ident(&mut self) -> String {
+        let rest = self.rest();
+        let len = rest.find(|c| !is_ident(c)).unwrap_or(rest.len());
+        self.pos += len;
+        rest[..len].to_string()
+    }
+
+    // Returns the string as written, without the quotes, the same way the parser stores it
+    fn string(&mut self) -> Result<String, JsonError> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut escaped = false;
+        for (i, c) in self.rest().char_indices() {
+            match c {
+                '"' if !escaped => {
+                    let s = unescape_str(&self.rest()[..i]).into_owned();
+                    self.pos += i + 1;
+                    return Ok(s);
+                }
+                '\\' => escaped = !escaped,
+                _ => escaped = false,
+            }
+        }
+        self.pos = start;
+        Err(self.error("Unterminated string"))
+    }
+
+    fn number(&mut self) -> Result<JsonData, JsonError> {
+        let rest = self.rest();
+        let len = rest
+            .char_indices()
+            .find(|&(_, c)| !(c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-')))
+            .map_or(rest.len(), |(i, _)| i);
+        let number = &rest[..len];
+        let data = if let Ok(i) = number.parse() {
+            JsonData::Integer(i)
+        } else if let Ok(f) = number.parse() {
+            JsonData::Float(f)
+        } else {
+            return Err(self.error(&format!("Invalid number `{}`", number)));
+        };
+        self.pos += len;
+        Ok(data)
+    }
+
+    fn pipe(&mut self) -> Result<Expr, JsonError> {
+        let mut expr = self.comma()?;
+        while self.eat("|") {
+            expr = Expr::Pipe(Box::new(expr), Box::new(self.comma()?));
+        }
+        Ok(expr)
+    }
+
+    fn comma(&mut self) -> Result<Expr, JsonError> {
+        let mut expr = self.or()?;
+        while self.eat(",") {
+            expr = Expr::Comma(Box::new(expr), Box::new(self.or()?));
+        }
+        Ok(expr)
+    }
+
+    fn or(&mut self) -> Result<Expr, JsonError> {
+        let mut expr = self.and()?;
+        while self.eat_keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, JsonError> {
+        let mut expr = self.compare()?;
+        while self.eat_keyword("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.compare()?));
+        }
+        Ok(expr)
+    }
+
+    fn compare(&mut self) -> Result<Expr, JsonError> {
+        let expr = self.postfix()?;
+        let ops = [
+            ("==", Op::Eq),
+            ("!=", Op::Ne),
+            ("<=", Op::Le),
+            (">=", Op::Ge),
+            ("<", Op::Lt),
+            (">", Op::Gt),
+        ];
+        for (s, op) in ops {
+            if self.eat(s) {
+                return Ok(Expr::Compare(Box::new(expr), op, Box::new(self.postfix()?)));
+            }
+        }
+        Ok(expr)
+    }
+
+    fn postfix(&mut self) -> Result<Expr, JsonError> {
+        let mut expr = self.primary()?;
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with('[') {
+                self.pos += 1;
+                expr = self.bracket(expr)?;
+            } else if rest.starts_with('.')
+                && rest[1..].starts_with(|c| is_ident_start(c) || c == '"' || c == '[')
+            {
+                self.pos += 1;
+                expr = self.after_dot(expr)?;
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    // Parses what comes directly after a `.` that is applied to `target`
+    fn after_dot(&mut self, target: Expr) -> Result<Expr, JsonError> {
+        match self.peek() {
+            Some('[') => {
+                self.pos += 1;
+                self.bracket(target)
+            }
+            Some('"') => Ok(Expr::Field(Box::new(target), self.string()?)),
+            Some(c) if is_ident_start(c) => Ok(Expr::Field(Box::new(target), self.ident())),
+            _ => Ok(target),
+        }
+    }
+
+    // Parses what comes after a `[` up to and including the matching `]`
+    fn bracket(&mut self, target: Expr) -> Result<Expr, JsonError> {
+        if self.eat("]") {
+            return Ok(Expr::Iterate(Box::new(target)));
+        }
+        self.skip_whitespace();
+        let expr = match self.peek() {
+            Some('"') => Expr::Field(Box::new(target), self.string()?),
+            Some(c) if c == '-' || c.is_ascii_digit() => match self.number()? {
+                JsonData::Integer(i) => Expr::Index(Box::new(target), i),
+                _ => return Err(self.error("Expected an integer index")),
+            },
+            _ => return Err(self.error("Expected a string, an integer or `]`")),
+        };
+        self.expect("]")?;
+        Ok(expr)
+    }
+
+    fn primary(&mut self) -> Result<Expr, JsonError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('.') => {
+                self.pos += 1;
+                self.after_dot(Expr::Identity)
+            }
+            Some('(') => {
+                self.pos += 1;
+                let expr = self.pipe()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Some('"') => Ok(Expr::Literal(JsonData::Str(self.string()?.into()))),
+            Some(c) if c == '-' || c.is_ascii_digit() => Ok(Expr::Literal(self.number()?)),
+            Some(c) if is_ident_start(c) => {
+                let start = self.pos;
+                let expr = match self.ident().as_str() {
+                    "null" => Expr::Literal(JsonData::Null),
+                    "true" => Expr::Literal(JsonData::Bool(true)),
+                    "false" => Expr::Literal(JsonData::Bool(false)),
+                    "not" => Expr::Not,
+                    "length" => Expr::Length,
+                    "keys" => Expr::Keys,
+                    "select" => Expr::Select(Box::new(self.argument()?)),
+                    "map" => Expr::Map(Box::new(self.argument()?)),
+                    name => {
+                        let name = name.to_string();
+                        self.pos = start;
+                        return Err(self.error(&format!("Unknown function `{}`", name)));
+                    }
+                };
+                Ok(expr)
+            }
+            Some(c) => Err(self.error(&format!("Unexpected `{}`", c))),
+            None => Err(self.error("Unexpected end")),
+        }
+    }
+
+    fn argument(&mut self) -> Result<Expr, JsonError> {
+        self.expect("(")?;
+        let expr = self.pipe()?;
+        self.expect(")")?;
+        Ok(expr)
+    }
+}
+
+fn is_truthy(data: &JsonData) -> bool {
+    !matches!(data, JsonData::Null | JsonData::Bool(false))
+}
+
+fn rank(data: &JsonData) -> u8 {
+    match data {
+        JsonData::Null => 0,
+        JsonData::Bool(false) => 1,
+        JsonData::Bool(true) => 2,
+        JsonData::Integer(_) | JsonData::Float(_) => 3,
+        #[cfg(feature = "decimal")]
+        JsonData::Decimal(_) => 3,
+        #[cfg(feature = "bigint")]
+        JsonData::BigInt(_) => 3,
+        #[cfg(feature = "raw_numbers")]
+        JsonData::RawNumber(_) => 3,
+        JsonData::Str(_) => 4,
+        JsonData::Array(_) => 5,
+        JsonData::Object(_) => 6,
+    }
+}
+
+// Orders values like jq does, null < false < true < numbers < strings < arrays < objects
+pub(crate) fn compare(a: &JsonData, b: &JsonData) -> Ordering {
+    match (a.unraw(), b.unraw()) {
+        (JsonData::Integer(a), JsonData::Integer(b)) => a.cmp(b),
+        (JsonData::Integer(a), JsonData::Float(b)) => (*a as f64).total_cmp(b),
+        (JsonData::Float(a), JsonData::Integer(b)) => a.total_cmp(&(*b as f64)),
+        (JsonData::Float(a), JsonData::Float(b)) => a.total_cmp(b),
+        (JsonData::Str(a), JsonData::Str(b)) => a.cmp(b),
+        (JsonData::Array(a), JsonData::Array(b)) => a
+            .iter()
+            .zip(b)
+            .map(|(a, b)| compare(a, b))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| a.len().cmp(&b.len())),
+        (JsonData::Object(a), JsonData::Object(b)) => {
+            let mut a: Vec<_> = a.iter().collect();
+            let mut b: Vec<_> = b.iter().collect();
+            a.sort_by(|a, b| a.0.cmp(b.0));
+            b.sort_by(|a, b| a.0.cmp(b.0));
+            a.iter()
+                .map(|(k, _)| k)
+                .cmp(b.iter().map(|(k, _)| k))
+                .then_with(|| {
+                    a.iter()
+                        .zip(&b)
+                        .map(|(a, b)| compare(a.1, b.1))
+                        .find(|ordering| ordering.is_ne())
+                        .unwrap_or(Ordering::Equal)
+                })
+        }
+        #[cfg(feature = "decimal")]
+        (JsonData::Decimal(a), JsonData::Decimal(b)) => a.cmp(b),
+        #[cfg(feature = "bigint")]
+        (JsonData::BigInt(a), JsonData::BigInt(b)) => a.cmp(b),
+        #[cfg(feature = "bigint")]
+        (JsonData::BigInt(a), JsonData::Integer(b)) => a.cmp(&(*b).into()),
+        #[cfg(feature = "bigint")]
+        (JsonData::Integer(a), JsonData::BigInt(b)) => num_bigint::BigInt::from(*a).cmp(b),
+        #[cfg(any(feature = "decimal", feature = "bigint"))]
+        (a, b) if rank(a) == 3 && rank(b) == 3 => {
+            let float = |data: &JsonData| data.as_f64().expect("THIS SHOULD NEVER PANIC");
+            float(a).total_cmp(&float(b))
+        }
+        (a, b) => rank(a).cmp(&rank(b)),
+    }
+}
+
+impl Op {
+    fn test(self, ordering: Ordering) -> bool {
+        match self {
+            Op::Eq => ordering.is_eq(),
+            Op::Ne => ordering.is_ne(),
+            Op::Lt => ordering.is_lt(),
+            Op::Le => ordering.is_le(),
+            Op::Gt => ordering.is_gt(),
+            Op::Ge => ordering.is_ge(),
+        }
+    }
+}
+
+impl Expr {
+    fn eval(&self, input: &Shared<JsonData>) -> Result<Vec<Shared<JsonData>>, JsonError> {
+        let null = || Shared::new(JsonData::Null);
+        let boolean = |b: bool| Shared::new(JsonData::Bool(b));
+        let mut out = Vec::new();
+        match self {
+            Expr::Identity => out.push(Shared::clone(input)),
+            Expr::Literal(data) => out.push(Shared::new(data.to_owned())),
+            Expr::Field(target, key) => {
+                for data in target.eval(input)? {
+                    out.push(match data.as_ref() {
+                        JsonData::Object(map) => {
+                            map.get(key.as_str()).cloned().unwrap_or_else(null)
+                        }
+                        JsonData::Null => null(),
+                        data => {
+                            let expected = [JsonType::Object, JsonType::Null];
+                            return Err(JsonError::incorrect_type("", &expected, data));
+                        }
+                    });
+                }
+            }
+            Expr::Index(target, index) => {
+                for data in target.eval(input)? {
+                    out.push(match data.as_ref() {
+                        JsonData::Array(arr) => {
+                            let index = if *index < 0 {
+                                arr.len() as i64 + index
+                            } else {
+                                *index
+                            };
+                            usize::try_from(index)
+                                .ok()
+                                .and_then(|index| arr.get(index))
+                                .cloned()
+                                .unwrap_or_else(null)
+                        }
+                        JsonData::Null => null(),
+                        data => {
+                            let expected = [JsonType::Array, JsonType::Null];
+                            return Err(JsonError::incorrect_type("", &expected, data));
+                        }
+                    });
+                }
+            }
+            Expr::Iterate(target) => {
+                for data in target.eval(input)? {
+                    match data.as_ref() {
+                        JsonData::Array(arr) => out.extend(arr.iter().cloned()),
+                        JsonData::Object(map) => out.extend(map.values().cloned()),
+                        data => {
+                            let expected = [JsonType::Array, JsonType::Object];
+                            return Err(JsonError::incorrect_type("", &expected, data));
+                        }
+                    }
+                }
+            }
+            Expr::Pipe(lhs, rhs) => {
+                for data in lhs.eval(input)? {
+                    out.extend(rhs.eval(&data)?);
+                }
+            }
+            Expr::Comma(lhs, rhs) => {
+                out.extend(lhs.eval(input)?);
+                out.extend(rhs.eval(input)?);
+            }
+            Expr::Compare(lhs, op, rhs) => {
+                for r in rhs.eval(input)? {
+                    for l in lhs.eval(input)? {
+                        out.push(boolean(op.test(compare(&l, &r))));
+                    }
+                }
+            }
+            Expr::And(lhs, rhs) => {
+                for l in lhs.eval(input)? {
+                    if !is_truthy(&l) {
+                        out.push(boolean(false));
+                        continue;
+                    }
+                    for r in rhs.eval(input)? {
+                        out.push(boolean(is_truthy(&r)));
+                    }
+                }
+            }
+            Expr::Or(lhs, rhs) => {
+                for l in lhs.eval(input)? {
+                    if is_truthy(&l) {
+                        out.push(boolean(true));
+                        continue;
+                    }
+                    for r in rhs.eval(input)? {
+                        out.push(boolean(is_truthy(&r)));
+                    }
+                }
+            }
+            Expr::Select(condition) => {
+                for data in condition.eval(input)? {
+                    if is_truthy(&data) {
+                        out.push(Shared::clone(input));
+                    }
+                }
+            }
+            Expr::Map(f) => {
+                let mut arr = Vec::new();
+                for data in Expr::Iterate(Box::new(Expr::Identity)).eval(input)? {
+                    arr.extend(f.eval(&data)?);
+                }
+                out.push(Shared::new(JsonData::Array(arr)));
+            }
+            Expr::Not => out.push(boolean(!is_truthy(input))),
+            Expr::Length => out.push(Shared::new(match input.as_ref() {
+                JsonData::Null => JsonData::Integer(0),
+                JsonData::Str(s) => JsonData::Integer(s.chars().count() as i64),
+                JsonData::Array(arr) => JsonData::Integer(arr.len() as i64),
+                JsonData::Object(map) => JsonData::Integer(map.len() as i64),
+                JsonData::Integer(i) => JsonData::Integer(i.saturating_abs()),
+                JsonData::Float(f) => JsonData::Float(f.abs()),
+                #[cfg(feature = "decimal")]
+                JsonData::Decimal(d) => JsonData::Decimal(d.abs()),
+                #[cfg(feature = "bigint")]
+                JsonData::BigInt(i) => JsonData::BigInt(num_traits::Signed::abs(i)),
+                #[cfg(feature = "raw_numbers")]
+                JsonData::RawNumber(raw) => {
+                    return Expr::Length.eval(&Shared::new(raw.data.clone()))
+                }
+                data @ JsonData::Bool(_) => {
+                    let expected = [
+                        JsonType::Null,
+                        JsonType::Integer,
+                        JsonType::Float,
+                        JsonType::String,
+                        JsonType::Array,
+                        JsonType::Object,
+                    ];
+                    return Err(JsonError::incorrect_type("", &expected, data));
+                }
+            })),
+            Expr::Keys => out.push(Shared::new(match input.as_ref() {
+                JsonData::Object(map) => {
+                    let mut keys: Vec<&SmallString> = map.keys().collect();
+                    keys.sort();
+                    JsonData::array(keys.into_iter().map(|key| JsonData::Str(key.clone())))
+                }
+                JsonData::Array(arr) => {
+                    JsonData::array((0..arr.len() as i64).map(JsonData::Integer))
+                }
+                data => {
+                    let expected = [JsonType::Object, JsonType::Array];
+                    return Err(JsonError::incorrect_type("", &expected, data));
+                }
+            })),
+        }
+        Ok(out)
+    }
+}
+
+impl Filter {
+    pub fn compile(filter: &str) -> Result<Self, JsonError> {
+        let mut parser = Parser { filter, pos: 0 };
+        let expr = parser.pipe()?;
+        parser.skip_whitespace();
+        match parser.peek() {
+            None => Ok(Filter { expr }),
+            Some(c) => Err(parser.error(&format!("Unexpected `{}`", c))),
+        }
+    }
+
+    // Runs the filter and returns every value it produced
+    pub fn eval<S: JsonState>(&self, json: &Json<S>) -> Result<Vec<AnyJson>, JsonError> {
+        Ok(self
+            .expr
+            .eval(&json.data)?
+            .into_iter()
+            .map(AnyJson::from_shared)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::FormatOptions;
+    use crate::json::JsonType;
+    use crate::json::Object;
+
+    fn run(filter: &str, json: &str) -> Result<Vec<String>, JsonError> {
+        let json: Json<Object> = Json::new(json)?;
+        Ok(Filter::compile(filter)?
+            .eval(&json)?
+            .iter()
+            .map(|json| json.to_formatted_string(&FormatOptions::minified()))
+            .collect())
+    }
+
+    #[test]
+    fn filter_paths() {
+        let json = "{\"items\": [{\"name\": \"a\", \"price\": 5}, {\"name\": \"b\", \"price\": 12.5}], \"a b\": 1}";
+        assert_eq!(Ok(vec![String::from("\"b\"")]), run(".items[1].name", json));
+        assert_eq!(
+            Ok(vec![String::from("\"b\"")]),
+            run(".items[-1][\"name\"]", json)
+        );
+        assert_eq!(Ok(vec![String::from("1")]), run(".\"a b\"", json));
+        assert_eq!(Ok(vec![String::from("null")]), run(".missing.name", json));
+        assert_eq!(
+            Ok(vec![String::from("5"), String::from("12.5")]),
+            run(".items[] | .price", json)
+        );
+        assert_eq!(
+            Ok(vec![String::from("[\"a\",\"b\"]"), String::from("2")]),
+            run(".items | map(.name), length", json)
+        );
+        assert_eq!(
+            Ok(vec![String::from("[\"a b\",\"items\"]")]),
+            run("keys", json)
+        );
+    }
+
+    #[test]
+    fn filter_select() {
+        let json = "{\"items\": [{\"name\": \"a\", \"price\": 5}, {\"name\": \"b\", \"price\": 12.5}, {\"name\": \"c\", \"price\": 20, \"sold\": true}]}";
+        assert_eq!(
+            Ok(vec![String::from("\"b\""), String::from("\"c\"")]),
+            run(".items[] | select(.price > 10) | .name", json)
+        );
+        assert_eq!(
+            Ok(vec![String::from("\"b\"")]),
+            run(
+                ".items[] | select(.price > 10 and (.sold | not)) | .name",
+                json
+            )
+        );
+        assert_eq!(
+            Ok(vec![String::from("\"a\""), String::from("\"c\"")]),
+            run(
+                ".items[] | select(.price == 5 or .name == \"c\") | .name",
+                json
+            )
+        );
+        assert_eq!(
+            Ok(vec![String::from("true"), String::from("false")]),
+            run(".items[0].price == 5.0, (.items[0] | not)", json)
+        );
+    }
+
+    #[test]
+    fn filter_errors() {
+        for filter in [
+            "",
+            ".[",
+            ".a |",
+            "select(.a",
+            "foo",
+            ".a ]",
+            "\"x",
+            ".[1.5]",
+        ] {
+            assert!(
+                matches!(Filter::compile(filter), Err(JsonError::InvalidFilter(_))),
+                "{filter}"
+            );
+        }
+        assert_eq!(
+            Err(JsonError::IncorrectType {
+                path: "".to_string(),
+                expected: vec![JsonType::Array, JsonType::Object],
+                found: JsonType::Integer,
+            }),
+            run(".a[]", "{\"a\": 1}")
+        );
+        assert_eq!(
+            Err(JsonError::IncorrectType {
+                path: "".to_string(),
+                expected: vec![JsonType::Object, JsonType::Null],
+                found: JsonType::Integer,
+            }),
+            run(".a.b", "{\"a\": 1}")
+        );
+    }
+}
@@ -0,0 +1,1159 @@
+use crate::cst::Cst;
+use crate::escape::escape_str;
+use crate::json::{AnyJson, Json, JsonError, JsonState};
+use crate::lexer::{Token, TokenKind};
+use crate::parser::{JsonData, Map, ParseError, Shared};
+use crate::small::SmallString;
+use crate::stream::{Event, Events, ReadChars};
+use crate::tokens::Loc;
+use std::cmp::Ordering;
+use std::ffi::OsString;
+use std::fmt;
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io;
+use std::io::{BufWriter, Read, Write};
+use std::ops::Range;
+use std::path::Path;
+
+// The order the members of an object are written in. `Preserve` is the order they were
+// parsed or inserted in with the `preserve_order` feature and sorted otherwise, as the
+// default hash map has no order that stays the same from run to run. `Sorted` and `Custom`
+// compare the unescaped keys.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum KeyOrder {
+    #[default]
+    Preserve,
+    Sorted,
+    Custom(fn(&str, &str) -> Ordering),
+}
+
+// Custom orders are the same if they are the same function
+impl PartialEq for KeyOrder {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (KeyOrder::Preserve, KeyOrder::Preserve) | (KeyOrder::Sorted, KeyOrder::Sorted) => true,
+            (KeyOrder::Custom(a), KeyOrder::Custom(b)) => std::ptr::fn_addr_eq(*a, *b),
+            _ => false,
+        }
+    }
+}
+
+// How floats are written. `Shortest` is the shortest text that reads back as the same
+// float, with an exponent below 1e-6 and from 1e21 on like JavaScript. `Fixed` writes that
+// many digits after the `.`, trailing zeros included and at least one. `Scientific` is the
+// shortest text too but with an exponent once it is at least that big, or that small for
+// negative exponents, so `Scientific(3)` writes `1234.5` as `1.2345e3`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FloatFormat {
+    #[default]
+    Shortest,
+    Fixed(usize),
+    Scientific(u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FormatOptions {
+    indent: Option<String>,
+    float_format: FloatFormat,
+    ascii_only: bool,
+    html_safe: bool,
+    key_order: KeyOrder,
+    inline_width: Option<usize>,
+}
+
+impl FormatOptions {
+    pub fn minified() -> Self {
+        Self {
+            indent: None,
+            float_format: FloatFormat::Shortest,
+            ascii_only: false,
+            html_safe: false,
+            key_order: KeyOrder::Preserve,
+            inline_width: None,
+        }
+    }
+
+    pub fn pretty(indent: usize) -> Self {
+        Self::indented(" ".repeat(indent))
+    }
+
+    // Pretty with one tab per level
+    pub fn tabs() -> Self {
+        Self::indented(String::from("\t"))
+    }
+
+    fn indented(indent: String) -> Self {
+        Self {
+            indent: Some(indent),
+            float_format: FloatFormat::Shortest,
+            ascii_only: false,
+            html_safe: false,
+            key_order: KeyOrder::Preserve,
+            inline_width: None,
+        }
+    }
+
+    // Writes the members of objects in `order`, e.g. sorted so the output of the same json
+    // is always the same. `reformat` keeps the members in the order they were written.
+    pub fn key_order(mut self, order: KeyOrder) -> Self {
+        self.key_order = order;
+        self
+    }
+
+    // Serializes floats with `digits` digits after the `.` instead of the shortest form that
+    // reads back as the same float, at least one digit is written so they stay floats.
+    // `reformat` keeps numbers as they were written.
+    pub fn float_precision(mut self, digits: usize) -> Self {
+        self.float_format = FloatFormat::Fixed(digits);
+        self
+    }
+
+    // Like `float_precision` but any `FloatFormat`. With the `raw_numbers` feature floats
+    // keep their text only with `FloatFormat::Shortest`.
+    pub fn float_format(mut self, format: FloatFormat) -> Self {
+        self.float_format = format;
+        self
+    }
+
+    // Escapes every character outside of ASCII as `\uXXXX`, as a surrogate pair when needed
+    pub fn ascii_only(mut self) -> Self {
+        self.ascii_only = true;
+        self
+    }
+
+    // Escapes `<`, `>`, `&`, U+2028 and U+2029 so the json can be put inside a `<script>` tag
+    pub fn html_safe(mut self) -> Self {
+        self.html_safe = true;
+        self
+    }
+
+    // Keeps arrays and objects that are at most `width` chars long on one line, like
+    // `[1, 2]` and `{"a": 1}`, when pretty. Larger ones are indented and their members get
+    // the same choice.
+    pub fn inline_width(mut self, width: usize) -> Self {
+        self.inline_width = Some(width);
+        self
+    }
+
+    pub fn is_pretty(&self) -> bool {
+        self.indent.is_some()
+    }
+}
+
+// Writes the escaped text of a string or key with the extra escaping `options` asks for. The
+// characters escaped here are never part of an escape sequence so they can be replaced one
+// by one.
+pub(crate) fn write_str(text: &str, out: &mut String, options: &FormatOptions) {
+    let escapes = |c: char| {
+        (options.ascii_only && !c.is_ascii())
+            || (options.html_safe && matches!(c, '<' | '>' | '&' | '\u{2028}' | '\u{2029}'))
+    };
+    if !text.contains(escapes) {
+        out.push_str(text);
+        return;
+    }
+    for c in text.chars() {
+        if escapes(c) {
+            for unit in c.encode_utf16(&mut [0; 2]) {
+                write!(out, "\\u{:04x}", unit).expect("THIS SHOULD NEVER PANIC");
+            }
+        } else {
+            out.push(c);
+        }
+    }
+}
+
+// The exponent of a float written as `scientific` by `{:e}`
+fn exponent(scientific: &str) -> i32 {
+    scientific
+        .split_once('e')
+        .and_then(|(_, exponent)| exponent.parse().ok())
+        .expect("THIS SHOULD NEVER PANIC")
+}
+
+// Whether a float written as `scientific` by `{:e}` is written without an exponent
+fn is_fixed(scientific: &str) -> bool {
+    (-6..21).contains(&exponent(scientific))
+}
+
+// `f` with `digits` digits after the `.`, with an exponent for the same floats as
+// `format_float`
+fn format_float_with_precision(f: f64, digits: usize) -> String {
+    if is_fixed(&format!("{:e}", f)) {
+        format!("{:.*}", digits, f)
+    } else {
+        format!("{:.*e}", digits, f)
+    }
+}
+
+// The shortest text that parses back to exactly `f`. Like JavaScript, numbers from 1e-6 up
+// to 1e21 are written out in full and others with an exponent, so `1e300` does not become
+// 301 digits. Floats without a fraction get a `.0` so they are not read back as integers.
+pub(crate) fn format_float(f: f64) -> String {
+    let scientific = format!("{:e}", f);
+    if !is_fixed(&scientific) {
+        return scientific;
+    }
+    let mut float = f.to_string();
+    if !float.contains('.') {
+        float.push_str(".0");
+    }
+    float
+}
+
+fn format_float_as(f: f64, format: FloatFormat) -> String {
+    match format {
+        FloatFormat::Shortest => format_float(f),
+        FloatFormat::Fixed(digits) => format_float_with_precision(f, digits.max(1)),
+        FloatFormat::Scientific(from) => {
+            let scientific = format!("{:e}", f);
+            if exponent(&scientific).unsigned_abs() >= from {
+                scientific
+            } else {
+                format_float(f)
+            }
+        }
+    }
+}
+
+pub(crate) fn display(data: &JsonData, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let options = if f.alternate() {
+        FormatOptions::pretty(2)
+    } else {
+        FormatOptions::minified()
+    };
+    let mut out = String::new();
+    write_data(data, &mut out, 0, &options);
+    f.write_str(&out)
+}
+
+pub(crate) fn io_error(e: io::Error) -> JsonError {
+    JsonError::FileError(e.kind())
+}
+
+fn write_token_str<W: Write>(
+    writer: &mut W,
+    text: &str,
+    options: &FormatOptions,
+) -> io::Result<()> {
+    let mut out = String::with_capacity(text.len());
+    write_str(text, &mut out, options);
+    writer.write_all(out.as_bytes())
+}
+
+// Writes a stream of events back out as json, keeping track of where commas and
+// newlines go
+pub(crate) struct EventWriter<'a> {
+    options: &'a FormatOptions,
+    depth: usize,
+    first: bool,
+    after_key: bool,
+    // Writes everything on one line
+    inline: bool,
+    // The array or object that is held back until it is known whether it fits on one line
+    pending: Option<Box<Pending<'a>>>,
+}
+
+// The events of an array or object with `inline_width` and its text on one line so far
+struct Pending<'a> {
+    events: Vec<Event>,
+    line: EventWriter<'a>,
+    text: Vec<u8>,
+    open: usize,
+}
+
+impl<'a> EventWriter<'a> {
+    pub(crate) fn new(options: &'a FormatOptions) -> Self {
+        Self {
+            options,
+            depth: 0,
+            first: true,
+            after_key: false,
+            inline: false,
+            pending: None,
+        }
+    }
+
+    pub(crate) fn write<W: Write>(&mut self, writer: &mut W, event: &Event) -> io::Result<()> {
+        let width = match self.options.inline_width {
+            Some(width) if self.options.is_pretty() && !self.inline => width,
+            _ => return self.write_event(writer, event),
+        };
+        let Some(pending) = &mut self.pending else {
+            if !matches!(event, Event::StartObject(_) | Event::StartArray(_)) {
+                return self.write_event(writer, event);
+            }
+            let mut line = EventWriter::new(self.options);
+            line.inline = true;
+            self.pending = Some(Box::new(Pending {
+                events: Vec::new(),
+                line,
+                text: Vec::new(),
+                open: 0,
+            }));
+            return self.write(writer, event);
+        };
+        pending.events.push(event.clone());
+        pending.line.write_event(&mut pending.text, event)?;
+        match event {
+            Event::StartObject(_) | Event::StartArray(_) => pending.open += 1,
+            Event::EndObject(_) | Event::EndArray(_) => pending.open -= 1,
+            _ => {}
+        }
+        let text = String::from_utf8_lossy(&pending.text);
+        if text.chars().count() > width {
+            // Too long, so only this array or object is indented and what is in it gets
+            // written again to see what fits
+            let events = self.pending.take().expect("THIS SHOULD NEVER PANIC").events;
+            self.write_event(writer, &events[0])?;
+            for event in &events[1..] {
+                self.write(writer, event)?;
+            }
+        } else if pending.open == 0 {
+            let text = text.into_owned();
+            self.pending = None;
+            self.write_event(writer, &Event::Scalar(token(TokenKind::Null, text)))?;
+        }
+        Ok(())
+    }
+
+    fn write_event<W: Write>(&mut self, writer: &mut W, event: &Event) -> io::Result<()> {
+        let is_end = matches!(event, Event::EndObject(_) | Event::EndArray(_));
+        if is_end {
+            self.depth -= 1;
+            if !self.first && !self.inline {
+                write_newline(writer, self.depth, self.options)?;
+            }
+        } else if !self.after_key && self.depth > 0 {
+            if !self.first {
+                writer.write_all(if self.inline { b", " } else { b"," })?;
+            }
+            if !self.inline {
+                write_newline(writer, self.depth, self.options)?;
+            }
+        }
+        self.first = false;
+        self.after_key = false;
+        match event {
+            Event::StartObject(token) | Event::StartArray(token) => {
+                writer.write_all(token.text.as_bytes())?;
+                self.depth += 1;
+                self.first = true;
+            }
+            Event::Key(token) => {
+                write_token_str(writer, &token.text, self.options)?;
+                let colon: &[u8] = if self.options.is_pretty() {
+                    b": "
+                } else {
+                    b":"
+                };
+                writer.write_all(colon)?;
+                self.after_key = true;
+            }
+            Event::Scalar(token) if token.kind == TokenKind::Str => {
+                write_token_str(writer, &token.text, self.options)?;
+            }
+            Event::EndObject(token) | Event::EndArray(token) | Event::Scalar(token) => {
+                writer.write_all(token.text.as_bytes())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// A failed read shows up as a premature end of the json, so the read error takes
+// precedence over the syntax error
+pub(crate) fn event_error(error: ParseError, read_error: Option<io::ErrorKind>) -> JsonError {
+    match read_error {
+        Some(kind) => JsonError::FileError(kind),
+        None => syntax_error(error),
+    }
+}
+
+// Rewrites the json read from `reader` into `writer` token by token, so the whole document
+// never has to be in memory. Scalars are copied exactly as they were written. Input with
+// nothing but whitespace is an error, like it is for `Json::new`.
+pub fn reformat<R: Read, W: Write>(
+    reader: R,
+    writer: W,
+    options: &FormatOptions,
+) -> Result<(), JsonError> {
+    let chars = ReadChars::new(reader);
+    let read_error = chars.error();
+    let mut writer = BufWriter::new(writer);
+    let mut event_writer = EventWriter::new(options);
+    let mut empty = true;
+    for event in Events::new(chars) {
+        let event = event.map_err(|error| event_error(error, read_error.get()))?;
+        event_writer.write(&mut writer, &event).map_err(io_error)?;
+        empty = false;
+    }
+    if let Some(kind) = read_error.get() {
+        return Err(JsonError::FileError(kind));
+    }
+    if empty {
+        return Err(syntax_error(ParseError::EmptyInput));
+    }
+    writer.flush().map_err(io_error)
+}
+
+// Reformats the smallest value in `source` that contains the byte range `range` and
+// leaves the text around it as it is. Lines added inside the value are indented like the
+// line the value starts on.
+pub fn format_range(
+    source: &str,
+    range: Range<usize>,
+    options: &FormatOptions,
+) -> Result<String, JsonError> {
+    if range.start > range.end || range.end > source.len() {
+        return Err(JsonError::index_not_found(""));
+    }
+    let span = Cst::parse(source)?.value_span(&range);
+    let mut formatted = Vec::new();
+    reformat(source[span.clone()].as_bytes(), &mut formatted, options)?;
+    let formatted = String::from_utf8(formatted).expect("THIS SHOULD NEVER PANIC");
+    let line = &source[source[..span.start].rfind('\n').map_or(0, |i| i + 1)..];
+    let indent = &line[..line.len() - line.trim_start_matches([' ', '\t']).len()];
+    Ok(format!(
+        "{}{}{}",
+        &source[..span.start],
+        formatted.replace('\n', &format!("\n{}", indent)),
+        &source[span.end..]
+    ))
+}
+
+fn write_newline<W: Write>(
+    writer: &mut W,
+    depth: usize,
+    options: &FormatOptions,
+) -> io::Result<()> {
+    if let Some(indent) = &options.indent {
+        writer.write_all(b"\n")?;
+        for _ in 0..depth {
+            writer.write_all(indent.as_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+// The members of `map` in the order `options` asks for
+fn members<'a>(
+    map: &'a Map<SmallString, Shared<JsonData>>,
+    options: &FormatOptions,
+) -> Vec<(&'a SmallString, &'a Shared<JsonData>)> {
+    let mut members: Vec<_> = map.iter().collect();
+    let compare: fn(&str, &str) -> Ordering = match options.key_order {
+        #[cfg(not(any(feature = "preserve_order", feature = "btreemap")))]
+        KeyOrder::Preserve => str::cmp,
+        #[cfg(any(feature = "preserve_order", feature = "btreemap"))]
+        KeyOrder::Preserve => return members,
+        KeyOrder::Sorted => str::cmp,
+        KeyOrder::Custom(compare) => compare,
+    };
+    members.sort_by(|(a, _), (b, _)| compare(a, b));
+    members
+}
+
+// Serializes a parsed json, the members of objects in the key order of `options`
+pub(crate) fn write_data(data: &JsonData, out: &mut String, depth: usize, options: &FormatOptions) {
+    write_value(data, out, depth, options, false)
+}
+
+// Like `write_data`, on one line when `inline`
+fn write_value(
+    data: &JsonData,
+    out: &mut String,
+    depth: usize,
+    options: &FormatOptions,
+    inline: bool,
+) {
+    if let (Some(width), false, JsonData::Array(_) | JsonData::Object(_)) =
+        (options.inline_width, inline, data)
+    {
+        if options.is_pretty() {
+            let mut line = String::new();
+            write_value(data, &mut line, depth, options, true);
+            if line.chars().count() <= width {
+                out.push_str(&line);
+                return;
+            }
+        }
+    }
+    let separator = if inline { ", " } else { "," };
+    let newline = |out: &mut String, depth: usize| {
+        if inline {
+            return;
+        }
+        if let Some(indent) = &options.indent {
+            out.push('\n');
+            for _ in 0..depth {
+                out.push_str(indent);
+            }
+        }
+    };
+    match data {
+        JsonData::Null => out.push_str("null"),
+        JsonData::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        JsonData::Str(s) => {
+            out.push('"');
+            write_str(&escape_str(s), out, options);
+            out.push('"');
+        }
+        JsonData::Integer(i) => out.push_str(&i.to_string()),
+        JsonData::Float(f) if !f.is_finite() => out.push_str("null"),
+        JsonData::Float(f) => out.push_str(&format_float_as(*f, options.float_format)),
+        #[cfg(feature = "decimal")]
+        JsonData::Decimal(d) => out.push_str(&d.to_string()),
+        #[cfg(feature = "raw_numbers")]
+        JsonData::RawNumber(raw) if options.float_format == FloatFormat::Shortest => {
+            out.push_str(&raw.text)
+        }
+        #[cfg(feature = "raw_numbers")]
+        JsonData::RawNumber(raw) => write_value(&raw.data, out, depth, options, inline),
+        #[cfg(feature = "bigint")]
+        JsonData::BigInt(i) => out.push_str(&i.to_string()),
+        JsonData::Array(arr) if arr.is_empty() => out.push_str("[]"),
+        JsonData::Object(map) if map.is_empty() => out.push_str("{}"),
+        JsonData::Array(arr) => {
+            out.push('[');
+            for (i, data) in arr.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(separator);
+                }
+                newline(out, depth + 1);
+                write_value(data, out, depth + 1, options, inline);
+            }
+            newline(out, depth);
+            out.push(']');
+        }
+        JsonData::Object(map) => {
+            out.push('{');
+            for (i, (key, data)) in members(map, options).into_iter().enumerate() {
+                if i > 0 {
+                    out.push_str(separator);
+                }
+                newline(out, depth + 1);
+                out.push('"');
+                write_str(&escape_str(key), out, options);
+                out.push_str(if options.is_pretty() { "\": " } else { "\":" });
+                write_value(data, out, depth + 1, options, inline);
+            }
+            newline(out, depth);
+            out.push('}');
+        }
+    }
+}
+
+impl<S: JsonState> Json<S> {
+    pub fn to_formatted_string(&self, options: &FormatOptions) -> String {
+        let mut out = String::new();
+        write_data(&self.data, &mut out, 0, options);
+        out
+    }
+
+    // Like `to_formatted_string` but writes to `writer` as it goes, without building the
+    // whole string first
+    pub fn write_to<W: Write>(&self, writer: W, options: &FormatOptions) -> Result<(), JsonError> {
+        let mut json_writer = JsonWriter::new(BufWriter::new(writer), options);
+        json_writer.value(self)?;
+        json_writer.finish()?.flush().map_err(io_error)
+    }
+
+    pub fn to_file<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: &FormatOptions,
+    ) -> Result<(), JsonError> {
+        self.write_to(File::create(path).map_err(io_error)?, options)
+    }
+
+    // Like `to_file` but the json is written to a temporary file next to `path` that then
+    // replaces it, so `path` holds either the old or the new json even if writing fails
+    // half way
+    pub fn to_file_atomic<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: &FormatOptions,
+    ) -> Result<(), JsonError> {
+        let path = path.as_ref();
+        let name = path
+            .file_name()
+            .ok_or(JsonError::FileError(io::ErrorKind::InvalidInput))?;
+        let mut temp_name = OsString::from(".");
+        temp_name.push(name);
+        temp_name.push(format!(".{}.tmp", std::process::id()));
+        let temp = path.with_file_name(temp_name);
+        let result = (|| {
+            let file = File::create(&temp).map_err(io_error)?;
+            self.write_to(&file, options)?;
+            file.sync_all().map_err(io_error)?;
+            fs::rename(&temp, path).map_err(io_error)
+        })();
+        if result.is_err() {
+            let _ = fs::remove_file(&temp);
+        }
+        result
+    }
+}
+
+// Writes a json piece by piece straight to `writer`, e.g. for responses too large to build
+// in memory first. A call that would make the json invalid, like a key in an array, returns
+// `InvalidJsonSyntax` and writes nothing. Small writes are made so `writer` should be
+// buffered.
+pub struct JsonWriter<'a, W: Write> {
+    writer: W,
+    events: EventWriter<'a>,
+    // true for the objects and false for the arrays that are open
+    levels: Vec<bool>,
+    after_key: bool,
+    done: bool,
+}
+
+fn misuse(msg: &str) -> JsonError {
+    JsonError::InvalidJsonSyntax(String::from(msg))
+}
+
+fn token(kind: TokenKind, text: String) -> Token {
+    Token {
+        kind,
+        text,
+        loc: Loc::start(),
+    }
+}
+
+impl<'a, W: Write> JsonWriter<'a, W> {
+    pub fn new(writer: W, options: &'a FormatOptions) -> Self {
+        Self {
+            writer,
+            events: EventWriter::new(options),
+            levels: Vec::new(),
+            after_key: false,
+            done: false,
+        }
+    }
+
+    pub fn begin_object(&mut self) -> Result<(), JsonError> {
+        self.start_value()?;
+        self.write(&Event::StartObject(token(
+            TokenKind::OpenCurly,
+            String::from("{"),
+        )))?;
+        self.levels.push(true);
+        Ok(())
+    }
+
+    pub fn end_object(&mut self) -> Result<(), JsonError> {
+        if self.levels.last() != Some(&true) || self.after_key {
+            return Err(misuse("There is no object to end"));
+        }
+        self.write(&Event::EndObject(token(
+            TokenKind::CloseCurly,
+            String::from("}"),
+        )))?;
+        self.end_level();
+        Ok(())
+    }
+
+    pub fn begin_array(&mut self) -> Result<(), JsonError> {
+        self.start_value()?;
+        self.write(&Event::StartArray(token(
+            TokenKind::OpenBracket,
+            String::from("["),
+        )))?;
+        self.levels.push(false);
+        Ok(())
+    }
+
+    pub fn end_array(&mut self) -> Result<(), JsonError> {
+        if self.levels.last() != Some(&false) {
+            return Err(misuse("There is no array to end"));
+        }
+        self.write(&Event::EndArray(token(
+            TokenKind::CloseBracket,
+            String::from("]"),
+        )))?;
+        self.end_level();
+        Ok(())
+    }
+
+    // The key of the next member of the current object, unescaped
+    pub fn key(&mut self, key: &str) -> Result<(), JsonError> {
+        if self.levels.last() != Some(&true) || self.after_key {
+            return Err(misuse("A key can only come before a value in an object"));
+        }
+        let text = format!("\"{}\"", escape_str(key));
+        self.write(&Event::Key(token(TokenKind::Str, text)))?;
+        self.after_key = true;
+        Ok(())
+    }
+
+    // Writes a whole json as the next value
+    pub fn value<S: JsonState>(&mut self, json: &Json<S>) -> Result<(), JsonError> {
+        self.start_value()?;
+        self.write_data(&json.data)?;
+        if self.levels.is_empty() {
+            self.done = true;
+        }
+        Ok(())
+    }
+
+    // Returns the writer once the json is complete
+    pub fn finish(self) -> Result<W, JsonError> {
+        if !self.done {
+            return Err(misuse("The json is not complete"));
+        }
+        Ok(self.writer)
+    }
+
+    fn start_value(&mut self) -> Result<(), JsonError> {
+        if self.done {
+            return Err(misuse("The json is already complete"));
+        }
+        if self.levels.last() == Some(&true) && !self.after_key {
+            return Err(misuse("A value in an object needs a key first"));
+        }
+        self.after_key = false;
+        Ok(())
+    }
+
+    fn end_level(&mut self) {
+        self.levels.pop();
+        if self.levels.is_empty() {
+            self.done = true;
+        }
+    }
+
+    fn write(&mut self, event: &Event) -> Result<(), JsonError> {
+        self.events.write(&mut self.writer, event).map_err(io_error)
+    }
+
+    fn write_data(&mut self, data: &JsonData) -> Result<(), JsonError> {
+        write_data_events(&mut self.events, &mut self.writer, data).map_err(io_error)
+    }
+}
+
+// Writes a parsed json as the events it would be read as
+pub(crate) fn write_data_events<W: Write>(
+    events: &mut EventWriter,
+    writer: &mut W,
+    data: &JsonData,
+) -> io::Result<()> {
+    match data {
+        JsonData::Object(map) => {
+            events.write(
+                writer,
+                &Event::StartObject(token(TokenKind::OpenCurly, String::from("{"))),
+            )?;
+            for (key, data) in members(map, events.options) {
+                let text = format!("\"{}\"", escape_str(key));
+                events.write(writer, &Event::Key(token(TokenKind::Str, text)))?;
+                write_data_events(events, writer, data)?;
+            }
+            events.write(
+                writer,
+                &Event::EndObject(token(TokenKind::CloseCurly, String::from("}"))),
+            )
+        }
+        JsonData::Array(arr) => {
+            events.write(
+                writer,
+                &Event::StartArray(token(TokenKind::OpenBracket, String::from("["))),
+            )?;
+            for data in arr {
+                write_data_events(events, writer, data)?;
+            }
+            events.write(
+                writer,
+                &Event::EndArray(token(TokenKind::CloseBracket, String::from("]"))),
+            )
+        }
+        JsonData::Str(s) => events.write(
+            writer,
+            &Event::Scalar(token(TokenKind::Str, format!("\"{}\"", escape_str(s)))),
+        ),
+        data => {
+            let mut text = String::new();
+            write_data(data, &mut text, 0, events.options);
+            events.write(writer, &Event::Scalar(token(TokenKind::Null, text)))
+        }
+    }
+}
+
+impl AnyJson {
+    pub fn to_formatted_string(&self, options: &FormatOptions) -> String {
+        let mut out = String::new();
+        write_data(self.data(), &mut out, 0, options);
+        out
+    }
+}
+
+pub(crate) fn syntax_error(error: ParseError) -> JsonError {
+    JsonError::InvalidJsonSyntax(format!("{}", error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{Array, Object, Value, MAX_DEPTH};
+
+    fn reformat_str(json: &str, options: &FormatOptions) -> Result<String, JsonError> {
+        let mut out = Vec::new();
+        reformat(json.as_bytes(), &mut out, options)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn format_range_selection() {
+        let source = "{\n  \"a\": [1,2],\n  \"b\": {\"c\":true}\n}\n";
+        let start = source.find("\"c").unwrap();
+        assert_eq!(
+            Ok(String::from(
+                "{\n  \"a\": [1,2],\n  \"b\": {\n    \"c\": true\n  }\n}\n"
+            )),
+            format_range(source, start..start + 3, &FormatOptions::pretty(2))
+        );
+        let start = source.find('1').unwrap();
+        assert_eq!(
+            Ok(String::from(
+                "{\n  \"a\": [1,2],\n  \"b\": {\"c\":true}\n}\n"
+            )),
+            format_range(source, start..start + 1, &FormatOptions::pretty(2))
+        );
+        assert_eq!(
+            Ok(String::from("{\"a\":[1,2],\"b\":{\"c\":true}}\n")),
+            format_range(source, 0..source.len(), &FormatOptions::minified())
+        );
+        assert_eq!(
+            Err(JsonError::IndexNotFound {
+                path: "".to_string()
+            }),
+            format_range(source, 0..100, &FormatOptions::minified())
+        );
+        assert!(format_range("[1,", 0..1, &FormatOptions::minified()).is_err());
+        let deep = format!("{}{}", "[".repeat(MAX_DEPTH + 1), "]".repeat(MAX_DEPTH + 1));
+        assert!(format_range(&deep, 0..1, &FormatOptions::minified()).is_err());
+        let deepest = format!("{}{}", "[".repeat(MAX_DEPTH), "]".repeat(MAX_DEPTH));
+        assert_eq!(
+            Ok(deepest.clone()),
+            format_range(&deepest, 0..1, &FormatOptions::minified())
+        );
+    }
+
+    #[test]
+    fn reformat_minified() {
+        assert_eq!(
+            Ok(String::from(
+                "{\"a\":[1,2.5,{}],\"b\":{\"c\":\"d e\"},\"f\":[]}"
+            )),
+            reformat_str(
+                "{ \"a\" : [ 1 , 2.5, { } ],\n \"b\": {\"c\": \"d e\"}, \"f\": [\n] }",
+                &FormatOptions::minified()
+            )
+        );
+    }
+
+    #[test]
+    fn reformat_pretty() {
+        assert_eq!(
+            Ok(String::from(
+                "{\n  \"a\": [\n    1,\n    {\n      \"b\": null\n    },\n    []\n  ],\n  \"c\": {}\n}"
+            )),
+            reformat_str(
+                "{\"a\":[1,{\"b\":null},[]],\"c\":{}}",
+                &FormatOptions::pretty(2)
+            )
+        );
+        assert_eq!(
+            Ok(String::from("\"s\"")),
+            reformat_str(" \"s\" ", &FormatOptions::pretty(4))
+        );
+    }
+
+    #[test]
+    fn to_formatted_string() {
+        let json: Json<Array> =
+            Json::new("[1, 2.0, {\"a\": [true, null]}, \"s\", [], {}]").unwrap();
+        assert_eq!(
+            "[1,2.0,{\"a\":[true,null]},\"s\",[],{}]",
+            json.to_formatted_string(&FormatOptions::minified())
+        );
+        assert_eq!(
+            "[\n 1,\n 2.0,\n {\n  \"a\": [\n   true,\n   null\n  ]\n },\n \"s\",\n [],\n {}\n]",
+            json.to_formatted_string(&FormatOptions::pretty(1))
+        );
+    }
+
+    #[test]
+    fn tabs_and_inline_width() {
+        let text =
+            "{\"a\": [1, 2], \"b\": {\"c\": [3, {\"d\": \"é\"}], \"e\": [4, 5, 6, 7, 8, 9]}}";
+        let json: Json<Object> = Json::new(text).unwrap();
+        let options = FormatOptions::tabs().inline_width(16);
+        let expected = "{\n\t\"a\": [1, 2],\n\t\"b\": {\n\t\t\"c\": [3, {\"d\": \"é\"}],\n\t\t\"e\": [\n\t\t\t4,\n\t\t\t5,\n\t\t\t6,\n\t\t\t7,\n\t\t\t8,\n\t\t\t9\n\t\t]\n\t}\n}";
+        assert_eq!(expected, json.to_formatted_string(&options));
+        assert_eq!(Ok(String::from(expected)), reformat_str(text, &options));
+        let mut written = Vec::new();
+        json.write_to(&mut written, &options).unwrap();
+        assert_eq!(expected.as_bytes(), written);
+        assert_eq!(
+            "[1, [], {}]",
+            reformat_str("[1,[],{}]", &FormatOptions::pretty(2).inline_width(11)).unwrap()
+        );
+        assert_eq!(
+            "[1,[]]",
+            reformat_str("[1, []]", &FormatOptions::minified().inline_width(80)).unwrap()
+        );
+    }
+
+    #[test]
+    fn reformat_invalid() {
+        assert!(matches!(
+            reformat_str("{\"a\": [1,]}", &FormatOptions::minified()),
+            Err(JsonError::InvalidJsonSyntax(_))
+        ));
+        let mut out = Vec::new();
+        assert_eq!(
+            Err(JsonError::FileError(io::ErrorKind::InvalidData)),
+            reformat(&[b'[', 0xc3][..], &mut out, &FormatOptions::minified())
+        );
+        for empty in ["", " \n\t"] {
+            assert_eq!(
+                Err(JsonError::InvalidJsonSyntax(String::from("Empty input"))),
+                reformat_str(empty, &FormatOptions::minified())
+            );
+        }
+    }
+
+    #[test]
+    fn shortest_floats() {
+        for (f, text) in [
+            (0.1, "0.1"),
+            (1.0, "1.0"),
+            (-2.5, "-2.5"),
+            (1e21, "1e21"),
+            (1e20, "100000000000000000000.0"),
+            (1e-6, "0.000001"),
+            (1.5e-6, "0.0000015"),
+            (1e-7, "1e-7"),
+            (1.5e-7, "1.5e-7"),
+            (-1.7976931348623157e308, "-1.7976931348623157e308"),
+            (5e-324, "5e-324"),
+            (0.30000000000000004, "0.30000000000000004"),
+        ] {
+            assert_eq!(text, format_float(f));
+            let json = Json::<Value>::new(text).unwrap();
+            assert_eq!(Ok(f.to_bits()), json.get_f64().map(f64::to_bits), "{text}");
+            assert_eq!(text, json.to_string());
+        }
+        let json: Json<Array> = Json::new("[1.23456, 2.0, 1e300, 1.5e-8]").unwrap();
+        assert_eq!(
+            "[1.23456,2.0,1e300,1.5e-8]",
+            json.to_formatted_string(&FormatOptions::minified())
+        );
+        assert_eq!(
+            "[1.235,2.000,1.000e300,1.500e-8]",
+            json.to_formatted_string(&FormatOptions::minified().float_precision(3))
+        );
+        assert_eq!(
+            "[1.2,2.0,1.0e300,1.5e-8]",
+            json.to_formatted_string(&FormatOptions::minified().float_precision(0))
+        );
+    }
+
+    #[test]
+    fn float_formats() {
+        let json: Json<Array> = Json::new("[12.5, 0.1, 3.0, 1234.5, 0.00012, 1e25, 7]").unwrap();
+        let format =
+            |format| json.to_formatted_string(&FormatOptions::minified().float_format(format));
+        assert_eq!(
+            "[12.5,0.1,3.0,1234.5,0.00012,1e25,7]",
+            format(FloatFormat::Shortest)
+        );
+        assert_eq!(
+            "[12.50,0.10,3.00,1234.50,0.00,1.00e25,7]",
+            format(FloatFormat::Fixed(2))
+        );
+        assert_eq!(
+            "[12.5,0.1,3.0,1.2345e3,1.2e-4,1e25,7]",
+            format(FloatFormat::Scientific(3))
+        );
+        assert_eq!(
+            "[1.25e1,1e-1,3e0,1.2345e3,1.2e-4,1e25,7]",
+            format(FloatFormat::Scientific(0))
+        );
+        for format in [FloatFormat::Fixed(0), FloatFormat::Scientific(0)] {
+            let text = json.to_formatted_string(&FormatOptions::minified().float_format(format));
+            let floats = Json::<Array>::new(text).unwrap();
+            assert!((0..6).all(|i| floats.get_value(i).unwrap().get_f64().is_ok()));
+        }
+    }
+
+    #[test]
+    fn ascii_only() {
+        let unicode = "{\"kéy\\u00e9\": [\"café \\\" 😀\", \"plain\"]}";
+        let options = FormatOptions::minified().ascii_only();
+        let expected = "{\"k\\u00e9y\\u00e9\":[\"caf\\u00e9 \\\" \\ud83d\\ude00\",\"plain\"]}";
+        assert_eq!(
+            expected,
+            Json::<Object>::new(unicode)
+                .unwrap()
+                .to_formatted_string(&options)
+        );
+        let mut out = Vec::new();
+        reformat(unicode.as_bytes(), &mut out, &options).unwrap();
+        assert_eq!(expected, String::from_utf8(out).unwrap());
+        let ascii = Json::<Object>::new(expected).unwrap();
+        assert_eq!(
+            Ok(String::from("café \" 😀")),
+            ascii
+                .get_array("kéyé")
+                .unwrap()
+                .get_value(0)
+                .unwrap()
+                .get_unescaped_string()
+        );
+    }
+
+    #[test]
+    fn html_safe() {
+        let json: Json<Object> =
+            Json::new("{\"</script>\": \"a & b\u{2028}<!-- \\u003c é\"}").unwrap();
+        let options = FormatOptions::minified().html_safe();
+        let expected = "{\"\\u003c/script\\u003e\":\"a \\u0026 b\\u2028\\u003c!-- \\u003c é\"}";
+        assert_eq!(expected, json.to_formatted_string(&options));
+        assert_eq!(
+            "{\"\\u003c/script\\u003e\":\"a \\u0026 b\\u2028\\u003c!-- \\u003c \\u00e9\"}",
+            json.to_formatted_string(&options.ascii_only())
+        );
+    }
+
+    #[test]
+    fn key_order() {
+        let json = Json::<Object>::new(
+            "{\"b\": 1, \"a\\u0062\": {\"z\": 2, \"y\": [{\"d\": 3, \"ab\": 4}]}, \"ac\": 5}",
+        )
+        .unwrap();
+        let sorted = FormatOptions::minified().key_order(KeyOrder::Sorted);
+        assert_eq!(
+            "{\"ab\":{\"y\":[{\"ab\":4,\"d\":3}],\"z\":2},\"ac\":5,\"b\":1}",
+            json.to_formatted_string(&sorted)
+        );
+        let mut out = Vec::new();
+        json.write_to(&mut out, &sorted).unwrap();
+        assert_eq!(json.to_formatted_string(&sorted).as_bytes(), out);
+        let longest_first = FormatOptions::minified().key_order(KeyOrder::Custom(|a, b| {
+            b.len().cmp(&a.len()).then(b.cmp(a))
+        }));
+        assert_eq!(
+            "{\"ac\":5,\"ab\":{\"z\":2,\"y\":[{\"ab\":4,\"d\":3}]},\"b\":1}",
+            json.to_formatted_string(&longest_first)
+        );
+        assert_eq!(FormatOptions::default(), FormatOptions::minified());
+        assert_ne!(sorted, FormatOptions::minified());
+    }
+
+    #[test]
+    fn output_is_deterministic() {
+        let text =
+            "{\"k\": 1, \"c\": {\"x\": [], \"b\": null, \"q\": 2}, \"a\": true, \"z\": \"\"}";
+        let first = Json::<Object>::new(text).unwrap();
+        for _ in 0..8 {
+            let json = Json::<Object>::new(text).unwrap();
+            assert_eq!(first.to_string(), json.to_string());
+            assert_eq!(format!("{:#}", first), format!("{:#}", json));
+            let again = Json::<Object>::new(json.to_string()).unwrap();
+            assert_eq!(json.to_string(), again.to_string());
+        }
+        #[cfg(not(feature = "preserve_order"))]
+        assert_eq!(
+            "{\"a\":true,\"c\":{\"b\":null,\"q\":2,\"x\":[]},\"k\":1,\"z\":\"\"}",
+            first.to_string()
+        );
+        #[cfg(feature = "preserve_order")]
+        assert_eq!(
+            "{\"k\":1,\"c\":{\"x\":[],\"b\":null,\"q\":2},\"a\":true,\"z\":\"\"}",
+            first.to_string()
+        );
+    }
+
+    #[test]
+    fn json_writer() {
+        let options = FormatOptions::pretty(2);
+        let mut writer = JsonWriter::new(Vec::new(), &options);
+        writer.begin_object().unwrap();
+        writer.key("items").unwrap();
+        writer.begin_array().unwrap();
+        writer.value(&Json::<Value>::from(1)).unwrap();
+        writer
+            .value(&Json::<Array>::new("[true, \"a\"]").unwrap())
+            .unwrap();
+        writer.begin_object().unwrap();
+        writer.end_object().unwrap();
+        writer.end_array().unwrap();
+        writer.key("name \"x\"").unwrap();
+        writer.value(&Json::<Value>::from(2.5)).unwrap();
+        writer.end_object().unwrap();
+        assert_eq!(
+            "{\n  \"items\": [\n    1,\n    [\n      true,\n      \"a\"\n    ],\n    {}\n  ],\n  \"name \\\"x\\\"\": 2.5\n}",
+            String::from_utf8(writer.finish().unwrap()).unwrap()
+        );
+
+        let options = FormatOptions::minified();
+        let mut writer = JsonWriter::new(Vec::new(), &options);
+        let misused = |result: Result<(), JsonError>| {
+            assert!(matches!(result, Err(JsonError::InvalidJsonSyntax(_))))
+        };
+        misused(writer.key("a"));
+        misused(writer.end_object());
+        writer.begin_object().unwrap();
+        misused(writer.value(&Json::<Value>::from(1)));
+        misused(writer.end_array());
+        writer.key("a").unwrap();
+        misused(writer.key("b"));
+        misused(writer.end_object());
+        writer.value(&Json::<Value>::from(1)).unwrap();
+        writer.end_object().unwrap();
+        misused(writer.begin_array());
+        assert_eq!(b"{\"a\":1}", &writer.finish().unwrap()[..]);
+        let unfinished = JsonWriter::new(Vec::new(), &options);
+        assert!(unfinished.finish().is_err());
+    }
+
+    #[test]
+    fn write_to() {
+        let json: Json<Array> = Json::new("[1, {\"a\": [null, \"é\"]}, 2.0]").unwrap();
+        for options in [
+            FormatOptions::minified(),
+            FormatOptions::pretty(4).ascii_only(),
+        ] {
+            let mut out = Vec::new();
+            json.write_to(&mut out, &options).unwrap();
+            assert_eq!(
+                json.to_formatted_string(&options),
+                String::from_utf8(out).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn to_file() {
+        let dir = std::env::temp_dir().join(format!("json-parser-to-file-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.json");
+        let json: Json<Array> = Json::new("[1, \"a\"]").unwrap();
+        json.to_file(&path, &FormatOptions::minified()).unwrap();
+        assert_eq!("[1,\"a\"]", fs::read_to_string(&path).unwrap());
+        let json: Json<Array> = Json::new("[2]").unwrap();
+        json.to_file_atomic(&path, &FormatOptions::pretty(2))
+            .unwrap();
+        assert_eq!("[\n  2\n]", fs::read_to_string(&path).unwrap());
+        // only the file itself is left, no temporary files
+        assert_eq!(1, fs::read_dir(&dir).unwrap().count());
+        assert_eq!(
+            Err(JsonError::FileError(io::ErrorKind::NotFound)),
+            json.to_file_atomic(dir.join("missing/config.json"), &FormatOptions::minified())
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
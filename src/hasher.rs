@@ -0,0 +1,125 @@
+use std::collections::hash_map::{DefaultHasher, RandomState};
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Arc;
+
+// The hasher the keys of parsed objects are hashed with, see `Json::new_with_hasher`. The
+// default is the standard library's DoS resistant `RandomState`, a faster one like FxHash or
+// ahash can be plugged in with `KeyHasher::new` when the input is trusted. With the
+// `btreemap` feature keys are not hashed and the hasher is not used.
+#[derive(Clone)]
+pub struct KeyHasher(Inner);
+
+#[derive(Clone)]
+enum Inner {
+    Random(RandomState),
+    Custom(Arc<dyn WriteWith + Send + Sync>),
+}
+
+// Implemented for every `BuildHasher`, so the hasher of a custom `KeyHasher` is not boxed:
+// each write runs the concrete hasher over the state so far and the bytes
+trait WriteWith {
+    fn write_with(&self, state: u64, bytes: &[u8]) -> u64;
+}
+
+impl<B: BuildHasher> WriteWith for B {
+    fn write_with(&self, state: u64, bytes: &[u8]) -> u64 {
+        let mut hasher = self.build_hasher();
+        hasher.write_u64(state);
+        hasher.write(bytes);
+        hasher.finish()
+    }
+}
+
+impl KeyHasher {
+    pub fn new<B: BuildHasher + Send + Sync + 'static>(build: B) -> Self {
+        KeyHasher(Inner::Custom(Arc::new(build)))
+    }
+}
+
+impl Default for KeyHasher {
+    fn default() -> Self {
+        KeyHasher(Inner::Random(RandomState::new()))
+    }
+}
+
+impl fmt::Debug for KeyHasher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            Inner::Random(_) => f.write_str("KeyHasher::Random"),
+            Inner::Custom(_) => f.write_str("KeyHasher::Custom"),
+        }
+    }
+}
+
+pub struct KeyHasherState(State);
+
+enum State {
+    Random(DefaultHasher),
+    Custom(Arc<dyn WriteWith + Send + Sync>, u64),
+}
+
+impl BuildHasher for KeyHasher {
+    type Hasher = KeyHasherState;
+
+    fn build_hasher(&self) -> Self::Hasher {
+        match &self.0 {
+            Inner::Random(random) => KeyHasherState(State::Random(random.build_hasher())),
+            Inner::Custom(build) => KeyHasherState(State::Custom(build.clone(), 0)),
+        }
+    }
+}
+
+impl Hasher for KeyHasherState {
+    fn finish(&self) -> u64 {
+        match &self.0 {
+            State::Random(hasher) => hasher.finish(),
+            State::Custom(_, state) => *state,
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        match &mut self.0 {
+            State::Random(hasher) => hasher.write(bytes),
+            State::Custom(build, state) => *state = build.write_with(*state, bytes),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{Json, Object};
+    use std::hash::BuildHasherDefault;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // Counts the hashers it builds
+    #[derive(Clone, Default)]
+    struct Counting(Arc<AtomicUsize>);
+
+    impl BuildHasher for Counting {
+        type Hasher = DefaultHasher;
+
+        fn build_hasher(&self) -> DefaultHasher {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            DefaultHasher::new()
+        }
+    }
+
+    #[test]
+    fn parse_with_custom_hasher() {
+        let text = "{\"a\": 1, \"b\": {\"c\": [2]}}";
+        let counting = Counting::default();
+        let json: Json<Object> =
+            Json::new_with_hasher(text, KeyHasher::new(counting.clone())).unwrap();
+        assert_eq!(Json::<Object>::new(text).unwrap(), json);
+        assert_eq!(Ok(1), json.get_value("a").unwrap().get_i64());
+        #[cfg(not(all(feature = "btreemap", not(feature = "preserve_order"))))]
+        assert!(counting.0.load(Ordering::Relaxed) > 0);
+        let fixed = KeyHasher::new(BuildHasherDefault::<DefaultHasher>::default());
+        assert_eq!(fixed.hash_one("key"), fixed.hash_one("key"));
+        assert_ne!(fixed.hash_one("key"), fixed.hash_one("kez"));
+        assert_ne!(fixed.hash_one(("a", "bc")), fixed.hash_one(("ab", "c")));
+        assert_eq!("KeyHasher::Random", format!("{:?}", KeyHasher::default()));
+    }
+}
@@ -0,0 +1,165 @@
+use crate::escape::unquote;
+use crate::format::syntax_error;
+use crate::json::{AnyJson, Json, JsonError, JsonState, Value};
+use crate::lexer::TokenKind;
+use crate::parser::{JsonData, ParseError};
+use crate::stream::{scalar_data, DataBuilder, Event, Events};
+use crate::tokens::DEFAULT_TAB_WIDTH;
+
+type TextHook = Box<dyn Fn(&str) -> String>;
+type NumberHook = Box<dyn Fn(&Json<Value>) -> AnyJson>;
+
+// Callbacks that `Json::new_with_options` runs on the keys, strings and numbers while it
+// parses, so they are changed before they are put in the json and it is built only once,
+// and how it locates syntax errors
+#[derive(Default)]
+pub struct ParseOptions {
+    on_key: Option<TextHook>,
+    on_string: Option<TextHook>,
+    on_number: Option<NumberHook>,
+    tab_width: Option<usize>,
+}
+
+impl ParseOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Gets the unescaped key of every member and returns the key to use. When two keys end
+    // up the same the last member wins.
+    pub fn on_key<F: Fn(&str) -> String + 'static>(mut self, f: F) -> Self {
+        self.on_key = Some(Box::new(f));
+        self
+    }
+
+    // Gets every unescaped string that is a value and returns the string to use
+    pub fn on_string<F: Fn(&str) -> String + 'static>(mut self, f: F) -> Self {
+        self.on_string = Some(Box::new(f));
+        self
+    }
+
+    // Gets every number and returns the json to put in its place, which does not have to be
+    // a number
+    pub fn on_number<F: Fn(&Json<Value>) -> AnyJson + 'static>(mut self, f: F) -> Self {
+        self.on_number = Some(Box::new(f));
+        self
+    }
+
+    // How many columns a tab moves the column of syntax errors to the next stop of,
+    // `DEFAULT_TAB_WIDTH` when not set
+    pub fn tab_width(mut self, tab_width: usize) -> Self {
+        self.tab_width = Some(tab_width);
+        self
+    }
+}
+
+impl<S: JsonState> Json<S> {
+    // Like `new` but with the callbacks of `options` run on the keys and values as they are
+    // parsed, like lowercasing the keys or trimming the strings
+    pub fn new_with_options<R: AsRef<str>>(
+        json: R,
+        options: &ParseOptions,
+    ) -> Result<Self, JsonError> {
+        let mut builder = DataBuilder::default();
+        let mut data = None;
+        let tab_width = options.tab_width.unwrap_or(DEFAULT_TAB_WIDTH);
+        for event in Events::new(json.as_ref().chars()).with_tab_width(tab_width) {
+            let value = match event.map_err(syntax_error)? {
+                Event::Key(token) => {
+                    let key = unquote(token.text);
+                    match &options.on_key {
+                        Some(f) => builder.push_key(f(&key).into()),
+                        None => builder.push_key(key.into()),
+                    }
+                    Ok(None)
+                }
+                Event::Scalar(token) if token.kind == TokenKind::Str => {
+                    let text = unquote(token.text);
+                    match &options.on_string {
+                        Some(f) => Ok(builder.push_value(JsonData::Str(f(&text).into()))),
+                        None => Ok(builder.push_value(JsonData::Str(text.into()))),
+                    }
+                }
+                Event::Scalar(token)
+                    if matches!(token.kind, TokenKind::Integer | TokenKind::Float) =>
+                {
+                    match &options.on_number {
+                        Some(f) => {
+                            let data = scalar_data(token).map_err(syntax_error)?;
+                            let replaced = f(&Json::from_data(data)).data().clone();
+                            Ok(builder.push_value(replaced))
+                        }
+                        None => builder.push(Event::Scalar(token)),
+                    }
+                }
+                event => builder.push(event),
+            };
+            if let Some(value) = value.map_err(syntax_error)? {
+                data = Some(value);
+            }
+        }
+        let data = data.ok_or_else(|| syntax_error(ParseError::EmptyInput))?;
+        Self::checked(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{Array, Object};
+
+    #[test]
+    fn normalize_while_parsing() {
+        let options = ParseOptions::new()
+            .on_key(|key| key.to_lowercase())
+            .on_string(|s| s.trim().to_string())
+            .on_number(|n| match n.get_i64() {
+                Ok(i) => AnyJson::from(i * 10),
+                Err(_) => AnyJson::from(n.to_string()),
+            });
+        let json = Json::<Object>::new_with_options(
+            "{\"Name\": \"  a\\n \", \"TAGS\": [\" x\", 2, 1.5, null], \"Ä\\u0042\": {\"K\": true}}",
+            &options,
+        )
+        .unwrap();
+        assert_eq!(
+            Json::<Object>::new(
+                "{\"name\": \"a\", \"tags\": [\"x\", 20, \"1.5\", null], \"äb\": {\"k\": true}}"
+            )
+            .unwrap(),
+            json
+        );
+        let keys = ParseOptions::new().on_key(|_| String::from("same\""));
+        let json = Json::<Object>::new_with_options("{\"a\": 1, \"b\": 2}", &keys).unwrap();
+        assert_eq!("{\"same\\\"\":2}", json.to_string());
+    }
+
+    #[test]
+    fn root_and_errors() {
+        let to_array = ParseOptions::new().on_number(|_| AnyJson::from(Json::<Array>::default()));
+        assert_eq!(
+            Json::<Array>::default(),
+            Json::<Array>::new_with_options("5", &to_array).unwrap()
+        );
+        assert!(matches!(
+            Json::<Object>::new_with_options("5", &to_array),
+            Err(JsonError::IncorrectType { .. })
+        ));
+        assert_eq!(
+            Json::<Object>::new("{\"a\":").err(),
+            Json::<Object>::new_with_options("{\"a\":", &ParseOptions::new()).err()
+        );
+        assert!(Json::<Value>::new_with_options(" ", &ParseOptions::new()).is_err());
+    }
+
+    #[test]
+    fn tab_width() {
+        let message = |options: &ParseOptions| {
+            Json::<Object>::new_with_options("{\n\t\"a\": x}", options)
+                .unwrap_err()
+                .to_string()
+        };
+        assert!(message(&ParseOptions::new()).contains("`x` at 2:10"));
+        assert!(message(&ParseOptions::new().tab_width(8)).contains("`x` at 2:14"));
+    }
+}
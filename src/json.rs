@@ -1,64 +1,467 @@
-use crate::parser::{parse_json, JsonData};
+use crate::format::{display, event_error, syntax_error};
+use crate::hasher::KeyHasher;
+use crate::parser::{parse_json, JsonData, Shared};
+use crate::pointer::pointer_token;
+use crate::stream::{build_data, build_data_into, DataBuilder, Events, ReadChars};
 use std::fmt::{Display, Formatter};
+use std::io::Read;
 use std::path::Path;
-use std::rc::Rc;
+use std::str::FromStr;
 use std::{fs, io};
 
-pub trait JsonState {}
+// The deepest nesting of arrays and objects that is parsed, deeper ones are an error
+pub const MAX_DEPTH: usize = 512;
+
+// The types of the root a json of the state can hold
+pub trait JsonState {
+    const TYPES: &'static [JsonType];
+}
 #[derive(Debug)]
 pub struct Array;
 #[derive(Debug)]
 pub struct Object;
 #[derive(Debug)]
 pub struct Value;
-impl JsonState for Array {}
-impl JsonState for Object {}
-impl JsonState for Value {}
+impl JsonState for Array {
+    const TYPES: &'static [JsonType] = &[JsonType::Array];
+}
+impl JsonState for Object {
+    const TYPES: &'static [JsonType] = &[JsonType::Object];
+}
+impl JsonState for Value {
+    const TYPES: &'static [JsonType] = SCALAR;
+}
+
+// The types `JsonError::IncorrectType` tells apart. Big integers are integers and decimals
+// are floats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum JsonType {
+    Null,
+    Bool,
+    Integer,
+    Float,
+    String,
+    Array,
+    Object,
+}
 
+// What `get_value` reads
+pub(crate) const SCALAR: &[JsonType] = &[
+    JsonType::Null,
+    JsonType::Bool,
+    JsonType::Integer,
+    JsonType::Float,
+    JsonType::String,
+];
+
+impl JsonType {
+    pub(crate) fn of(data: &JsonData) -> Self {
+        match data {
+            JsonData::Null => JsonType::Null,
+            JsonData::Bool(_) => JsonType::Bool,
+            JsonData::Integer(_) => JsonType::Integer,
+            #[cfg(feature = "bigint")]
+            JsonData::BigInt(_) => JsonType::Integer,
+            JsonData::Float(_) => JsonType::Float,
+            #[cfg(feature = "decimal")]
+            JsonData::Decimal(_) => JsonType::Float,
+            #[cfg(feature = "raw_numbers")]
+            JsonData::RawNumber(raw) => JsonType::of(&raw.data),
+            JsonData::Str(_) => JsonType::String,
+            JsonData::Array(_) => JsonType::Array,
+            JsonData::Object(_) => JsonType::Object,
+        }
+    }
+}
+
+impl Display for JsonType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            JsonType::Null => "null",
+            JsonType::Bool => "bool",
+            JsonType::Integer => "integer",
+            JsonType::Float => "float",
+            JsonType::String => "string",
+            JsonType::Array => "array",
+            JsonType::Object => "object",
+        })
+    }
+}
+
+// `path` in the errors for reading a value is the json pointer of the value with the wrong
+// type or of the missing key or index, as far as it is known. `expected` are the types that
+// could have been read and `found` is the type that was there. Features add variants for
+// their formats, so matches outside of the crate need a `_` arm.
 #[derive(Debug, PartialEq)]
+#[non_exhaustive]
 pub enum JsonError {
-    IncorrectType,
-    KeyNotFound,
-    IndexNotFound,
+    IncorrectType {
+        path: String,
+        expected: Vec<JsonType>,
+        found: JsonType,
+    },
+    KeyNotFound {
+        path: String,
+    },
+    IndexNotFound {
+        path: String,
+    },
+    // A number changed in place that would no longer fit in its type
+    Overflow {
+        path: String,
+    },
     InvalidJsonSyntax(String),
+    InvalidCsvSyntax(String),
+    InvalidFilter(String),
+    InvalidPath(String),
+    InvalidExpansion(String),
+    // A configured limit was reached, like `EqOptions::max_buffered_members`
+    LimitExceeded(String),
+    #[cfg(feature = "arrow")]
+    InvalidArrow(String),
+    #[cfg(feature = "bson")]
+    InvalidBson(String),
+    #[cfg(feature = "bson")]
+    UnrepresentableInBson(String),
+    #[cfg(feature = "cbor")]
+    InvalidCbor(String),
+    #[cfg(feature = "decimal")]
+    InvalidDecimal(String),
+    #[cfg(feature = "time")]
+    InvalidDateTime(String),
+    #[cfg(feature = "toml")]
+    InvalidToml(String),
+    #[cfg(feature = "toml")]
+    UnrepresentableInToml(String),
+    #[cfg(feature = "uuid")]
+    InvalidUuid(String),
+    #[cfg(feature = "xml")]
+    InvalidXml(String),
+    #[cfg(feature = "xml")]
+    UnrepresentableInXml(String),
     FileError(io::ErrorKind),
 }
 
+impl JsonError {
+    pub(crate) fn incorrect_type<P: Into<String>>(
+        path: P,
+        expected: &[JsonType],
+        found: &JsonData,
+    ) -> Self {
+        JsonError::IncorrectType {
+            path: path.into(),
+            expected: expected.to_vec(),
+            found: JsonType::of(found),
+        }
+    }
+
+    pub(crate) fn key_not_found<P: Into<String>>(path: P) -> Self {
+        JsonError::KeyNotFound { path: path.into() }
+    }
+
+    pub(crate) fn index_not_found<P: Into<String>>(path: P) -> Self {
+        JsonError::IndexNotFound { path: path.into() }
+    }
+
+    // The error with `prefix` put in front of its path, for an error about a value inside
+    // the value at `prefix`
+    pub(crate) fn under(mut self, prefix: &str) -> Self {
+        if let JsonError::IncorrectType { path, .. }
+        | JsonError::KeyNotFound { path }
+        | JsonError::IndexNotFound { path }
+        | JsonError::Overflow { path } = &mut self
+        {
+            path.insert_str(0, prefix);
+        }
+        self
+    }
+
+    // The json pointer the error is about, for the errors of reading a value
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            JsonError::IncorrectType { path, .. }
+            | JsonError::KeyNotFound { path }
+            | JsonError::IndexNotFound { path }
+            | JsonError::Overflow { path } => Some(path),
+            _ => None,
+        }
+    }
+
+    // A code for the kind of error that stays the same between versions, unlike the
+    // messages. Codes are never reused, the syntax errors have their own codes in
+    // `Diagnostic::code`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            JsonError::IncorrectType { .. } => "E0001",
+            JsonError::KeyNotFound { .. } => "E0002",
+            JsonError::IndexNotFound { .. } => "E0003",
+            JsonError::InvalidJsonSyntax(_) => "E0004",
+            JsonError::InvalidCsvSyntax(_) => "E0005",
+            JsonError::InvalidFilter(_) => "E0006",
+            JsonError::InvalidPath(_) => "E0007",
+            JsonError::InvalidExpansion(_) => "E0008",
+            JsonError::FileError(_) => "E0009",
+            #[cfg(feature = "arrow")]
+            JsonError::InvalidArrow(_) => "E0010",
+            #[cfg(feature = "bson")]
+            JsonError::InvalidBson(_) => "E0011",
+            #[cfg(feature = "bson")]
+            JsonError::UnrepresentableInBson(_) => "E0012",
+            #[cfg(feature = "cbor")]
+            JsonError::InvalidCbor(_) => "E0013",
+            #[cfg(feature = "decimal")]
+            JsonError::InvalidDecimal(_) => "E0014",
+            #[cfg(feature = "time")]
+            JsonError::InvalidDateTime(_) => "E0015",
+            #[cfg(feature = "toml")]
+            JsonError::InvalidToml(_) => "E0016",
+            #[cfg(feature = "toml")]
+            JsonError::UnrepresentableInToml(_) => "E0017",
+            #[cfg(feature = "uuid")]
+            JsonError::InvalidUuid(_) => "E0018",
+            #[cfg(feature = "xml")]
+            JsonError::InvalidXml(_) => "E0019",
+            #[cfg(feature = "xml")]
+            JsonError::UnrepresentableInXml(_) => "E0020",
+            JsonError::LimitExceeded(_) => "E0021",
+            JsonError::Overflow { .. } => "E0022",
+        }
+    }
+
+    // The error as `{"code": ..., "message": ...}`, with the `path` of the errors of
+    // reading a value and the `expected` and `found` types of `IncorrectType`
+    pub fn to_json(&self) -> Json<Object> {
+        let text = |s: &str| JsonData::Str(s.into());
+        let mut members = vec![
+            ("code", text(self.code())),
+            ("message", text(&self.to_string())),
+        ];
+        if let Some(path) = self.path() {
+            members.push(("path", text(path)));
+        }
+        if let JsonError::IncorrectType {
+            expected, found, ..
+        } = self
+        {
+            let expected = expected.iter().map(|t| text(&t.to_string()));
+            members.push(("expected", JsonData::array(expected)));
+            members.push(("found", text(&found.to_string())));
+        }
+        Json::from_data(JsonData::object(members))
+    }
+}
+
+impl Display for JsonError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JsonError::IncorrectType {
+                path,
+                expected,
+                found,
+            } => {
+                let expected: Vec<String> = expected.iter().map(|t| t.to_string()).collect();
+                write!(
+                    f,
+                    "Expected {} at `{}`, found {}",
+                    expected.join(" or "),
+                    path,
+                    found
+                )
+            }
+            JsonError::KeyNotFound { path } => write!(f, "No key at `{}`", path),
+            JsonError::IndexNotFound { path } => write!(f, "No index at `{}`", path),
+            JsonError::Overflow { path } => write!(f, "The number at `{}` overflows", path),
+            JsonError::FileError(kind) => write!(f, "{}", io::Error::from(*kind)),
+            JsonError::InvalidJsonSyntax(message)
+            | JsonError::InvalidCsvSyntax(message)
+            | JsonError::InvalidFilter(message)
+            | JsonError::InvalidPath(message)
+            | JsonError::InvalidExpansion(message)
+            | JsonError::LimitExceeded(message) => f.write_str(message),
+            #[cfg(feature = "arrow")]
+            JsonError::InvalidArrow(message) => f.write_str(message),
+            #[cfg(feature = "bson")]
+            JsonError::InvalidBson(message) | JsonError::UnrepresentableInBson(message) => {
+                f.write_str(message)
+            }
+            #[cfg(feature = "cbor")]
+            JsonError::InvalidCbor(message) => f.write_str(message),
+            #[cfg(feature = "decimal")]
+            JsonError::InvalidDecimal(message) => f.write_str(message),
+            #[cfg(feature = "time")]
+            JsonError::InvalidDateTime(message) => f.write_str(message),
+            #[cfg(feature = "toml")]
+            JsonError::InvalidToml(message) | JsonError::UnrepresentableInToml(message) => {
+                f.write_str(message)
+            }
+            #[cfg(feature = "uuid")]
+            JsonError::InvalidUuid(message) => f.write_str(message),
+            #[cfg(feature = "xml")]
+            JsonError::InvalidXml(message) | JsonError::UnrepresentableInXml(message) => {
+                f.write_str(message)
+            }
+        }
+    }
+}
+
+// Each value in a parsed json takes 32 bytes plus the reference counts of `Shared`, and
+// strings, arrays and objects the heap memory of their contents
 #[derive(Debug)]
 pub struct Json<S: JsonState> {
-    data: Rc<JsonData>,
+    pub(crate) data: Shared<JsonData>,
+    // The json pointer of the value in the json it was read from, empty for the root
+    pub(crate) path: String,
     marker: std::marker::PhantomData<S>,
 }
 
+// Cloning shares the data with the original, it is only copied once one of them is mutated
+impl<S: JsonState> Clone for Json<S> {
+    fn clone(&self) -> Self {
+        Self {
+            data: Shared::clone(&self.data),
+            path: self.path.clone(),
+            marker: Default::default(),
+        }
+    }
+}
+
+impl Default for Json<Object> {
+    fn default() -> Self {
+        Json::from_data(JsonData::Object(Default::default()))
+    }
+}
+
+impl Default for Json<Array> {
+    fn default() -> Self {
+        Json::from_data(JsonData::Array(Vec::new()))
+    }
+}
+
+// A json whose kind is only known at runtime, as returned by queries
+#[derive(Debug, Clone)]
+pub enum AnyJson {
+    Object(Json<Object>),
+    Array(Json<Array>),
+    Value(Json<Value>),
+}
+
+// The `JsonType` of a `JsonData` variant
+macro_rules! json_type {
+    (Str) => {
+        JsonType::String
+    };
+    ($type:ident) => {
+        JsonType::$type
+    };
+}
+
 macro_rules! expect_json_type {
     ($self:expr, $type:ident, $var1:ident, $code:block) => {
-        if let JsonData::$type(inner) = $self.data.as_ref() {
+        if let JsonData::$type(inner) = $self.data.as_ref().unraw() {
             let $var1 = inner;
             $code
         } else {
-            Err(JsonError::IncorrectType)
+            Err(JsonError::incorrect_type(
+                $self.path.as_str(),
+                &[json_type!($type)],
+                &$self.data,
+            ))
         }
     };
 }
 
+// Floats and, with the `decimal` feature, decimals but not integers
+fn float(data: &JsonData, path: &str) -> Result<f64, JsonError> {
+    match data.unraw() {
+        JsonData::Float(f) => Ok(*f),
+        #[cfg(feature = "decimal")]
+        JsonData::Decimal(_) => data
+            .as_f64()
+            .ok_or_else(|| JsonError::incorrect_type(path, &[JsonType::Float], data)),
+        _ => Err(JsonError::incorrect_type(path, &[JsonType::Float], data)),
+    }
+}
+
+// Minified, or pretty with an indent of 2 for `{:#}`
 impl<S: JsonState> Display for Json<S> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", &self.data)
+        display(&self.data, f)
     }
 }
 
 impl<S: JsonState> Json<S> {
+    pub(crate) fn from_data(data: JsonData) -> Self {
+        Self::from_shared(Shared::new(data))
+    }
+
+    pub(crate) fn from_shared(data: Shared<JsonData>) -> Self {
+        Self {
+            data,
+            path: String::new(),
+            marker: Default::default(),
+        }
+    }
+
+    // Parsed data as a json of this state, or an error if the root is of another type
+    pub(crate) fn checked<D: Into<Shared<JsonData>>>(data: D) -> Result<Self, JsonError> {
+        let data = data.into();
+        if S::TYPES.contains(&JsonType::of(&data)) {
+            Ok(Self::from_shared(data))
+        } else {
+            Err(JsonError::incorrect_type("", S::TYPES, &data))
+        }
+    }
+
+    pub(crate) fn at(mut self, path: String) -> Self {
+        self.path = path;
+        self
+    }
+
+    // The json pointer of this value in the json it was read from with `get_*` or
+    // `pointer`, empty for the json itself
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    // The same json in another state, without copying it. The path is kept and the error
+    // is the one `get_*` gives for a value of the wrong type.
+    pub fn try_into_object(self) -> Result<Json<Object>, JsonError> {
+        Json::<Object>::from_json(AnyJson::from(self))
+    }
+
+    pub fn try_into_array(self) -> Result<Json<Array>, JsonError> {
+        Json::<Array>::from_json(AnyJson::from(self))
+    }
+
+    pub fn try_into_value(self) -> Result<Json<Value>, JsonError> {
+        Json::<Value>::from_json(AnyJson::from(self))
+    }
+
     pub fn new<R: AsRef<str>>(json: R) -> Result<Self, JsonError> {
         let json = parse_json(json);
         match json {
-            Ok(json_data) => Ok(Self {
-                data: Rc::new(json_data),
-                marker: Default::default(),
-            }),
+            Ok(json_data) => Self::checked(json_data),
             Err(error) => Err(JsonError::InvalidJsonSyntax(format!("{}", error))),
         }
     }
 
+    // Like `new` but the keys of the objects are hashed with `hasher`
+    pub fn new_with_hasher<R: AsRef<str>>(json: R, hasher: KeyHasher) -> Result<Self, JsonError> {
+        let mut events = Events::new(json.as_ref().chars());
+        let data = build_data_into(&mut events, &mut DataBuilder::with_hasher(hasher))
+            .map_err(syntax_error)?;
+        Self::checked(data)
+    }
+
+    // Parses any bytes without panicking. Invalid utf-8 is an error too, besides the errors
+    // of `new`.
+    pub fn try_parse<B: AsRef<[u8]>>(json: B) -> Result<Self, JsonError> {
+        let json = std::str::from_utf8(json.as_ref()).map_err(|e| {
+            JsonError::InvalidJsonSyntax(format!("Invalid utf-8 at byte {}", e.valid_up_to()))
+        })?;
+        Self::new(json)
+    }
+
     pub fn from_file<R: AsRef<Path>>(file: R) -> Result<Self, JsonError> {
         match fs::read_to_string(file.as_ref()) {
             Ok(s) => Self::new(s),
@@ -67,79 +470,352 @@ impl<S: JsonState> Json<S> {
     }
 }
 
+impl Json<Value> {
+    // The same as `AnyJson::parse`, for when the type of the root is not known up front
+    pub fn parse<R: AsRef<str>>(json: R) -> Result<AnyJson, JsonError> {
+        AnyJson::parse(json)
+    }
+}
+
+// The same as `Json::new`, `Json::try_parse` and `Display`, as free functions like
+// serde_json has them
+pub fn from_str<S: JsonState>(json: &str) -> Result<Json<S>, JsonError> {
+    Json::new(json)
+}
+
+pub fn from_slice<S: JsonState>(json: &[u8]) -> Result<Json<S>, JsonError> {
+    Json::try_parse(json)
+}
+
+// Parses while reading, the text is never held in memory as a whole
+pub fn from_reader<S: JsonState, R: Read>(reader: R) -> Result<Json<S>, JsonError> {
+    let chars = ReadChars::new(reader);
+    let read_error = chars.error();
+    let data = build_data(&mut Events::new(chars))
+        .map_err(|error| event_error(error, read_error.get()))?;
+    match read_error.get() {
+        Some(kind) => Err(JsonError::FileError(kind)),
+        None => Json::checked(data),
+    }
+}
+
+pub fn to_string<S: JsonState>(json: &Json<S>) -> String {
+    json.to_string()
+}
+
+pub fn to_string_pretty<S: JsonState>(json: &Json<S>) -> String {
+    format!("{:#}", json)
+}
+
+impl AnyJson {
+    // Parses json whose root can be of any type, the variant tells which it is
+    pub fn parse<R: AsRef<str>>(json: R) -> Result<Self, JsonError> {
+        Ok(Self::from_data(parse_json(json).map_err(syntax_error)?))
+    }
+
+    pub fn from_file<R: AsRef<Path>>(file: R) -> Result<Self, JsonError> {
+        match fs::read_to_string(file.as_ref()) {
+            Ok(s) => Self::parse(s),
+            Err(e) => Err(JsonError::FileError(e.kind())),
+        }
+    }
+
+    pub(crate) fn from_data(data: JsonData) -> Self {
+        Self::from_shared(Shared::new(data))
+    }
+
+    pub(crate) fn from_shared(data: Shared<JsonData>) -> Self {
+        match data.as_ref() {
+            JsonData::Object(_) => AnyJson::Object(Json::from_shared(data)),
+            JsonData::Array(_) => AnyJson::Array(Json::from_shared(data)),
+            _ => AnyJson::Value(Json::from_shared(data)),
+        }
+    }
+
+    pub(crate) fn at(self, path: String) -> Self {
+        match self {
+            AnyJson::Object(json) => AnyJson::Object(json.at(path)),
+            AnyJson::Array(json) => AnyJson::Array(json.at(path)),
+            AnyJson::Value(json) => AnyJson::Value(json.at(path)),
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        match self {
+            AnyJson::Object(json) => json.path(),
+            AnyJson::Array(json) => json.path(),
+            AnyJson::Value(json) => json.path(),
+        }
+    }
+
+    pub(crate) fn into_shared(self) -> Shared<JsonData> {
+        match self {
+            AnyJson::Object(json) => json.data,
+            AnyJson::Array(json) => json.data,
+            AnyJson::Value(json) => json.data,
+        }
+    }
+
+    pub(crate) fn data(&self) -> &JsonData {
+        match self {
+            AnyJson::Object(json) => &json.data,
+            AnyJson::Array(json) => &json.data,
+            AnyJson::Value(json) => &json.data,
+        }
+    }
+}
+
+impl<S: JsonState> From<Json<S>> for AnyJson {
+    fn from(json: Json<S>) -> Self {
+        AnyJson::from_shared(json.data).at(json.path)
+    }
+}
+
+impl Display for AnyJson {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        display(self.data(), f)
+    }
+}
+
 macro_rules! get_from_json_object {
-    ($self:expr, $key:expr, $var1:ident, $code:block) => {
+    ($self:expr, $key:expr, $var1:ident, $path:ident, $code:block) => {
         expect_json_type!($self, Object, map, {
+            let $path = format!("{}/{}", $self.path, pointer_token($key));
             if let Some(data) = map.get($key) {
                 let $var1 = data;
                 $code
             } else {
-                Err(JsonError::KeyNotFound)
+                Err(JsonError::key_not_found($path))
             }
         })
     };
 }
 
+// `$data` at `$path` as the first of the types it is, `$path` is moved into the json.
+// `$expected` are the `JsonType`s of the types for the error.
 macro_rules! create_json_of_type {
-    (@create $data:expr) => {
+    (@create $data:expr, $path:expr) => {
         Json {
-            data: Rc::new($data.to_owned()),
+            data: Shared::clone($data),
+            path: $path,
             marker: Default::default(),
         }
     };
-    ($data:expr, Null) => {
-        if let JsonData::Null = $data {
-            Ok(create_json_of_type!(@create $data))
+    // big integers only exist with the `bigint` feature
+    ($data:expr, $path:expr, $expected:expr, BigInt, $($rest:ident),*) => {{
+        #[cfg(feature = "bigint")]
+        let is_bigint = matches!($data.as_ref(), JsonData::BigInt(_));
+        #[cfg(not(feature = "bigint"))]
+        let is_bigint = false;
+        if is_bigint {
+            Ok(create_json_of_type!(@create $data, $path))
+        } else {
+            create_json_of_type!($data, $path, $expected, $($rest),*)
+        }
+    }};
+    // raw numbers only exist with the `raw_numbers` feature
+    ($data:expr, $path:expr, $expected:expr, RawNumber, $($rest:ident),*) => {{
+        #[cfg(feature = "raw_numbers")]
+        let is_raw = matches!($data.as_ref(), JsonData::RawNumber(_));
+        #[cfg(not(feature = "raw_numbers"))]
+        let is_raw = false;
+        if is_raw {
+            Ok(create_json_of_type!(@create $data, $path))
+        } else {
+            create_json_of_type!($data, $path, $expected, $($rest),*)
+        }
+    }};
+    // decimals only exist with the `decimal` feature
+    ($data:expr, $path:expr, $expected:expr, Decimal, $($rest:ident),*) => {{
+        #[cfg(feature = "decimal")]
+        let is_decimal = matches!($data.as_ref(), JsonData::Decimal(_));
+        #[cfg(not(feature = "decimal"))]
+        let is_decimal = false;
+        if is_decimal {
+            Ok(create_json_of_type!(@create $data, $path))
         } else {
-            Err(JsonError::IncorrectType)
+            create_json_of_type!($data, $path, $expected, $($rest),*)
+        }
+    }};
+    ($data:expr, $path:expr, $expected:expr, Null) => {
+        if let JsonData::Null = $data.as_ref() {
+            Ok(create_json_of_type!(@create $data, $path))
+        } else {
+            Err(JsonError::incorrect_type($path, $expected, $data))
         }
     };
-    ($data:expr, $type:ident) => {
-        if let JsonData::$type(_) = $data {
-            Ok(create_json_of_type!(@create $data))
+    ($data:expr, $path:expr, $expected:expr, $type:ident) => {
+        if let JsonData::$type(_) = $data.as_ref() {
+            Ok(create_json_of_type!(@create $data, $path))
         } else {
-            Err(JsonError::IncorrectType)
+            Err(JsonError::incorrect_type($path, $expected, $data))
         }
     };
-    ($data:expr, Null, $($rest:ident),*) => {
-        if let JsonData::Null = $data {
-            Ok(create_json_of_type!(@create $data))
+    ($data:expr, $path:expr, $expected:expr, Null, $($rest:ident),*) => {
+        if let JsonData::Null = $data.as_ref() {
+            Ok(create_json_of_type!(@create $data, $path))
         } else {
-            create_json_of_type!($data, $($rest),*)
+            create_json_of_type!($data, $path, $expected, $($rest),*)
         }
     };
-    ($data:expr, $type:ident, $($rest:ident),*) => {
-        if let JsonData::$type(_) = $data {
-            Ok(create_json_of_type!(@create $data))
+    ($data:expr, $path:expr, $expected:expr, $type:ident, $($rest:ident),*) => {
+        if let JsonData::$type(_) = $data.as_ref() {
+            Ok(create_json_of_type!(@create $data, $path))
         } else {
-            create_json_of_type!($data, $($rest),*)
+            create_json_of_type!($data, $path, $expected, $($rest),*)
         }
     };
 }
 
 impl Json<Object> {
     pub fn get_object(&self, key: &str) -> Result<Json<Object>, JsonError> {
-        get_from_json_object!(self, key, data, { create_json_of_type!(data, Object) })
+        get_from_json_object!(self, key, data, path, {
+            create_json_of_type!(data, path, &[JsonType::Object], Object)
+        })
     }
     pub fn get_array(&self, key: &str) -> Result<Json<Array>, JsonError> {
-        get_from_json_object!(self, key, data, { create_json_of_type!(data, Array) })
+        get_from_json_object!(self, key, data, path, {
+            create_json_of_type!(data, path, &[JsonType::Array], Array)
+        })
     }
 
     pub fn get_value(&self, key: &str) -> Result<Json<Value>, JsonError> {
-        get_from_json_object!(self, key, data, {
-            create_json_of_type!(data, Integer, Null, Float, Bool, Str)
+        get_from_json_object!(self, key, data, path, {
+            create_json_of_type!(
+                data, path, SCALAR, Integer, BigInt, Null, Float, Decimal, RawNumber, Bool, Str
+            )
         })
     }
 }
 
+// Types that `Json::<Object>::get_or` and `get_or_default` can read a member as
+pub trait FromJson: Sized {
+    fn from_json(json: AnyJson) -> Result<Self, JsonError>;
+}
+
+impl FromJson for AnyJson {
+    fn from_json(json: AnyJson) -> Result<Self, JsonError> {
+        Ok(json)
+    }
+}
+
+impl FromJson for Json<Object> {
+    fn from_json(json: AnyJson) -> Result<Self, JsonError> {
+        match json {
+            AnyJson::Object(json) => Ok(json),
+            json => Err(JsonError::incorrect_type(
+                json.path(),
+                &[JsonType::Object],
+                json.data(),
+            )),
+        }
+    }
+}
+
+impl FromJson for Json<Array> {
+    fn from_json(json: AnyJson) -> Result<Self, JsonError> {
+        match json {
+            AnyJson::Array(json) => Ok(json),
+            json => Err(JsonError::incorrect_type(
+                json.path(),
+                &[JsonType::Array],
+                json.data(),
+            )),
+        }
+    }
+}
+
+impl FromJson for Json<Value> {
+    fn from_json(json: AnyJson) -> Result<Self, JsonError> {
+        match json {
+            AnyJson::Value(json) => Ok(json),
+            json => Err(JsonError::incorrect_type(json.path(), SCALAR, json.data())),
+        }
+    }
+}
+
+// The same as `Json::new`
+impl<S: JsonState> FromStr for Json<S> {
+    type Err = JsonError;
+
+    fn from_str(json: &str) -> Result<Self, Self::Err> {
+        Self::new(json)
+    }
+}
+
+impl FromJson for bool {
+    fn from_json(json: AnyJson) -> Result<Self, JsonError> {
+        Json::<Value>::from_json(json)?.get_bool()
+    }
+}
+
+impl FromJson for i64 {
+    fn from_json(json: AnyJson) -> Result<Self, JsonError> {
+        Json::<Value>::from_json(json)?.get_i64()
+    }
+}
+
+// Integers are read as floats too
+impl FromJson for f64 {
+    fn from_json(json: AnyJson) -> Result<Self, JsonError> {
+        match json.data().unraw() {
+            JsonData::Integer(i) => Ok(*i as f64),
+            _ => Json::<Value>::from_json(json)?.get_f64(),
+        }
+    }
+}
+
+// Null is read as `None`
+impl<T: FromJson> FromJson for Option<T> {
+    fn from_json(json: AnyJson) -> Result<Self, JsonError> {
+        match json.data() {
+            JsonData::Null => Ok(None),
+            _ => T::from_json(json).map(Some),
+        }
+    }
+}
+
+// The string unescaped
+impl FromJson for String {
+    fn from_json(json: AnyJson) -> Result<Self, JsonError> {
+        Json::<Value>::from_json(json)?.get_unescaped_string()
+    }
+}
+
+impl Json<Object> {
+    // The member `key` as a `T`, or `default` if it is missing, null or not a `T`
+    pub fn get_or<T: FromJson>(&self, key: &str, default: T) -> T {
+        let member = get_from_json_object!(self, key, data, path, {
+            Ok(AnyJson::from_shared(Shared::clone(data)).at(path))
+        });
+        member.and_then(T::from_json).unwrap_or(default)
+    }
+    pub fn get_or_default<T: FromJson + Default>(&self, key: &str) -> T {
+        self.get_or(key, T::default())
+    }
+    pub fn get_i64_or(&self, key: &str, default: i64) -> i64 {
+        self.get_or(key, default)
+    }
+    pub fn get_f64_or(&self, key: &str, default: f64) -> f64 {
+        self.get_or(key, default)
+    }
+    pub fn get_bool_or(&self, key: &str, default: bool) -> bool {
+        self.get_or(key, default)
+    }
+    pub fn get_string_or(&self, key: &str, default: &str) -> String {
+        self.get_or(key, default.to_string())
+    }
+}
+
 macro_rules! get_from_json_array {
-    ($self:expr, $index:expr, $var1:ident, $code:block) => {
+    ($self:expr, $index:expr, $var1:ident, $path:ident, $code:block) => {
         expect_json_type!($self, Array, arr, {
+            let $path = format!("{}/{}", $self.path, $index);
             if let Some(data) = arr.get($index) {
                 let $var1 = data;
                 $code
             } else {
-                Err(JsonError::IndexNotFound)
+                Err(JsonError::index_not_found($path))
             }
         })
     };
@@ -153,14 +829,20 @@ impl Json<Array> {
         expect_json_type!(self, Array, arr, { Ok(arr.len()) })
     }
     pub fn get_object(&self, index: usize) -> Result<Json<Object>, JsonError> {
-        get_from_json_array!(self, index, data, { create_json_of_type!(data, Object) })
+        get_from_json_array!(self, index, data, path, {
+            create_json_of_type!(data, path, &[JsonType::Object], Object)
+        })
     }
     pub fn get_array(&self, index: usize) -> Result<Json<Array>, JsonError> {
-        get_from_json_array!(self, index, data, { create_json_of_type!(data, Array) })
+        get_from_json_array!(self, index, data, path, {
+            create_json_of_type!(data, path, &[JsonType::Array], Array)
+        })
     }
     pub fn get_value(&self, index: usize) -> Result<Json<Value>, JsonError> {
-        get_from_json_array!(self, index, data, {
-            create_json_of_type!(data, Integer, Float, Bool, Str, Null)
+        get_from_json_array!(self, index, data, path, {
+            create_json_of_type!(
+                data, path, SCALAR, Integer, BigInt, Float, Decimal, RawNumber, Bool, Str, Null
+            )
         })
     }
 }
@@ -169,17 +851,196 @@ impl Json<Value> {
     pub fn is_null(&self) -> bool {
         self.data.as_ref() == &JsonData::Null
     }
-    pub fn is_eof(&self) -> bool {
-        self.data.as_ref() == &JsonData::Eof
+    // Failing to read the value as one of `expected`
+    pub(crate) fn incorrect_type(&self, expected: &[JsonType]) -> JsonError {
+        JsonError::incorrect_type(self.path.as_str(), expected, &self.data)
     }
     pub fn get_bool(&self) -> Result<bool, JsonError> {
         expect_json_type!(self, Bool, b, { Ok(*b) })
     }
-    pub fn get_string(&self) -> Result<&String, JsonError> {
-        expect_json_type!(self, Str, s, { Ok(s) })
+    pub fn get_string(&self) -> Result<&str, JsonError> {
+        expect_json_type!(self, Str, s, { Ok(s.as_str()) })
+    }
+    pub fn get_unescaped_string(&self) -> Result<String, JsonError> {
+        expect_json_type!(self, Str, s, { Ok(s.to_string()) })
     }
     pub fn get_f64(&self) -> Result<f64, JsonError> {
-        expect_json_type!(self, Float, f, { Ok(*f) })
+        float(&self.data, &self.path)
+    }
+    pub fn get_i64(&self) -> Result<i64, JsonError> {
+        expect_json_type!(self, Integer, i, { Ok(*i) })
+    }
+
+    // Like `get_i64` but also accepts floats without a fractional part and strings holding
+    // such a number, e.g. `"123"` or `"1e3"`
+    pub fn get_i64_lenient(&self) -> Result<i64, JsonError> {
+        let float_to_i64 = |f: f64| {
+            // `i64::MAX as f64` rounds up to 2^63 which is out of range
+            if f.fract() == 0.0 && f >= i64::MIN as f64 && f < i64::MAX as f64 {
+                Ok(f as i64)
+            } else {
+                Err(self.incorrect_type(&[JsonType::Integer]))
+            }
+        };
+        match self.data.as_ref().unraw() {
+            JsonData::Integer(i) => Ok(*i),
+            JsonData::Float(_) => float_to_i64(float(&self.data, &self.path)?),
+            #[cfg(feature = "decimal")]
+            JsonData::Decimal(_) => float_to_i64(float(&self.data, &self.path)?),
+            JsonData::Str(s) => {
+                let s = s.trim();
+                match s.parse() {
+                    Ok(i) => Ok(i),
+                    Err(_) => float_to_i64(
+                        s.parse()
+                            .map_err(|_| self.incorrect_type(&[JsonType::Integer]))?,
+                    ),
+                }
+            }
+            _ => Err(self.incorrect_type(&[JsonType::Integer])),
+        }
+    }
+
+    // Like `get_f64` but also accepts integers and numeric strings
+    pub fn get_f64_lenient(&self) -> Result<f64, JsonError> {
+        if let Some(f) = self.data.as_f64() {
+            return Ok(f);
+        }
+        match self.data.as_ref() {
+            JsonData::Str(s) => s
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|f| f.is_finite())
+                .ok_or_else(|| self.incorrect_type(&[JsonType::Float])),
+            _ => Err(self.incorrect_type(&[JsonType::Float])),
+        }
+    }
+
+    // Like `get_bool` but also accepts the strings `"true"` and `"false"`
+    pub fn get_bool_lenient(&self) -> Result<bool, JsonError> {
+        match self.data.as_ref() {
+            JsonData::Bool(b) => Ok(*b),
+            JsonData::Str(s) => match s.trim() {
+                "true" => Ok(true),
+                "false" => Ok(false),
+                _ => Err(self.incorrect_type(&[JsonType::Bool])),
+            },
+            _ => Err(self.incorrect_type(&[JsonType::Bool])),
+        }
+    }
+}
+
+impl From<bool> for Json<Value> {
+    fn from(b: bool) -> Self {
+        Json::from_data(JsonData::Bool(b))
+    }
+}
+
+impl From<i64> for Json<Value> {
+    fn from(i: i64) -> Self {
+        Json::from_data(JsonData::Integer(i))
+    }
+}
+
+impl From<f64> for Json<Value> {
+    fn from(f: f64) -> Self {
+        Json::from_data(JsonData::Float(f))
+    }
+}
+
+impl From<&str> for Json<Value> {
+    fn from(s: &str) -> Self {
+        Json::from_data(JsonData::Str(s.into()))
+    }
+}
+
+impl From<String> for Json<Value> {
+    fn from(s: String) -> Self {
+        Json::from(s.as_str())
+    }
+}
+
+// The scalars a `Json<Value>` can be made from, so they can be collected into arrays and
+// objects next to other json
+macro_rules! any_json_from {
+    ($($type:ty),*) => {
+        $(
+            impl From<$type> for AnyJson {
+                fn from(value: $type) -> Self {
+                    AnyJson::Value(Json::from(value))
+                }
+            }
+        )*
+    };
+}
+
+any_json_from!(bool, i64, f64, &str, String);
+
+// Compares the data only, the same json read from different places is equal whatever its
+// state or path
+impl<S: JsonState, T: JsonState> PartialEq<Json<T>> for Json<S> {
+    fn eq(&self, other: &Json<T>) -> bool {
+        Shared::ptr_eq(&self.data, &other.data) || self.data == other.data
+    }
+}
+
+// Strings are compared by their unescaped text, so `"\\u0041"` equals `"A"`
+impl PartialEq<&str> for Json<Value> {
+    fn eq(&self, other: &&str) -> bool {
+        matches!(self.data.as_ref(), JsonData::Str(s) if s == *other)
+    }
+}
+
+impl PartialEq<i64> for Json<Value> {
+    fn eq(&self, other: &i64) -> bool {
+        self.data.as_ref() == &JsonData::Integer(*other)
+    }
+}
+
+impl PartialEq<f64> for Json<Value> {
+    fn eq(&self, other: &f64) -> bool {
+        self.data.as_ref() == &JsonData::Float(*other)
+    }
+}
+
+impl PartialEq<bool> for Json<Value> {
+    fn eq(&self, other: &bool) -> bool {
+        self.data.as_ref() == &JsonData::Bool(*other)
+    }
+}
+
+// A mutable reference to a value inside a json, only the part of the json that is changed
+// through it stops being shared with other views
+pub struct JsonMut<'a> {
+    pub(crate) data: &'a mut Shared<JsonData>,
+    pub(crate) path: String,
+}
+
+impl<'a> JsonMut<'a> {
+    // The current value, later changes through `set` are not reflected in it
+    pub fn get(&self) -> AnyJson {
+        AnyJson::from_shared(Shared::clone(self.data)).at(self.path.clone())
+    }
+    pub fn set<S: JsonState>(&mut self, value: Json<S>) {
+        *self.data = value.data;
+    }
+    // Moves the value out and leaves `null` in its place
+    pub fn into_value(self) -> AnyJson {
+        let data = std::mem::replace(self.data, Shared::new(JsonData::Null));
+        AnyJson::from_shared(data).at(self.path)
+    }
+    pub fn is_null(&self) -> bool {
+        self.data.as_ref() == &JsonData::Null
+    }
+    pub fn get_bool(&self) -> Result<bool, JsonError> {
+        expect_json_type!(self, Bool, b, { Ok(*b) })
+    }
+    pub fn get_string(&self) -> Result<&str, JsonError> {
+        expect_json_type!(self, Str, s, { Ok(s.as_str()) })
+    }
+    pub fn get_f64(&self) -> Result<f64, JsonError> {
+        float(self.data, &self.path)
     }
     pub fn get_i64(&self) -> Result<i64, JsonError> {
         expect_json_type!(self, Integer, i, { Ok(*i) })
@@ -188,7 +1049,216 @@ impl Json<Value> {
 
 #[cfg(test)]
 mod tests {
-    use crate::json::{Array, Json, Object, Value};
+    use crate::json::JsonType;
+    use crate::json::{AnyJson, Array, Json, JsonError, Object, Value, MAX_DEPTH};
+    use crate::parser::{JsonData, Shared};
+    use proptest::prelude::*;
+
+    proptest! {
+        // Fuzzes `try_parse` with random bytes and with valid json that had bytes changed
+        #[test]
+        fn try_parse_never_panics(bytes in any::<Vec<u8>>()) {
+            let _ = Json::<Value>::try_parse(&bytes);
+        }
+
+        #[test]
+        fn try_parse_mutated_json_never_panics(
+            json in crate::arbitrary::arb_any_json(),
+            changes in prop::collection::vec((any::<prop::sample::Index>(), any::<u8>()), 1..8)
+        ) {
+            let mut bytes = json.to_string().into_bytes();
+            if !bytes.is_empty() {
+                for (index, byte) in changes {
+                    let i = index.index(bytes.len());
+                    bytes[i] = byte;
+                }
+            }
+            let _ = Json::<Value>::try_parse(&bytes);
+        }
+
+        #[test]
+        fn try_parse_valid_json(json in crate::arbitrary::arb_array()) {
+            let parsed = Json::<Array>::try_parse(json.to_string()).unwrap();
+            prop_assert_eq!(&json.data, &parsed.data);
+        }
+    }
+
+    #[test]
+    fn try_parse_errors() {
+        assert!(matches!(
+            Json::<Value>::try_parse(b"\"\xff\""),
+            Err(JsonError::InvalidJsonSyntax(e)) if e.contains("utf-8 at byte 1")
+        ));
+        assert!(Json::<Value>::try_parse("[".repeat(100_000)).is_err());
+        let nested = format!("{}{}", "[".repeat(MAX_DEPTH), "]".repeat(MAX_DEPTH));
+        assert!(Json::<Array>::try_parse(&nested).is_ok());
+        let deeper = format!("{}{}", "[".repeat(200_000), "]".repeat(200_000));
+        assert_eq!(
+            Some(JsonError::InvalidJsonSyntax(
+                "Arrays and objects nested deeper than 512 levels, this array starts at 1:513"
+                    .to_string()
+            )),
+            Json::<Value>::try_parse(&deeper).err()
+        );
+        assert!(Json::<Value>::new(&deeper).is_err());
+        assert!(
+            Json::<Value>::new(format!("{}1{}", "{\"a\":".repeat(600), "}".repeat(600))).is_err()
+        );
+        assert_eq!(
+            Some(JsonError::InvalidJsonSyntax("Empty input".to_string())),
+            Json::<Value>::try_parse(" ").err()
+        );
+        assert!(Json::<Value>::try_parse("[1,]").is_err());
+        assert_eq!(
+            Some(JsonError::InvalidJsonSyntax(
+                "Unclosed object started at 1:2".to_string()
+            )),
+            Json::<Value>::try_parse("[{\"a\": [1]").err()
+        );
+    }
+
+    #[test]
+    fn new_nesting_limit() {
+        let objects = |depth: usize| format!("{}1{}", "{\"a\":".repeat(depth), "}".repeat(depth));
+        let arrays = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(Json::<Object>::new(objects(MAX_DEPTH)).is_ok());
+        assert!(Json::<Array>::new(arrays(MAX_DEPTH)).is_ok());
+        assert!(Json::<Object>::new(objects(MAX_DEPTH + 1)).is_err());
+        assert!(Json::<Array>::new(arrays(200_000)).is_err());
+        assert!(AnyJson::parse(arrays(MAX_DEPTH + 1)).is_err());
+        let hasher = crate::hasher::KeyHasher::default;
+        assert!(Json::<Object>::new_with_hasher(objects(MAX_DEPTH), hasher()).is_ok());
+        assert!(Json::<Object>::new_with_hasher(objects(200_000), hasher()).is_err());
+    }
+
+    #[test]
+    fn top_level_functions() {
+        let json: Json<Object> = crate::from_str("{\"a\": [1, 2]}").unwrap();
+        assert_eq!("{\"a\":[1,2]}", crate::to_string(&json));
+        assert_eq!(
+            "{\n  \"a\": [\n    1,\n    2\n  ]\n}",
+            crate::to_string_pretty(&json)
+        );
+        let slice: Json<Object> = crate::from_slice(b"{\"a\": [1, 2]}").unwrap();
+        assert_eq!(json, slice);
+        let read: Json<Object> = crate::from_reader("{\"a\": [1, 2]}".as_bytes()).unwrap();
+        assert_eq!(json, read);
+        assert!(crate::from_reader::<Value, _>("[1".as_bytes()).is_err());
+        assert_eq!(
+            Some(JsonError::FileError(std::io::ErrorKind::InvalidData)),
+            crate::from_reader::<Value, _>(&b"\"\xff\""[..]).err()
+        );
+        let deep = format!("{}{}", "[".repeat(MAX_DEPTH + 1), "]".repeat(MAX_DEPTH + 1));
+        assert!(crate::from_str::<Array>(&deep).is_err());
+        assert!(crate::from_slice::<Array>(deep.as_bytes()).is_err());
+        assert!(crate::from_reader::<Array, _>(deep.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn from_str_checks_the_root() {
+        let json: Json<Object> = "{\"a\": 1}".parse().unwrap();
+        assert_eq!(Ok(1), json.get_value("a").unwrap().get_i64());
+        assert_eq!(Ok(2), "[1, 2]".parse::<Json<Array>>().unwrap().len());
+        assert_eq!("\"x\"".parse::<Json<Value>>().unwrap(), "x");
+        assert_eq!(
+            Err(JsonError::IncorrectType {
+                path: String::new(),
+                expected: vec![JsonType::Object],
+                found: JsonType::Array
+            }),
+            "[1]".parse::<Json<Object>>().map(|_| ())
+        );
+        assert!("{}".parse::<Json<Value>>().is_err());
+        assert!(matches!(
+            "[1".parse::<Json<Array>>(),
+            Err(JsonError::InvalidJsonSyntax(_))
+        ));
+    }
+
+    #[test]
+    fn constructors_check_the_root() {
+        let mismatch = |expected: &[JsonType], found| {
+            Err(JsonError::IncorrectType {
+                path: String::new(),
+                expected: expected.to_vec(),
+                found,
+            })
+        };
+        assert_eq!(
+            mismatch(&[JsonType::Array], JsonType::Object),
+            Json::<Array>::new("{\"a\": 1}").map(|_| ())
+        );
+        assert_eq!(
+            mismatch(&[JsonType::Object], JsonType::Integer),
+            Json::<Object>::try_parse("1").map(|_| ())
+        );
+        assert_eq!(
+            mismatch(crate::json::SCALAR, JsonType::Array),
+            crate::json::from_reader::<Value, _>("[]".as_bytes()).map(|_| ())
+        );
+        assert!(Json::<Object>::from_file("src/__test_data__/test_data1.json").is_ok());
+        assert!(matches!(Json::parse("{}"), Ok(AnyJson::Object(_))));
+        assert!(matches!(Json::parse("[]"), Ok(AnyJson::Array(_))));
+        assert!(matches!(Json::parse("null"), Ok(AnyJson::Value(_))));
+        assert!(Json::parse("[").is_err());
+    }
+
+    #[test]
+    fn convert_between_states() {
+        let json = Json::<Object>::new("{\"a\": [{\"b\": 1}]}").unwrap();
+        let any = json.pointer("/a/0").unwrap();
+        let AnyJson::Object(object) = any else {
+            panic!("not an object")
+        };
+        let object = object.try_into_object().unwrap();
+        assert_eq!("/a/0", object.path());
+        assert!(Shared::ptr_eq(
+            &object.data,
+            &json.get_array("a").unwrap().get_object(0).unwrap().data
+        ));
+        assert_eq!(
+            Err(JsonError::IncorrectType {
+                path: "/a/0".to_string(),
+                expected: vec![JsonType::Array],
+                found: JsonType::Object
+            }),
+            object.clone().try_into_array().map(|_| ())
+        );
+        assert!(object.try_into_value().is_err());
+        let value = json.get_array("a").unwrap().try_into_value();
+        assert!(matches!(value, Err(JsonError::IncorrectType { path, .. }) if path == "/a"));
+    }
+
+    #[test]
+    fn error_codes_and_json() {
+        let json = Json::<Object>::new("{\"a/b\": [1]}").unwrap();
+        let error = json.get_array("a/b").unwrap().get_object(0).unwrap_err();
+        assert_eq!("E0001", error.code());
+        assert_eq!(
+            "Expected object at `/a~1b/0`, found integer",
+            error.to_string()
+        );
+        assert_eq!(
+            Json::<Object>::new(
+                "{\"code\": \"E0001\", \"message\": \"Expected object at `/a~1b/0`, found integer\",
+                  \"path\": \"/a~1b/0\", \"expected\": [\"object\"], \"found\": \"integer\"}"
+            )
+            .unwrap(),
+            error.to_json()
+        );
+        let error = Json::<Array>::new("[1,]").unwrap_err();
+        assert_eq!("E0004", error.code());
+        assert_eq!(
+            Some("E0004"),
+            error.to_json().get_value("code").unwrap().get_string().ok()
+        );
+        assert_eq!(None, error.to_json().get_value("path").ok());
+        assert_eq!("E0002", json.get_value("b").unwrap_err().code());
+        assert_eq!(
+            "E0009",
+            JsonError::FileError(std::io::ErrorKind::NotFound).code()
+        );
+    }
 
     #[test]
     fn read_from_file_test_data1() {
@@ -230,7 +1300,7 @@ mod tests {
     fn read_from_file_test_data_my_1() {
         let json = Json::<Object>::from_file("src/__test_data__/test_data_my_1.json").unwrap();
         assert_eq!(
-            &String::from("{\\\"hej\\\":null}"),
+            &String::from("{\"hej\":null}"),
             json.get_value("json_str_in_json")
                 .unwrap()
                 .get_string()
@@ -242,14 +1312,152 @@ mod tests {
         let json = Json::<Object>::from_file("src/__test_data__/test_data_my_2.json").unwrap();
         assert!(json.get_value("distinct").unwrap().get_bool().unwrap());
         assert_eq!(
-            Ok(&String::from("simpler non-flash version\\\\")),
+            Ok("simpler non-flash version\\"),
             json.get_value("message").unwrap().get_string()
         );
     }
 
+    #[cfg(feature = "sync")]
+    #[test]
+    fn shared_between_threads() {
+        use std::sync::OnceLock;
+        static CONFIG: OnceLock<Json<Object>> = OnceLock::new();
+        CONFIG.get_or_init(|| Json::new("{\"workers\": 4}").unwrap());
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                std::thread::spawn(|| {
+                    CONFIG
+                        .get()
+                        .unwrap()
+                        .get_value("workers")
+                        .unwrap()
+                        .get_i64()
+                })
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(Ok(4), handle.join().unwrap());
+        }
+    }
+
+    #[test]
+    fn errors_carry_the_path() {
+        let json: Json<Object> =
+            Json::new("{\"servers\": [{\"port\": \"80\"}], \"a/b\": {}}").unwrap();
+        let server = json.get_array("servers").unwrap().get_object(0).unwrap();
+        assert_eq!("/servers/0", server.path());
+        assert_eq!(
+            Some("/servers/0/port"),
+            server
+                .get_value("port")
+                .unwrap()
+                .get_i64()
+                .unwrap_err()
+                .path()
+        );
+        assert_eq!(
+            Some("/servers/0/host"),
+            server.get_value("host").unwrap_err().path()
+        );
+        assert_eq!(
+            Err(JsonError::IndexNotFound {
+                path: "/servers/1".to_string()
+            }),
+            json.get_array("servers").unwrap().get_object(1).map(|_| ())
+        );
+        assert_eq!(
+            Some("/a~1b/c"),
+            json.get_object("a/b")
+                .unwrap()
+                .get_array("c")
+                .unwrap_err()
+                .path()
+        );
+        assert_eq!("/servers/0/port", server.pointer("/port").unwrap().path());
+        assert_eq!(None, JsonError::InvalidPath(String::new()).path());
+    }
+
+    #[test]
+    fn clone_shares_data_and_default_is_empty() {
+        let json: Json<Object> = Json::new("{\"a\": [1]}").unwrap();
+        let a = json.get_array("a").unwrap();
+        let clone = a.clone();
+        assert!(Shared::ptr_eq(&a.data, &clone.data));
+        assert_eq!("/a", clone.path());
+        assert_eq!("{}", Json::<Object>::default().to_string());
+        assert_eq!(Ok(0), Json::<Array>::default().len());
+    }
+
+    #[test]
+    fn equality_between_wrappers() {
+        let json: Json<Object> =
+            Json::new("{\"a\": {\"b\": [1, 2]}, \"c\": {\"b\": [1, 2]}, \"s\": \"\\u0041\"}")
+                .unwrap();
+        let a = json.get_object("a").unwrap();
+        assert_eq!(a, json.get_object("c").unwrap());
+        assert_ne!(a, json);
+        let b: Json<Array> = Json::new("[1, 2]").unwrap();
+        assert_eq!(a.get_array("b").unwrap(), b);
+        assert_eq!(b.get_value(0).unwrap(), Json::<Value>::new("1").unwrap());
+        assert_eq!(b.get_value(0).unwrap(), 1);
+        assert_ne!(b.get_value(0).unwrap(), 1.0);
+        assert_eq!(json.get_value("s").unwrap(), "A");
+        assert_ne!(json.get_value("s").unwrap(), "\\u0041");
+        assert_eq!(Json::from(true), true);
+        assert_eq!(Json::from(1.5), 1.5);
+    }
+
+    #[test]
+    fn incorrect_type_reports_expected_and_found() {
+        let json: Json<Object> = Json::new("{\"port\": \"80\", \"hosts\": [1]}").unwrap();
+        assert_eq!(
+            Err(JsonError::IncorrectType {
+                path: "/port".to_string(),
+                expected: vec![JsonType::Integer],
+                found: JsonType::String
+            }),
+            json.get_value("port").unwrap().get_i64()
+        );
+        assert_eq!(
+            Err(JsonError::IncorrectType {
+                path: "/hosts".to_string(),
+                expected: vec![JsonType::Object],
+                found: JsonType::Array
+            }),
+            json.get_object("hosts").map(|_| ())
+        );
+        assert_eq!("string", JsonType::String.to_string());
+        assert_eq!("object", JsonType::Object.to_string());
+    }
+
     #[test]
-    fn is_eof() {
-        assert!(Json::new("").unwrap().is_eof())
+    fn views_share_data_until_mutated() {
+        let json: Json<Object> = Json::new("{\"a\": {\"b\": [1, 2]}}").unwrap();
+        let mut a = json.get_object("a").unwrap();
+        assert!(Shared::ptr_eq(&a.data, &json.get_object("a").unwrap().data));
+        let b = a.get_array("b").unwrap();
+        if let JsonData::Object(map) = Shared::make_mut(&mut a.data) {
+            map.insert("c".into(), Shared::new(JsonData::Null));
+        }
+        assert!(a.get_value("c").unwrap().is_null());
+        assert!(matches!(
+            json.get_object("a").unwrap().get_value("c"),
+            Err(JsonError::KeyNotFound { path }) if path == "/a/c"
+        ));
+        // only the mutated level was copied, the array below it is still shared
+        assert!(Shared::ptr_eq(&b.data, &a.get_array("b").unwrap().data));
+        assert!(Shared::ptr_eq(
+            &b.data,
+            &json.get_object("a").unwrap().get_array("b").unwrap().data
+        ));
+    }
+
+    #[test]
+    fn empty_input() {
+        assert_eq!(
+            Some(JsonError::InvalidJsonSyntax("Empty input".to_string())),
+            Json::<Value>::new("").err()
+        );
     }
 
     #[test]
@@ -295,10 +1503,7 @@ mod tests {
         .unwrap();
         assert_eq!(Ok(123), json.get_value("test1").unwrap().get_i64());
         let sub_obj = json.get_object("sub_obj").unwrap();
-        assert_eq!(
-            Ok(&String::from("abc")),
-            sub_obj.get_value("test2").unwrap().get_string()
-        );
+        assert_eq!(Ok("abc"), sub_obj.get_value("test2").unwrap().get_string());
         let sub_obj_arr = sub_obj.get_array("testarr1").unwrap();
         assert_eq!(
             Ok(1),
@@ -352,13 +1557,10 @@ mod tests {
         let arr3 = json.get_array("arr3").unwrap();
         let arr3_arr = arr3.get_array(5).unwrap();
         assert_eq!(
-            Ok(&String::from("string1")),
+            Ok("string1"),
             json.get_value("string1").unwrap().get_string()
         );
-        assert_eq!(
-            Ok(&String::from("")),
-            json.get_value("string2").unwrap().get_string()
-        );
+        assert_eq!(Ok(""), json.get_value("string2").unwrap().get_string());
         assert!(json.get_value("null").unwrap().is_null());
         assert_eq!(Ok(1337), json.get_value("integer").unwrap().get_i64());
         assert_eq!(Ok(1337.0), json.get_value("float").unwrap().get_f64());
@@ -366,28 +1568,122 @@ mod tests {
         assert_eq!(Ok(false), json.get_value("false").unwrap().get_bool());
         assert!(arr1.is_empty() == Ok(true));
         assert!(arr2.get_value(0).unwrap().is_null());
-        assert_eq!(
-            Ok(&String::from("hej")),
-            arr2.get_value(1).unwrap().get_string()
-        );
+        assert_eq!(Ok("hej"), arr2.get_value(1).unwrap().get_string());
         assert_eq!(Ok(1337), arr2.get_value(2).unwrap().get_i64());
         assert_eq!(Ok(true), arr2.get_value(3).unwrap().get_bool());
         assert_eq!(Ok(false), arr2.get_value(4).unwrap().get_bool());
         assert!(arr3.get_value(0).unwrap().is_null());
-        assert_eq!(
-            Ok(&String::from("hej")),
-            arr3.get_value(1).unwrap().get_string()
-        );
+        assert_eq!(Ok("hej"), arr3.get_value(1).unwrap().get_string());
         assert_eq!(Ok(1337), arr3.get_value(2).unwrap().get_i64());
         assert_eq!(Ok(true), arr3.get_value(3).unwrap().get_bool());
         assert_eq!(Ok(false), arr3.get_value(4).unwrap().get_bool());
         assert!(arr3_arr.get_value(0).unwrap().is_null());
-        assert_eq!(
-            Ok(&String::from("hej")),
-            arr3_arr.get_value(1).unwrap().get_string()
-        );
+        assert_eq!(Ok("hej"), arr3_arr.get_value(1).unwrap().get_string());
         assert_eq!(Ok(1337), arr3_arr.get_value(2).unwrap().get_i64());
         assert_eq!(Ok(true), arr3_arr.get_value(3).unwrap().get_bool());
         assert_eq!(Ok(false), arr3_arr.get_value(4).unwrap().get_bool());
     }
+
+    #[test]
+    fn lenient_accessors() {
+        let json: Json<Array> =
+            Json::new("[12, \"34\", 5.0, \" 6e2 \", 7.5, \"abc\", \"true\", false, \"NaN\", 1e19]")
+                .unwrap();
+        let value = |i| json.get_value(i).unwrap();
+        assert_eq!(Ok(12), value(0).get_i64_lenient());
+        assert_eq!(Ok(34), value(1).get_i64_lenient());
+        assert_eq!(Ok(5), value(2).get_i64_lenient());
+        assert_eq!(Ok(600), value(3).get_i64_lenient());
+        assert_eq!(
+            Err(JsonError::IncorrectType {
+                path: "/4".to_string(),
+                expected: vec![JsonType::Integer],
+                found: JsonType::Float,
+            }),
+            value(4).get_i64_lenient()
+        );
+        assert_eq!(
+            Err(JsonError::IncorrectType {
+                path: "/5".to_string(),
+                expected: vec![JsonType::Integer],
+                found: JsonType::String,
+            }),
+            value(5).get_i64_lenient()
+        );
+        assert_eq!(
+            Err(JsonError::IncorrectType {
+                path: "/9".to_string(),
+                expected: vec![JsonType::Integer],
+                found: JsonType::Float,
+            }),
+            value(9).get_i64_lenient()
+        );
+        assert_eq!(Ok(34.0), value(1).get_f64_lenient());
+        assert_eq!(Ok(12.0), value(0).get_f64_lenient());
+        assert_eq!(
+            Err(JsonError::IncorrectType {
+                path: "/8".to_string(),
+                expected: vec![JsonType::Float],
+                found: JsonType::String,
+            }),
+            value(8).get_f64_lenient()
+        );
+        assert_eq!(Ok(true), value(6).get_bool_lenient());
+        assert_eq!(Ok(false), value(7).get_bool_lenient());
+        assert_eq!(
+            Err(JsonError::IncorrectType {
+                path: "/0".to_string(),
+                expected: vec![JsonType::Bool],
+                found: JsonType::Integer,
+            }),
+            value(0).get_bool_lenient()
+        );
+        // the strict accessors are unchanged
+        assert_eq!(
+            Err(JsonError::IncorrectType {
+                path: "/1".to_string(),
+                expected: vec![JsonType::Integer],
+                found: JsonType::String,
+            }),
+            value(1).get_i64()
+        );
+    }
+
+    #[test]
+    fn defaulting_accessors() {
+        let config: Json<Object> = Json::new(
+            "{\"port\": 9000, \"host\": \"a\\\"b\", \"debug\": null, \"ratio\": 2, \"tags\": [1]}",
+        )
+        .unwrap();
+        assert_eq!(9000, config.get_i64_or("port", 8080));
+        assert_eq!(8080, config.get_i64_or("missing", 8080));
+        assert_eq!(8080, config.get_i64_or("host", 8080));
+        assert_eq!("a\"b", config.get_string_or("host", "localhost"));
+        assert!(!config.get_bool_or("debug", false));
+        assert_eq!(2.0, config.get_f64_or("ratio", 1.0));
+        assert_eq!(String::new(), config.get_or_default::<String>("port"));
+        assert_eq!(0, config.get_or_default::<i64>("nope"));
+        assert_eq!(
+            Ok(1),
+            config
+                .get_or_default::<Option<Json<Array>>>("tags")
+                .map_or(Ok(0), |tags| tags.len())
+        );
+    }
+
+    #[test]
+    fn display_minified_and_alternate() {
+        let json: Json<Array> = Json::new("[1, {\"a\": [true, 2.0]}, []]").unwrap();
+        assert_eq!("[1,{\"a\":[true,2.0]},[]]", format!("{}", json));
+        assert_eq!(
+            "[\n  1,\n  {\n    \"a\": [\n      true,\n      2.0\n    ]\n  },\n  []\n]",
+            format!("{:#}", json)
+        );
+        let any = json.pointer("/1").unwrap();
+        assert_eq!("{\"a\":[true,2.0]}", any.to_string());
+        assert_eq!(
+            "{\n  \"a\": [\n    true,\n    2.0\n  ]\n}",
+            format!("{:#}", any)
+        );
+    }
 }
@@ -0,0 +1,132 @@
+use crate::format::{io_error, reformat, write_data, FormatOptions};
+use crate::json::{Json, JsonError, JsonState};
+use crate::split::split_array_to_ndjson;
+use std::io::{BufRead, BufReader, Read, Write};
+
+// Writes one minified json per line (NDJSON / JSON Lines)
+pub struct Writer<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> Writer<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub fn write<S: JsonState>(&mut self, json: &Json<S>) -> Result<(), JsonError> {
+        let mut line = String::new();
+        write_data(&json.data, &mut line, 0, &FormatOptions::minified());
+        line.push('\n');
+        self.writer.write_all(line.as_bytes()).map_err(io_error)
+    }
+
+    pub fn flush(&mut self) -> Result<(), JsonError> {
+        self.writer.flush().map_err(io_error)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+// Streams the top level array in `reader` into `writer` with one element per line.
+// Returns the number of lines written.
+pub fn array_to_ndjson<R: Read, W: Write>(reader: R, mut writer: W) -> Result<usize, JsonError> {
+    let mut lines = 0;
+    split_array_to_ndjson(reader, 1, |line| {
+        lines += 1;
+        writer.write_all(line.as_bytes())
+    })?;
+    writer.flush().map_err(io_error)?;
+    Ok(lines)
+}
+
+// Streams NDJSON from `reader` into `writer` as a single minified array, blank lines are
+// skipped. Returns the number of elements written.
+pub fn ndjson_to_array<R: Read, W: Write>(reader: R, mut writer: W) -> Result<usize, JsonError> {
+    let mut count = 0;
+    writer.write_all(b"[").map_err(io_error)?;
+    for (row, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line.map_err(io_error)?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if count > 0 {
+            writer.write_all(b",").map_err(io_error)?;
+        }
+        reformat(line.as_bytes(), &mut writer, &FormatOptions::minified()).map_err(|error| {
+            match error {
+                JsonError::InvalidJsonSyntax(msg) => {
+                    JsonError::InvalidJsonSyntax(format!("Line {}: {}", row + 1, msg))
+                }
+                error => error,
+            }
+        })?;
+        count += 1;
+    }
+    writer.write_all(b"]").map_err(io_error)?;
+    writer.flush().map_err(io_error)?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::JsonType;
+    use crate::json::{Array, Object, Value};
+
+    #[test]
+    fn writer_one_json_per_line() {
+        let mut writer = Writer::new(Vec::new());
+        writer
+            .write(&Json::<Array>::new("[1, {\"a\": [true]}]").unwrap())
+            .unwrap();
+        writer
+            .write(&Json::<Object>::new("{\"b\": \"x y\"}").unwrap())
+            .unwrap();
+        writer.write(&Json::<Value>::new("null").unwrap()).unwrap();
+        assert_eq!(
+            "[1,{\"a\":[true]}]\n{\"b\":\"x y\"}\nnull\n",
+            String::from_utf8(writer.into_inner()).unwrap()
+        );
+    }
+
+    #[test]
+    fn array_and_ndjson_roundtrip() {
+        let mut ndjson = Vec::new();
+        assert_eq!(
+            Ok(3),
+            array_to_ndjson("[{\"id\": 1}, [2, 3],\n \"four\"]".as_bytes(), &mut ndjson)
+        );
+        assert_eq!(
+            "{\"id\":1}\n[2,3]\n\"four\"\n",
+            String::from_utf8(ndjson.clone()).unwrap()
+        );
+        let mut array = Vec::new();
+        assert_eq!(Ok(3), ndjson_to_array(&ndjson[..], &mut array));
+        assert_eq!(
+            "[{\"id\":1},[2,3],\"four\"]",
+            String::from_utf8(array).unwrap()
+        );
+        let mut empty = Vec::new();
+        assert_eq!(Ok(0), ndjson_to_array("\n  \n".as_bytes(), &mut empty));
+        assert_eq!(b"[]", &empty[..]);
+    }
+
+    #[test]
+    fn ndjson_to_array_invalid_line() {
+        let error = ndjson_to_array("{\"a\": 1}\n\n{\"a\": }\n".as_bytes(), Vec::new());
+        assert!(
+            matches!(&error, Err(JsonError::InvalidJsonSyntax(msg)) if msg.starts_with("Line 3: ")),
+            "{error:?}"
+        );
+        assert_eq!(
+            Err(JsonError::IncorrectType {
+                path: "".to_string(),
+                expected: vec![JsonType::Array],
+                found: JsonType::Object,
+            }),
+            array_to_ndjson("{}".as_bytes(), Vec::new())
+        );
+    }
+}
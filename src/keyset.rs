@@ -0,0 +1,193 @@
+use crate::escape::unescape_str;
+use crate::format::syntax_error;
+use crate::json::{AnyJson, Json, JsonError, JsonType, Object};
+use crate::parser::{JsonData, ParseError};
+use crate::pointer::pointer_token;
+use crate::reuse::Parser;
+use crate::stream::{scalar_data, Event, Events};
+use std::collections::HashMap;
+
+// Keys that are known up front, like the fields of records that all have the same schema.
+// Each key gets the index it was given at, so the members of many objects can be read
+// into slots or columns without looking their keys up one by one.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KeySet {
+    keys: Vec<String>,
+    indices: HashMap<String, usize>,
+}
+
+impl KeySet {
+    // A key that is given twice keeps its first index
+    pub fn new<'a, I: IntoIterator<Item = &'a str>>(keys: I) -> Self {
+        let mut set = KeySet::default();
+        for key in keys {
+            if !set.indices.contains_key(key) {
+                set.indices.insert(key.to_string(), set.keys.len());
+                set.keys.push(key.to_string());
+            }
+        }
+        set
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn index(&self, key: &str) -> Option<usize> {
+        self.indices.get(key).copied()
+    }
+
+    pub fn key(&self, index: usize) -> Option<&str> {
+        self.keys.get(index).map(String::as_str)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.keys.iter().map(String::as_str)
+    }
+
+    // The members of `object` at the index of their key, members with other keys are left
+    // out and keys that are missing are `None`
+    pub fn values(&self, object: &Json<Object>) -> Vec<Option<AnyJson>> {
+        let mut values = vec![None; self.len()];
+        if let JsonData::Object(map) = object.data.as_ref() {
+            for (key, data) in map.iter() {
+                if let Some(index) = self.index(key) {
+                    let path = format!("{}/{}", object.path, pointer_token(key));
+                    values[index] = Some(AnyJson::from_shared(data.clone()).at(path));
+                }
+            }
+        }
+        values
+    }
+}
+
+impl Parser {
+    // Parses an object straight into the slots of `keys` like `KeySet::values`. The values
+    // of the other members are checked but never built.
+    pub fn parse_record<R: AsRef<str>>(
+        &mut self,
+        json: R,
+        keys: &KeySet,
+    ) -> Result<Vec<Option<AnyJson>>, JsonError> {
+        let stack = std::mem::take(&mut self.stack);
+        let mut events = Events::reusing(json.as_ref().chars(), stack);
+        let values = self.record(&mut events, keys);
+        self.stack = events.into_stack();
+        if values.is_err() {
+            self.builder.clear();
+        }
+        values
+    }
+
+    fn record<Chars: Iterator<Item = char>>(
+        &mut self,
+        events: &mut Events<Chars>,
+        keys: &KeySet,
+    ) -> Result<Vec<Option<AnyJson>>, JsonError> {
+        let found = match events.next() {
+            Some(Ok(Event::StartObject(_))) => None,
+            Some(Ok(Event::StartArray(_))) => Some(JsonData::Array(Vec::new())),
+            Some(Ok(Event::Scalar(token))) => Some(scalar_data(token).map_err(syntax_error)?),
+            Some(Ok(_)) => unreachable!(),
+            Some(Err(error)) => return Err(syntax_error(error)),
+            None => return Err(syntax_error(ParseError::EmptyInput)),
+        };
+        if let Some(found) = found {
+            return Err(JsonError::incorrect_type("", &[JsonType::Object], &found));
+        }
+        let mut values = vec![None; keys.len()];
+        // The slot and pointer of the member whose value is being built
+        let mut member: Option<(usize, String)> = None;
+        for event in events.by_ref() {
+            let event = event.map_err(syntax_error)?;
+            match (&event, &member) {
+                (Event::Key(token), None) => {
+                    let key = unescape_str(&token.text[1..token.text.len() - 1]);
+                    member = keys
+                        .index(&key)
+                        .map(|index| (index, format!("/{}", pointer_token(&key))));
+                }
+                (Event::EndObject(_), None) => {}
+                (_, Some(_)) => {
+                    if let Some(data) = self.builder.push(event).map_err(syntax_error)? {
+                        let (index, path) = member.take().expect("THIS SHOULD NEVER PANIC");
+                        values[index] = Some(AnyJson::from_data(data).at(path));
+                    }
+                }
+                // a member that is not in `keys`
+                (_, None) => {}
+            }
+        }
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{FromJson, Value};
+
+    #[test]
+    fn key_indices() {
+        let keys = KeySet::new(["id", "price", "id", "a/b"]);
+        assert_eq!(3, keys.len());
+        assert_eq!(Some(1), keys.index("price"));
+        assert_eq!(None, keys.index("ts"));
+        assert_eq!(Some("a/b"), keys.key(2));
+        assert_eq!(vec!["id", "price", "a/b"], keys.keys().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn values_by_index() {
+        let keys = KeySet::new(["id", "price", "a/b", "ts"]);
+        let record: Json<Object> =
+            Json::new("{\"price\": 2.5, \"i\\u0064\": 7, \"other\": [1], \"a/b\": null}").unwrap();
+        let values = keys.values(&record);
+        let value = |i: usize| Json::<Value>::from_json(values[i].clone().unwrap()).unwrap();
+        assert_eq!(value(0), 7);
+        assert_eq!(value(1), 2.5);
+        assert!(value(2).is_null());
+        assert_eq!("/a~1b", value(2).path());
+        assert!(values[3].is_none());
+    }
+
+    #[test]
+    fn parse_records() {
+        let keys = KeySet::new(["id", "tags"]);
+        let mut parser = Parser::new();
+        let lines = [
+            "{\"skip\": {\"id\": 0, \"x\": [{}]}, \"tags\": [\"a\"], \"id\": 1}",
+            "{\"id\": 2}",
+        ];
+        for (line, id) in lines.iter().zip(1..) {
+            let values = parser.parse_record(line, &keys).unwrap();
+            let json = Json::<Object>::new(line).unwrap();
+            assert_eq!(
+                keys.values(&json)
+                    .iter()
+                    .map(|value| value.as_ref().map(|value| value.to_string()))
+                    .collect::<Vec<_>>(),
+                values
+                    .iter()
+                    .map(|value| value.as_ref().map(|value| value.to_string()))
+                    .collect::<Vec<_>>()
+            );
+            assert_eq!(Ok(id), i64::from_json(values[0].clone().unwrap()));
+        }
+        assert_eq!(
+            Err(JsonError::IncorrectType {
+                path: String::new(),
+                expected: vec![JsonType::Object],
+                found: JsonType::Array
+            }),
+            parser.parse_record("[1]", &keys).map(|_| ())
+        );
+        assert!(parser.parse_record("{\"id\": [1}", &keys).is_err());
+        assert!(parser.parse_record("{\"id\": 1} 2", &keys).is_err());
+        assert_eq!(2, parser.parse_record("{\"id\": 3}", &keys).unwrap().len());
+    }
+}
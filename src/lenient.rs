@@ -0,0 +1,300 @@
+use crate::format::syntax_error;
+use crate::json::{Json, JsonError, JsonState};
+use crate::stream::{build_data, Events};
+use crate::tokens::{Loc, TokenKind, DEFAULT_TAB_WIDTH};
+use std::fmt;
+
+// A problem in the input that parsing recovered from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub loc: Loc,
+    // The kinds of tokens that would have been accepted at `loc`, empty when the token
+    // there is malformed by itself or the diagnostic is not a syntax error
+    pub expected: Vec<TokenKind>,
+    // The stable code of a syntax error, like the codes of `JsonError::code`
+    pub code: Option<&'static str>,
+}
+
+impl Diagnostic {
+    pub(crate) fn new(message: String, loc: &Loc) -> Self {
+        Self {
+            message,
+            loc: *loc,
+            expected: Vec::new(),
+            code: None,
+        }
+    }
+}
+
+// How `Diagnostic::render` shows the source around a diagnostic
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiagnosticStyle {
+    before: usize,
+    after: usize,
+    line_numbers: bool,
+}
+
+impl Default for DiagnosticStyle {
+    fn default() -> Self {
+        Self {
+            before: 2,
+            after: 2,
+            line_numbers: true,
+        }
+    }
+}
+
+impl DiagnosticStyle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Shows `lines` lines both before and after the line of the diagnostic
+    pub fn context(self, lines: usize) -> Self {
+        self.before(lines).after(lines)
+    }
+
+    pub fn before(mut self, lines: usize) -> Self {
+        self.before = lines;
+        self
+    }
+
+    pub fn after(mut self, lines: usize) -> Self {
+        self.after = lines;
+        self
+    }
+
+    // Leaves out the gutter with the line numbers
+    pub fn without_line_numbers(mut self) -> Self {
+        self.line_numbers = false;
+        self
+    }
+}
+
+impl Diagnostic {
+    // The diagnostic followed by the lines of `source` around it, with a `^` under the
+    // column it points at. `source` must be the text the diagnostic came from.
+    pub fn render(&self, source: &str, style: &DiagnosticStyle) -> String {
+        let lines: Vec<&str> = source
+            .split('\n')
+            .map(|line| line.strip_suffix('\r').unwrap_or(line))
+            .collect();
+        let row = self.loc.row.clamp(1, lines.len()) - 1;
+        let first = row.saturating_sub(style.before);
+        let last = (row + style.after).min(lines.len() - 1);
+        let width = (last + 1).to_string().len();
+        let gutter = |number: String| match style.line_numbers {
+            true => format!("{:>width$} |", number, width = width),
+            false => String::new(),
+        };
+
+        // Tabs are copied so the marker lines up however wide the terminal shows them
+        let line_start = source
+            .get(..self.loc.byte)
+            .and_then(|before| before.rfind('\n'))
+            .map_or(0, |newline| newline + 1);
+        let marker: String = source
+            .get(line_start..self.loc.byte)
+            .unwrap_or_default()
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+
+        let mut out = format!("{}\n", self);
+        for (i, line) in lines.iter().enumerate().take(last + 1).skip(first) {
+            let number = gutter((i + 1).to_string());
+            let separator = if number.is_empty() || line.is_empty() {
+                ""
+            } else {
+                " "
+            };
+            out.push_str(&format!("{}{}{}\n", number, separator, line));
+            if i == row {
+                let blank = gutter(String::new());
+                let separator = if blank.is_empty() { "" } else { " " };
+                out.push_str(&format!("{}{}{}^\n", blank, separator, marker));
+            }
+        }
+        out
+    }
+}
+
+// The syntax error `Json::new` fails with for `json` as a diagnostic, so it can be rendered
+// with the lines around it. `None` when `json` is valid.
+pub fn syntax_diagnostic<R: AsRef<str>>(json: R) -> Option<Diagnostic> {
+    syntax_diagnostic_with_tab_width(json, DEFAULT_TAB_WIDTH)
+}
+
+// Like `syntax_diagnostic` but the `col` of the location counts tabs as `tab_width` columns
+pub fn syntax_diagnostic_with_tab_width<R: AsRef<str>>(
+    json: R,
+    tab_width: usize,
+) -> Option<Diagnostic> {
+    let json = json.as_ref();
+    let mut events = Events::new(json.chars()).with_tab_width(tab_width);
+    build_data(&mut events)
+        .err()
+        .map(|error| error.diagnostic(json, tab_width))
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}:{}", self.message, self.loc.row, self.loc.col)
+    }
+}
+
+impl<S: JsonState> Json<S> {
+    // Like `new` but a missing `,` between the members of an array or object, or a missing
+    // `:` after a key, only adds a diagnostic and parsing goes on as if it was there. Other
+    // syntax errors still fail.
+    pub fn new_lenient<R: AsRef<str>>(json: R) -> Result<(Self, Vec<Diagnostic>), JsonError> {
+        let mut events = Events::lenient(json.as_ref().chars());
+        let data = build_data(&mut events).map_err(syntax_error)?;
+        Ok((Self::checked(data)?, events.take_warnings()))
+    }
+}
+
+impl<S: JsonState> Json<S> {
+    // Like `new` but also takes `'single quoted'` strings and bare identifiers like
+    // `name` as object keys, which most hand written json-like configs use
+    pub fn new_relaxed<R: AsRef<str>>(json: R) -> Result<Self, JsonError> {
+        let mut events = Events::relaxed(json.as_ref().chars());
+        let data = build_data(&mut events).map_err(syntax_error)?;
+        Self::checked(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{Array, Object, Value};
+
+    #[test]
+    fn recover_missing_separators() {
+        let (json, diagnostics) =
+            Json::<Object>::new_lenient("{\"a\": [1 2, 3] \"b\" {\"c\" null}}").unwrap();
+        assert_eq!(
+            Json::<Object>::new("{\"a\": [1, 2, 3], \"b\": {\"c\": null}}")
+                .unwrap()
+                .data,
+            json.data
+        );
+        let messages: Vec<String> = diagnostics.iter().map(|d| d.message.clone()).collect();
+        assert_eq!(
+            vec![
+                "Missing `,` before `2`",
+                "Missing `,` before `\"b\"`",
+                "Missing `:` before `{`",
+                "Missing `:` before `null`",
+            ],
+            messages
+        );
+        assert_eq!(1, diagnostics[0].loc.row);
+        assert_eq!(10, diagnostics[0].loc.col);
+    }
+
+    #[test]
+    fn valid_json_has_no_diagnostics() {
+        let (json, diagnostics) = Json::<Array>::new_lenient("[1, {\"a\": 2}]").unwrap();
+        assert!(diagnostics.is_empty());
+        assert_eq!(
+            Json::<Array>::new("[1, {\"a\": 2}]").unwrap().data,
+            json.data
+        );
+        assert!(Json::<Value>::new_lenient("").is_err());
+    }
+
+    #[test]
+    fn relaxed_quotes_and_keys() {
+        let json = Json::<Object>::new_relaxed(
+            "{name: 'it\\'s \"x\"', $id_2: 'a\\nb', null: true, 'q': [\"d\", 'e']}",
+        )
+        .unwrap();
+        assert_eq!("it's \"x\"", json.get_string_or("name", ""));
+        assert_eq!("a\nb", json.get_string_or("$id_2", ""));
+        assert!(json.get_bool_or("null", false));
+        assert_eq!(
+            Json::<Array>::new("[\"d\", \"e\"]").unwrap().data,
+            json.get_array("q").unwrap().data
+        );
+        assert!(Json::<Object>::new_relaxed("{a: b}").is_err());
+        assert!(Json::<Object>::new_relaxed("{a: 'open}").is_err());
+        assert!(Json::<Object>::new("{a: 1}").is_err());
+        assert!(Json::<Value>::new("'a'").is_err());
+    }
+
+    #[test]
+    fn other_errors_still_fail() {
+        assert!(Json::<Array>::new_lenient("[1,,2]").is_err());
+        assert!(Json::<Object>::new_lenient("{\"a\": 1 2}").is_err());
+        assert!(Json::<Array>::new_lenient("[1").is_err());
+        assert!(Json::<Value>::new_lenient("1 2").is_err());
+    }
+
+    #[test]
+    fn render_context() {
+        let source = "{\n  \"a\": 1,\n  \"b\": [1, 2 3],\n  \"c\": 3,\n  \"d\": 4\n}";
+        let (_, diagnostics) = Json::<Object>::new_lenient(source).unwrap();
+        assert_eq!(
+            "Missing `,` before `3` at 3:14\n\
+             2 |   \"a\": 1,\n\
+             3 |   \"b\": [1, 2 3],\n\
+             \x20 |              ^\n\
+             4 |   \"c\": 3,\n",
+            diagnostics[0].render(source, &DiagnosticStyle::new().context(1))
+        );
+        assert_eq!(
+            "Missing `,` before `3` at 3:14\n  \"b\": [1, 2 3],\n             ^\n",
+            diagnostics[0].render(
+                source,
+                &DiagnosticStyle::new().context(0).without_line_numbers()
+            )
+        );
+    }
+
+    #[test]
+    fn render_syntax_errors() {
+        assert_eq!(None, syntax_diagnostic("[1, 2]"));
+        let diagnostic = syntax_diagnostic("{\"a\": 1]").unwrap();
+        assert_eq!(
+            "Invalid Json Syntax `]`, expected `,` or `}`, found `]`",
+            diagnostic.message
+        );
+        assert_eq!(
+            vec![TokenKind::Comma, TokenKind::CloseCurly],
+            diagnostic.expected
+        );
+        assert_eq!(Some("E0101"), diagnostic.code);
+        assert_eq!(Some("E0104"), syntax_diagnostic("[1").unwrap().code);
+        assert_eq!(Some("E0103"), syntax_diagnostic(" ").unwrap().code);
+        let deep = syntax_diagnostic("[".repeat(600)).unwrap();
+        assert_eq!(Some("E0105"), deep.code);
+        assert_eq!((1, 513), (deep.loc.row, deep.loc.col));
+        let source = "[\n\ttrue,\n\tflase\n]";
+        let diagnostic = syntax_diagnostic(source).unwrap();
+        assert_eq!(
+            "Invalid Json Syntax `flase`, expected `false` at 3:5\n\
+             1 | [\n\
+             2 | \ttrue,\n\
+             3 | \tflase\n\
+             \x20 | \t^\n\
+             4 | ]\n",
+            diagnostic.render(source, &DiagnosticStyle::new())
+        );
+        let wide = syntax_diagnostic_with_tab_width(source, 8).unwrap();
+        assert_eq!((3, 9, 1), (wide.loc.row, wide.loc.col, wide.loc.utf16_col));
+        assert_eq!(
+            9,
+            syntax_diagnostic_with_tab_width("\t", 8).unwrap().loc.col
+        );
+        let source = "[\n\n  [";
+        let rendered = syntax_diagnostic(source)
+            .unwrap()
+            .render(source, &DiagnosticStyle::new().after(5).before(0));
+        assert_eq!(
+            "Unclosed array started at 3:3\n3 |   [\n  |   ^\n",
+            rendered
+        );
+    }
+}
@@ -1,5 +1,6 @@
 #![allow(dead_code)]
 
+use crate::tokens::{Loc, DEFAULT_TAB_WIDTH};
 use std::iter::Peekable;
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -16,17 +17,12 @@ pub(crate) enum TokenKind {
     Null,
     True,
     False,
+    // A bare identifier, only lexed in relaxed mode
+    Ident,
     Eof,
     Invalid,
 }
 
-#[derive(Debug, Clone)]
-#[cfg_attr(test, derive(PartialEq))]
-pub(crate) struct Loc {
-    pub(crate) col: usize,
-    pub(crate) row: usize,
-}
-
 #[derive(Debug, Clone)]
 #[cfg_attr(test, derive(PartialEq))]
 pub(crate) struct Token {
@@ -35,13 +31,24 @@ pub(crate) struct Token {
     pub(crate) loc: Loc,
 }
 
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == '$'
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
 #[derive(Debug)]
 pub(crate) struct Lexer<Chars: Iterator<Item = char>> {
     chars: Peekable<Chars>,
     exhausted: bool,
-    col: usize,
-    row: usize,
-    char_count: usize,
+    relaxed: bool,
+    // How many columns `Loc::col` moves a tab to the next stop of
+    tab_width: usize,
+    // Where the next char is and where the token being lexed starts
+    pos: Loc,
+    start: Loc,
 }
 
 impl<Chars: Iterator<Item = char>> Lexer<Chars> {
@@ -49,55 +56,80 @@ impl<Chars: Iterator<Item = char>> Lexer<Chars> {
         Self {
             chars: chars.peekable(),
             exhausted: false,
-            col: 0,
-            row: 0,
-            char_count: 0,
+            relaxed: false,
+            tab_width: DEFAULT_TAB_WIDTH,
+            pos: Loc::start(),
+            start: Loc::start(),
         }
     }
 
-    fn get_loc(&self) -> Loc {
-        Loc {
-            col: self.col - self.char_count + 1,
-            row: self.row + 1,
+    // Also lexes `'single quoted'` strings, as `Str` tokens with the text of the same string
+    // in double quotes, and bare identifiers like `name` as `Ident` tokens
+    pub(crate) fn relaxed(chars: Chars) -> Self {
+        Self {
+            relaxed: true,
+            ..Self::new(chars)
         }
     }
 
+    pub(crate) fn with_tab_width(self, tab_width: usize) -> Self {
+        Self { tab_width, ..self }
+    }
+
+    fn get_loc(&self) -> Loc {
+        self.start
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        self.pos.advance(c, self.tab_width);
+        Some(c)
+    }
+
+    fn bump_if(&mut self, f: impl FnOnce(&char) -> bool) -> Option<char> {
+        let c = self.chars.next_if(f)?;
+        self.pos.advance(c, self.tab_width);
+        Some(c)
+    }
+
     fn next_token(&mut self) -> Token {
         self.trim();
+        self.start = self.pos;
 
-        if let Some(c) = self.chars.peek() {
-            self.col += 1;
+        if let Some(&c) = self.chars.peek() {
             match c {
                 '{' => Token {
                     kind: TokenKind::OpenCurly,
-                    text: self.chars.next().unwrap().to_string(),
+                    text: self.bump().unwrap().to_string(),
                     loc: self.get_loc(),
                 },
                 '[' => Token {
                     kind: TokenKind::OpenBracket,
-                    text: self.chars.next().unwrap().to_string(),
+                    text: self.bump().unwrap().to_string(),
                     loc: self.get_loc(),
                 },
                 '}' => Token {
                     kind: TokenKind::CloseCurly,
-                    text: self.chars.next().unwrap().to_string(),
+                    text: self.bump().unwrap().to_string(),
                     loc: self.get_loc(),
                 },
                 ']' => Token {
                     kind: TokenKind::CloseBracket,
-                    text: self.chars.next().unwrap().to_string(),
+                    text: self.bump().unwrap().to_string(),
                     loc: self.get_loc(),
                 },
                 ',' => Token {
                     kind: TokenKind::Comma,
-                    text: self.chars.next().unwrap().to_string(),
+                    text: self.bump().unwrap().to_string(),
                     loc: self.get_loc(),
                 },
                 ':' => Token {
                     kind: TokenKind::Colon,
-                    text: self.chars.next().unwrap().to_string(),
+                    text: self.bump().unwrap().to_string(),
                     loc: self.get_loc(),
                 },
+                '\'' if self.relaxed => self.get_single_quoted_token(),
+                c if self.relaxed && is_ident_start(c) => self.get_ident_token(),
                 'n' => self.get_null_token(),
                 't' => self.get_true_token(),
                 'f' => self.get_false_token(),
@@ -105,11 +137,17 @@ impl<Chars: Iterator<Item = char>> Lexer<Chars> {
                 '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' | '-' => {
                     self.get_number_token()
                 }
-                _ => Token {
-                    kind: TokenKind::Invalid,
-                    text: self.chars.next().unwrap().to_string(),
-                    loc: self.get_loc(),
-                },
+                _ => {
+                    let mut text = self.bump().unwrap().to_string();
+                    if text.starts_with(char::is_alphabetic) {
+                        self.take_word(&mut text);
+                    }
+                    Token {
+                        kind: TokenKind::Invalid,
+                        text,
+                        loc: self.get_loc(),
+                    }
+                }
             }
         } else {
             self.exhausted = true;
@@ -121,15 +159,73 @@ impl<Chars: Iterator<Item = char>> Lexer<Chars> {
         }
     }
 
+    fn get_single_quoted_token(&mut self) -> Token {
+        self.bump(); // take the first quotation mark
+        let mut text = String::from('"');
+        while let Some(c) = self.bump() {
+            match c {
+                '\'' => {
+                    text.push('"');
+                    return Token {
+                        kind: TokenKind::Str,
+                        text,
+                        loc: self.get_loc(),
+                    };
+                }
+                '"' => text.push_str("\\\""),
+                '\\' => match self.bump() {
+                    Some(escaped) => {
+                        if escaped != '\'' {
+                            text.push('\\');
+                        }
+                        text.push(escaped);
+                    }
+                    None => break,
+                },
+                c => text.push(c),
+            }
+        }
+        Token {
+            kind: TokenKind::Invalid,
+            text,
+            loc: self.get_loc(),
+        }
+    }
+
+    // Keywords are still lexed as keywords
+    fn get_ident_token(&mut self) -> Token {
+        let mut text = String::new();
+        while let Some(c) = self.bump_if(|c| is_ident_char(*c)) {
+            text.push(c);
+        }
+        let kind = match text.as_str() {
+            "null" => TokenKind::Null,
+            "true" => TokenKind::True,
+            "false" => TokenKind::False,
+            _ => TokenKind::Ident,
+        };
+        Token {
+            kind,
+            text,
+            loc: self.get_loc(),
+        }
+    }
+
+    // The rest of a bare word like `ture` or `nil`, so the invalid token holds all of it
+    fn take_word(&mut self, text: &mut String) {
+        while let Some(c) = self.bump_if(|c| c.is_alphanumeric() || *c == '_') {
+            text.push(c);
+        }
+    }
+
     fn get_null_token(&mut self) -> Token {
         const ARR: [char; 4] = ['n', 'u', 'l', 'l'];
-        self.col -= 1;
         let mut text = String::new();
         for expected in ARR {
-            if let Some(c) = self.chars.next_if_eq(&expected) {
+            if let Some(c) = self.bump_if(|c| *c == expected) {
                 text.push(c);
-                self.col += 1;
             } else {
+                self.take_word(&mut text);
                 return Token {
                     kind: TokenKind::Invalid,
                     text,
@@ -146,13 +242,12 @@ impl<Chars: Iterator<Item = char>> Lexer<Chars> {
 
     fn get_true_token(&mut self) -> Token {
         const ARR: [char; 4] = ['t', 'r', 'u', 'e'];
-        self.col -= 1;
         let mut text = String::new();
         for expected in ARR {
-            if let Some(c) = self.chars.next_if_eq(&expected) {
+            if let Some(c) = self.bump_if(|c| *c == expected) {
                 text.push(c);
-                self.col += 1;
             } else {
+                self.take_word(&mut text);
                 return Token {
                     kind: TokenKind::Invalid,
                     text,
@@ -169,13 +264,12 @@ impl<Chars: Iterator<Item = char>> Lexer<Chars> {
 
     fn get_false_token(&mut self) -> Token {
         const ARR: [char; 5] = ['f', 'a', 'l', 's', 'e'];
-        self.col -= 1;
         let mut text = String::new();
         for expected in ARR {
-            if let Some(c) = self.chars.next_if_eq(&expected) {
+            if let Some(c) = self.bump_if(|c| *c == expected) {
                 text.push(c);
-                self.col += 1;
             } else {
+                self.take_word(&mut text);
                 return Token {
                     kind: TokenKind::Invalid,
                     text,
@@ -191,10 +285,9 @@ impl<Chars: Iterator<Item = char>> Lexer<Chars> {
     }
 
     fn get_str_token(&mut self) -> Token {
-        let mut text = String::from(self.chars.next().unwrap()); // take the first quotation mark
+        let mut text = String::from(self.bump().unwrap()); // take the first quotation mark
         let mut escape_next = false;
-        while let Some(c) = self.chars.next() {
-            self.col += 1;
+        while let Some(c) = self.bump() {
             text.push(c);
             if escape_next {
                 escape_next = false;
@@ -227,11 +320,9 @@ impl<Chars: Iterator<Item = char>> Lexer<Chars> {
         let mut is_dot_used = false;
         let mut plus_count = 0;
         let mut minus_count = 0;
-        while let Some(c) = self
-            .chars
-            .next_if(|c| c.is_ascii_digit() || c == &'+' || c == &'-' || c == &'.' || c == &'e')
+        while let Some(c) =
+            self.bump_if(|c| c.is_ascii_digit() || c == &'+' || c == &'-' || c == &'.' || c == &'e')
         {
-            self.col += 1;
             text.push(c);
             match c {
                 'e' => {
@@ -314,17 +405,7 @@ impl<Chars: Iterator<Item = char>> Lexer<Chars> {
     }
 
     fn trim(&mut self) {
-        loop {
-            if self.chars.next_if_eq(&'\n').is_some() {
-                self.row += 1;
-                self.char_count = self.col;
-                continue;
-            } else if self.chars.next_if(|c| c.is_whitespace()).is_some() {
-                self.col += 1;
-                continue;
-            }
-            break;
-        }
+        while self.bump_if(|c| c.is_whitespace()).is_some() {}
     }
 }
 
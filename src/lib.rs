@@ -1,3 +1,76 @@
+pub mod aggregate;
+#[cfg(any(test, feature = "proptest"))]
+pub mod arbitrary;
+#[cfg(feature = "arrow")]
+pub(crate) mod arrow;
+#[cfg(feature = "bigint")]
+pub(crate) mod bigint;
+#[cfg(feature = "bson")]
+pub(crate) mod bson;
+#[cfg(feature = "cbor")]
+pub(crate) mod cbor;
+#[cfg(feature = "color")]
+pub mod color;
+pub mod columns;
+pub mod compare;
+pub mod cst;
+pub(crate) mod csv;
+#[cfg(feature = "decimal")]
+pub(crate) mod decimal;
+pub mod edit;
+pub mod entry;
+pub(crate) mod escape;
+pub mod expand;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod filter;
+pub mod format;
+pub mod hasher;
+pub mod hooks;
 pub mod json;
+pub mod json_lines;
+pub mod keyset;
+pub mod lenient;
 pub(crate) mod lexer;
+pub mod lint;
+pub mod merge;
+pub mod multimap;
+pub mod number;
+pub mod page;
 pub(crate) mod parser;
+pub(crate) mod path;
+pub mod pointer;
+#[cfg(feature = "protobuf")]
+pub(crate) mod protobuf;
+#[cfg(feature = "raw_numbers")]
+pub(crate) mod raw;
+pub mod reference;
+pub mod reuse;
+#[cfg(feature = "serde_json")]
+pub(crate) mod serde_json;
+pub(crate) mod small;
+pub mod sniff;
+pub mod source_map;
+pub mod split;
+pub(crate) mod stream;
+#[cfg(any(test, feature = "proptest"))]
+pub mod testing;
+#[cfg(feature = "time")]
+pub mod time;
+pub mod tokens;
+#[cfg(feature = "toml")]
+pub(crate) mod toml;
+pub mod transform;
+#[cfg(feature = "uuid")]
+pub(crate) mod uuid;
+pub mod warnings;
+#[cfg(feature = "xml")]
+pub(crate) mod xml;
+pub(crate) mod yaml;
+
+pub use crate::json::{
+    from_reader, from_slice, from_str, to_string, to_string_pretty, AnyJson, Array, Json,
+    JsonError, JsonType, Object, Value,
+};
+pub use crate::number::Number;
+pub use crate::reuse::Parser;
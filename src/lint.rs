@@ -0,0 +1,358 @@
+use crate::edit::Case;
+use crate::escape::unescape_str;
+use crate::format::syntax_error;
+use crate::json::JsonError;
+use crate::lenient::Diagnostic;
+use crate::lexer::{Token, TokenKind};
+use crate::parser::{float_data, JsonData};
+use crate::stream::{Event, Events};
+use crate::tokens::{Loc, DEFAULT_TAB_WIDTH};
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Range;
+
+// Not every integer above this can be a float
+const EXACT: u64 = 1 << 53;
+
+// The checks `lint` can make
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rule {
+    // Two keys of an object that are the same once unescaped
+    DuplicateKeys,
+    // Keys that are not in the case of `LintOptions::key_case`
+    KeyCase,
+    // Numbers that lose precision when read as a float or don't fit in one
+    PrecisionLoss,
+    // Escapes like `\/`, `\u0041` or unpaired surrogates
+    UnusualEscapes,
+    // Arrays and objects nested deeper than `LintOptions::max_depth`
+    MaxDepth,
+}
+
+impl Rule {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rule::DuplicateKeys => "duplicate-keys",
+            Rule::KeyCase => "key-case",
+            Rule::PrecisionLoss => "precision-loss",
+            Rule::UnusualEscapes => "unusual-escapes",
+            Rule::MaxDepth => "max-depth",
+        }
+    }
+}
+
+// The rules `lint` checks. By default duplicate keys, precision loss and unusual escapes
+// are checked, the case of keys and the depth only once they are set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintOptions {
+    duplicate_keys: bool,
+    precision_loss: bool,
+    unusual_escapes: bool,
+    key_case: Option<Case>,
+    max_depth: Option<usize>,
+}
+
+impl Default for LintOptions {
+    fn default() -> Self {
+        Self {
+            duplicate_keys: true,
+            precision_loss: true,
+            unusual_escapes: true,
+            key_case: None,
+            max_depth: None,
+        }
+    }
+}
+
+impl LintOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn key_case(mut self, case: Case) -> Self {
+        self.key_case = Some(case);
+        self
+    }
+
+    // The root is at depth 1, so a depth of 1 allows no nested arrays or objects
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    pub fn without(mut self, rule: Rule) -> Self {
+        match rule {
+            Rule::DuplicateKeys => self.duplicate_keys = false,
+            Rule::PrecisionLoss => self.precision_loss = false,
+            Rule::UnusualEscapes => self.unusual_escapes = false,
+            Rule::KeyCase => self.key_case = None,
+            Rule::MaxDepth => self.max_depth = None,
+        }
+        self
+    }
+}
+
+// A rule the input breaks, `bytes` is the part of the input it is about and `loc` where
+// that starts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub rule: Rule,
+    pub message: String,
+    pub loc: Loc,
+    pub bytes: Range<usize>,
+}
+
+impl Finding {
+    // To render the finding with the lines around it
+    pub fn diagnostic(&self) -> Diagnostic {
+        Diagnostic::new(self.message.clone(), &self.loc)
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at {}:{} [{}]",
+            self.message,
+            self.loc.row,
+            self.loc.col,
+            self.rule.name()
+        )
+    }
+}
+
+fn case_name(case: Case) -> &'static str {
+    match case {
+        Case::SnakeCase => "snake_case",
+        Case::CamelCase => "camelCase",
+        Case::PascalCase => "PascalCase",
+        Case::KebabCase => "kebab-case",
+        Case::ScreamingSnakeCase => "SCREAMING_SNAKE_CASE",
+    }
+}
+
+// Numbers that can't be read as they are written
+fn number_message(token: &Token) -> Option<String> {
+    let text = &token.text;
+    if token.kind == TokenKind::Integer {
+        let i: i64 = text.parse().ok()?;
+        return (i.unsigned_abs() > EXACT)
+            .then(|| format!("Integer `{}` loses precision when read as a float", text));
+    }
+    let f: f64 = text.parse().ok()?;
+    if !f.is_finite() {
+        return Some(format!("Number `{}` is out of the range of a float", text));
+    }
+    // With the `bigint` and `decimal` features these numbers are kept exactly
+    if !matches!(float_data(text, f), JsonData::Float(_)) {
+        return None;
+    }
+    if !text.contains(['.', 'e', 'E']) {
+        return Some(format!(
+            "Integer `{}` does not fit in an i64 and is read as the float {}",
+            text, f
+        ));
+    }
+    let mantissa = text.split(['e', 'E']).next().unwrap_or(text);
+    let digits: String = mantissa.chars().filter(char::is_ascii_digit).collect();
+    let significant = digits.trim_start_matches('0').trim_end_matches('0').len();
+    (significant > 17).then(|| format!("Number `{}` has more digits than a float holds", text))
+}
+
+// Runs the rules over the events of a document one at a time
+pub(crate) struct Checks<'a> {
+    options: &'a LintOptions,
+    // The keys of the objects that are being read, `None` for arrays
+    keys: Vec<Option<HashSet<String>>>,
+    pub(crate) findings: Vec<Finding>,
+}
+
+impl<'a> Checks<'a> {
+    pub(crate) fn new(options: &'a LintOptions) -> Self {
+        Self {
+            options,
+            keys: Vec::new(),
+            findings: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, rule: Rule, message: String, loc: Loc, len: usize) {
+        self.findings.push(Finding {
+            rule,
+            message,
+            loc,
+            bytes: loc.byte..loc.byte + len,
+        });
+    }
+
+    pub(crate) fn event(&mut self, event: &Event) {
+        match event {
+            Event::StartObject(token) | Event::StartArray(token) => {
+                let keys = matches!(event, Event::StartObject(_)).then(HashSet::new);
+                self.keys.push(keys);
+                if self.options.max_depth == Some(self.keys.len() - 1) {
+                    let message = format!("Nested deeper than {} levels", self.keys.len() - 1);
+                    self.push(Rule::MaxDepth, message, token.loc, 1);
+                }
+            }
+            Event::EndObject(_) | Event::EndArray(_) => {
+                self.keys.pop();
+            }
+            Event::Key(token) => self.key(token),
+            Event::Scalar(token) if token.kind == TokenKind::Str => self.escapes(token),
+            Event::Scalar(token) => {
+                if !self.options.precision_loss {
+                    return;
+                }
+                if let Some(message) = number_message(token) {
+                    self.push(Rule::PrecisionLoss, message, token.loc, token.text.len());
+                }
+            }
+        }
+    }
+
+    fn key(&mut self, token: &Token) {
+        self.escapes(token);
+        let key = unescape_str(&token.text[1..token.text.len() - 1]).into_owned();
+        if let Some(case) = self.options.key_case {
+            let expected = case.convert(&key);
+            if expected != key {
+                let message = format!(
+                    "Key `{}` is not {}, it would be `{}`",
+                    key,
+                    case_name(case),
+                    expected
+                );
+                self.push(Rule::KeyCase, message, token.loc, token.text.len());
+            }
+        }
+        if !self.options.duplicate_keys {
+            return;
+        }
+        if let Some(Some(keys)) = self.keys.last_mut() {
+            if let Some(key) = keys.replace(key) {
+                let message = format!("Duplicate key `{}`, only the last value is kept", key);
+                self.push(Rule::DuplicateKeys, message, token.loc, token.text.len());
+            }
+        }
+    }
+
+    // Escapes in the text of a string token that are valid but likely a mistake or an
+    // encoding artifact, each at the `\` it starts with
+    fn escapes(&mut self, token: &Token) {
+        if !self.options.unusual_escapes {
+            return;
+        }
+        let chars: Vec<char> = token.text.chars().collect();
+        // The code unit of the `\u` escape at `i`
+        let unit = |i: usize| {
+            let digits: String = chars.get(i + 2..i + 6)?.iter().collect();
+            (chars[i..].starts_with(&['\\', 'u']))
+                .then(|| u16::from_str_radix(&digits, 16).ok())
+                .flatten()
+        };
+        let mut loc = token.loc;
+        let mut i = 0;
+        while i < chars.len() {
+            let (len, message) = match unit(i) {
+                _ if chars[i] != '\\' => (1, None),
+                Some(0xd800..=0xdbff) if matches!(unit(i + 6), Some(0xdc00..=0xdfff)) => (12, None),
+                Some(unit @ 0xd800..=0xdfff) => {
+                    (6, Some(format!("Unpaired surrogate `\\u{:04x}`", unit)))
+                }
+                Some(unit @ 0x20..=0x7e) if unit != 0x22 && unit != 0x5c => (
+                    6,
+                    Some(format!(
+                        "Unnecessary escape `\\u{:04x}` of `{}`",
+                        unit,
+                        char::from(unit as u8)
+                    )),
+                ),
+                Some(_) => (6, None),
+                None if chars.get(i + 1) == Some(&'/') => {
+                    (2, Some("Unnecessary escape `\\/`".to_string()))
+                }
+                None => (2, None),
+            };
+            if let Some(message) = message {
+                self.push(Rule::UnusualEscapes, message, loc, len);
+            }
+            for &c in &chars[i..(i + len).min(chars.len())] {
+                loc.advance(c, DEFAULT_TAB_WIDTH);
+            }
+            i += len;
+        }
+    }
+}
+
+// Checks `json` against the rules of `options`, in the order the findings are in the
+// input. Fails only when `json` is not valid json.
+pub fn lint<R: AsRef<str>>(json: R, options: &LintOptions) -> Result<Vec<Finding>, JsonError> {
+    let mut checks = Checks::new(options);
+    for event in Events::new(json.as_ref().chars()) {
+        checks.event(&event.map_err(syntax_error)?);
+    }
+    Ok(checks.findings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(json: &str, options: &LintOptions) -> Vec<(Rule, String)> {
+        lint(json, options)
+            .unwrap()
+            .into_iter()
+            .map(|finding| (finding.rule, finding.message))
+            .collect()
+    }
+
+    #[test]
+    fn configured_rules() {
+        let json = "{\"userId\": 1, \"user_name\": [[{\"a\\/b\": 2}]], \"userId\": 3}";
+        assert_eq!(
+            vec![
+                (Rule::UnusualEscapes, "Unnecessary escape `\\/`".to_string()),
+                (
+                    Rule::DuplicateKeys,
+                    "Duplicate key `userId`, only the last value is kept".to_string()
+                ),
+            ],
+            rules(json, &LintOptions::new())
+        );
+        let options = LintOptions::new()
+            .key_case(Case::CamelCase)
+            .max_depth(3)
+            .without(Rule::DuplicateKeys)
+            .without(Rule::UnusualEscapes);
+        assert_eq!(
+            vec![
+                (
+                    Rule::KeyCase,
+                    "Key `user_name` is not camelCase, it would be `userName`".to_string()
+                ),
+                (Rule::MaxDepth, "Nested deeper than 3 levels".to_string()),
+            ],
+            rules(json, &options)
+        );
+        assert!(lint("[1,]", &LintOptions::new()).is_err());
+    }
+
+    #[test]
+    fn findings_have_spans() {
+        let json = "[\n  {\"a\": 1, \"a\": 9007199254740993}\n]";
+        let findings = lint(json, &LintOptions::new().max_depth(1)).unwrap();
+        let spans: Vec<&str> = findings.iter().map(|f| &json[f.bytes.clone()]).collect();
+        assert_eq!(vec!["{", "\"a\"", "9007199254740993"], spans);
+        assert_eq!((2, 12), (findings[1].loc.row, findings[1].loc.col));
+        assert_eq!(
+            "Duplicate key `a`, only the last value is kept at 2:12 [duplicate-keys]",
+            findings[1].to_string()
+        );
+        assert!(findings[2]
+            .diagnostic()
+            .render(json, &Default::default())
+            .contains("9007199254740993}\n  |"));
+    }
+}
@@ -0,0 +1,197 @@
+use crate::json::{AnyJson, Json, JsonState};
+use crate::parser::{JsonData, Shared};
+use crate::pointer::pointer_token;
+use std::collections::HashSet;
+
+// A value that was changed in different ways on both sides of a `merge3`. `None` means
+// the value was not there or was removed.
+#[derive(Debug)]
+pub struct Conflict {
+    pub pointer: String,
+    pub base: Option<AnyJson>,
+    pub ours: Option<AnyJson>,
+    pub theirs: Option<AnyJson>,
+}
+
+type Side<'a> = Option<&'a Shared<JsonData>>;
+
+fn any(data: Side) -> Option<AnyJson> {
+    data.map(|data| AnyJson::from_shared(Shared::clone(data)))
+}
+
+fn same(a: Side, b: Side) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => Shared::ptr_eq(a, b) || a == b,
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+fn merge(
+    base: Side,
+    ours: Side,
+    theirs: Side,
+    pointer: &str,
+    conflicts: &mut Vec<Conflict>,
+) -> Option<Shared<JsonData>> {
+    if same(ours, theirs) || same(base, theirs) {
+        return ours.cloned();
+    }
+    if same(base, ours) {
+        return theirs.cloned();
+    }
+    match (
+        base.map(AsRef::as_ref),
+        ours.map(AsRef::as_ref),
+        theirs.map(AsRef::as_ref),
+    ) {
+        (
+            Some(JsonData::Object(base)),
+            Some(JsonData::Object(ours)),
+            Some(JsonData::Object(theirs)),
+        ) => {
+            // The keys of `base` come first, then the new ones of `ours` and of `theirs`
+            let mut seen = HashSet::new();
+            let merged = base
+                .keys()
+                .chain(ours.keys())
+                .chain(theirs.keys())
+                .filter(|key| seen.insert(*key))
+                .filter_map(|key| {
+                    let pointer = format!("{}/{}", pointer, pointer_token(key));
+                    merge(
+                        base.get(key),
+                        ours.get(key),
+                        theirs.get(key),
+                        &pointer,
+                        conflicts,
+                    )
+                    .map(|value| (key.clone(), value))
+                })
+                .collect();
+            Some(Shared::new(JsonData::Object(Box::new(merged))))
+        }
+        (
+            Some(JsonData::Array(base)),
+            Some(JsonData::Array(ours)),
+            Some(JsonData::Array(theirs)),
+        ) if base.len() == ours.len() && base.len() == theirs.len() => {
+            let merged = (0..base.len())
+                .map(|i| {
+                    let pointer = format!("{}/{}", pointer, i);
+                    merge(
+                        Some(&base[i]),
+                        Some(&ours[i]),
+                        Some(&theirs[i]),
+                        &pointer,
+                        conflicts,
+                    )
+                    .expect("THIS SHOULD NEVER PANIC")
+                })
+                .collect();
+            Some(Shared::new(JsonData::Array(merged)))
+        }
+        _ => {
+            conflicts.push(Conflict {
+                pointer: pointer.to_string(),
+                base: any(base),
+                ours: any(ours),
+                theirs: any(theirs),
+            });
+            ours.cloned()
+        }
+    }
+}
+
+// Merges the changes `ours` and `theirs` made to `base`. Objects are merged member by
+// member and arrays item by item when none of them changed length, otherwise the whole
+// array has to be the same on one side as in `base`. Every value both sides changed
+// differently is returned as a conflict with its json pointer.
+pub fn merge3<S: JsonState>(
+    base: &Json<S>,
+    ours: &Json<S>,
+    theirs: &Json<S>,
+) -> Result<Json<S>, Vec<Conflict>> {
+    let mut conflicts = Vec::new();
+    let merged = merge(
+        Some(&base.data),
+        Some(&ours.data),
+        Some(&theirs.data),
+        "",
+        &mut conflicts,
+    )
+    .expect("THIS SHOULD NEVER PANIC");
+    if conflicts.is_empty() {
+        Ok(Json::from_shared(merged))
+    } else {
+        Err(conflicts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{Object, Value};
+
+    fn obj(json: &str) -> Json<Object> {
+        Json::new(json).unwrap()
+    }
+
+    #[test]
+    fn merge_independent_changes() {
+        let base = obj("{\"a\": 1, \"b\": {\"c\": 2, \"d\": 3}, \"e\": [1, 2], \"f\": 0}");
+        let ours = obj("{\"a\": 10, \"b\": {\"c\": 2, \"d\": 3}, \"e\": [5, 2], \"g\": 1}");
+        let theirs =
+            obj("{\"a\": 1, \"b\": {\"c\": 20, \"d\": 3, \"x\": true}, \"e\": [1, 6], \"f\": 0}");
+        let merged = merge3(&base, &ours, &theirs).unwrap();
+        assert_eq!(
+            obj("{\"a\":10,\"b\":{\"c\":20,\"d\":3,\"x\":true},\"e\":[5,6],\"g\":1}").data,
+            merged.data
+        );
+    }
+
+    #[test]
+    fn report_conflicts() {
+        let base = obj("{\"a\": 1, \"b/c\": {\"d\": 1}, \"e\": [1], \"same\": 1}");
+        let ours = obj("{\"a\": 2, \"b/c\": {\"d\": 2}, \"e\": [1, 2], \"same\": 5}");
+        let theirs = obj("{\"a\": 3, \"e\": [3], \"same\": 5}");
+        let mut conflicts = merge3(&base, &ours, &theirs).unwrap_err();
+        conflicts.sort_by(|a, b| a.pointer.cmp(&b.pointer));
+        let pointers: Vec<&str> = conflicts.iter().map(|c| c.pointer.as_str()).collect();
+        assert_eq!(vec!["/a", "/b~1c", "/e"], pointers);
+        assert_eq!("3", conflicts[0].theirs.as_ref().unwrap().to_string());
+        assert!(conflicts[1].theirs.is_none());
+        assert_eq!("{\"d\":2}", conflicts[1].ours.as_ref().unwrap().to_string());
+
+        let value = |json: &str| Json::<Value>::new(json).unwrap();
+        assert!(merge3(&value("1"), &value("2"), &value("3")).is_err());
+        assert_eq!(
+            "2",
+            merge3(&value("1"), &value("2"), &value("1"))
+                .unwrap()
+                .to_string()
+        );
+    }
+
+    #[cfg(feature = "preserve_order")]
+    #[test]
+    fn merge_in_document_order() {
+        let base = obj("{\"z\": 0, \"m\": 0}");
+        let ours = obj("{\"z\": 1, \"new\": 1, \"m\": 0}");
+        let theirs = obj("{\"b\": 3, \"m\": 2, \"z\": 0}");
+        for _ in 0..10 {
+            assert_eq!(
+                "{\"z\":1,\"m\":2,\"new\":1,\"b\":3}",
+                merge3(&base, &ours, &theirs).unwrap().to_string()
+            );
+        }
+        let conflicts = merge3(
+            &base,
+            &obj("{\"z\": 1, \"m\": 1}"),
+            &obj("{\"m\": 2, \"z\": 2}"),
+        )
+        .unwrap_err();
+        let pointers: Vec<&str> = conflicts.iter().map(|c| c.pointer.as_str()).collect();
+        assert_eq!(vec!["/z", "/m"], pointers);
+    }
+}
@@ -0,0 +1,272 @@
+use crate::escape::{escape_str, unquote};
+use crate::format::syntax_error;
+use crate::json::{AnyJson, Array, Json, JsonError, Object, Value};
+use crate::parser::{JsonData, ParseError, Shared};
+use crate::stream::{scalar_data, Event, Events};
+use std::fmt;
+
+// A json whose objects keep every member in the order they were written, also those with a
+// key that was already used, for producers that repeat keys like dumps of http headers
+#[derive(Debug, Clone)]
+pub enum MultiValue {
+    Scalar(Json<Value>),
+    Array(Vec<MultiValue>),
+    Object(MultiObject),
+}
+
+// The members of an object with their unescaped keys, duplicates included
+#[derive(Debug, Clone, Default)]
+pub struct MultiObject {
+    members: Vec<(String, MultiValue)>,
+}
+
+enum Open {
+    Array(Vec<MultiValue>),
+    Object(Vec<(String, MultiValue)>, Option<String>),
+}
+
+impl MultiValue {
+    // Parses `json` like `Json::new` but without dropping the members of repeated keys
+    pub fn parse<R: AsRef<str>>(json: R) -> Result<Self, JsonError> {
+        let mut stack: Vec<Open> = Vec::new();
+        let mut root = None;
+        for event in Events::new(json.as_ref().chars()) {
+            let value = match event.map_err(syntax_error)? {
+                Event::StartObject(_) => {
+                    stack.push(Open::Object(Vec::new(), None));
+                    continue;
+                }
+                Event::StartArray(_) => {
+                    stack.push(Open::Array(Vec::new()));
+                    continue;
+                }
+                Event::Key(token) => {
+                    if let Some(Open::Object(_, key)) = stack.last_mut() {
+                        *key = Some(unquote(token.text));
+                    }
+                    continue;
+                }
+                Event::Scalar(token) => {
+                    MultiValue::Scalar(Json::from_data(scalar_data(token).map_err(syntax_error)?))
+                }
+                Event::EndObject(_) | Event::EndArray(_) => {
+                    match stack.pop().expect("THIS SHOULD NEVER PANIC") {
+                        Open::Array(items) => MultiValue::Array(items),
+                        Open::Object(members, _) => MultiValue::Object(MultiObject { members }),
+                    }
+                }
+            };
+            match stack.last_mut() {
+                None => root = Some(value),
+                Some(Open::Array(items)) => items.push(value),
+                Some(Open::Object(members, key)) => {
+                    members.push((key.take().expect("THIS SHOULD NEVER PANIC"), value))
+                }
+            }
+        }
+        root.ok_or_else(|| syntax_error(ParseError::EmptyInput))
+    }
+
+    pub fn as_object(&self) -> Option<&MultiObject> {
+        match self {
+            MultiValue::Object(object) => Some(object),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[MultiValue]> {
+        match self {
+            MultiValue::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_scalar(&self) -> Option<&Json<Value>> {
+        match self {
+            MultiValue::Scalar(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    // The json `Json::new` would have made, the last member of a repeated key wins
+    pub fn to_json(&self) -> AnyJson {
+        match self {
+            MultiValue::Scalar(value) => AnyJson::from(value.clone()),
+            MultiValue::Array(items) => AnyJson::from(
+                items
+                    .iter()
+                    .map(MultiValue::to_json)
+                    .collect::<Json<Array>>(),
+            ),
+            MultiValue::Object(object) => AnyJson::from(
+                object
+                    .iter()
+                    .map(|(key, value)| (key, value.to_json()))
+                    .collect::<Json<Object>>(),
+            ),
+        }
+    }
+}
+
+impl MultiObject {
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    // The members in the order they were written
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &MultiValue)> {
+        self.members
+            .iter()
+            .map(|(key, value)| (key.as_str(), value))
+    }
+
+    // Every value of `key` in the order they were written
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a MultiValue> {
+        self.iter()
+            .filter(move |(k, _)| *k == key)
+            .map(|(_, value)| value)
+    }
+
+    // The first value of `key`
+    pub fn get(&self, key: &str) -> Option<&MultiValue> {
+        self.members
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value)
+    }
+
+    // The keys without their repeats, in the order they first show up
+    pub fn keys(&self) -> Vec<&str> {
+        let mut keys: Vec<&str> = Vec::new();
+        for (key, _) in self.iter() {
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+        keys
+    }
+
+    pub fn push<V: Into<MultiValue>>(&mut self, key: &str, value: V) {
+        self.members.push((key.to_string(), value.into()));
+    }
+}
+
+fn from_data(data: &Shared<JsonData>) -> MultiValue {
+    match data.as_ref() {
+        JsonData::Array(arr) => MultiValue::Array(arr.iter().map(from_data).collect()),
+        JsonData::Object(map) => MultiValue::Object(MultiObject {
+            members: map
+                .iter()
+                .map(|(key, value)| (key.to_string(), from_data(value)))
+                .collect(),
+        }),
+        _ => MultiValue::Scalar(Json::from_shared(Shared::clone(data))),
+    }
+}
+
+impl<V: Into<AnyJson>> From<V> for MultiValue {
+    fn from(value: V) -> Self {
+        from_data(&value.into().into_shared())
+    }
+}
+
+// Written minified with every member, so it parses back to the same `MultiValue`
+impl fmt::Display for MultiValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MultiValue::Scalar(value) => write!(f, "{}", value),
+            MultiValue::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            MultiValue::Object(object) => write!(f, "{}", object),
+        }
+    }
+}
+
+impl fmt::Display for MultiObject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        for (i, (key, value)) in self.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "\"{}\":{}", escape_str(key), value)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::FromJson;
+
+    #[test]
+    fn repeated_keys() {
+        let json = "{\"Set-Cookie\": \"a=1\", \"Host\": \"x\", \"Set-Cookie\": \"b=2\",
+                     \"nested\": [{\"k\": 1, \"k\": {\"q\\\"\": 2, \"q\\\"\": 3}}]}";
+        let parsed = MultiValue::parse(json).unwrap();
+        let headers = parsed.as_object().unwrap();
+        assert_eq!(4, headers.len());
+        assert_eq!(vec!["Set-Cookie", "Host", "nested"], headers.keys());
+        let cookies: Vec<String> = headers
+            .get_all("Set-Cookie")
+            .map(|value| value.as_scalar().unwrap().get_unescaped_string().unwrap())
+            .collect();
+        assert_eq!(vec!["a=1", "b=2"], cookies);
+        assert_eq!(0, headers.get_all("missing").count());
+        let inner = headers.get("nested").unwrap().as_array().unwrap()[0]
+            .as_object()
+            .unwrap();
+        assert_eq!(2, inner.get_all("k").count());
+        assert_eq!(
+            2,
+            inner
+                .get_all("k")
+                .nth(1)
+                .unwrap()
+                .as_object()
+                .unwrap()
+                .get_all("q\"")
+                .count()
+        );
+        assert_eq!(
+            "{\"Set-Cookie\":\"a=1\",\"Host\":\"x\",\"Set-Cookie\":\"b=2\",\"nested\":[{\"k\":1,\"k\":{\"q\\\"\":2,\"q\\\"\":3}}]}",
+            parsed.to_string()
+        );
+        assert_eq!(
+            parsed.to_string(),
+            MultiValue::parse(parsed.to_string()).unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn to_and_from_json() {
+        let parsed = MultiValue::parse("{\"a\": 1, \"b\": [true], \"a\": 2}").unwrap();
+        assert_eq!(
+            Json::<Object>::new("{\"a\": 2, \"b\": [true]}").unwrap(),
+            Json::<Object>::from_json(parsed.to_json()).unwrap()
+        );
+        let mut object = MultiObject::default();
+        object.push("a", 1);
+        object.push("a", Json::<Array>::new("[{\"b\": null}]").unwrap());
+        assert_eq!(
+            "{\"a\":1,\"a\":[{\"b\":null}]}",
+            MultiValue::Object(object).to_string()
+        );
+        assert_eq!("5", MultiValue::parse(" 5 ").unwrap().to_string());
+        assert!(MultiValue::parse("{\"a\": }").is_err());
+        assert!(MultiValue::parse("").is_err());
+    }
+}
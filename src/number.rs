@@ -0,0 +1,246 @@
+use crate::json::{Json, JsonError, JsonMut, JsonState, JsonType, Value};
+use crate::parser::{JsonData, Shared};
+use crate::pointer::{parse_pointer, resolve_mut, to_pointer};
+use std::cmp::Ordering;
+use std::fmt;
+
+// Any json number. `UInt` is only used for integers above `i64::MAX`, which the `bigint`
+// feature keeps, other integers are `Int` and decimals and integers too big for a `u64`
+// are `Float`. Numbers compare by their value whatever variant they are.
+#[derive(Debug, Clone, Copy)]
+pub enum Number {
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+}
+
+impl Number {
+    pub fn as_f64_lossy(&self) -> f64 {
+        match *self {
+            Number::Int(i) => i as f64,
+            Number::UInt(u) => u as f64,
+            Number::Float(f) => f,
+        }
+    }
+
+    // `None` unless the number is an integer, or a float without a fractional part, that
+    // fits in an `i64`
+    pub fn as_i64_checked(&self) -> Option<i64> {
+        self.as_i128().and_then(|i| i64::try_from(i).ok())
+    }
+
+    pub fn as_u64_checked(&self) -> Option<u64> {
+        self.as_i128().and_then(|i| u64::try_from(i).ok())
+    }
+
+    // The exact value of an integral number, floats too big for an `i128` are never equal
+    // to an integer anyway
+    fn as_i128(&self) -> Option<i128> {
+        match *self {
+            Number::Int(i) => Some(i.into()),
+            Number::UInt(u) => Some(u.into()),
+            Number::Float(f) if f.fract() == 0.0 && f.abs() < 2f64.powi(127) => Some(f as i128),
+            Number::Float(_) => None,
+        }
+    }
+}
+
+impl PartialEq for Number {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self.as_i128(), other.as_i128()) {
+            (Some(a), Some(b)) => Some(a.cmp(&b)),
+            _ => self.as_f64_lossy().partial_cmp(&other.as_f64_lossy()),
+        }
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Number::Int(i) => write!(f, "{}", i),
+            Number::UInt(u) => write!(f, "{}", u),
+            Number::Float(x) => write!(f, "{}", x),
+        }
+    }
+}
+
+impl Json<Value> {
+    // Any number, instead of trying `get_i64` and then `get_f64`
+    pub fn get_number(&self) -> Result<Number, JsonError> {
+        match self.data.as_ref().unraw() {
+            JsonData::Integer(i) => Ok(Number::Int(*i)),
+            #[cfg(feature = "bigint")]
+            JsonData::BigInt(i) => Ok(match u64::try_from(i) {
+                Ok(u) => Number::UInt(u),
+                Err(_) => Number::Float(crate::bigint::bigint_to_f64(i)),
+            }),
+            data => match data.as_f64() {
+                Some(f) => Ok(Number::Float(f)),
+                None => Err(self.incorrect_type(&[JsonType::Integer, JsonType::Float])),
+            },
+        }
+    }
+
+    // Integers as floats too, rounded when they are too big to be exact. Unlike
+    // `get_f64_lenient` strings are not read.
+    pub fn get_f64_lossy(&self) -> Result<f64, JsonError> {
+        self.get_number().map(|n| n.as_f64_lossy())
+    }
+
+    // Integers and floats without a fractional part that fit in an `i64`, so `1.0` is read
+    // like `1`
+    pub fn get_i64_exact(&self) -> Result<i64, JsonError> {
+        self.get_number()?
+            .as_i64_checked()
+            .ok_or_else(|| self.incorrect_type(&[JsonType::Integer]))
+    }
+}
+
+impl JsonMut<'_> {
+    // Adds `by` to an integer and returns the new value, or an `Overflow` error when it
+    // doesn't fit in an `i64` and the integer is left as it was
+    pub fn add_i64(&mut self, by: i64) -> Result<i64, JsonError> {
+        let sum = self
+            .get_i64()?
+            .checked_add(by)
+            .ok_or_else(|| JsonError::Overflow {
+                path: self.path.clone(),
+            })?;
+        *self.data = Shared::new(JsonData::Integer(sum));
+        Ok(sum)
+    }
+
+    // Adds `by` to a float and returns the new value, a sum that isn't finite is an
+    // `Overflow` error
+    pub fn add_f64(&mut self, by: f64) -> Result<f64, JsonError> {
+        let sum = self.get_f64()? + by;
+        if !sum.is_finite() {
+            return Err(JsonError::Overflow {
+                path: self.path.clone(),
+            });
+        }
+        *self.data = Shared::new(JsonData::Float(sum));
+        Ok(sum)
+    }
+}
+
+impl<S: JsonState> Json<S> {
+    // Adds `by` to the integer at the json pointer `pointer` like `JsonMut::add_i64`, e.g.
+    // for counters. Only the containers on the way are copied if they are shared.
+    pub fn increment(&mut self, pointer: &str, by: i64) -> Result<i64, JsonError> {
+        let tokens = parse_pointer(pointer)?;
+        let path = format!("{}{}", self.path, to_pointer(&tokens));
+        let data = resolve_mut(&mut self.data, &tokens).map_err(|e| e.under(&self.path))?;
+        JsonMut { data, path }.add_i64(by)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{Array, Object};
+
+    #[test]
+    fn get_number() {
+        let json = Json::<Array>::new("[1, -2.5, 3.0, \"4\"]").unwrap();
+        let number = |i| json.get_value(i).unwrap().get_number();
+        assert!(matches!(number(0), Ok(Number::Int(1))));
+        assert!(matches!(number(1), Ok(Number::Float(f)) if f == -2.5));
+        assert_eq!(Ok(Some(3)), number(2).map(|n| n.as_i64_checked()));
+        assert_eq!(Ok(None), number(1).map(|n| n.as_i64_checked()));
+        assert_eq!(
+            Err(JsonError::IncorrectType {
+                path: "/3".to_string(),
+                expected: vec![JsonType::Integer, JsonType::Float],
+                found: JsonType::String
+            }),
+            number(3)
+        );
+        #[cfg(feature = "bigint")]
+        {
+            let big = Json::<Value>::new("18446744073709551615").unwrap();
+            assert!(matches!(big.get_number(), Ok(Number::UInt(u64::MAX))));
+        }
+    }
+
+    #[test]
+    fn coerce_integers_and_floats() {
+        let json = Json::<Array>::new("[1, 1.0, 1.5, 1e19, \"2\"]").unwrap();
+        let value = |i| json.get_value(i).unwrap();
+        assert_eq!(Ok(1.0), value(0).get_f64_lossy());
+        assert_eq!(Ok(1.5), value(2).get_f64_lossy());
+        assert_eq!(Ok(1), value(1).get_i64_exact());
+        assert_eq!(Ok(1), value(0).get_i64_exact());
+        assert!(matches!(
+            value(2).get_i64_exact(),
+            Err(JsonError::IncorrectType { path, .. }) if path == "/2"
+        ));
+        assert!(value(3).get_i64_exact().is_err());
+        assert!(value(4).get_f64_lossy().is_err());
+        assert!(value(4).get_i64_exact().is_err());
+    }
+
+    #[test]
+    fn arithmetic() {
+        let mut json = Json::<Object>::new(
+            "{\"hits\": {\"a\": 1}, \"avg\": 0.5, \"max\": 9223372036854775807, \"s\": \"1\"}",
+        )
+        .unwrap();
+        let copy = json.clone();
+        assert_eq!(Ok(3), json.increment("/hits/a", 2));
+        assert_eq!(Ok(0), json.increment("/hits/a", -3));
+        assert_eq!(
+            Ok(1),
+            copy.get_object("hits")
+                .unwrap()
+                .get_value("a")
+                .unwrap()
+                .get_i64()
+        );
+        assert_eq!(
+            Err(JsonError::Overflow {
+                path: String::from("/max")
+            }),
+            json.increment("/max", 1)
+        );
+        assert_eq!("E0022", json.increment("/max", 1).unwrap_err().code());
+        assert_eq!(Ok(i64::MAX), json.get_value("max").unwrap().get_i64());
+        assert!(matches!(
+            json.increment("/avg", 1),
+            Err(JsonError::IncorrectType { path, .. }) if path == "/avg"
+        ));
+        assert!(matches!(
+            json.increment("/s", 1),
+            Err(JsonError::IncorrectType { .. })
+        ));
+        assert!(matches!(
+            json.increment("/hits/b", 1),
+            Err(JsonError::KeyNotFound { path }) if path == "/hits/b"
+        ));
+        json.entry("avg").and_modify(|avg| {
+            assert_eq!(Ok(1.75), avg.add_f64(1.25));
+            assert!(avg.add_f64(f64::INFINITY).is_err());
+            assert!(avg.add_i64(1).is_err());
+        });
+        assert_eq!(Ok(1.75), json.get_value("avg").unwrap().get_f64());
+    }
+
+    #[test]
+    fn compare_numbers() {
+        assert_eq!(Number::Int(3), Number::Float(3.0));
+        assert_eq!(Number::UInt(5), Number::Int(5));
+        assert!(Number::Int(i64::MAX) < Number::UInt(i64::MAX as u64 + 1));
+        assert!(Number::Int(i64::MAX) < Number::Float(9223372036854775808.0));
+        assert!(Number::Float(-0.5) < Number::Int(0));
+        assert!(Number::Float(f64::NAN) != Number::Float(f64::NAN));
+        assert_eq!(None, Number::Int(-1).as_u64_checked());
+        assert_eq!(2.0, Number::UInt(2).as_f64_lossy());
+        assert_eq!("-7", Number::Int(-7).to_string());
+    }
+}
@@ -0,0 +1,84 @@
+use crate::edit::range_bounds;
+use crate::json::{Array, Json};
+use crate::parser::{JsonData, Shared};
+use std::ops::RangeBounds;
+
+impl Json<Array> {
+    fn elements(&self) -> &[Shared<JsonData>] {
+        match self.data.as_ref() {
+            JsonData::Array(arr) => arr,
+            _ => &[],
+        }
+    }
+
+    // The elements in `range` as a new array that shares them with this one, so only the
+    // references are copied. Like `slice` in JavaScript the range is cut to the length of
+    // the array, a page past the end is empty. Paths in the new array count from its start.
+    pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> Json<Array> {
+        let elements = self.elements();
+        let (start, end) = range_bounds(&range, elements.len());
+        let end = end.min(elements.len());
+        let page = elements.get(start..end).unwrap_or_default();
+        Json::from_data(JsonData::Array(page.to_vec())).at(self.path.clone())
+    }
+
+    // The elements in pages of `size`, the last one may be shorter. Panics if `size` is 0.
+    pub fn chunks(&self, size: usize) -> impl Iterator<Item = Json<Array>> + '_ {
+        assert!(size != 0, "size must be non-zero");
+        self.elements()
+            .chunks(size)
+            .map(|page| Json::from_data(JsonData::Array(page.to_vec())).at(self.path.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::Object;
+
+    #[test]
+    fn slices() {
+        let json = Json::<Array>::new("[0, 1, {\"a\": [2]}, 3, 4]").unwrap();
+        assert_eq!(
+            Json::<Array>::new("[1, {\"a\": [2]}]").unwrap(),
+            json.slice(1..3)
+        );
+        assert_eq!(Json::<Array>::new("[3, 4]").unwrap(), json.slice(3..));
+        assert_eq!(Json::<Array>::new("[0, 1]").unwrap(), json.slice(..=1));
+        assert_eq!(Json::<Array>::new("[4]").unwrap(), json.slice(4..100));
+        assert_eq!(Json::<Array>::default(), json.slice(10..20));
+        assert_eq!(json, json.slice(..=usize::MAX));
+        let (start, end) = (3, 1);
+        assert_eq!(Json::<Array>::default(), json.slice(start..end));
+        let shared = json.slice(2..3).get_object(0).unwrap();
+        let original: Json<Object> = json.get_object(2).unwrap();
+        assert!(Shared::ptr_eq(&shared.data, &original.data));
+        let records = Json::<Object>::new("{\"items\": [1, 2, \"x\"]}").unwrap();
+        let items = records.get_array("items").unwrap().slice(1..);
+        assert_eq!(
+            "/items/1",
+            items
+                .get_value(1)
+                .unwrap()
+                .get_i64()
+                .unwrap_err()
+                .path()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn pages() {
+        let json = Json::<Array>::new("[1, 2, 3, 4, 5]").unwrap();
+        let pages: Vec<String> = json.chunks(2).map(|page| page.to_string()).collect();
+        assert_eq!(vec!["[1,2]", "[3,4]", "[5]"], pages);
+        assert_eq!(0, Json::<Array>::default().chunks(3).count());
+        assert_eq!(1, json.chunks(10).count());
+    }
+
+    #[test]
+    #[should_panic(expected = "size must be non-zero")]
+    fn empty_pages() {
+        let _ = Json::<Array>::default().chunks(0);
+    }
+}
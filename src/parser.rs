@@ -1,346 +1,415 @@
-#![allow(dead_code)]
-
-use crate::lexer::{Lexer, Token, TokenKind};
-use std::collections::HashMap;
+use crate::format::display;
+use crate::hasher::KeyHasher;
+use crate::json::MAX_DEPTH;
+use crate::lenient::Diagnostic;
+use crate::lexer::{Token, TokenKind};
+use crate::small::SmallString;
+use crate::stream::{build_data, Events};
+use crate::tokens::{locate, public_kind};
+use std::borrow::Borrow;
 use std::fmt;
 use std::fmt::Formatter;
-use std::iter::Peekable;
+use std::hash::Hash;
+
+// `Arc` with the `sync` feature so a `Json` can be shared between threads
+#[cfg(not(feature = "sync"))]
+pub(crate) type Shared<T> = std::rc::Rc<T>;
+#[cfg(feature = "sync")]
+pub(crate) type Shared<T> = std::sync::Arc<T>;
+
+// How the members of an object are stored. With the `preserve_order` feature they are kept
+// in the order they were written and with `btreemap` they are sorted by key, otherwise their
+// order is not specified. `preserve_order` wins when both are enabled. Keys are hashed with
+// a `KeyHasher` unless they are sorted.
+#[cfg(feature = "preserve_order")]
+pub(crate) type Map<K, V> = indexmap::IndexMap<K, V, KeyHasher>;
+#[cfg(feature = "preserve_order")]
+pub(crate) type MapEntry<'a, K, V> = indexmap::map::Entry<'a, K, V>;
+#[cfg(all(feature = "btreemap", not(feature = "preserve_order")))]
+pub(crate) type Map<K, V> = std::collections::BTreeMap<K, V>;
+#[cfg(all(feature = "btreemap", not(feature = "preserve_order")))]
+pub(crate) type MapEntry<'a, K, V> = std::collections::btree_map::Entry<'a, K, V>;
+#[cfg(not(any(feature = "preserve_order", feature = "btreemap")))]
+pub(crate) type Map<K, V> = std::collections::HashMap<K, V, KeyHasher>;
+#[cfg(not(any(feature = "preserve_order", feature = "btreemap")))]
+pub(crate) type MapEntry<'a, K, V> = std::collections::hash_map::Entry<'a, K, V>;
+
+// An empty map whose keys are hashed with `hasher`
+pub(crate) fn map_with_hasher<K, V>(_hasher: &KeyHasher) -> Map<K, V> {
+    #[cfg(all(feature = "btreemap", not(feature = "preserve_order")))]
+    return Map::new();
+    #[cfg(not(all(feature = "btreemap", not(feature = "preserve_order"))))]
+    Map::with_hasher(_hasher.clone())
+}
 
-#[derive(PartialEq)]
-enum In {
-    Nothing,
-    Array,
-    Object,
+// Removes the member `key`, the members after it keep their order with `preserve_order`
+pub(crate) fn remove_member<K: Borrow<str> + Hash + Ord, V>(
+    map: &mut Map<K, V>,
+    key: &str,
+) -> Option<V> {
+    #[cfg(feature = "preserve_order")]
+    return map.shift_remove(key);
+    #[cfg(not(feature = "preserve_order"))]
+    map.remove(key)
 }
 
-#[derive(Debug, PartialEq, Clone)]
+// The children of arrays and objects are shared so that views into a json don't have to
+// copy the subtree, they are only cloned when they are mutated through `Shared::make_mut`.
+// Every value is 32 bytes whatever features are enabled, objects are boxed so that arrays
+// of scalars stay compact.
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "raw_numbers"), derive(PartialEq))]
 pub(crate) enum JsonData {
-    Eof,
     Null,
     Bool(bool),
-    Str(String),
+    // Unescaped when parsed, inline when it is short, see `SmallString`
+    Str(SmallString),
     Float(f64),
+    // A number that would not keep its exact value as a `Float`
+    #[cfg(feature = "decimal")]
+    Decimal(rust_decimal::Decimal),
     Integer(i64),
-    Array(Vec<JsonData>),
-    Object(HashMap<String, JsonData>),
-}
-
-#[derive(Debug)]
-#[cfg_attr(test, derive(PartialEq))]
-pub(crate) enum ParseError {
-    SyntaxError(Token),
-    UnexpectedEof,
+    // An integer that does not fit in an `i64`
+    #[cfg(feature = "bigint")]
+    BigInt(num_bigint::BigInt),
+    // A number with the text it was parsed from, see `raw_number`
+    #[cfg(feature = "raw_numbers")]
+    RawNumber(Box<crate::raw::RawNumber>),
+    // The elements are always allocated, only strings and keys are kept inline
+    Array(Vec<Shared<JsonData>>),
+    // The keys are kept inline when they are short, see `SmallString`. The map is boxed as it
+    // is bigger than the other variants.
+    Object(Box<Map<SmallString, Shared<JsonData>>>),
 }
 
-impl fmt::Display for ParseError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+impl JsonData {
+    // Any kind of number as a float
+    pub(crate) fn as_f64(&self) -> Option<f64> {
         match self {
-            ParseError::SyntaxError(token) => {
-                let msg = "Invalid Json Syntax";
-                write!(
-                    f,
-                    "{} `{}` at {}:{}\n{}{}",
-                    msg,
-                    token.text,
-                    token.loc.row,
-                    token.loc.col,
-                    " ".repeat(msg.len() + 2),
-                    "^".repeat(token.text.len())
-                )
-            }
-            ParseError::UnexpectedEof => {
-                write!(f, "Unexpected end of file")
-            }
+            JsonData::Integer(i) => Some(*i as f64),
+            JsonData::Float(f) => Some(*f),
+            #[cfg(feature = "decimal")]
+            JsonData::Decimal(d) => Some(crate::decimal::decimal_to_f64(d)),
+            #[cfg(feature = "bigint")]
+            JsonData::BigInt(i) => Some(crate::bigint::bigint_to_f64(i)),
+            #[cfg(feature = "raw_numbers")]
+            JsonData::RawNumber(raw) => raw.data.as_f64(),
+            _ => None,
         }
     }
-}
 
-impl fmt::Display for JsonData {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        match self {
-            JsonData::Eof => write!(f, ""),
-            JsonData::Null => write!(f, "null"),
-            JsonData::Bool(b) => write!(f, "{b}"),
-            JsonData::Str(s) => write!(f, "\"{s}\""),
-            JsonData::Float(float) => write!(f, "{float}"),
-            JsonData::Integer(i) => write!(f, "{i}"),
-            JsonData::Array(v) => {
-                if v.is_empty() {
-                    write!(f, "[]")
-                } else {
-                    write!(f, "[").expect("THIS SHOULD NEVER PANIC");
-                    for i in 0..v.len() - 1 {
-                        write!(f, "{}, ", v[i]).expect("THIS SHOULD NEVER PANIC");
-                    }
-                    write!(f, "{}]", v[v.len() - 1])
-                }
-            }
-            JsonData::Object(m) => {
-                if m.is_empty() {
-                    write!(f, "{{}}")
-                } else {
-                    writeln!(f, "{{").expect("THIS SHOULD NEVER PANIC");
-                    for (count, (s, j)) in m.iter().enumerate() {
-                        if m.len() - 1 == count {
-                            return write!(f, "\"{s}\" : {j}\n}}");
-                        } else {
-                            writeln!(f, "\"{s}\" : {j},").expect("THIS SHOULD NEVER PANIC");
-                        }
-                    }
-                    unreachable!();
-                }
-            }
+    // The number a raw number was parsed as, any other value as it is
+    pub(crate) fn unraw(&self) -> &JsonData {
+        #[cfg(feature = "raw_numbers")]
+        if let JsonData::RawNumber(raw) = self {
+            return &raw.data;
         }
+        self
     }
-}
 
-pub(crate) fn parse_json<S: AsRef<str>>(json: S) -> Result<JsonData, ParseError> {
-    let mut lexer = Lexer::new(json.as_ref().chars()).peekable();
-    eat(&mut lexer, &In::Nothing)
+    pub(crate) fn array<I: IntoIterator<Item = JsonData>>(items: I) -> Self {
+        JsonData::Array(items.into_iter().map(Shared::new).collect())
+    }
+
+    pub(crate) fn object<K: Into<SmallString>, I: IntoIterator<Item = (K, JsonData)>>(
+        members: I,
+    ) -> Self {
+        JsonData::Object(Box::new(
+            members
+                .into_iter()
+                .map(|(key, data)| (key.into(), Shared::new(data)))
+                .collect(),
+        ))
+    }
 }
 
-fn eat(
-    lexer: &mut Peekable<Lexer<impl Iterator<Item = char>>>,
-    is_in: &In,
-) -> Result<JsonData, ParseError> {
-    if let Some(token) = lexer.peek() {
-        // println!("{token:?}");
-        match token.kind {
-            TokenKind::CloseBracket => Err(ParseError::SyntaxError(token.to_owned())),
-            TokenKind::Comma => Err(ParseError::SyntaxError(token.to_owned())),
-            TokenKind::Colon => Err(ParseError::SyntaxError(token.to_owned())),
-            TokenKind::CloseCurly => Err(ParseError::SyntaxError(token.to_owned())),
-            TokenKind::Invalid => Err(ParseError::SyntaxError(token.to_owned())),
-            TokenKind::OpenCurly => parse_json_object(lexer, is_in),
-            TokenKind::OpenBracket => parse_json_array(lexer, is_in),
-            TokenKind::Integer => parse_json_integer(lexer, is_in),
-            TokenKind::Float => parse_json_float(lexer, is_in),
-            TokenKind::Str => parse_json_str(lexer, is_in),
-            TokenKind::Null => parse_json_null(lexer, is_in),
-            TokenKind::True => parse_json_true(lexer, is_in),
-            TokenKind::False => parse_json_false(lexer, is_in),
-            TokenKind::Eof => parse_json_eof(lexer, is_in),
-        }
+// NaN and infinity can't be written as json numbers
+#[cfg(any(feature = "arrow", feature = "bson", feature = "cbor"))]
+pub(crate) fn float_or_null(f: f64) -> JsonData {
+    if f.is_finite() {
+        JsonData::Float(f)
     } else {
-        parse_json_eof(lexer, is_in)
+        JsonData::Null
     }
 }
 
-fn parse_json_eof(
-    lexer: &mut Peekable<Lexer<impl Iterator<Item = char>>>,
-    _is_in: &In,
-) -> Result<JsonData, ParseError> {
-    lexer.next();
-    Ok(JsonData::Eof)
+// Integers that do not fit in an `i64` are lexed as floats. With the `bigint` feature they
+// are kept exactly as big integers and with the `decimal` feature a float that `f64` can
+// not hold exactly is kept as a decimal.
+pub(crate) fn float_data(_text: &str, f: f64) -> JsonData {
+    #[cfg(feature = "bigint")]
+    if !_text.contains(['.', 'e', 'E']) {
+        if let Ok(i) = _text.parse() {
+            return JsonData::BigInt(i);
+        }
+    }
+    #[cfg(feature = "decimal")]
+    if let Some(decimal) = crate::decimal::inexact_float(_text, f) {
+        return JsonData::Decimal(decimal);
+    }
+    JsonData::Float(f)
 }
 
-fn parse_json_null(
-    lexer: &mut Peekable<Lexer<impl Iterator<Item = char>>>,
-    is_in: &In,
-) -> Result<JsonData, ParseError> {
-    lexer.next();
-    is_next_valid(lexer, JsonData::Null, is_in)
+// The number of single character insertions, deletions, substitutions and swaps of two
+// neighbouring characters that turn `a` into `b`
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut rows = vec![(0..=b.len()).collect::<Vec<_>>()];
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            row[j] = (rows[i - 1][j] + 1)
+                .min(row[j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(rows[i - 2][j - 2] + 1);
+            }
+        }
+        rows.push(row);
+    }
+    rows[a.len()][b.len()]
 }
 
-fn parse_json_false(
-    lexer: &mut Peekable<Lexer<impl Iterator<Item = char>>>,
-    is_in: &In,
-) -> Result<JsonData, ParseError> {
-    lexer.next();
-    is_next_valid(lexer, JsonData::Bool(false), is_in)
+// The literal a bare word like `ture`, `flase` or `nil` was most likely meant to be
+fn suggestion(word: &str) -> Option<&'static str> {
+    if !word.chars().all(char::is_alphabetic) {
+        return None;
+    }
+    let word = word.to_lowercase();
+    if matches!(word.as_str(), "nil" | "none" | "undefined") {
+        return Some("null");
+    }
+    let chars: Vec<char> = word.chars().collect();
+    ["null", "true", "false"]
+        .into_iter()
+        .map(|keyword| {
+            let keyword_chars: Vec<char> = keyword.chars().collect();
+            (edit_distance(&chars, &keyword_chars), keyword)
+        })
+        .filter(|(distance, _)| *distance <= 2 && *distance < chars.len())
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, keyword)| keyword)
 }
 
-fn parse_json_true(
-    lexer: &mut Peekable<Lexer<impl Iterator<Item = char>>>,
-    is_in: &In,
-) -> Result<JsonData, ParseError> {
-    lexer.next();
-    is_next_valid(lexer, JsonData::Bool(true), is_in)
+#[derive(Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+pub(crate) enum ParseError {
+    // `expected` holds the kinds of tokens that would have been accepted instead of
+    // `found`, it is empty when `found` itself is malformed
+    SyntaxError {
+        found: Token,
+        expected: Vec<TokenKind>,
+    },
+    UnexpectedEof,
+    // There was no value at all, only whitespace
+    EmptyInput,
+    // The input ended inside the array or object that `Token` opened
+    Unclosed(Token),
+    // The array or object that `Token` opened is nested deeper than `MAX_DEPTH`
+    TooDeep(Token),
 }
 
-fn parse_json_str(
-    lexer: &mut Peekable<Lexer<impl Iterator<Item = char>>>,
-    is_in: &In,
-) -> Result<JsonData, ParseError> {
-    let token = lexer.next().unwrap();
-    // println!("Current Token: {token:?}");
-    is_next_valid(
-        lexer,
-        JsonData::Str(remove_surrounding_quotes(token.text.as_str())),
-        is_in,
-    )
-}
+// The tokens a value can start with
+pub(crate) const VALUE: [TokenKind; 8] = [
+    TokenKind::OpenCurly,
+    TokenKind::OpenBracket,
+    TokenKind::Str,
+    TokenKind::Integer,
+    TokenKind::Float,
+    TokenKind::Null,
+    TokenKind::True,
+    TokenKind::False,
+];
+
+impl ParseError {
+    pub(crate) fn unexpected(found: Token, expected: &[TokenKind]) -> Self {
+        ParseError::SyntaxError {
+            found,
+            expected: expected.to_vec(),
+        }
+    }
 
-fn parse_json_float(
-    lexer: &mut Peekable<Lexer<impl Iterator<Item = char>>>,
-    is_in: &In,
-) -> Result<JsonData, ParseError> {
-    let token = lexer.next().unwrap();
-    // println!("Current Token: {token:?}");
-    if let Ok(f) = token.text.parse::<f64>() {
-        is_next_valid(lexer, JsonData::Float(f), is_in)
-    } else {
-        Err(ParseError::SyntaxError(token))
+    // `found` is not a valid token by itself, nothing else was expected in its place
+    pub(crate) fn malformed(found: Token) -> Self {
+        Self::unexpected(found, &[])
     }
-}
 
-fn parse_json_integer(
-    lexer: &mut Peekable<Lexer<impl Iterator<Item = char>>>,
-    is_in: &In,
-) -> Result<JsonData, ParseError> {
-    let token = lexer.next().unwrap();
-    // println!("Current Token: {token:?}");
-    if let Ok(i) = token.text.parse::<i64>() {
-        let next = is_next_valid(lexer, JsonData::Integer(i), is_in);
-        if next.is_err() {
-            Err(ParseError::SyntaxError(token))
-        } else {
-            next
+    // What was expected instead of `found`, like "expected `,` or `]`, found `:`"
+    fn expectation(found: &Token, expected: &[TokenKind]) -> Option<String> {
+        if let Some(keyword) = suggestion(&found.text) {
+            return Some(format!("expected `{}`", keyword));
         }
-    } else {
-        Err(ParseError::SyntaxError(token))
+        if expected.is_empty() {
+            return None;
+        }
+        let mut names = Vec::new();
+        let any_value = VALUE.iter().all(|kind| expected.contains(kind));
+        if any_value {
+            names.push("a value");
+        }
+        for kind in expected {
+            let name = describe(kind);
+            let is_listed = names.contains(&name) || any_value && VALUE.contains(kind);
+            if !is_listed {
+                names.push(name);
+            }
+        }
+        let names = match names.split_last() {
+            Some((last, [])) => last.to_string(),
+            Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+            None => return None,
+        };
+        Some(format!("expected {}, found `{}`", names, found.text))
+    }
+
+    // Syntax errors are all `JsonError::InvalidJsonSyntax`, the diagnostic of one tells
+    // them apart with these codes
+    pub(crate) fn code(&self) -> &'static str {
+        match self {
+            ParseError::SyntaxError { .. } => "E0101",
+            ParseError::UnexpectedEof => "E0102",
+            ParseError::EmptyInput => "E0103",
+            ParseError::Unclosed(_) => "E0104",
+            ParseError::TooDeep(_) => "E0105",
+        }
+    }
+
+    // The error as a diagnostic pointing into `json`, the text that failed to parse with
+    // tabs `tab_width` columns wide
+    pub(crate) fn diagnostic(&self, json: &str, tab_width: usize) -> Diagnostic {
+        let mut diagnostic = self.located(json, tab_width);
+        diagnostic.code = Some(self.code());
+        diagnostic
     }
-}
 
-fn parse_json_array(
-    lexer: &mut Peekable<Lexer<impl Iterator<Item = char>>>,
-    is_in: &In,
-) -> Result<JsonData, ParseError> {
-    lexer.next();
-    let mut arr: Vec<JsonData> = Vec::new();
-    let mut elem: Result<JsonData, ParseError>;
-    while let Some(token) = lexer.peek() {
-        // println!("Current Token: {token:?}");
-        elem = match token.kind {
-            TokenKind::CloseBracket => {
-                lexer.next();
-                break;
+    fn located(&self, json: &str, tab_width: usize) -> Diagnostic {
+        match self {
+            ParseError::SyntaxError { found, expected } => {
+                let mut message = format!("Invalid Json Syntax `{}`", found.text);
+                if let Some(expectation) = Self::expectation(found, expected) {
+                    message.push_str(&format!(", {}", expectation));
+                }
+                let mut diagnostic = Diagnostic::new(message, &found.loc);
+                diagnostic.expected = expected.iter().map(|kind| public_kind(*kind)).collect();
+                diagnostic
             }
-            TokenKind::Comma => {
-                lexer.next();
-                continue;
+            ParseError::UnexpectedEof => Diagnostic::new(
+                "Unexpected end of file".to_string(),
+                &locate(json, json.len(), tab_width),
+            ),
+            ParseError::EmptyInput => Diagnostic::new(
+                "Empty input".to_string(),
+                &locate(json, json.len(), tab_width),
+            ),
+            ParseError::Unclosed(open) => {
+                Diagnostic::new(format!("Unclosed {} started", container(open)), &open.loc)
             }
-            _ => eat(lexer, &In::Array),
-        };
-        if let Ok(e) = elem {
-            arr.push(e);
-        } else {
-            return elem;
+            ParseError::TooDeep(open) => Diagnostic::new(too_deep(open), &open.loc),
         }
     }
-    is_next_valid(lexer, JsonData::Array(arr), is_in)
 }
 
-fn parse_json_object(
-    lexer: &mut Peekable<Lexer<impl Iterator<Item = char>>>,
-    is_in: &In,
-) -> Result<JsonData, ParseError> {
-    lexer.next();
-    let mut map: HashMap<String, JsonData> = HashMap::new();
-    let mut elem: Result<JsonData, ParseError>;
-    let mut is_key = true;
-    let mut key: String = "".into();
-    while let Some(token) = lexer.peek() {
-        // println!("Current Token: {token:?}");
-        elem = match token.kind {
-            TokenKind::CloseCurly => {
-                lexer.next();
-                break;
-            }
-            TokenKind::Comma => {
-                if is_key {
-                    return Err(ParseError::SyntaxError(token.to_owned()));
-                }
-                is_key = true;
-                lexer.next();
-                continue;
-            }
-            TokenKind::Colon => {
-                if !is_key {
-                    return Err(ParseError::SyntaxError(token.to_owned()));
+fn too_deep(open: &Token) -> String {
+    format!(
+        "Arrays and objects nested deeper than {} levels, this {} starts",
+        MAX_DEPTH,
+        container(open)
+    )
+}
+
+fn container(open: &Token) -> &'static str {
+    match open.kind {
+        TokenKind::OpenCurly => "object",
+        _ => "array",
+    }
+}
+
+fn describe(kind: &TokenKind) -> &'static str {
+    match kind {
+        TokenKind::OpenCurly => "`{`",
+        TokenKind::CloseCurly => "`}`",
+        TokenKind::OpenBracket => "`[`",
+        TokenKind::CloseBracket => "`]`",
+        TokenKind::Comma => "`,`",
+        TokenKind::Colon => "`:`",
+        TokenKind::Integer | TokenKind::Float => "a number",
+        TokenKind::Str => "a string",
+        TokenKind::Null => "`null`",
+        TokenKind::True => "`true`",
+        TokenKind::False => "`false`",
+        TokenKind::Ident => "an identifier",
+        TokenKind::Eof => "the end of the input",
+        TokenKind::Invalid => "an invalid token",
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::SyntaxError { found, expected } => {
+                let msg = "Invalid Json Syntax";
+                write!(
+                    f,
+                    "{} `{}` at {}:{}\n{}{}",
+                    msg,
+                    found.text,
+                    found.loc.row,
+                    found.loc.col,
+                    " ".repeat(msg.len() + 2),
+                    "^".repeat(found.text.len())
+                )?;
+                match Self::expectation(found, expected) {
+                    Some(expectation) => write!(f, "\n{}", expectation),
+                    None => Ok(()),
                 }
-                is_key = false;
-                lexer.next();
-                continue;
             }
-            TokenKind::Str => {
-                if is_key {
-                    key = remove_surrounding_quotes(token.text.as_str());
-                    lexer.next();
-                    continue;
-                } else {
-                    parse_json_str(lexer, &In::Object)
-                }
+            ParseError::UnexpectedEof => {
+                write!(f, "Unexpected end of file")
             }
-            _ => {
-                if is_key {
-                    Err(ParseError::SyntaxError(token.to_owned()))
-                } else {
-                    eat(lexer, &In::Object)
-                }
+            ParseError::EmptyInput => write!(f, "Empty input"),
+            ParseError::Unclosed(open) => write!(
+                f,
+                "Unclosed {} started at {}:{}",
+                container(open),
+                open.loc.row,
+                open.loc.col
+            ),
+            ParseError::TooDeep(open) => {
+                write!(f, "{} at {}:{}", too_deep(open), open.loc.row, open.loc.col)
             }
-        };
-        if let Ok(e) = elem {
-            map.insert(key.to_string(), e);
-        } else {
-            return elem;
         }
     }
-    is_next_valid(lexer, JsonData::Object(map), is_in)
 }
 
-fn is_next_valid(
-    lexer: &mut Peekable<Lexer<impl Iterator<Item = char>>>,
-    current: JsonData,
-    is_in: &In,
-) -> Result<JsonData, ParseError> {
-    if let Some(next_token) = lexer.peek() {
-        // println!("Next Token: {next_token:?}");
-        let kind = &next_token.kind;
-        return if (kind == &TokenKind::Comma && (is_in == &In::Array || is_in == &In::Object))
-            || (kind == &TokenKind::CloseBracket && is_in == &In::Array)
-            || (kind == &TokenKind::CloseCurly && is_in == &In::Object)
-            || (kind == &TokenKind::Eof && is_in == &In::Nothing)
-        {
-            Ok(current)
-        } else {
-            Err(ParseError::SyntaxError(next_token.to_owned()))
-        };
+// Strict minified json, the same as `Json`'s `Display`
+impl fmt::Display for JsonData {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        display(self, f)
     }
-    Err(ParseError::UnexpectedEof)
 }
 
-// Removes the surrounding quotes from the string
-fn remove_surrounding_quotes<S: AsRef<str>>(text: S) -> String {
-    // println!("{:?}", text.as_ref());
-    let text = text.as_ref();
-    debug_assert!(
-        text.find('"') == Some(0) && text.rfind('"') == Some(text.len() - 1) && text.len() >= 2,
-        "String was not surrounded with quotes, THIS SHOULD ALWAYS BE TRUE"
-    );
-    text[1..text.len() - 1].to_string()
+// Parses with the same event stream that `Json::try_parse`, the lenient and relaxed parsers
+// and the streaming readers are built on, so they all accept the same json
+pub(crate) fn parse_json<S: AsRef<str>>(json: S) -> Result<JsonData, ParseError> {
+    build_data(&mut Events::new(json.as_ref().chars()))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::arbitrary::arb_data;
+    use crate::format::{reformat, FormatOptions};
+    use crate::tokens::DEFAULT_TAB_WIDTH;
     use proptest::prelude::*;
 
-    fn arb_json() -> impl Strategy<Value = JsonData> {
-        // https://altsysrq.github.io/proptest-book/proptest/tutorial/recursive.html
-        let leaf = prop_oneof![
-            Just(JsonData::Null),
-            any::<bool>().prop_map(JsonData::Bool),
-            any::<i64>().prop_map(JsonData::Integer),
-            (-1000.0..1000.0).prop_map(JsonData::Float),
-            r#"[^\\"]*"#.prop_map(JsonData::Str)
-        ];
-        leaf.prop_recursive(4, 64, 8, |inner| {
-            prop_oneof![
-                prop::collection::vec(inner.clone(), 0..12).prop_map(JsonData::Array),
-                prop::collection::hash_map(r#"[^\\"]*"#, inner, 0..12).prop_map(JsonData::Object),
-            ]
-        })
+    // Removes the surrounding quotes from the string, the lexer only makes `Str` tokens
+    // that have them
+    fn remove_surrounding_quotes<S: AsRef<str>>(text: S) -> String {
+        let text = text.as_ref();
+        text.strip_prefix('"')
+            .and_then(|text| text.strip_suffix('"'))
+            .unwrap_or(text)
+            .to_string()
     }
 
     proptest! {
@@ -350,14 +419,12 @@ mod tests {
             let s = s.trim();
             // println!("{s}");
             if s.is_empty() {
-                prop_assert_eq!(Ok(JsonData::Eof), json);
+                prop_assert_eq!(Err(ParseError::EmptyInput), json);
             }
             else if s.find('\"') == Some(0) && s.rfind('\"') == Some(s.len()-1) && s.len() >= 2 {
-                prop_assume!(
-                    (remove_surrounding_quotes(s).contains('\"') && remove_surrounding_quotes(s).contains(r#"\\""#))
-                    || (!remove_surrounding_quotes(s).contains('\"') && !remove_surrounding_quotes(s).contains(r#"\\""#))
-                );
-                prop_assert_eq!(Ok(JsonData::Str(remove_surrounding_quotes(s))), json);
+                // escapes are unescaped when parsed, `escape.rs` tests those
+                prop_assume!(!remove_surrounding_quotes(s).contains(['\"', '\\']));
+                prop_assert_eq!(Ok(JsonData::Str(remove_surrounding_quotes(s).into())), json);
             }
             else if let Ok(i) =  s.parse::<i64>() {
                 if s.starts_with('+') || s.starts_with('.') {
@@ -377,7 +444,7 @@ mod tests {
             }
             else if s.find('{') == Some(0) && s.rfind('}') == Some(s.len()-1){
                 prop_assume!(s.len() == 2);
-                prop_assert_eq!(Ok(JsonData::Object(HashMap::new())), json);
+                prop_assert_eq!(Ok(JsonData::Object(Box::default())), json);
             }
             else {
                 prop_assert!(json.is_err());
@@ -389,14 +456,35 @@ mod tests {
         fn valid_random_str(ref s in r#"\s*"[^\\"]*"\s*"#) {
             let json = parse_json(s);
             let s = s.trim();
-            prop_assert_eq!(Ok(JsonData::Str(remove_surrounding_quotes(s))), json)
+            prop_assert_eq!(Ok(JsonData::Str(remove_surrounding_quotes(s).into())), json)
         }
 
         #[test]
-        fn valid_random_json(ref s in arb_json()) {
+        fn valid_random_json(ref s in arb_data()) {
             let json = parse_json(s.to_string()).unwrap();
             prop_assert_eq!(s, &json);
         }
+
+        #[test]
+        fn display_is_minified(ref s in arb_data()) {
+            let text = s.to_string();
+            let mut minified = Vec::new();
+            reformat(text.as_bytes(), &mut minified, &FormatOptions::minified()).unwrap();
+            prop_assert_eq!(&text, &String::from_utf8(minified).unwrap());
+            let pretty = format!("{:#}", s);
+            prop_assert_eq!(s, &parse_json(pretty).unwrap());
+        }
+    }
+
+    #[test]
+    fn value_size() {
+        assert_eq!(32, size_of::<JsonData>());
+        let inline = |json: &str| match parse_json(json) {
+            Ok(JsonData::Str(s)) => s.is_inline(),
+            _ => unreachable!(),
+        };
+        assert!(inline("\"a short string\""));
+        assert!(!inline("\"a string that is too long to fit\""));
     }
 
     #[test]
@@ -413,7 +501,7 @@ mod tests {
     fn valid_str_one_escaped_quotation() {
         let s = r#""\"""#;
         let json = parse_json(s);
-        assert_eq!(Ok(JsonData::Str(remove_surrounding_quotes(s))), json);
+        assert_eq!(Ok(JsonData::Str("\"".into())), json);
     }
 
     #[test]
@@ -435,21 +523,22 @@ mod tests {
     }
 
     #[test]
-    fn valid_eof() {
-        let json = parse_json("");
-        assert_eq!(Ok(JsonData::Eof), json);
+    fn empty_input() {
+        assert_eq!(Err(ParseError::EmptyInput), parse_json(""));
+        assert_eq!(Err(ParseError::EmptyInput), parse_json(" \n\t"));
+        assert_eq!("Empty input", ParseError::EmptyInput.to_string());
     }
 
     #[test]
     fn valid_empty_str() {
         let json = parse_json("\"\"");
-        assert_eq!(Ok(JsonData::Str(String::from(""))), json);
+        assert_eq!(Ok(JsonData::Str("".into())), json);
     }
 
     #[test]
     fn valid_str() {
         let json = parse_json("\"test1234\"");
-        assert_eq!(Ok(JsonData::Str(String::from("test1234"))), json);
+        assert_eq!(Ok(JsonData::Str("test1234".into())), json);
     }
 
     #[test]
@@ -467,19 +556,19 @@ mod tests {
     #[test]
     fn valid_empty_array() {
         let json = parse_json("[]");
-        assert_eq!(Ok(JsonData::Array(vec![])), json);
+        assert_eq!(Ok(JsonData::array(vec![])), json);
     }
 
     #[test]
     fn valid_array_one_str_elem_array() {
         let json = parse_json("[\"t\"]");
-        assert_eq!(Ok(JsonData::Array(vec![JsonData::Str("t".into())])), json);
+        assert_eq!(Ok(JsonData::array(vec![JsonData::Str("t".into())])), json);
     }
 
     #[test]
     fn valid_array_one_integer_elem_array() {
         let json = parse_json("[4]");
-        assert_eq!(Ok(JsonData::Array(vec![JsonData::Integer(4)])), json);
+        assert_eq!(Ok(JsonData::array(vec![JsonData::Integer(4)])), json);
     }
 
     #[test]
@@ -487,7 +576,7 @@ mod tests {
         let json = parse_json("[\"t\", \"e\", \"s\", \"t\", 1, 2, 3, 4]");
         // println!("{}", json.as_ref().unwrap());
         assert_eq!(
-            Ok(JsonData::Array(vec![
+            Ok(JsonData::array(vec![
                 JsonData::Str("t".into()),
                 JsonData::Str("e".into()),
                 JsonData::Str("s".into()),
@@ -501,6 +590,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn invalid_stray_commas_and_colons() {
+        for json in [
+            "[1,,2]",
+            "[,1]",
+            "[1,]",
+            "{\"a\":1,}",
+            "{,\"a\":1}",
+            "{\"a\":}",
+            "{\"a\"}",
+        ] {
+            assert!(parse_json(json).is_err(), "{}", json);
+        }
+    }
+
     #[test]
     fn invalid_object_no_colon() {
         let json = parse_json("{\"hej\"123}");
@@ -512,12 +616,12 @@ mod tests {
         let json =
             parse_json("{\"test_name1\":1,\"test_name2\":2,\"test_name3\":3,\"test_name4\":4}");
         assert_eq!(
-            Ok(JsonData::Object(HashMap::from([
+            Ok(JsonData::object([
                 ("test_name1".to_string(), JsonData::Integer(1)),
                 ("test_name2".to_string(), JsonData::Integer(2)),
                 ("test_name3".to_string(), JsonData::Integer(3)),
                 ("test_name4".to_string(), JsonData::Integer(4)),
-            ]))),
+            ])),
             json
         );
     }
@@ -526,10 +630,10 @@ mod tests {
     fn valid_object_one_kv() {
         let json = parse_json("{\"test_name\":1}");
         assert_eq!(
-            Ok(JsonData::Object(HashMap::from([(
+            Ok(JsonData::object([(
                 "test_name".to_string(),
                 JsonData::Integer(1)
-            )]))),
+            )])),
             json
         );
     }
@@ -537,16 +641,17 @@ mod tests {
     #[test]
     fn valid_empty_object() {
         let json = parse_json("{}");
-        assert_eq!(Ok(JsonData::Object(HashMap::from([]))), json);
+        assert_eq!(Ok(JsonData::Object(Box::default())), json);
     }
 
+    #[test]
     fn parse_array_of_all_non_recursive_types() {
         let json = parse_json("[null, \"hej\", 1337, 1337.0, true, false]");
         // println!("{}", json.as_ref().unwrap());
         assert_eq!(
-            Ok(JsonData::Array(vec![
+            Ok(JsonData::array(vec![
                 JsonData::Null,
-                JsonData::Str(String::from("hej")),
+                JsonData::Str("hej".into()),
                 JsonData::Integer(1337),
                 JsonData::Float(1337.0),
                 JsonData::Bool(true),
@@ -563,16 +668,16 @@ mod tests {
         );
         // println!("{}", json.as_ref().unwrap());
         assert_eq!(
-            Ok(JsonData::Array(vec![
+            Ok(JsonData::array(vec![
                 JsonData::Null,
-                JsonData::Str(String::from("hej")),
+                JsonData::Str("hej".into()),
                 JsonData::Integer(1337),
                 JsonData::Float(1337.0),
                 JsonData::Bool(true),
                 JsonData::Bool(false),
-                JsonData::Array(vec![
+                JsonData::array(vec![
                     JsonData::Null,
-                    JsonData::Str(String::from("hej")),
+                    JsonData::Str("hej".into()),
                     JsonData::Integer(1337),
                     JsonData::Bool(true),
                     JsonData::Bool(false),
@@ -585,9 +690,9 @@ mod tests {
     #[test]
     fn parse_object_with_a_json_value_in_str() {
         assert_eq!(
-            Ok(JsonData::Object({
-                let mut h = HashMap::new();
-                h.insert(String::from("s1"), JsonData::Str(String::from("s1val")));
+            Ok(JsonData::object({
+                let mut h = Map::default();
+                h.insert(String::from("s1"), JsonData::Str("s1val".into()));
                 h
             })),
             parse_json("{\"s1\":\"s1val\"}")
@@ -612,24 +717,21 @@ mod tests {
     }",
         );
         assert_eq!(
-            Ok(JsonData::Object({
-                let mut h = HashMap::new();
-                h.insert(
-                    String::from("string1"),
-                    JsonData::Str(String::from("string1")),
-                );
-                h.insert(String::from("string2"), JsonData::Str(String::from("")));
+            Ok(JsonData::object({
+                let mut h = Map::default();
+                h.insert(String::from("string1"), JsonData::Str("string1".into()));
+                h.insert(String::from("string2"), JsonData::Str("".into()));
                 h.insert(String::from("null"), JsonData::Null);
                 h.insert(String::from("integer"), JsonData::Integer(1337));
                 h.insert(String::from("float"), JsonData::Float(1337.0));
                 h.insert(String::from("true"), JsonData::Bool(true));
                 h.insert(String::from("false"), JsonData::Bool(false));
-                h.insert(String::from("arr1"), JsonData::Array(vec![]));
+                h.insert(String::from("arr1"), JsonData::array(vec![]));
                 h.insert(
                     String::from("arr2"),
-                    JsonData::Array(vec![
+                    JsonData::array(vec![
                         JsonData::Null,
-                        JsonData::Str(String::from("hej")),
+                        JsonData::Str("hej".into()),
                         JsonData::Integer(1337),
                         JsonData::Bool(true),
                         JsonData::Bool(false),
@@ -637,15 +739,15 @@ mod tests {
                 );
                 h.insert(
                     String::from("arr3"),
-                    JsonData::Array(vec![
+                    JsonData::array(vec![
                         JsonData::Null,
-                        JsonData::Str(String::from("hej")),
+                        JsonData::Str("hej".into()),
                         JsonData::Integer(1337),
                         JsonData::Bool(true),
                         JsonData::Bool(false),
-                        JsonData::Array(vec![
+                        JsonData::array(vec![
                             JsonData::Null,
-                            JsonData::Str(String::from("hej")),
+                            JsonData::Str("hej".into()),
                             JsonData::Integer(1337),
                             JsonData::Bool(true),
                             JsonData::Bool(false),
@@ -657,4 +759,94 @@ mod tests {
             json
         );
     }
+
+    #[cfg(feature = "preserve_order")]
+    #[test]
+    fn members_keep_their_order() {
+        let json = parse_json("{\"b\": 1, \"a\": {\"z\": 2, \"y\": 3}, \"c\": 4}").unwrap();
+        assert_eq!(
+            "{\"b\":1,\"a\":{\"z\":2,\"y\":3},\"c\":4}",
+            json.to_string()
+        );
+        let JsonData::Object(mut map) = json else {
+            panic!("expected an object");
+        };
+        remove_member(&mut map, "b");
+        assert_eq!(
+            vec!["a", "c"],
+            map.keys().map(|key| key.as_str()).collect::<Vec<_>>()
+        );
+    }
+
+    #[cfg(all(feature = "btreemap", not(feature = "preserve_order")))]
+    #[test]
+    fn members_are_sorted() {
+        let json = parse_json("{\"b\": 1, \"a\": {\"z\": 2, \"y\": 3}, \"c\": 4}").unwrap();
+        assert_eq!(
+            "{\"a\":{\"y\":3,\"z\":2},\"b\":1,\"c\":4}",
+            json.to_string()
+        );
+    }
+
+    #[test]
+    fn error_location() {
+        let message = parse_json("{\n\t\"é😀\": x}").unwrap_err().to_string();
+        assert!(message.contains("`x` at 2:11"), "{}", message);
+        assert!(parse_json("[1,\n  \"a\"\n  :]")
+            .unwrap_err()
+            .to_string()
+            .contains("`:` at 3:3"));
+    }
+
+    #[test]
+    fn suggest_misspelled_literals() {
+        let message = |json: &str| parse_json(json).unwrap_err().to_string();
+        assert!(
+            message("[ture]").contains("`ture`"),
+            "{}",
+            message("[ture]")
+        );
+        assert!(message("[ture]").ends_with("\nexpected `true`"));
+        assert!(message("{\"a\": flase}").ends_with("\nexpected `false`"));
+        assert!(message("nil").ends_with("\nexpected `null`"));
+        assert!(message("None").ends_with("\nexpected `null`"));
+        assert!(message("TRUE").ends_with("\nexpected `true`"));
+        assert!(!message("[banana]").contains("expected `"));
+        assert!(!message("[x]").contains("expected `"));
+        assert_eq!(Some("true"), suggestion("tru"));
+        assert_eq!(None, suggestion("t"));
+    }
+
+    #[test]
+    fn expected_tokens() {
+        let message = |json: &str| parse_json(json).unwrap_err().to_string();
+        assert!(message("[1 :]").ends_with("\nexpected `,` or `]`, found `:`"));
+        assert!(message("{\"a\" 1}").ends_with("\nexpected `:`, found `1`"));
+        assert!(message("{1: 2}").ends_with("\nexpected a string or `}`, found `1`"));
+        assert!(message("[1, :]").ends_with("\nexpected a value, found `:`"));
+        assert!(message("1 2").ends_with("\nexpected the end of the input, found `2`"));
+        let Err(ParseError::SyntaxError { expected, .. }) = parse_json("[true}") else {
+            panic!("expected a syntax error");
+        };
+        assert_eq!(vec![TokenKind::Comma, TokenKind::CloseBracket], expected);
+    }
+
+    #[test]
+    fn unclosed_containers() {
+        let message = |json: &str| parse_json(json).unwrap_err().to_string();
+        assert_eq!("Unclosed object started at 1:1", message("{\"a\":1"));
+        assert_eq!(
+            "Unclosed array started at 2:8",
+            message("{\"a\": {\n  \"b\": [1, 2")
+        );
+        assert_eq!("Unclosed object started at 1:2", message("[{"));
+        assert_eq!("Unclosed array started at 1:1", message("["));
+        assert_eq!(
+            "Unclosed object started at 1:1",
+            parse_json("{\"a\":1")
+                .unwrap_err()
+                .diagnostic("", DEFAULT_TAB_WIDTH)
+                .to_string()
+        );
+    }
 }
@@ -0,0 +1,246 @@
+use crate::json::{AnyJson, Json, JsonError, JsonState};
+use crate::parser::{JsonData, Shared};
+use std::iter::Peekable;
+use std::str::Chars;
+
+#[derive(Debug, PartialEq, Clone)]
+enum Selector {
+    Name(String),
+    Index(i64),
+    Wildcard,
+    Union(Vec<Selector>),
+}
+
+#[derive(Debug, PartialEq)]
+struct Step {
+    descendants: bool,
+    selector: Selector,
+}
+
+fn path_error(path: &str, msg: &str) -> JsonError {
+    JsonError::InvalidPath(format!("{} in json path `{}`", msg, path))
+}
+
+// Parses the subset of JSONPath made up of `$`, `.name`, `.*`, `..`, `['name']`, `[0]`,
+// `[-1]`, `[*]` and unions like `[0,2]` or `['a','b']`
+fn parse_path(path: &str) -> Result<Vec<Step>, JsonError> {
+    let mut chars = path.trim().chars().peekable();
+    if chars.next() != Some('$') {
+        return Err(path_error(path, "Expected `$` at the start"));
+    }
+    let mut steps = Vec::new();
+    while let Some(c) = chars.next() {
+        let step = match c {
+            '.' => {
+                let descendants = chars.next_if_eq(&'.').is_some();
+                let selector = if chars.next_if_eq(&'[').is_some() {
+                    if !descendants {
+                        return Err(path_error(path, "Unexpected `.` before `[`"));
+                    }
+                    parse_bracket(path, &mut chars)?
+                } else if chars.next_if_eq(&'*').is_some() {
+                    Selector::Wildcard
+                } else {
+                    let mut name = String::new();
+                    while let Some(c) = chars.next_if(|c| !matches!(c, '.' | '[')) {
+                        name.push(c);
+                    }
+                    if name.is_empty() {
+                        return Err(path_error(path, "Expected a name after `.`"));
+                    }
+                    Selector::Name(name)
+                };
+                Step {
+                    descendants,
+                    selector,
+                }
+            }
+            '[' => Step {
+                descendants: false,
+                selector: parse_bracket(path, &mut chars)?,
+            },
+            c => return Err(path_error(path, &format!("Unexpected `{}`", c))),
+        };
+        steps.push(step);
+    }
+    Ok(steps)
+}
+
+// Parses what comes after a `[` up to and including the matching `]`
+fn parse_bracket(path: &str, chars: &mut Peekable<Chars>) -> Result<Selector, JsonError> {
+    let mut selectors = Vec::new();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let selector = match chars.next() {
+            Some('*') => Selector::Wildcard,
+            Some(quote @ ('\'' | '"')) => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\\') => match chars.next() {
+                            Some(c) => name.push(c),
+                            None => return Err(path_error(path, "Unterminated name")),
+                        },
+                        Some(c) if c == quote => break,
+                        Some(c) => name.push(c),
+                        None => return Err(path_error(path, "Unterminated name")),
+                    }
+                }
+                Selector::Name(name)
+            }
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let mut index = String::from(c);
+                while let Some(c) = chars.next_if(char::is_ascii_digit) {
+                    index.push(c);
+                }
+                Selector::Index(
+                    index
+                        .parse()
+                        .map_err(|_| path_error(path, &format!("Invalid index `{}`", index)))?,
+                )
+            }
+            _ => return Err(path_error(path, "Expected a name, index or `*` after `[`")),
+        };
+        selectors.push(selector);
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        match chars.next() {
+            Some(',') => continue,
+            Some(']') => break,
+            _ => return Err(path_error(path, "Expected `,` or `]`")),
+        }
+    }
+    Ok(if selectors.len() == 1 {
+        selectors.remove(0)
+    } else {
+        Selector::Union(selectors)
+    })
+}
+
+fn push_descendants<'a>(data: &'a Shared<JsonData>, out: &mut Vec<&'a Shared<JsonData>>) {
+    out.push(data);
+    match data.as_ref() {
+        JsonData::Array(arr) => arr.iter().for_each(|data| push_descendants(data, out)),
+        JsonData::Object(map) => map.values().for_each(|data| push_descendants(data, out)),
+        _ => {}
+    }
+}
+
+fn apply<'a>(selector: &Selector, data: &'a Shared<JsonData>, out: &mut Vec<&'a Shared<JsonData>>) {
+    match (selector, data.as_ref()) {
+        (Selector::Name(name), JsonData::Object(map)) => out.extend(map.get(name.as_str())),
+        (Selector::Index(index), JsonData::Array(arr)) => {
+            let index = if *index < 0 {
+                arr.len() as i64 + index
+            } else {
+                *index
+            };
+            if index >= 0 {
+                out.extend(arr.get(index as usize));
+            }
+        }
+        (Selector::Wildcard, JsonData::Array(arr)) => out.extend(arr.iter()),
+        (Selector::Wildcard, JsonData::Object(map)) => out.extend(map.values()),
+        (Selector::Union(selectors), _) => {
+            for selector in selectors {
+                apply(selector, data, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn select<'a>(data: &'a Shared<JsonData>, steps: &[Step]) -> Vec<&'a Shared<JsonData>> {
+    let mut current = vec![data];
+    for step in steps {
+        if step.descendants {
+            let mut all = Vec::new();
+            for data in current {
+                push_descendants(data, &mut all);
+            }
+            current = all;
+        }
+        let mut next = Vec::new();
+        for data in current {
+            apply(&step.selector, data, &mut next);
+        }
+        current = next;
+    }
+    current
+}
+
+impl<S: JsonState> Json<S> {
+    // Returns every value matched by the JSONPath `path`, e.g. `$.items[*].id`.
+    // Members of objects are visited in no particular order.
+    pub fn select(&self, path: &str) -> Result<Vec<AnyJson>, JsonError> {
+        let steps = parse_path(path)?;
+        Ok(select(&self.data, &steps)
+            .into_iter()
+            .map(|data| AnyJson::from_shared(Shared::clone(data)))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::Object;
+
+    fn select_i64(json: &Json<Object>, path: &str) -> Vec<i64> {
+        let mut found: Vec<i64> = json
+            .select(path)
+            .unwrap()
+            .into_iter()
+            .map(|json| match json {
+                AnyJson::Value(value) => value.get_i64().unwrap(),
+                json => panic!("{json}"),
+            })
+            .collect();
+        found.sort();
+        found
+    }
+
+    #[test]
+    fn path_parse() {
+        assert_eq!(
+            Ok(vec![
+                Step {
+                    descendants: false,
+                    selector: Selector::Name(String::from("a"))
+                },
+                Step {
+                    descendants: true,
+                    selector: Selector::Wildcard
+                },
+                Step {
+                    descendants: false,
+                    selector: Selector::Union(vec![
+                        Selector::Index(-1),
+                        Selector::Name(String::from("b]'"))
+                    ])
+                },
+            ]),
+            parse_path("$.a..*[-1, 'b]\\'']")
+        );
+        for path in ["", "a", "$.", "$[", "$[1", "$['a]", "$.[0]", "$x", "$[?]"] {
+            assert!(
+                matches!(parse_path(path), Err(JsonError::InvalidPath(_))),
+                "{path}"
+            );
+        }
+    }
+
+    #[test]
+    fn path_select() {
+        let json: Json<Object> = Json::new(
+            "{\"items\": [{\"id\": 1, \"tags\": [5]}, {\"id\": 2}, {\"name\": 3}], \"id\": 4}",
+        )
+        .unwrap();
+        assert_eq!(vec![1, 2], select_i64(&json, "$.items[*].id"));
+        assert_eq!(vec![1, 2, 4], select_i64(&json, "$..id"));
+        assert_eq!(vec![2], select_i64(&json, "$['items'][-2].id"));
+        assert_eq!(vec![1, 3], select_i64(&json, "$.items[0,2]['id','name']"));
+        assert_eq!(vec![5], select_i64(&json, "$..tags[0]"));
+        assert_eq!(Vec::<i64>::new(), select_i64(&json, "$.items[7].id"));
+        assert_eq!(1, json.select("$").unwrap().len());
+    }
+}
@@ -0,0 +1,392 @@
+use crate::escape::unescape_str;
+use crate::format::syntax_error;
+use crate::json::{AnyJson, Json, JsonError, JsonState, JsonType};
+use crate::parser::{JsonData, Shared};
+use crate::stream::{DataBuilder, Event, Events};
+
+// Splits a json pointer (RFC 6901) into its unescaped reference tokens
+pub(crate) fn parse_pointer(pointer: &str) -> Result<Vec<String>, JsonError> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    if !pointer.starts_with('/') {
+        return Err(JsonError::InvalidPath(format!(
+            "Json pointer `{}` has to start with `/`",
+            pointer
+        )));
+    }
+    pointer[1..]
+        .split('/')
+        .map(|token| {
+            let mut unescaped = String::with_capacity(token.len());
+            let mut chars = token.chars();
+            while let Some(c) = chars.next() {
+                if c != '~' {
+                    unescaped.push(c);
+                    continue;
+                }
+                match chars.next() {
+                    Some('0') => unescaped.push('~'),
+                    Some('1') => unescaped.push('/'),
+                    _ => {
+                        return Err(JsonError::InvalidPath(format!(
+                            "`~` has to be followed by `0` or `1` in json pointer `{}`",
+                            pointer
+                        )))
+                    }
+                }
+            }
+            Ok(unescaped)
+        })
+        .collect()
+}
+
+// Escapes a key to a json pointer reference token, the reverse of `parse_pointer`
+pub(crate) fn pointer_token(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+// The json pointer made of the unescaped reference `tokens`
+pub(crate) fn to_pointer(tokens: &[String]) -> String {
+    tokens
+        .iter()
+        .map(|token| format!("/{}", pointer_token(token)))
+        .collect()
+}
+
+pub(crate) fn array_index(token: &str) -> Option<usize> {
+    if token.is_empty()
+        || (token.len() > 1 && token.starts_with('0'))
+        || !token.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    token.parse().ok()
+}
+
+pub(crate) fn resolve<'a>(
+    data: &'a Shared<JsonData>,
+    tokens: &[String],
+) -> Result<&'a Shared<JsonData>, JsonError> {
+    tokens
+        .iter()
+        .enumerate()
+        .try_fold(data, |data, (i, token)| {
+            // the pointer of the value the error is about
+            let path = |len: usize| to_pointer(&tokens[..len]);
+            match data.as_ref() {
+                JsonData::Object(map) => map
+                    .get(token.as_str())
+                    .ok_or_else(|| JsonError::key_not_found(path(i + 1))),
+                JsonData::Array(arr) => array_index(token)
+                    .and_then(|index| arr.get(index))
+                    .ok_or_else(|| JsonError::index_not_found(path(i + 1))),
+                data => Err(JsonError::incorrect_type(
+                    path(i),
+                    &[JsonType::Object, JsonType::Array],
+                    data,
+                )),
+            }
+        })
+}
+
+// Like `resolve` but copies the containers on the way that are shared with another view
+pub(crate) fn resolve_mut<'a>(
+    data: &'a mut Shared<JsonData>,
+    tokens: &[String],
+) -> Result<&'a mut Shared<JsonData>, JsonError> {
+    // checked first so nothing is copied for a path that does not exist
+    resolve(data, tokens)?;
+    Ok(tokens.iter().fold(data, |data, token| {
+        match Shared::make_mut(data) {
+            JsonData::Object(map) => map.get_mut(token.as_str()),
+            JsonData::Array(arr) => array_index(token).and_then(|index| arr.get_mut(index)),
+            _ => None,
+        }
+        .expect("THIS SHOULD NEVER PANIC")
+    }))
+}
+
+impl<S: JsonState> Json<S> {
+    // Looks up a json pointer like `/items/0/id`, `~1` and `~0` stand for `/` and `~`
+    pub fn pointer(&self, pointer: &str) -> Result<AnyJson, JsonError> {
+        let tokens = parse_pointer(pointer)?;
+        let path = format!("{}{}", self.path, to_pointer(&tokens));
+        resolve(&self.data, &tokens)
+            .map(|data| AnyJson::from_shared(Shared::clone(data)).at(path))
+            .map_err(|error| error.under(&self.path))
+    }
+}
+
+// Looks up every pointer in a single pass over the tokens of `json`, only the values that
+// were asked for are built. A key that appears twice resolves to its last value, like
+// `Json::new` does.
+pub fn extract<R: AsRef<str>>(
+    json: R,
+    pointers: &[&str],
+) -> Result<Vec<Option<AnyJson>>, JsonError> {
+    let pointers = pointers
+        .iter()
+        .map(|pointer| parse_pointer(pointer))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut found: Vec<Option<JsonData>> = vec![None; pointers.len()];
+    // the path to the current value, `None` marks an object level and `Some` holds the
+    // next index of an array level
+    let mut path: Vec<String> = Vec::new();
+    let mut levels: Vec<Option<usize>> = Vec::new();
+    let mut captures: Vec<(Vec<usize>, DataBuilder)> = Vec::new();
+    for event in Events::new(json.as_ref().chars()) {
+        let event = event.map_err(syntax_error)?;
+        match &event {
+            Event::Key(token) => {
+                let key = unescape_str(&token.text[1..token.text.len() - 1]).into_owned();
+                *path.last_mut().expect("THIS SHOULD NEVER PANIC") = key;
+            }
+            Event::StartObject(_) | Event::StartArray(_) | Event::Scalar(_) => {
+                if let (Some(Some(index)), Some(segment)) = (levels.last_mut(), path.last_mut()) {
+                    *segment = index.to_string();
+                    *index += 1;
+                }
+                // a repeated key replaces whatever was found under its first value
+                for (pointer, found) in pointers.iter().zip(found.iter_mut()) {
+                    if pointer.starts_with(&path) {
+                        *found = None;
+                    }
+                }
+                let wanted: Vec<usize> = (0..pointers.len())
+                    .filter(|&i| pointers[i] == path)
+                    .collect();
+                if !wanted.is_empty() {
+                    captures.push((wanted, DataBuilder::default()));
+                }
+            }
+            Event::EndObject(_) | Event::EndArray(_) => {}
+        }
+        let mut i = 0;
+        while i < captures.len() {
+            match captures[i].1.push(event.clone()).map_err(syntax_error)? {
+                Some(data) => {
+                    let (wanted, _) = captures.remove(i);
+                    for index in wanted {
+                        found[index] = Some(data.to_owned());
+                    }
+                }
+                None => i += 1,
+            }
+        }
+        match &event {
+            Event::StartObject(_) => {
+                levels.push(None);
+                path.push(String::new());
+            }
+            Event::StartArray(_) => {
+                levels.push(Some(0));
+                path.push(String::new());
+            }
+            Event::EndObject(_) | Event::EndArray(_) => {
+                levels.pop();
+                path.pop();
+            }
+            Event::Key(_) | Event::Scalar(_) => {}
+        }
+    }
+    Ok(found
+        .into_iter()
+        .map(|data| data.map(AnyJson::from_data))
+        .collect())
+}
+
+// The value at `pointer`, reading `json` only up to its end. Arrays and objects that can't
+// hold it are skipped without being built and nothing after the value is read, so it is
+// not checked either. Unlike `extract` a key that appears twice resolves to its first value.
+pub fn find_first<R: AsRef<str>>(json: R, pointer: &str) -> Result<Option<AnyJson>, JsonError> {
+    let tokens = parse_pointer(pointer)?;
+    let mut events = Events::new(json.as_ref().chars());
+    // the arrays and objects on the way to the value, arrays hold the index of their next
+    // value
+    let mut levels: Vec<Option<usize>> = Vec::new();
+    let mut key = String::new();
+    while let Some(event) = events.next() {
+        let event = event.map_err(syntax_error)?;
+        let segment = match (&event, levels.last_mut()) {
+            (Event::Key(token), _) => {
+                key = unescape_str(&token.text[1..token.text.len() - 1]).into_owned();
+                continue;
+            }
+            (Event::EndObject(_) | Event::EndArray(_), _) => {
+                levels.pop();
+                continue;
+            }
+            (_, None) => None,
+            (_, Some(Some(index))) => {
+                *index += 1;
+                Some((*index - 1).to_string())
+            }
+            (_, Some(None)) => Some(std::mem::take(&mut key)),
+        };
+        let depth = levels.len();
+        let on_the_way = segment.is_none_or(|segment| segment == tokens[depth - 1]);
+        if on_the_way && depth == tokens.len() {
+            let mut builder = DataBuilder::default();
+            let mut event = event;
+            loop {
+                if let Some(data) = builder.push(event).map_err(syntax_error)? {
+                    return Ok(Some(AnyJson::from_data(data)));
+                }
+                event = events
+                    .next()
+                    .expect("THIS SHOULD NEVER PANIC")
+                    .map_err(syntax_error)?;
+            }
+        }
+        match event {
+            Event::StartObject(_) if on_the_way => levels.push(None),
+            Event::StartArray(_) if on_the_way => levels.push(Some(0)),
+            event => events.skip_value(&event).map_err(syntax_error)?,
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{extract, find_first};
+    use crate::json::JsonType;
+    use crate::json::{AnyJson, Json, JsonError, Object};
+
+    fn pointer_i64(json: &Json<Object>, pointer: &str) -> Result<i64, JsonError> {
+        match json.pointer(pointer)? {
+            AnyJson::Value(value) => value.get_i64(),
+            json => Err(JsonError::incorrect_type(
+                json.path(),
+                &[JsonType::Integer],
+                json.data(),
+            )),
+        }
+    }
+
+    #[test]
+    fn pointer_lookup() {
+        let json: Json<Object> =
+            Json::new("{\"a\": {\"b\": [10, 20]}, \"c/d\": 1, \"e~f\": 2, \"\": 3, \"q\\\"\": 4}")
+                .unwrap();
+        assert_eq!(Ok(20), pointer_i64(&json, "/a/b/1"));
+        assert_eq!(Ok(1), pointer_i64(&json, "/c~1d"));
+        assert_eq!(Ok(2), pointer_i64(&json, "/e~0f"));
+        assert_eq!(Ok(3), pointer_i64(&json, "/"));
+        assert_eq!(Ok(4), pointer_i64(&json, "/q\""));
+        assert!(matches!(json.pointer(""), Ok(AnyJson::Object(_))));
+        assert!(matches!(json.pointer("/a/b"), Ok(AnyJson::Array(_))));
+    }
+
+    #[test]
+    fn extract_many() {
+        let json = "{\"a\": {\"b\": [1, {\"c\": \"x\"}]}, \"items\": [{\"id\": 7}, {\"id\": 8}], \"k\\\"\": 2, \"items\": [{\"id\": 9}]}";
+        let found: Vec<Option<String>> = extract(
+            json,
+            &[
+                "/a/b",
+                "/a/b/1/c",
+                "/items/0/id",
+                "/items/1/id",
+                "/k\"",
+                "/missing",
+                "/a/b",
+            ],
+        )
+        .unwrap()
+        .into_iter()
+        .map(|json| json.map(|json| json.to_string()))
+        .collect();
+        assert_eq!(
+            vec![
+                Some(String::from("[1,{\"c\":\"x\"}]")),
+                Some(String::from("\"x\"")),
+                Some(String::from("9")),
+                None,
+                Some(String::from("2")),
+                None,
+                Some(String::from("[1,{\"c\":\"x\"}]")),
+            ],
+            found
+        );
+        assert!(
+            matches!(extract(json, &[""]), Ok(found) if matches!(found[0], Some(AnyJson::Object(_))))
+        );
+        assert!(matches!(
+            extract(json, &["a"]),
+            Err(JsonError::InvalidPath(_))
+        ));
+        assert!(matches!(
+            extract("{\"a\": [1,]}", &["/a"]),
+            Err(JsonError::InvalidJsonSyntax(_))
+        ));
+    }
+
+    #[test]
+    fn pointer_errors() {
+        let json: Json<Object> = Json::new("{\"a\": [1], \"b\": 2}").unwrap();
+        assert_eq!(
+            Err(JsonError::KeyNotFound {
+                path: "/x".to_string()
+            }),
+            pointer_i64(&json, "/x")
+        );
+        assert_eq!(
+            Err(JsonError::IndexNotFound {
+                path: "/a/1".to_string()
+            }),
+            pointer_i64(&json, "/a/1")
+        );
+        assert_eq!(
+            Err(JsonError::IndexNotFound {
+                path: "/a/01".to_string()
+            }),
+            pointer_i64(&json, "/a/01")
+        );
+        assert_eq!(
+            Err(JsonError::IndexNotFound {
+                path: "/a/-".to_string()
+            }),
+            pointer_i64(&json, "/a/-")
+        );
+        assert_eq!(
+            Err(JsonError::IncorrectType {
+                path: "/b".to_string(),
+                expected: vec![JsonType::Object, JsonType::Array],
+                found: JsonType::Integer,
+            }),
+            pointer_i64(&json, "/b/c")
+        );
+        assert!(matches!(json.pointer("a"), Err(JsonError::InvalidPath(_))));
+        assert!(matches!(
+            json.pointer("/a~2"),
+            Err(JsonError::InvalidPath(_))
+        ));
+    }
+
+    #[test]
+    fn find_first_value() {
+        let json = "{\"header\": {\"id\": 7, \"k/\\\"\": [1, {\"x\": null}]}, \"header\": {\"id\": 8}, \"body\": [";
+        let find = |pointer| find_first(json, pointer).map(|found| found.map(|j| j.to_string()));
+        assert_eq!(Ok(Some(String::from("7"))), find("/header/id"));
+        assert_eq!(
+            Ok(Some(String::from("{\"x\":null}"))),
+            find("/header/k~1\"/1")
+        );
+        assert_eq!(Ok(Some(String::from("null"))), find("/header/k~1\"/1/x"));
+        assert!(find("/body").is_err());
+        assert!(find("/missing").is_err());
+        assert!(matches!(find("header"), Err(JsonError::InvalidPath(_))));
+        assert_eq!(
+            Ok(Some(String::from("3"))),
+            find_first("[[1, [2]], 3, {]", "/1").map(|found| found.map(|j| j.to_string()))
+        );
+        assert!(matches!(
+            find_first("[[1, 2]]", ""),
+            Ok(Some(AnyJson::Array(_)))
+        ));
+        assert!(find_first("[1, {\"0\": 2}]", "/2").unwrap().is_none());
+        assert!(find_first("5", "/a").unwrap().is_none());
+    }
+}
@@ -0,0 +1,157 @@
+use crate::json::{AnyJson, Json, JsonError, JsonState, Object};
+use crate::parser::JsonData;
+use prost_types::value::Kind;
+use prost_types::{ListValue, Struct, Value};
+
+impl From<Value> for AnyJson {
+    fn from(value: Value) -> Self {
+        AnyJson::from_data(from_proto_value(value))
+    }
+}
+
+// An `IncorrectType` error when the value is not of the type of `S`
+impl<S: JsonState> TryFrom<Value> for Json<S> {
+    type Error = JsonError;
+
+    fn try_from(value: Value) -> Result<Self, JsonError> {
+        Self::checked(from_proto_value(value))
+    }
+}
+
+impl<S: JsonState> From<&Json<S>> for Value {
+    fn from(json: &Json<S>) -> Self {
+        to_proto_value(&json.data)
+    }
+}
+
+impl<S: JsonState> From<Json<S>> for Value {
+    fn from(json: Json<S>) -> Self {
+        to_proto_value(&json.data)
+    }
+}
+
+impl From<Struct> for Json<Object> {
+    fn from(value: Struct) -> Self {
+        Self::from_data(from_proto_struct(value))
+    }
+}
+
+impl From<&Json<Object>> for Struct {
+    fn from(json: &Json<Object>) -> Self {
+        match to_proto_value(&json.data).kind {
+            Some(Kind::StructValue(value)) => value,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl From<Json<Object>> for Struct {
+    fn from(json: Json<Object>) -> Self {
+        Struct::from(&json)
+    }
+}
+
+// The largest integer up to which every integer is exact as a float
+const EXACT: f64 = 9007199254740992.0;
+
+fn from_proto_value(value: Value) -> JsonData {
+    match value.kind {
+        None | Some(Kind::NullValue(_)) => JsonData::Null,
+        Some(Kind::BoolValue(b)) => JsonData::Bool(b),
+        // protobuf only has floats, the ones that are exact integers are read as integers
+        Some(Kind::NumberValue(n)) if n.fract() == 0.0 && n.abs() <= EXACT => {
+            JsonData::Integer(n as i64)
+        }
+        Some(Kind::NumberValue(n)) if n.is_finite() => JsonData::Float(n),
+        Some(Kind::NumberValue(_)) => JsonData::Null,
+        Some(Kind::StringValue(s)) => JsonData::Str(s.into()),
+        Some(Kind::ListValue(list)) => {
+            JsonData::array(list.values.into_iter().map(from_proto_value))
+        }
+        Some(Kind::StructValue(value)) => from_proto_struct(value),
+    }
+}
+
+fn from_proto_struct(value: Struct) -> JsonData {
+    JsonData::object(
+        value
+            .fields
+            .into_iter()
+            .map(|(key, value)| (key, from_proto_value(value))),
+    )
+}
+
+fn to_proto_value(data: &JsonData) -> Value {
+    let kind = match data {
+        JsonData::Null => Kind::NullValue(0),
+        JsonData::Bool(b) => Kind::BoolValue(*b),
+        JsonData::Str(s) => Kind::StringValue(s.to_string()),
+        JsonData::Array(v) => Kind::ListValue(ListValue {
+            values: v.iter().map(|data| to_proto_value(data)).collect(),
+        }),
+        JsonData::Object(m) => Kind::StructValue(Struct {
+            fields: m
+                .iter()
+                .map(|(key, value)| (key.to_string(), to_proto_value(value)))
+                .collect(),
+        }),
+        // integers outside of the range a float holds exactly lose precision
+        number => Kind::NumberValue(number.as_f64().expect("THIS SHOULD NEVER PANIC")),
+    };
+    Value { kind: Some(kind) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{Array, Value as JsonValue};
+
+    #[test]
+    fn from_proto_struct() {
+        let value = Struct {
+            fields: [
+                ("name".to_string(), Value::from("a \"b\"")),
+                ("count".to_string(), Value::from(3.0)),
+                (
+                    "items".to_string(),
+                    Value::from(vec![
+                        Value::from(1.5),
+                        Value::from(true),
+                        Value {
+                            kind: Some(Kind::NullValue(0)),
+                        },
+                    ]),
+                ),
+            ]
+            .into(),
+        };
+        let json = Json::<Object>::from(value.clone());
+        assert_eq!(Ok("a \"b\""), json.get_value("name").unwrap().get_string());
+        assert_eq!(Ok(3), json.get_value("count").unwrap().get_i64());
+        let items = json.get_array("items").unwrap();
+        assert_eq!(Ok(1.5), items.get_value(0).unwrap().get_f64());
+        assert!(items.get_value(2).unwrap().is_null());
+        assert_eq!(value, Struct::from(&json));
+        assert_eq!(
+            Json::<JsonValue>::new("null").unwrap(),
+            Json::<JsonValue>::try_from(Value { kind: None }).unwrap()
+        );
+    }
+
+    #[test]
+    fn to_proto_value() {
+        let json = Json::<Object>::from_file("src/__test_data__/test_data6.json").unwrap();
+        let value = Value::from(&json);
+        assert_eq!(json.data, Json::<Object>::try_from(value).unwrap().data);
+        assert_eq!(
+            Value::from(vec![Value::from(2.0), Value::from("tab\there")]),
+            Value::from(Json::<Array>::new("[2, \"tab\\there\"]").unwrap())
+        );
+        let list = Value::from(vec![Value::from(1.0)]);
+        assert!(matches!(
+            Json::<Object>::try_from(list.clone()),
+            Err(JsonError::IncorrectType { .. })
+        ));
+        assert!(matches!(AnyJson::from(list), AnyJson::Array(_)));
+    }
+}
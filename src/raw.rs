@@ -0,0 +1,76 @@
+use crate::parser::JsonData;
+
+// A number together with the text it was parsed from
+#[derive(Debug, Clone)]
+pub(crate) struct RawNumber {
+    pub(crate) data: JsonData,
+    pub(crate) text: Box<str>,
+}
+
+// With the `raw_numbers` feature a parsed number whose text is not how it would be written,
+// like `1.50`, `1e3` or `-0`, keeps its text and is written back exactly the same way until
+// it is replaced. Strings always keep their escapes, so with it a json that is not edited
+// is serialized to the same scalars it was parsed from.
+pub(crate) fn raw_number(text: &str, data: JsonData) -> JsonData {
+    if data.to_string() == text {
+        return data;
+    }
+    JsonData::RawNumber(Box::new(RawNumber {
+        data,
+        text: text.into(),
+    }))
+}
+
+// Raw numbers are equal to the number they were parsed as, whatever their text
+impl PartialEq for JsonData {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (JsonData::RawNumber(a), b) => a.data == *b,
+            (a, JsonData::RawNumber(b)) => *a == b.data,
+            (JsonData::Null, JsonData::Null) => true,
+            (JsonData::Bool(a), JsonData::Bool(b)) => a == b,
+            (JsonData::Str(a), JsonData::Str(b)) => a == b,
+            (JsonData::Float(a), JsonData::Float(b)) => a == b,
+            #[cfg(feature = "decimal")]
+            (JsonData::Decimal(a), JsonData::Decimal(b)) => a == b,
+            (JsonData::Integer(a), JsonData::Integer(b)) => a == b,
+            #[cfg(feature = "bigint")]
+            (JsonData::BigInt(a), JsonData::BigInt(b)) => a == b,
+            (JsonData::Array(a), JsonData::Array(b)) => a == b,
+            (JsonData::Object(a), JsonData::Object(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::format::FormatOptions;
+    use crate::json::{Array, Json, Object, Value};
+
+    #[test]
+    fn numbers_keep_their_text() {
+        let text = "[1.50,1e3,-0,0.1,12,1e400,\"A\"]";
+        let json = Json::<Array>::new(text).unwrap();
+        assert_eq!(text, json.to_string());
+        let value = |i| json.get_value(i).unwrap();
+        assert_eq!(Ok(1.5), value(0).get_f64());
+        assert_eq!(Ok(1000.0), value(1).get_f64());
+        assert_eq!(Ok(0), value(2).get_i64());
+        assert_eq!(
+            Json::<Array>::new("[1.5,1000.0,0,0.1,12,1e400,\"\\u0041\"]").unwrap(),
+            json
+        );
+        assert!(json
+            .to_formatted_string(&FormatOptions::pretty(2))
+            .contains("\n  1e3,"));
+    }
+
+    #[test]
+    fn edited_numbers_lose_their_text() {
+        let mut json = Json::<Object>::new("{\"a\": 2.50, \"b\": 1.0e0}").unwrap();
+        json.entry("a")
+            .and_modify(|a| a.set(Json::<Value>::from(2.5)));
+        assert_eq!("{\"a\":2.5,\"b\":1.0e0}", json.to_string());
+    }
+}
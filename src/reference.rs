@@ -0,0 +1,255 @@
+use crate::json::{AnyJson, Json, JsonError, JsonState};
+use crate::parser::{JsonData, Shared};
+use crate::pointer::{parse_pointer, resolve};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// A document refs are resolved in, `file` is `None` for the json that is being resolved
+#[derive(Clone)]
+struct Document {
+    file: Option<PathBuf>,
+    root: Shared<JsonData>,
+    base_dir: PathBuf,
+}
+
+#[derive(Default)]
+struct Resolver {
+    files: HashMap<PathBuf, Shared<JsonData>>,
+    // The refs being resolved, innermost last, to catch cycles
+    resolving: Vec<(Option<PathBuf>, String)>,
+}
+
+// The `$ref` of an object like `{"$ref": "#/definitions/user"}`
+fn reference(data: &JsonData) -> Option<String> {
+    match data {
+        JsonData::Object(map) => match map.get("$ref").map(|r| r.as_ref()) {
+            Some(JsonData::Str(reference)) => Some(reference.to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// Decodes the `%XX` escapes of a uri fragment
+fn percent_decode(text: &str) -> Result<String, JsonError> {
+    let invalid = || JsonError::InvalidPath(format!("Invalid percent escape in `{}`", text));
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        if b == b'%' {
+            let hex = tail.get(..2).ok_or_else(invalid)?;
+            let hex = std::str::from_utf8(hex).map_err(|_| invalid())?;
+            bytes.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(b);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| invalid())
+}
+
+impl Resolver {
+    fn load(&mut self, path: &Path) -> Result<Document, JsonError> {
+        let canonical = fs::canonicalize(path).map_err(|e| JsonError::FileError(e.kind()))?;
+        let root = match self.files.get(&canonical) {
+            Some(root) => Shared::clone(root),
+            None => {
+                let root = AnyJson::from_file(&canonical)?.into_shared();
+                self.files.insert(canonical.clone(), Shared::clone(&root));
+                root
+            }
+        };
+        Ok(Document {
+            base_dir: canonical
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default(),
+            file: Some(canonical),
+            root,
+        })
+    }
+
+    fn target(
+        &mut self,
+        reference: &str,
+        document: &Document,
+    ) -> Result<Shared<JsonData>, JsonError> {
+        let (file, fragment) = reference.split_once('#').unwrap_or((reference, ""));
+        if file.contains("://") {
+            return Err(JsonError::InvalidPath(format!(
+                "Only refs to local files are supported, not `{}`",
+                reference
+            )));
+        }
+        let document = if file.is_empty() {
+            document.clone()
+        } else {
+            self.load(&document.base_dir.join(file))?
+        };
+        let pointer = percent_decode(fragment)?;
+        let key = (document.file.clone(), pointer.clone());
+        if self.resolving.contains(&key) {
+            return Err(JsonError::InvalidExpansion(format!(
+                "`$ref` `{}` refers to itself",
+                reference
+            )));
+        }
+        let data = Shared::clone(resolve(&document.root, &parse_pointer(&pointer)?)?);
+        self.resolving.push(key);
+        let resolved = self.resolve(&data, &document);
+        self.resolving.pop();
+        resolved
+    }
+
+    // `data` with every ref replaced, the parts without refs are shared with it
+    fn resolve(
+        &mut self,
+        data: &Shared<JsonData>,
+        document: &Document,
+    ) -> Result<Shared<JsonData>, JsonError> {
+        if let Some(reference) = reference(data) {
+            return self.target(&reference, document);
+        }
+        match data.as_ref() {
+            JsonData::Object(map) => {
+                let mut resolved = map.clone();
+                let mut changed = false;
+                for value in resolved.values_mut() {
+                    let new = self.resolve(value, document)?;
+                    changed |= !Shared::ptr_eq(value, &new);
+                    *value = new;
+                }
+                Ok(if changed {
+                    Shared::new(JsonData::Object(resolved))
+                } else {
+                    Shared::clone(data)
+                })
+            }
+            JsonData::Array(arr) => {
+                let resolved = arr
+                    .iter()
+                    .map(|value| self.resolve(value, document))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let changed = arr
+                    .iter()
+                    .zip(&resolved)
+                    .any(|(a, b)| !Shared::ptr_eq(a, b));
+                Ok(if changed {
+                    Shared::new(JsonData::Array(resolved))
+                } else {
+                    Shared::clone(data)
+                })
+            }
+            _ => Ok(Shared::clone(data)),
+        }
+    }
+}
+
+impl<S: JsonState> Json<S> {
+    // Replaces every object with a `$ref` like `{"$ref": "#/definitions/user"}` or
+    // `{"$ref": "common.json#/user"}` with the json it refers to, the other members of the
+    // object are dropped. Fragments are json pointers and files are relative to `base_dir`.
+    // Nothing is changed when it fails.
+    pub fn resolve_refs<P: AsRef<Path>>(&mut self, base_dir: P) -> Result<(), JsonError> {
+        let document = Document {
+            file: None,
+            root: Shared::clone(&self.data),
+            base_dir: base_dir.as_ref().to_path_buf(),
+        };
+        self.data = Resolver::default().resolve(&self.data, &document)?;
+        Ok(())
+    }
+
+    // Reads `file` and resolves its refs, refs to other files are relative to `file`
+    pub fn from_file_resolved<P: AsRef<Path>>(file: P) -> Result<Self, JsonError> {
+        let mut resolver = Resolver::default();
+        let document = resolver.load(file.as_ref())?;
+        let data = resolver.resolve(&document.root, &document)?;
+        Self::checked(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::Object;
+
+    #[test]
+    fn local_refs() {
+        let mut json = Json::<Object>::new(
+            "{\"definitions\": {\"id\": {\"type\": \"integer\"}, \"user\": {\"id\": {\"$ref\": \"#/definitions/id\"}}, \"a/b\": 1}, \
+             \"user\": {\"$ref\": \"#/definitions/user\", \"description\": \"dropped\"}, \"slash\": {\"$ref\": \"#/definitions/a~1b\"}, \
+             \"encoded\": {\"$ref\": \"#/definitions/a%7E1b\"}}",
+        )
+        .unwrap();
+        json.resolve_refs(".").unwrap();
+        assert_eq!(
+            "{\"id\":{\"type\":\"integer\"}}",
+            json.get_object("user").unwrap().to_string()
+        );
+        assert_eq!(1, json.get_i64_or("slash", 0));
+        assert_eq!(1, json.get_i64_or("encoded", 0));
+    }
+
+    #[test]
+    fn ref_errors() {
+        let mut json =
+            Json::<Object>::new("{\"a\": {\"$ref\": \"#/b\"}, \"b\": [{\"$ref\": \"#/a\"}]}")
+                .unwrap();
+        assert!(matches!(
+            json.resolve_refs("."),
+            Err(JsonError::InvalidExpansion(_))
+        ));
+        assert!(json.get_object("a").is_ok());
+        let mut json = Json::<Object>::new("{\"a\": {\"$ref\": \"#/missing\"}}").unwrap();
+        assert_eq!(
+            Err(JsonError::KeyNotFound {
+                path: "/missing".to_string()
+            }),
+            json.resolve_refs(".")
+        );
+        let mut json = Json::<Object>::new("{\"a\": {\"$ref\": \"https://x.io/s.json\"}}").unwrap();
+        assert!(matches!(
+            json.resolve_refs("."),
+            Err(JsonError::InvalidPath(_))
+        ));
+    }
+
+    #[test]
+    fn refs_across_files() {
+        let dir = std::env::temp_dir().join(format!("json-refs-{}", std::process::id()));
+        fs::create_dir_all(dir.join("common")).unwrap();
+        fs::write(
+            dir.join("api.json"),
+            "{\"pet\": {\"$ref\": \"common/types.json#/Pet\"}, \"all\": {\"$ref\": \"common/types.json\"}}",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("common/types.json"),
+            "{\"Name\": {\"type\": \"string\"}, \"Pet\": {\"name\": {\"$ref\": \"#/Name\"}}}",
+        )
+        .unwrap();
+        let json = Json::<Object>::from_file_resolved(dir.join("api.json")).unwrap();
+        assert_eq!(
+            "{\"name\":{\"type\":\"string\"}}",
+            json.get_object("pet").unwrap().to_string()
+        );
+        assert_eq!(
+            "{\"type\":\"string\"}",
+            json.get_object("all")
+                .unwrap()
+                .get_object("Name")
+                .unwrap()
+                .to_string()
+        );
+        fs::write(
+            dir.join("self.json"),
+            "{\"a\": {\"$ref\": \"self.json#/a\"}}",
+        )
+        .unwrap();
+        assert!(Json::<Object>::from_file_resolved(dir.join("self.json")).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,71 @@
+use crate::format::syntax_error;
+use crate::hasher::KeyHasher;
+use crate::json::{Json, JsonError, JsonState};
+use crate::lexer::Token;
+use crate::stream::{build_data_into, DataBuilder, Events};
+
+// Parses many documents one after the other, like the lines of NDJSON, and keeps the
+// memory of its stacks of open arrays and objects between them instead of allocating it
+// anew for every document
+#[derive(Default)]
+pub struct Parser {
+    pub(crate) builder: DataBuilder,
+    pub(crate) stack: Vec<Token>,
+}
+
+impl Parser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // The keys of the objects are hashed with `hasher`, see `Json::new_with_hasher`
+    pub fn with_hasher(hasher: KeyHasher) -> Self {
+        Self {
+            builder: DataBuilder::with_hasher(hasher),
+            stack: Vec::new(),
+        }
+    }
+
+    // The same as `Json::new`
+    pub fn parse_into<S: JsonState, R: AsRef<str>>(
+        &mut self,
+        json: R,
+    ) -> Result<Json<S>, JsonError> {
+        let stack = std::mem::take(&mut self.stack);
+        let mut events = Events::reusing(json.as_ref().chars(), stack);
+        let data = build_data_into(&mut events, &mut self.builder);
+        self.stack = events.into_stack();
+        match data {
+            Ok(data) => Json::checked(data),
+            Err(error) => {
+                self.builder.clear();
+                Err(syntax_error(error))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{Array, Object, Value};
+
+    #[test]
+    fn parse_many_documents() {
+        let mut parser = Parser::new();
+        let lines = ["{\"a\": [1, {\"b\": 2}]}", "[[[]]]", "{\"a\": [", "3"];
+        let first: Json<Object> = parser.parse_into(lines[0]).unwrap();
+        assert_eq!(Json::<Object>::new(lines[0]).unwrap(), first);
+        assert!(parser.stack.capacity() >= 3);
+        let second: Json<Array> = parser.parse_into(lines[1]).unwrap();
+        assert_eq!("[[[]]]", second.to_string());
+        assert_eq!(
+            Some(JsonError::InvalidJsonSyntax(
+                "Unclosed array started at 1:7".to_string()
+            )),
+            parser.parse_into::<Value, _>(lines[2]).err()
+        );
+        let last: Json<Value> = parser.parse_into(lines[3]).unwrap();
+        assert_eq!(last, 3);
+    }
+}
@@ -0,0 +1,152 @@
+use crate::json::{AnyJson, Json, JsonError, JsonState};
+use crate::parser::{float_data, JsonData};
+
+impl From<::serde_json::Value> for AnyJson {
+    fn from(value: ::serde_json::Value) -> Self {
+        AnyJson::from_data(from_serde_value(value))
+    }
+}
+
+// An `IncorrectType` error when the value is not of the type of `S`
+impl<S: JsonState> TryFrom<::serde_json::Value> for Json<S> {
+    type Error = JsonError;
+
+    fn try_from(value: ::serde_json::Value) -> Result<Self, JsonError> {
+        Self::checked(from_serde_value(value))
+    }
+}
+
+impl<S: JsonState> From<&Json<S>> for ::serde_json::Value {
+    fn from(json: &Json<S>) -> Self {
+        to_serde_value(&json.data)
+    }
+}
+
+impl<S: JsonState> From<Json<S>> for ::serde_json::Value {
+    fn from(json: Json<S>) -> Self {
+        to_serde_value(&json.data)
+    }
+}
+
+fn from_serde_value(value: ::serde_json::Value) -> JsonData {
+    match value {
+        ::serde_json::Value::Null => JsonData::Null,
+        ::serde_json::Value::Bool(b) => JsonData::Bool(b),
+        ::serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => JsonData::Integer(i),
+            // Floats and u64 above i64::MAX take the path of lexed floats, so they become big
+            // integers, decimals or raw numbers with those features
+            None => {
+                let text = n.to_string();
+                let data = float_data(&text, n.as_f64().unwrap_or_default());
+                #[cfg(feature = "raw_numbers")]
+                let data = crate::raw::raw_number(&text, data);
+                data
+            }
+        },
+        ::serde_json::Value::String(s) => JsonData::Str(s.into()),
+        ::serde_json::Value::Array(v) => JsonData::array(v.into_iter().map(from_serde_value)),
+        ::serde_json::Value::Object(m) => JsonData::object(
+            m.into_iter()
+                .map(|(key, value)| (key, from_serde_value(value))),
+        ),
+    }
+}
+
+fn to_serde_value(data: &JsonData) -> ::serde_json::Value {
+    match data {
+        JsonData::Null => ::serde_json::Value::Null,
+        JsonData::Bool(b) => ::serde_json::Value::Bool(*b),
+        JsonData::Integer(i) => ::serde_json::Value::from(*i),
+        #[cfg(feature = "bigint")]
+        JsonData::BigInt(i) => match u64::try_from(i) {
+            Ok(n) => ::serde_json::Value::from(n),
+            Err(_) => to_serde_value(&JsonData::Float(
+                data.as_f64().expect("THIS SHOULD NEVER PANIC"),
+            )),
+        },
+        #[cfg(feature = "decimal")]
+        JsonData::Decimal(_) => to_serde_value(&JsonData::Float(
+            data.as_f64().expect("THIS SHOULD NEVER PANIC"),
+        )),
+        #[cfg(feature = "raw_numbers")]
+        JsonData::RawNumber(raw) => to_serde_value(&raw.data),
+        JsonData::Float(f) => ::serde_json::Number::from_f64(*f)
+            .map(::serde_json::Value::Number)
+            .unwrap_or(::serde_json::Value::Null),
+        JsonData::Str(s) => ::serde_json::Value::String(s.to_string()),
+        JsonData::Array(v) => {
+            ::serde_json::Value::Array(v.iter().map(|data| to_serde_value(data)).collect())
+        }
+        JsonData::Object(m) => ::serde_json::Value::Object(
+            m.iter()
+                .map(|(key, value)| (key.to_string(), to_serde_value(value)))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json::{AnyJson, Array, Json, JsonError, Object, Value};
+
+    #[test]
+    fn from_serde_json_value() {
+        let value = ::serde_json::json!({
+            "name": "a \"b\"",
+            "big": u64::MAX,
+            "items": [1, -2, 3.5, null, true, {}],
+        });
+        let json = Json::<Object>::try_from(value).unwrap();
+        assert_eq!(Ok("a \"b\""), json.get_value("name").unwrap().get_string());
+        assert_eq!(
+            Ok(u64::MAX as f64),
+            json.get_value("big").unwrap().get_f64_lenient()
+        );
+        let lexed = Json::<Value>::new(u64::MAX.to_string()).unwrap();
+        assert_eq!(lexed.data, json.get_value("big").unwrap().data);
+        assert_eq!(
+            lexed.to_string(),
+            json.get_value("big").unwrap().to_string()
+        );
+        let items = json.get_array("items").unwrap();
+        assert_eq!(Ok(-2), items.get_value(1).unwrap().get_i64());
+        assert_eq!(Ok(3.5), items.get_value(2).unwrap().get_f64());
+        assert!(items.get_value(3).unwrap().is_null());
+        assert!(items.get_object(5).is_ok());
+    }
+
+    #[test]
+    fn to_serde_json_value() {
+        let json = Json::<Object>::from_file("src/__test_data__/test_data6.json").unwrap();
+        let value = ::serde_json::Value::from(&json);
+        let expected: ::serde_json::Value = ::serde_json::from_str(
+            &std::fs::read_to_string("src/__test_data__/test_data6.json").unwrap(),
+        )
+        .unwrap();
+        assert_eq!(expected, value);
+        assert_eq!(json.data, Json::<Object>::try_from(value).unwrap().data);
+    }
+
+    #[test]
+    fn to_serde_json_scalar() {
+        assert_eq!(
+            ::serde_json::Value::from("tab\there"),
+            ::serde_json::Value::from(Json::<Value>::new("\"tab\\there\"").unwrap())
+        );
+    }
+
+    #[test]
+    fn from_serde_json_checks_the_type() {
+        let value = ::serde_json::json!([1, 2]);
+        assert!(matches!(
+            Json::<Object>::try_from(value.clone()),
+            Err(JsonError::IncorrectType { .. })
+        ));
+        assert_eq!(
+            "[1,2]",
+            Json::<Array>::try_from(value.clone()).unwrap().to_string()
+        );
+        assert!(matches!(AnyJson::from(value), AnyJson::Array(_)));
+    }
+}
@@ -0,0 +1,166 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+
+// The longest text a `SmallString` keeps inline, so it is no bigger than a `String`
+const INLINE: usize = 22;
+
+// A string that is kept inline without allocating when it is at most 22 bytes long, which
+// most keys of objects and many strings are. It hashes, compares and orders like the `str` it holds so maps
+// keyed by it can be looked up with a `&str`.
+#[derive(Clone)]
+pub(crate) enum SmallString {
+    Inline { len: u8, bytes: [u8; INLINE] },
+    Heap(Box<str>),
+}
+
+impl SmallString {
+    pub(crate) fn as_str(&self) -> &str {
+        match self {
+            SmallString::Inline { len, bytes } => {
+                std::str::from_utf8(&bytes[..*len as usize]).expect("THIS SHOULD NEVER PANIC")
+            }
+            SmallString::Heap(text) => text,
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn is_inline(&self) -> bool {
+        matches!(self, SmallString::Inline { .. })
+    }
+}
+
+impl From<&str> for SmallString {
+    fn from(text: &str) -> Self {
+        if text.len() <= INLINE {
+            let mut bytes = [0; INLINE];
+            bytes[..text.len()].copy_from_slice(text.as_bytes());
+            SmallString::Inline {
+                len: text.len() as u8,
+                bytes,
+            }
+        } else {
+            SmallString::Heap(text.into())
+        }
+    }
+}
+
+impl From<String> for SmallString {
+    fn from(text: String) -> Self {
+        if text.len() <= INLINE {
+            SmallString::from(text.as_str())
+        } else {
+            SmallString::Heap(text.into_boxed_str())
+        }
+    }
+}
+
+impl From<&String> for SmallString {
+    fn from(text: &String) -> Self {
+        SmallString::from(text.as_str())
+    }
+}
+
+impl From<SmallString> for String {
+    fn from(text: SmallString) -> Self {
+        match text {
+            SmallString::Heap(text) => text.into_string(),
+            inline => inline.as_str().to_string(),
+        }
+    }
+}
+
+impl Deref for SmallString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for SmallString {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Borrow<str> for SmallString {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl PartialEq for SmallString {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for SmallString {}
+
+impl PartialEq<str> for SmallString {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for SmallString {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialOrd for SmallString {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SmallString {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl Hash for SmallString {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl fmt::Debug for SmallString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for SmallString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn inline_up_to_22_bytes() {
+        assert_eq!(size_of::<String>(), size_of::<SmallString>());
+        let short = SmallString::from("name");
+        assert!(short.is_inline());
+        assert_eq!("name", short.as_str());
+        let exact = SmallString::from("é".repeat(11));
+        assert!(exact.is_inline());
+        let long = SmallString::from("a".repeat(23));
+        assert!(!long.is_inline());
+        assert_eq!("a".repeat(23), String::from(long));
+        let map = HashMap::from([(short, 1)]);
+        assert_eq!(Some(&1), map.get("name"));
+        let (a, b) = (SmallString::from("a"), SmallString::from("b".repeat(30)));
+        assert!(a < b);
+    }
+}
@@ -0,0 +1,50 @@
+use crate::json::JsonType;
+use crate::stream::{scalar_data, Event, Events};
+
+// What a document is at the top level, as far as its first token tells
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RootKind {
+    Object,
+    Array,
+    Scalar(JsonType),
+    // Nothing but whitespace
+    Empty,
+    // The first token is not the start of a json
+    Invalid,
+}
+
+// The kind of `json` from its first token only, to pick between `Json::<Object>::new`,
+// `Json::<Array>::new` or a streaming reader without reading the rest. The rest may still
+// be invalid, so `[1,` is an `Array`.
+pub fn sniff<R: AsRef<str>>(json: R) -> RootKind {
+    match Events::new(json.as_ref().chars()).next() {
+        Some(Ok(Event::StartObject(_))) => RootKind::Object,
+        Some(Ok(Event::StartArray(_))) => RootKind::Array,
+        Some(Ok(Event::Scalar(token))) => match scalar_data(token) {
+            Ok(data) => RootKind::Scalar(JsonType::of(&data)),
+            Err(_) => RootKind::Invalid,
+        },
+        Some(Ok(_)) | Some(Err(_)) => RootKind::Invalid,
+        None => RootKind::Empty,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kinds() {
+        assert_eq!(RootKind::Object, sniff(" \n{\"a\": "));
+        assert_eq!(RootKind::Array, sniff("[1, 2]"));
+        assert_eq!(RootKind::Scalar(JsonType::String), sniff("\"a\" "));
+        assert_eq!(RootKind::Scalar(JsonType::Integer), sniff("-12"));
+        assert_eq!(RootKind::Scalar(JsonType::Float), sniff("1e3"));
+        assert_eq!(RootKind::Scalar(JsonType::Null), sniff("null"));
+        assert_eq!(RootKind::Scalar(JsonType::Bool), sniff("false"));
+        assert_eq!(RootKind::Empty, sniff(" \t\n"));
+        for invalid in ["}", "]", ",", "tru", "'a'", "\"a"] {
+            assert_eq!(RootKind::Invalid, sniff(invalid), "{}", invalid);
+        }
+    }
+}
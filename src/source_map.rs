@@ -0,0 +1,162 @@
+use crate::escape::unescape_str;
+use crate::format::syntax_error;
+use crate::json::{Json, JsonError, JsonState};
+use crate::parser::ParseError;
+use crate::pointer::pointer_token;
+use crate::stream::{DataBuilder, Event, Events};
+use crate::tokens::Loc;
+use std::collections::HashMap;
+use std::ops::Range;
+
+// Where a value is in the text it was parsed from. `start` is the position of its first
+// char and `bytes` goes from there to the end of the value, closing bracket included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub start: Loc,
+    pub bytes: Range<usize>,
+}
+
+// The location of every value of a parsed json by its json pointer, the same pointer that
+// `Json::path` and `JsonError::path` give, so an error about a value can be shown where
+// the value is in the input
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceMap {
+    locations: HashMap<String, Location>,
+}
+
+impl SourceMap {
+    pub fn get(&self, pointer: &str) -> Option<&Location> {
+        self.locations.get(pointer)
+    }
+
+    pub fn len(&self) -> usize {
+        self.locations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.locations.is_empty()
+    }
+
+    // In no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Location)> {
+        self.locations
+            .iter()
+            .map(|(pointer, location)| (pointer.as_str(), location))
+    }
+}
+
+// An array or object that is being read
+struct Open {
+    pointer: String,
+    start: Loc,
+    // The index of the next item of an array, `None` for objects
+    next_index: Option<usize>,
+}
+
+impl<S: JsonState> Json<S> {
+    // Like `Json::new` but also returns where each value is in `json`
+    pub fn parse_with_source_map<R: AsRef<str>>(json: R) -> Result<(Self, SourceMap), JsonError> {
+        let mut builder = DataBuilder::default();
+        let mut map = SourceMap::default();
+        let mut open: Vec<Open> = Vec::new();
+        let mut key = None;
+        let mut data = None;
+        for event in Events::new(json.as_ref().chars()) {
+            let event = event.map_err(syntax_error)?;
+            let mut pointer = || match open.last_mut() {
+                None => String::new(),
+                Some(Open {
+                    pointer,
+                    next_index: Some(index),
+                    ..
+                }) => {
+                    *index += 1;
+                    format!("{}/{}", pointer, *index - 1)
+                }
+                Some(Open { pointer, .. }) => format!(
+                    "{}/{}",
+                    pointer,
+                    key.take().expect("THIS SHOULD NEVER PANIC")
+                ),
+            };
+            match &event {
+                Event::Key(token) => {
+                    key = Some(pointer_token(&unescape_str(
+                        &token.text[1..token.text.len() - 1],
+                    )))
+                }
+                Event::StartObject(token) | Event::StartArray(token) => {
+                    let next_index = matches!(event, Event::StartArray(_)).then_some(0);
+                    let pointer = pointer();
+                    open.push(Open {
+                        pointer,
+                        start: token.loc,
+                        next_index,
+                    });
+                }
+                Event::Scalar(token) => {
+                    let bytes = token.loc.byte..token.loc.byte + token.text.len();
+                    map.locations.insert(
+                        pointer(),
+                        Location {
+                            start: token.loc,
+                            bytes,
+                        },
+                    );
+                }
+                Event::EndObject(token) | Event::EndArray(token) => {
+                    let Open { pointer, start, .. } = open.pop().expect("THIS SHOULD NEVER PANIC");
+                    let bytes = start.byte..token.loc.byte + token.text.len();
+                    map.locations.insert(pointer, Location { start, bytes });
+                }
+            }
+            if let Some(value) = builder.push(event).map_err(syntax_error)? {
+                data = Some(value);
+            }
+        }
+        let data = data.ok_or_else(|| syntax_error(ParseError::EmptyInput))?;
+        Ok((Json::checked(data)?, map))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{Array, Object};
+
+    #[test]
+    fn locations_of_values() {
+        let text = "{\n  \"a\": [1, {\"b/c\": true}],\n\t\"d\": \"é\"\n}";
+        let (json, map) = Json::<Object>::parse_with_source_map(text).unwrap();
+        assert_eq!(Json::<Object>::new(text).unwrap(), json);
+        assert_eq!(6, map.len());
+        let at = |pointer| {
+            let location = map.get(pointer).unwrap();
+            (
+                location.start.row,
+                location.start.col,
+                &text[location.bytes.clone()],
+            )
+        };
+        assert_eq!((1, 1), (at("").0, at("").1));
+        assert_eq!(text, at("").2);
+        assert_eq!((2, 8, "[1, {\"b/c\": true}]"), at("/a"));
+        assert_eq!((2, 9, "1"), at("/a/0"));
+        assert_eq!((2, 12, "{\"b/c\": true}"), at("/a/1"));
+        assert_eq!((2, 20, "true"), at("/a/1/b~1c"));
+        assert_eq!((3, 10, "\"é\""), at("/d"));
+        assert_eq!(None, map.get("/a/2"));
+    }
+
+    #[test]
+    fn error_paths_are_in_the_map() {
+        let text = "[{\"id\": 1}, {\"id\": \"2\"}]";
+        let (json, map) = Json::<Array>::parse_with_source_map(text).unwrap();
+        let id = json.get_object(1).unwrap().get_value("id").unwrap();
+        let error = id.get_i64().unwrap_err();
+        let location = map.get(error.path().unwrap()).unwrap();
+        assert_eq!(20, location.start.col);
+        assert!(Json::<Array>::parse_with_source_map("{}").is_err());
+        assert!(Json::<Array>::parse_with_source_map("[1,]").is_err());
+    }
+}
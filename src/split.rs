@@ -0,0 +1,244 @@
+use crate::format::{event_error, io_error, syntax_error, EventWriter, FormatOptions};
+use crate::json::{JsonError, JsonType};
+use crate::parser::{JsonData, ParseError};
+use crate::stream::{scalar_data, Event, Events, ReadChars};
+use std::cell::Cell;
+use std::io;
+use std::io::Read;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Chunk {
+    Array,
+    Lines,
+}
+
+// Streams the top level array in `reader` and hands `sink` one minified array per
+// `chunk_size` elements, the last one may be shorter. Returns the number of chunks.
+pub fn split_array<R, F>(reader: R, chunk_size: usize, sink: F) -> Result<usize, JsonError>
+where
+    R: Read,
+    F: FnMut(&str) -> io::Result<()>,
+{
+    split(reader, chunk_size, Chunk::Array, sink)
+}
+
+// Like `split_array` but every chunk is NDJSON, one element per line
+pub fn split_array_to_ndjson<R, F>(
+    reader: R,
+    chunk_size: usize,
+    sink: F,
+) -> Result<usize, JsonError>
+where
+    R: Read,
+    F: FnMut(&str) -> io::Result<()>,
+{
+    split(reader, chunk_size, Chunk::Lines, sink)
+}
+
+fn split<R, F>(reader: R, chunk_size: usize, kind: Chunk, mut sink: F) -> Result<usize, JsonError>
+where
+    R: Read,
+    F: FnMut(&str) -> io::Result<()>,
+{
+    assert!(chunk_size != 0, "chunk_size must be non-zero");
+    let chars = ReadChars::new(reader);
+    let read_error = chars.error();
+    let mut events = Events::new(chars);
+    start_array(&mut events, &read_error)?;
+    let options = FormatOptions::minified();
+    let mut chunk = Vec::new();
+    let mut count = 0;
+    let mut chunks = 0;
+    let mut element: Option<EventWriter> = None;
+    while let Some(event) = events.next() {
+        let event = event.map_err(|error| event_error(error, read_error.get()))?;
+        if events.depth() == 0 {
+            // the `]` closing the top level array
+            break;
+        }
+        let writer = element.get_or_insert_with(|| {
+            if kind == Chunk::Array {
+                chunk.push(if count == 0 { b'[' } else { b',' });
+            }
+            EventWriter::new(&options)
+        });
+        writer.write(&mut chunk, &event).map_err(io_error)?;
+        if events.depth() == 1 {
+            element = None;
+            count += 1;
+            if kind == Chunk::Lines {
+                chunk.push(b'\n');
+            }
+            if count == chunk_size {
+                emit(&mut chunk, kind, &mut sink)?;
+                count = 0;
+                chunks += 1;
+            }
+        }
+    }
+    // makes sure nothing but whitespace follows the array
+    for event in events {
+        event.map_err(|error| event_error(error, read_error.get()))?;
+    }
+    if let Some(kind) = read_error.get() {
+        return Err(JsonError::FileError(kind));
+    }
+    if count > 0 {
+        emit(&mut chunk, kind, &mut sink)?;
+        chunks += 1;
+    }
+    Ok(chunks)
+}
+
+// Reads the `[` the input has to start with
+fn start_array<Chars: Iterator<Item = char>>(
+    events: &mut Events<Chars>,
+    read_error: &Cell<Option<io::ErrorKind>>,
+) -> Result<(), JsonError> {
+    match events.next() {
+        Some(Ok(Event::StartArray(_))) => Ok(()),
+        Some(Ok(Event::Scalar(token))) => {
+            let found = scalar_data(token).map_err(syntax_error)?;
+            Err(JsonError::incorrect_type("", &[JsonType::Array], &found))
+        }
+        Some(Ok(_)) => {
+            let found = JsonData::Object(Box::default());
+            Err(JsonError::incorrect_type("", &[JsonType::Array], &found))
+        }
+        Some(Err(error)) => Err(event_error(error, read_error.get())),
+        None => Err(event_error(ParseError::UnexpectedEof, read_error.get())),
+    }
+}
+
+// The number of elements of the top level array in `reader`, which is validated but never
+// built, e.g. to size a buffer before reading it
+pub fn count_array_elements<R: Read>(reader: R) -> Result<usize, JsonError> {
+    let chars = ReadChars::new(reader);
+    let read_error = chars.error();
+    let mut events = Events::new(chars);
+    start_array(&mut events, &read_error)?;
+    let mut count = 0;
+    let result = (|| {
+        while let Some(event) = events.next() {
+            let event = event?;
+            if events.depth() == 0 {
+                // the `]` closing the top level array, what follows must be whitespace
+                return events.next().map_or(Ok(()), |event| event.map(|_| ()));
+            }
+            events.skip_value(&event)?;
+            count += 1;
+        }
+        Ok(())
+    })();
+    result.map_err(|error| event_error(error, read_error.get()))?;
+    if let Some(kind) = read_error.get() {
+        return Err(JsonError::FileError(kind));
+    }
+    Ok(count)
+}
+
+fn emit<F>(chunk: &mut Vec<u8>, kind: Chunk, sink: &mut F) -> Result<(), JsonError>
+where
+    F: FnMut(&str) -> io::Result<()>,
+{
+    if kind == Chunk::Array {
+        chunk.push(b']');
+    }
+    let text = std::str::from_utf8(chunk).expect("THIS SHOULD NEVER PANIC");
+    sink(text).map_err(io_error)?;
+    chunk.clear();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::JsonType;
+
+    fn collect(json: &str, chunk_size: usize, ndjson: bool) -> Result<Vec<String>, JsonError> {
+        let mut chunks = Vec::new();
+        let sink = |chunk: &str| {
+            chunks.push(chunk.to_string());
+            Ok(())
+        };
+        let count = if ndjson {
+            split_array_to_ndjson(json.as_bytes(), chunk_size, sink)?
+        } else {
+            split_array(json.as_bytes(), chunk_size, sink)?
+        };
+        assert_eq!(count, chunks.len());
+        Ok(chunks)
+    }
+
+    #[test]
+    fn split_into_arrays() {
+        let json = "[1, {\"a\": [2, 3]}, \"x\", [], null]";
+        assert_eq!(
+            Ok(vec![
+                String::from("[1,{\"a\":[2,3]}]"),
+                String::from("[\"x\",[]]"),
+                String::from("[null]"),
+            ]),
+            collect(json, 2, false)
+        );
+        assert_eq!(
+            Ok(vec![String::from("[1,{\"a\":[2,3]},\"x\",[],null]")]),
+            collect(json, 10, false)
+        );
+        assert_eq!(Ok(Vec::new()), collect(" [ ] ", 3, false));
+    }
+
+    #[test]
+    fn split_into_ndjson() {
+        assert_eq!(
+            Ok(vec![
+                String::from("{\"id\":1}\n{\"id\":2}\n"),
+                String::from("{\"id\":3}\n"),
+            ]),
+            collect("[{\"id\": 1}, {\"id\": 2},\n{\"id\": 3}]", 2, true)
+        );
+    }
+
+    #[test]
+    fn split_errors() {
+        assert_eq!(
+            Err(JsonError::IncorrectType {
+                path: "".to_string(),
+                expected: vec![JsonType::Array],
+                found: JsonType::Object,
+            }),
+            collect("{\"a\": 1}", 2, false)
+        );
+        assert!(matches!(
+            collect("[1, 2, 3", 2, false),
+            Err(JsonError::InvalidJsonSyntax(_))
+        ));
+        assert!(matches!(
+            collect("[1] 2", 2, false),
+            Err(JsonError::InvalidJsonSyntax(_))
+        ));
+        assert!(matches!(
+            collect("", 2, false),
+            Err(JsonError::InvalidJsonSyntax(_))
+        ));
+        let failing = split_array("[1, 2]".as_bytes(), 1, |_| {
+            Err(io::Error::from(io::ErrorKind::WriteZero))
+        });
+        assert_eq!(Err(JsonError::FileError(io::ErrorKind::WriteZero)), failing);
+    }
+
+    #[test]
+    fn count_elements() {
+        let count = |json: &str| count_array_elements(json.as_bytes());
+        assert_eq!(Ok(0), count(" [ ] "));
+        assert_eq!(Ok(5), count("[1, {\"a\": [2, 3]}, \"x\", [], null]"));
+        assert_eq!(Ok(2), count("[[[[]]], {\"b\": {}}]\n"));
+        assert!(matches!(count("{}"), Err(JsonError::IncorrectType { .. })));
+        for invalid in ["[1, 2", "[1] 2", "[{\"a\" 1}]", "", "[1,]"] {
+            assert!(matches!(
+                count(invalid),
+                Err(JsonError::InvalidJsonSyntax(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,571 @@
+use crate::escape::unquote;
+use crate::hasher::KeyHasher;
+use crate::json::MAX_DEPTH;
+use crate::lenient::Diagnostic;
+use crate::lexer::{Lexer, Token, TokenKind};
+use crate::parser::{float_data, map_with_hasher, JsonData, ParseError, Shared, VALUE};
+use crate::small::SmallString;
+use std::cell::Cell;
+use std::io;
+use std::io::{BufReader, Bytes, Read};
+use std::rc::Rc;
+
+// Decodes utf-8 from a reader one char at a time so the lexer can run over inputs that
+// don't fit in memory. Reading stops at the first io or utf-8 error, which is kept in
+// `error` so the caller can tell it apart from a premature end of the json.
+pub(crate) struct ReadChars<R: Read> {
+    bytes: Bytes<BufReader<R>>,
+    error: Rc<Cell<Option<io::ErrorKind>>>,
+}
+
+impl<R: Read> ReadChars<R> {
+    pub(crate) fn new(reader: R) -> Self {
+        Self {
+            bytes: BufReader::new(reader).bytes(),
+            error: Rc::new(Cell::new(None)),
+        }
+    }
+
+    pub(crate) fn error(&self) -> Rc<Cell<Option<io::ErrorKind>>> {
+        Rc::clone(&self.error)
+    }
+
+    fn next_byte(&mut self) -> Option<u8> {
+        match self.bytes.next()? {
+            Ok(b) => Some(b),
+            Err(e) => {
+                self.error.set(Some(e.kind()));
+                None
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for ReadChars<R> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.error.get().is_some() {
+            return None;
+        }
+        let first = self.next_byte()?;
+        let len = match first {
+            0x00..=0x7f => return Some(first as char),
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _ => 0,
+        };
+        let mut buf = [first, 0, 0, 0];
+        for b in buf.iter_mut().take(len).skip(1) {
+            match self.next_byte() {
+                Some(next) => *b = next,
+                None => break,
+            }
+        }
+        match std::str::from_utf8(&buf[..len])
+            .ok()
+            .and_then(|s| s.chars().next())
+        {
+            Some(c) => Some(c),
+            None => {
+                if self.error.get().is_none() {
+                    self.error.set(Some(io::ErrorKind::InvalidData));
+                }
+                None
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+pub(crate) enum Event {
+    StartObject(Token),
+    EndObject(Token),
+    StartArray(Token),
+    EndArray(Token),
+    Key(Token),
+    Scalar(Token),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Expect {
+    Root,
+    Value,
+    FirstValueOrEnd,
+    FirstKeyOrEnd,
+    Key,
+    Colon,
+    CommaOrEnd,
+    Eof,
+    Done,
+}
+
+// Pull parser that validates the token stream and emits one event per structural token,
+// without building any `JsonData`. An empty input yields no events.
+pub(crate) struct Events<Chars: Iterator<Item = char>> {
+    lexer: Lexer<Chars>,
+    // The tokens that opened the arrays and objects the parser is in
+    stack: Vec<Token>,
+    expect: Expect,
+    lenient: bool,
+    relaxed: bool,
+    // A token that still has to be handled after a missing token was made up for it
+    pending: Option<Token>,
+    warnings: Vec<Diagnostic>,
+}
+
+fn starts_value(kind: TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::OpenCurly
+            | TokenKind::OpenBracket
+            | TokenKind::Str
+            | TokenKind::Integer
+            | TokenKind::Float
+            | TokenKind::Null
+            | TokenKind::True
+            | TokenKind::False
+    )
+}
+
+impl<Chars: Iterator<Item = char>> Events<Chars> {
+    pub(crate) fn new(chars: Chars) -> Self {
+        Self {
+            lexer: Lexer::new(chars),
+            stack: Vec::new(),
+            expect: Expect::Root,
+            lenient: false,
+            relaxed: false,
+            pending: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    // Goes on as if a missing `,` between members or `:` after a key was there and keeps a
+    // warning for each of them
+    pub(crate) fn lenient(chars: Chars) -> Self {
+        Self {
+            lenient: true,
+            ..Self::new(chars)
+        }
+    }
+
+    // Lexes with `Lexer::relaxed` and also takes bare identifiers as object keys
+    pub(crate) fn relaxed(chars: Chars) -> Self {
+        Self {
+            lexer: Lexer::relaxed(chars),
+            stack: Vec::new(),
+            expect: Expect::Root,
+            lenient: false,
+            relaxed: true,
+            pending: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    // Keeps the tokens of the open arrays and objects in `stack`, so its memory is reused
+    pub(crate) fn reusing(chars: Chars, mut stack: Vec<Token>) -> Self {
+        stack.clear();
+        Self {
+            stack,
+            ..Self::new(chars)
+        }
+    }
+
+    // Locates the tokens with tabs `tab_width` columns wide instead of `DEFAULT_TAB_WIDTH`
+    pub(crate) fn with_tab_width(self, tab_width: usize) -> Self {
+        Self {
+            lexer: self.lexer.with_tab_width(tab_width),
+            ..self
+        }
+    }
+
+    pub(crate) fn into_stack(self) -> Vec<Token> {
+        self.stack
+    }
+
+    pub(crate) fn take_warnings(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    fn missing(&mut self, missing: &str, token: Token, expect: Expect) {
+        self.warnings.push(Diagnostic::new(
+            format!("Missing `{}` before `{}`", missing, token.text),
+            &token.loc,
+        ));
+        self.expect = expect;
+        self.pending = Some(token);
+    }
+
+    pub(crate) fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    // Reads past the value that `first` is the first event of, without building it
+    pub(crate) fn skip_value(&mut self, first: &Event) -> Result<(), ParseError> {
+        if !matches!(first, Event::StartObject(_) | Event::StartArray(_)) {
+            return Ok(());
+        }
+        let depth = self.depth() - 1;
+        while self.depth() > depth {
+            self.next().ok_or(ParseError::UnexpectedEof)??;
+        }
+        Ok(())
+    }
+
+    // The kinds of tokens that can come next
+    fn expected(&self) -> Vec<TokenKind> {
+        let key = if self.relaxed {
+            vec![TokenKind::Str, TokenKind::Ident]
+        } else {
+            vec![TokenKind::Str]
+        };
+        match self.expect {
+            Expect::Root => [&VALUE[..], &[TokenKind::Eof]].concat(),
+            Expect::Value => VALUE.to_vec(),
+            Expect::FirstValueOrEnd => [&VALUE[..], &[TokenKind::CloseBracket]].concat(),
+            Expect::FirstKeyOrEnd => [key, vec![TokenKind::CloseCurly]].concat(),
+            Expect::Key => key,
+            Expect::Colon => vec![TokenKind::Colon],
+            Expect::CommaOrEnd => match self.stack.last().map(|open| open.kind) {
+                Some(TokenKind::OpenBracket) => vec![TokenKind::Comma, TokenKind::CloseBracket],
+                _ => vec![TokenKind::Comma, TokenKind::CloseCurly],
+            },
+            Expect::Eof => vec![TokenKind::Eof],
+            Expect::Done => Vec::new(),
+        }
+    }
+
+    fn unexpected(&self, token: Token) -> ParseError {
+        ParseError::SyntaxError {
+            found: token,
+            expected: self.expected(),
+        }
+    }
+
+    fn after_value(&mut self) {
+        self.expect = if self.stack.is_empty() {
+            Expect::Eof
+        } else {
+            Expect::CommaOrEnd
+        };
+    }
+
+    fn next_event(&mut self) -> Result<Option<Event>, ParseError> {
+        loop {
+            if self.expect == Expect::Done {
+                return Ok(None);
+            }
+            let token = match self.pending.take().or_else(|| self.lexer.next()) {
+                Some(token) => token,
+                None => return Err(ParseError::UnexpectedEof),
+            };
+            let top = self.stack.last().map(|open| open.kind);
+            match (self.expect, token.kind) {
+                (_, TokenKind::Invalid) => return Err(self.unexpected(token)),
+                (Expect::Root | Expect::Eof, TokenKind::Eof) => {
+                    self.expect = Expect::Done;
+                    return Ok(None);
+                }
+                (_, TokenKind::Eof) => {
+                    return Err(match self.stack.pop() {
+                        Some(open) => ParseError::Unclosed(open),
+                        None => ParseError::UnexpectedEof,
+                    })
+                }
+                (Expect::Root | Expect::Value | Expect::FirstValueOrEnd, TokenKind::OpenCurly) => {
+                    self.stack.push(token.clone());
+                    self.expect = Expect::FirstKeyOrEnd;
+                    return Ok(Some(Event::StartObject(token)));
+                }
+                (
+                    Expect::Root | Expect::Value | Expect::FirstValueOrEnd,
+                    TokenKind::OpenBracket,
+                ) => {
+                    self.stack.push(token.clone());
+                    self.expect = Expect::FirstValueOrEnd;
+                    return Ok(Some(Event::StartArray(token)));
+                }
+                (
+                    Expect::Root | Expect::Value | Expect::FirstValueOrEnd,
+                    TokenKind::Str
+                    | TokenKind::Integer
+                    | TokenKind::Float
+                    | TokenKind::Null
+                    | TokenKind::True
+                    | TokenKind::False,
+                ) => {
+                    self.after_value();
+                    return Ok(Some(Event::Scalar(token)));
+                }
+                (Expect::FirstKeyOrEnd | Expect::Key, TokenKind::Str) => {
+                    self.expect = Expect::Colon;
+                    return Ok(Some(Event::Key(token)));
+                }
+                (
+                    Expect::FirstKeyOrEnd | Expect::Key,
+                    TokenKind::Ident | TokenKind::Null | TokenKind::True | TokenKind::False,
+                ) if self.relaxed => {
+                    self.expect = Expect::Colon;
+                    return Ok(Some(Event::Key(Token {
+                        kind: TokenKind::Str,
+                        text: format!("\"{}\"", token.text),
+                        loc: token.loc,
+                    })));
+                }
+                (Expect::Colon, TokenKind::Colon) => self.expect = Expect::Value,
+                (Expect::CommaOrEnd, TokenKind::Comma) => {
+                    self.expect = if top == Some(TokenKind::OpenBracket) {
+                        Expect::Value
+                    } else {
+                        Expect::Key
+                    };
+                }
+                (Expect::FirstValueOrEnd | Expect::CommaOrEnd, TokenKind::CloseBracket)
+                    if top == Some(TokenKind::OpenBracket) =>
+                {
+                    self.stack.pop();
+                    self.after_value();
+                    return Ok(Some(Event::EndArray(token)));
+                }
+                (Expect::FirstKeyOrEnd | Expect::CommaOrEnd, TokenKind::CloseCurly)
+                    if top == Some(TokenKind::OpenCurly) =>
+                {
+                    self.stack.pop();
+                    self.after_value();
+                    return Ok(Some(Event::EndObject(token)));
+                }
+                (Expect::CommaOrEnd, kind) if self.lenient && starts_value(kind) => {
+                    if top == Some(TokenKind::OpenBracket) {
+                        self.missing(",", token, Expect::Value);
+                    } else if kind == TokenKind::Str {
+                        self.missing(",", token, Expect::Key);
+                    } else {
+                        return Err(self.unexpected(token));
+                    }
+                }
+                (Expect::Colon, kind) if self.lenient && starts_value(kind) => {
+                    self.missing(":", token, Expect::Value);
+                }
+                _ => return Err(self.unexpected(token)),
+            }
+        }
+    }
+}
+
+// Builds the whole document out of `events`, an empty input is `ParseError::EmptyInput`
+pub(crate) fn build_data<Chars: Iterator<Item = char>>(
+    events: &mut Events<Chars>,
+) -> Result<JsonData, ParseError> {
+    build_data_into(events, &mut DataBuilder::default())
+}
+
+pub(crate) fn build_data_into<Chars: Iterator<Item = char>>(
+    events: &mut Events<Chars>,
+    builder: &mut DataBuilder,
+) -> Result<JsonData, ParseError> {
+    let mut data = None;
+    for event in events {
+        if let Some(value) = builder.push(event?)? {
+            data = Some(value);
+        }
+    }
+    data.ok_or(ParseError::EmptyInput)
+}
+
+impl<Chars: Iterator<Item = char>> Iterator for Events<Chars> {
+    type Item = Result<Event, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_event() {
+            Ok(event) => event.map(Ok),
+            Err(error) => {
+                self.expect = Expect::Done;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+// Strings are unescaped, see `unquote`, and inline when they are short, see `SmallString`
+pub(crate) fn scalar_data(token: Token) -> Result<JsonData, ParseError> {
+    if token.kind == TokenKind::Str {
+        return Ok(JsonData::Str(unquote(token.text).into()));
+    }
+    let error = || ParseError::malformed(token.to_owned());
+    let data = match token.kind {
+        TokenKind::Integer => JsonData::Integer(token.text.parse().map_err(|_| error())?),
+        TokenKind::Float => float_data(&token.text, token.text.parse().map_err(|_| error())?),
+        TokenKind::Null => JsonData::Null,
+        TokenKind::True => JsonData::Bool(true),
+        TokenKind::False => JsonData::Bool(false),
+        _ => return Err(error()),
+    };
+    #[cfg(feature = "raw_numbers")]
+    if matches!(token.kind, TokenKind::Integer | TokenKind::Float) {
+        return Ok(crate::raw::raw_number(&token.text, data));
+    }
+    Ok(data)
+}
+
+// Builds the `JsonData` of a single value out of its events
+#[derive(Default)]
+pub(crate) struct DataBuilder {
+    stack: Vec<(JsonData, Option<SmallString>)>,
+    hasher: KeyHasher,
+}
+
+impl DataBuilder {
+    pub(crate) fn with_hasher(hasher: KeyHasher) -> Self {
+        Self {
+            stack: Vec::new(),
+            hasher,
+        }
+    }
+
+    // Drops what was left of a value that failed to build, the memory of the stack is kept
+    pub(crate) fn clear(&mut self) {
+        self.stack.clear();
+    }
+
+    // Returns the value once its last event was pushed. Arrays and objects nested deeper
+    // than `MAX_DEPTH` are an error, so a parsed value can be dropped, compared and written
+    // without overflowing the stack.
+    pub(crate) fn push(&mut self, event: Event) -> Result<Option<JsonData>, ParseError> {
+        let data = match event {
+            Event::StartObject(token) | Event::StartArray(token)
+                if self.stack.len() >= MAX_DEPTH =>
+            {
+                return Err(ParseError::TooDeep(token));
+            }
+            Event::StartObject(_) => {
+                self.stack.push((
+                    JsonData::Object(Box::new(map_with_hasher(&self.hasher))),
+                    None,
+                ));
+                return Ok(None);
+            }
+            Event::StartArray(_) => {
+                self.stack.push((JsonData::Array(Vec::new()), None));
+                return Ok(None);
+            }
+            Event::Key(token) => {
+                self.push_key(unquote(token.text).into());
+                return Ok(None);
+            }
+            Event::Scalar(token) => scalar_data(token)?,
+            Event::EndObject(_) | Event::EndArray(_) => {
+                self.stack.pop().expect("THIS SHOULD NEVER PANIC").0
+            }
+        };
+        Ok(self.push_value(data))
+    }
+
+    // The key of the next value of the object being built
+    pub(crate) fn push_key(&mut self, key: SmallString) {
+        if let Some((_, next)) = self.stack.last_mut() {
+            *next = Some(key);
+        }
+    }
+
+    // Puts a whole value where the next scalar would go, returns it when it is the root
+    pub(crate) fn push_value(&mut self, data: JsonData) -> Option<JsonData> {
+        match self.stack.last_mut() {
+            None => Some(data),
+            Some((JsonData::Array(arr), _)) => {
+                arr.push(Shared::new(data));
+                None
+            }
+            Some((JsonData::Object(map), key)) => {
+                map.insert(
+                    key.take().expect("THIS SHOULD NEVER PANIC"),
+                    Shared::new(data),
+                );
+                None
+            }
+            Some(_) => unreachable!(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(json: &str) -> Result<Vec<&'static str>, ParseError> {
+        Events::new(json.chars())
+            .map(|event| {
+                event.map(|event| match event {
+                    Event::StartObject(_) => "{",
+                    Event::EndObject(_) => "}",
+                    Event::StartArray(_) => "[",
+                    Event::EndArray(_) => "]",
+                    Event::Key(_) => "key",
+                    Event::Scalar(_) => "scalar",
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn events_nested() {
+        assert_eq!(
+            Ok(vec![
+                "{", "key", "[", "scalar", "{", "}", "[", "]", "]", "key", "scalar", "}"
+            ]),
+            kinds("{\"a\": [1, {}, []], \"b\": null}")
+        );
+        assert_eq!(Ok(vec!["scalar"]), kinds(" \"s\" "));
+        assert_eq!(Ok(vec![]), kinds("  "));
+    }
+
+    #[test]
+    fn events_invalid() {
+        for json in [
+            "[1,,2]",
+            "[,1]",
+            "[1,]",
+            "{\"a\":}",
+            "{\"a\" 1}",
+            "{,}",
+            "{\"a\":1,}",
+            "[}",
+            "{]",
+            "1 2",
+            "[1",
+            "{\"a\"",
+            "{1:2}",
+            "]",
+        ] {
+            assert!(kinds(json).is_err(), "{json}");
+        }
+    }
+
+    #[test]
+    fn data_builder() {
+        let json = "{\"a\": [1, 2.5, \"s\", {}], \"b\": {\"c\": null}, \"d\": true}";
+        let mut builder = DataBuilder::default();
+        let mut built = None;
+        for event in Events::new(json.chars()) {
+            built = builder.push(event.unwrap()).unwrap();
+        }
+        assert_eq!(crate::parser::parse_json(json).ok(), built);
+    }
+
+    #[test]
+    fn read_chars_utf8() {
+        let s = "a å € 😀";
+        assert_eq!(s, ReadChars::new(s.as_bytes()).collect::<String>());
+    }
+
+    #[test]
+    fn read_chars_invalid_utf8() {
+        let mut chars = ReadChars::new(&[b'a', 0xff, b'b'][..]);
+        let error = chars.error();
+        assert_eq!(Some('a'), chars.next());
+        assert_eq!(None, chars.next());
+        assert_eq!(Some(io::ErrorKind::InvalidData), error.get());
+    }
+}
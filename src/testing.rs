@@ -0,0 +1,66 @@
+use crate::json::{Json, JsonState};
+use std::fmt::Debug;
+
+// Helpers for property tests of code that writes or reads json, with the strategies this
+// crate tests itself with
+pub use crate::arbitrary::{arb_any_json, arb_array, arb_json, arb_object};
+
+// Panics unless `json` is read back the same from its compact and its pretty text
+pub fn assert_roundtrip<S: JsonState>(json: &Json<S>) {
+    assert_roundtrip_with(json, |json| json.to_string(), |text| Json::new(text));
+    assert_roundtrip_with(json, |json| format!("{:#}", json), |text| Json::new(text));
+}
+
+// Panics unless `parse(serialize(json))` is the same json, for serializers of other crates
+pub fn assert_roundtrip_with<S, T, E, W, P>(json: &Json<S>, serialize: W, parse: P)
+where
+    S: JsonState,
+    T: Debug,
+    E: Debug,
+    W: FnOnce(&Json<S>) -> T,
+    P: FnOnce(&T) -> Result<Json<S>, E>,
+{
+    let serialized = serialize(json);
+    match parse(&serialized) {
+        Ok(parsed) if parsed == *json => {}
+        Ok(parsed) => panic!("{} was read back as {} from {:?}", json, parsed, serialized),
+        Err(error) => panic!(
+            "{} could not be read back from {:?}: {:?}",
+            json, serialized, error
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{Array, Object};
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn generated_documents_round_trip(
+            object in arb_object(),
+            array in arb_array(),
+            value in arb_json(),
+        ) {
+            assert_roundtrip(&object);
+            assert_roundtrip(&array);
+            assert_roundtrip(&value);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "was read back as")]
+    fn lossy_serializer_panics() {
+        let json = Json::<Array>::new("[1, 2]").unwrap();
+        assert_roundtrip_with(&json, |_| "[1]", |text| Json::new(text));
+    }
+
+    #[test]
+    #[should_panic(expected = "could not be read back")]
+    fn invalid_output_panics() {
+        let json = Json::<Object>::new("{}").unwrap();
+        assert_roundtrip_with(&json, |_| "{", |text| Json::new(text));
+    }
+}
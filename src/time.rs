@@ -0,0 +1,123 @@
+use crate::json::{Json, JsonError, JsonType, Value};
+use crate::parser::JsonData;
+use ::time::format_description::well_known::Rfc3339;
+use ::time::OffsetDateTime;
+
+// What an epoch number counts since 1970-01-01T00:00:00Z
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EpochUnit {
+    Seconds,
+    Milliseconds,
+    Microseconds,
+    Nanoseconds,
+}
+
+impl EpochUnit {
+    fn nanos(&self) -> i128 {
+        match self {
+            EpochUnit::Seconds => 1_000_000_000,
+            EpochUnit::Milliseconds => 1_000_000,
+            EpochUnit::Microseconds => 1_000,
+            EpochUnit::Nanoseconds => 1,
+        }
+    }
+}
+
+fn datetime_error<E: std::fmt::Display>(error: E) -> JsonError {
+    JsonError::InvalidDateTime(error.to_string())
+}
+
+impl Json<Value> {
+    // Parses a RFC 3339 string like `"2024-05-01T12:30:00+02:00"`
+    pub fn get_datetime(&self) -> Result<OffsetDateTime, JsonError> {
+        match self.data.as_ref() {
+            JsonData::Str(s) => OffsetDateTime::parse(s, &Rfc3339).map_err(datetime_error),
+            _ => Err(self.incorrect_type(&[JsonType::String])),
+        }
+    }
+
+    // Reads an integer or float as a timestamp counted in `unit`, in UTC
+    pub fn get_epoch_datetime(&self, unit: EpochUnit) -> Result<OffsetDateTime, JsonError> {
+        let nanos = match self.data.as_ref().unraw() {
+            JsonData::Integer(i) => *i as i128 * unit.nanos(),
+            // whole units and the fraction separately so the float error stays below a unit
+            JsonData::Float(f) if f.is_finite() => (f.trunc() as i128)
+                .checked_mul(unit.nanos())
+                .map(|nanos| nanos + (f.fract() * unit.nanos() as f64).round() as i128)
+                .ok_or_else(|| datetime_error(format!("{} is out of range", f)))?,
+            _ => return Err(self.incorrect_type(&[JsonType::Integer, JsonType::Float])),
+        };
+        OffsetDateTime::from_unix_timestamp_nanos(nanos).map_err(datetime_error)
+    }
+}
+
+// Written as a RFC 3339 string
+impl TryFrom<OffsetDateTime> for Json<Value> {
+    type Error = JsonError;
+
+    fn try_from(datetime: OffsetDateTime) -> Result<Self, Self::Error> {
+        let s = datetime.format(&Rfc3339).map_err(datetime_error)?;
+        Ok(Json::from_data(JsonData::Str(s.into())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::Array;
+    use crate::json::JsonType;
+
+    #[test]
+    fn datetime_accessors() {
+        let json: Json<Array> = Json::new(
+            "[\"2024-05-01T12:30:00+02:00\", \"2024-05-01\", 1714559400, 1714559400500, 1714559400.25]",
+        )
+        .unwrap();
+        let expected = OffsetDateTime::from_unix_timestamp(1714559400).unwrap();
+        assert_eq!(Ok(expected), json.get_value(0).unwrap().get_datetime());
+        assert!(matches!(
+            json.get_value(1).unwrap().get_datetime(),
+            Err(JsonError::InvalidDateTime(_))
+        ));
+        assert_eq!(
+            Err(JsonError::IncorrectType {
+                path: "/2".to_string(),
+                expected: vec![JsonType::String],
+                found: JsonType::Integer,
+            }),
+            json.get_value(2).unwrap().get_datetime()
+        );
+        assert_eq!(
+            Ok(expected),
+            json.get_value(2)
+                .unwrap()
+                .get_epoch_datetime(EpochUnit::Seconds)
+        );
+        assert_eq!(
+            Ok(expected + ::time::Duration::milliseconds(500)),
+            json.get_value(3)
+                .unwrap()
+                .get_epoch_datetime(EpochUnit::Milliseconds)
+        );
+        assert_eq!(
+            Ok(expected + ::time::Duration::milliseconds(250)),
+            json.get_value(4)
+                .unwrap()
+                .get_epoch_datetime(EpochUnit::Seconds)
+        );
+        assert!(matches!(
+            json.get_value(3)
+                .unwrap()
+                .get_epoch_datetime(EpochUnit::Seconds),
+            Err(JsonError::InvalidDateTime(_))
+        ));
+    }
+
+    #[test]
+    fn datetime_roundtrip() {
+        let datetime = OffsetDateTime::from_unix_timestamp(1714559400).unwrap();
+        let json = Json::<Value>::try_from(datetime).unwrap();
+        assert_eq!(Ok("2024-05-01T10:30:00Z"), json.get_string());
+        assert_eq!(Ok(datetime), json.get_datetime());
+    }
+}
@@ -0,0 +1,244 @@
+use crate::lexer::{self, Lexer};
+use std::ops::Range;
+
+// The column `Loc::col` puts the char after a tab at is the next multiple of this plus one
+pub const DEFAULT_TAB_WIDTH: usize = 4;
+
+// A position in a json text. `row` and `col` start at 1 and `col` counts chars with tabs
+// expanded to the next tab stop. `utf16_col` counts UTF-16 code units from 0, so it can be
+// used as the `character` of a language server protocol position as it is, and `byte` is
+// the UTF-8 offset from the start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Loc {
+    pub row: usize,
+    pub col: usize,
+    pub utf16_col: usize,
+    pub byte: usize,
+}
+
+impl Loc {
+    pub(crate) fn start() -> Self {
+        Self {
+            row: 1,
+            col: 1,
+            utf16_col: 0,
+            byte: 0,
+        }
+    }
+
+    // Moves past `c`
+    pub(crate) fn advance(&mut self, c: char, tab_width: usize) {
+        self.byte += c.len_utf8();
+        match c {
+            '\n' => {
+                self.row += 1;
+                self.col = 1;
+                self.utf16_col = 0;
+            }
+            '\t' => {
+                let tab_width = tab_width.max(1);
+                self.col += tab_width - (self.col - 1) % tab_width;
+                self.utf16_col += 1;
+            }
+            c => {
+                self.col += 1;
+                self.utf16_col += c.len_utf16();
+            }
+        }
+    }
+}
+
+// The position of the char that starts at or after byte `byte` of `json`, with tabs
+// `tab_width` columns wide
+pub fn locate(json: &str, byte: usize, tab_width: usize) -> Loc {
+    let mut loc = Loc::start();
+    for c in json.chars() {
+        if loc.byte >= byte {
+            break;
+        }
+        loc.advance(c, tab_width);
+    }
+    loc
+}
+
+// The kinds of tokens `tokenize` splits a json text into. Unlike the parser it also
+// classifies the whitespace and `//` or `/* */` comments between tokens, and text it can
+// not make sense of is `Invalid` so highlighting can go on after it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    OpenCurly,
+    CloseCurly,
+    OpenBracket,
+    CloseBracket,
+    Comma,
+    Colon,
+    Integer,
+    Float,
+    Str,
+    Null,
+    True,
+    False,
+    Whitespace,
+    Comment,
+    Invalid,
+    // The end of the input, `tokenize` never makes it but a `Diagnostic` can expect it
+    Eof,
+}
+
+// A token with its text and the byte range of the text in the source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub span: Range<usize>,
+}
+
+// The whitespace or comment `rest` starts with and its length in bytes, a block comment
+// that is never closed is `Invalid`
+pub(crate) fn trivia(rest: &str) -> Option<(TokenKind, usize)> {
+    let whitespace = rest.len() - rest.trim_start().len();
+    if whitespace > 0 {
+        Some((TokenKind::Whitespace, whitespace))
+    } else if rest.starts_with("//") {
+        Some((TokenKind::Comment, rest.find('\n').unwrap_or(rest.len())))
+    } else if let Some(comment) = rest.strip_prefix("/*") {
+        match comment.find("*/") {
+            Some(end) => Some((TokenKind::Comment, end + 4)),
+            None => Some((TokenKind::Invalid, rest.len())),
+        }
+    } else {
+        None
+    }
+}
+
+pub(crate) fn public_kind(kind: lexer::TokenKind) -> TokenKind {
+    match kind {
+        lexer::TokenKind::OpenCurly => TokenKind::OpenCurly,
+        lexer::TokenKind::CloseCurly => TokenKind::CloseCurly,
+        lexer::TokenKind::OpenBracket => TokenKind::OpenBracket,
+        lexer::TokenKind::CloseBracket => TokenKind::CloseBracket,
+        lexer::TokenKind::Comma => TokenKind::Comma,
+        lexer::TokenKind::Colon => TokenKind::Colon,
+        lexer::TokenKind::Integer => TokenKind::Integer,
+        lexer::TokenKind::Float => TokenKind::Float,
+        lexer::TokenKind::Str => TokenKind::Str,
+        lexer::TokenKind::Null => TokenKind::Null,
+        lexer::TokenKind::True => TokenKind::True,
+        lexer::TokenKind::False => TokenKind::False,
+        lexer::TokenKind::Eof => TokenKind::Eof,
+        lexer::TokenKind::Ident | lexer::TokenKind::Invalid => TokenKind::Invalid,
+    }
+}
+
+// Splits `json` into tokens that together cover all of it, for syntax highlighting. It
+// does not check that the tokens form valid json.
+pub fn tokenize(json: &str) -> impl Iterator<Item = Token<'_>> {
+    let mut pos = 0;
+    std::iter::from_fn(move || {
+        let rest = &json[pos..];
+        if rest.is_empty() {
+            return None;
+        }
+        let (kind, len) = trivia(rest).unwrap_or_else(|| {
+            let token = Lexer::new(rest.chars())
+                .next()
+                .expect("THIS SHOULD NEVER PANIC");
+            (public_kind(token.kind), token.text.len())
+        });
+        let span = pos..pos + len;
+        pos += len;
+        Some(Token {
+            kind,
+            text: &json[span.clone()],
+            span,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(json: &str) -> Vec<(TokenKind, &str)> {
+        tokenize(json)
+            .map(|token| (token.kind, token.text))
+            .collect()
+    }
+
+    #[test]
+    fn classify_tokens() {
+        use TokenKind::*;
+        assert_eq!(
+            vec![
+                (Comment, "// c"),
+                (Whitespace, "\n"),
+                (OpenCurly, "{"),
+                (Str, "\"k\""),
+                (Colon, ":"),
+                (Whitespace, " "),
+                (OpenBracket, "["),
+                (Integer, "1"),
+                (Comma, ","),
+                (Float, "-2.5e3"),
+                (Comma, ","),
+                (Null, "null"),
+                (Comma, ","),
+                (True, "true"),
+                (Comment, "/* x */"),
+                (Comma, ","),
+                (False, "false"),
+                (CloseBracket, "]"),
+                (CloseCurly, "}"),
+            ],
+            kinds("// c\n{\"k\": [1,-2.5e3,null,true/* x */,false]}")
+        );
+        assert!(kinds("").is_empty());
+    }
+
+    #[test]
+    fn locations() {
+        let json = "{\n\t\"😀é\": x";
+        let x = json.find('x').unwrap();
+        assert_eq!(
+            Loc {
+                row: 2,
+                col: 11,
+                utf16_col: 8,
+                byte: x
+            },
+            locate(json, x, DEFAULT_TAB_WIDTH)
+        );
+        assert_eq!(9, locate(json, x, 2).col);
+        assert_eq!(Loc::start(), locate(json, 0, 4));
+        assert_eq!(0, locate(json, 2, 4).utf16_col);
+        assert_eq!(json.len(), locate(json, 1000, 4).byte);
+    }
+
+    #[test]
+    fn spans_cover_the_input() {
+        let json = "{\"ä\": nul @ \"open";
+        let tokens: Vec<_> = tokenize(json).collect();
+        assert_eq!(
+            vec![
+                TokenKind::OpenCurly,
+                TokenKind::Str,
+                TokenKind::Colon,
+                TokenKind::Whitespace,
+                TokenKind::Invalid,
+                TokenKind::Whitespace,
+                TokenKind::Invalid,
+                TokenKind::Whitespace,
+                TokenKind::Invalid,
+            ],
+            tokens.iter().map(|token| token.kind).collect::<Vec<_>>()
+        );
+        let mut end = 0;
+        for token in &tokens {
+            assert_eq!(end, token.span.start);
+            assert_eq!(&json[token.span.clone()], token.text);
+            end = token.span.end;
+        }
+        assert_eq!(json.len(), end);
+        assert_eq!(vec![(TokenKind::Invalid, "/* open")], kinds("/* open"));
+    }
+}
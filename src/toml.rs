@@ -0,0 +1,177 @@
+use crate::json::{Json, JsonError, JsonState};
+use crate::parser::JsonData;
+use crate::pointer::pointer_token;
+
+impl<S: JsonState> Json<S> {
+    // Toml documents are always tables so only objects without nulls can be converted
+    pub fn to_toml_string(&self) -> Result<String, JsonError> {
+        match to_toml_value(&self.data, "")? {
+            ::toml::Value::Table(table) => ::toml::to_string(&table)
+                .map_err(|error| JsonError::UnrepresentableInToml(error.to_string())),
+            _ => Err(JsonError::UnrepresentableInToml(String::from(
+                "The root must be an object",
+            ))),
+        }
+    }
+
+    pub fn from_toml<R: AsRef<str>>(toml: R) -> Result<Self, JsonError> {
+        match toml.as_ref().parse::<::toml::Table>() {
+            Ok(table) => Self::checked(from_toml_value(::toml::Value::Table(table))?),
+            Err(error) => Err(JsonError::InvalidToml(format!("{}", error))),
+        }
+    }
+}
+
+fn to_toml_value(data: &JsonData, path: &str) -> Result<::toml::Value, JsonError> {
+    match data {
+        JsonData::Null => Err(JsonError::UnrepresentableInToml(format!(
+            "Toml has no null value, found null at `{}`",
+            path
+        ))),
+        JsonData::Bool(b) => Ok(::toml::Value::Boolean(*b)),
+        JsonData::Integer(i) => Ok(::toml::Value::Integer(*i)),
+        #[cfg(feature = "bigint")]
+        JsonData::BigInt(i) => Err(JsonError::UnrepresentableInToml(format!(
+            "Toml integers are 64 bit, found {} at `{}`",
+            i, path
+        ))),
+        JsonData::Float(f) => Ok(::toml::Value::Float(*f)),
+        #[cfg(feature = "decimal")]
+        JsonData::Decimal(_) => Ok(::toml::Value::Float(
+            data.as_f64().expect("THIS SHOULD NEVER PANIC"),
+        )),
+        #[cfg(feature = "raw_numbers")]
+        JsonData::RawNumber(raw) => to_toml_value(&raw.data, path),
+        JsonData::Str(s) => Ok(::toml::Value::String(s.to_string())),
+        JsonData::Array(v) => v
+            .iter()
+            .enumerate()
+            .map(|(i, item)| to_toml_value(item, &format!("{}/{}", path, i)))
+            .collect::<Result<_, _>>()
+            .map(::toml::Value::Array),
+        JsonData::Object(m) => m
+            .iter()
+            .map(|(key, value)| {
+                let key = key.to_string();
+                let value = to_toml_value(value, &format!("{}/{}", path, pointer_token(&key)))?;
+                Ok((key, value))
+            })
+            .collect::<Result<_, _>>()
+            .map(::toml::Value::Table),
+    }
+}
+
+fn from_toml_value(value: ::toml::Value) -> Result<JsonData, JsonError> {
+    Ok(match value {
+        ::toml::Value::String(s) => JsonData::Str(s.into()),
+        ::toml::Value::Integer(i) => JsonData::Integer(i),
+        ::toml::Value::Float(f) if f.is_finite() => JsonData::Float(f),
+        ::toml::Value::Float(f) => {
+            return Err(JsonError::InvalidToml(format!(
+                "The float `{}` has no json representation",
+                f
+            )))
+        }
+        ::toml::Value::Boolean(b) => JsonData::Bool(b),
+        ::toml::Value::Datetime(datetime) => JsonData::Str(datetime.to_string().into()),
+        ::toml::Value::Array(v) => JsonData::array(
+            v.into_iter()
+                .map(from_toml_value)
+                .collect::<Result<Vec<_>, _>>()?,
+        ),
+        ::toml::Value::Table(table) => JsonData::object(
+            table
+                .into_iter()
+                .map(|(key, value)| Ok((key, from_toml_value(value)?)))
+                .collect::<Result<Vec<_>, JsonError>>()?,
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json::{Array, Json, JsonError, Object};
+
+    #[test]
+    fn from_toml_config() {
+        let json = Json::<Object>::from_toml(
+            "title = \"demo \\\"app\\\"\"\n\
+             released = 1979-05-27T07:32:00Z\n\
+             [server]\n\
+             port = 8080\n\
+             ratio = 0.5\n\
+             tags = [\"a\", 1, true]\n\
+             [[users]]\n\
+             name = \"first\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            Ok("demo \"app\""),
+            json.get_value("title").unwrap().get_string()
+        );
+        assert_eq!(
+            Ok("1979-05-27T07:32:00Z"),
+            json.get_value("released").unwrap().get_string()
+        );
+        let server = json.get_object("server").unwrap();
+        assert_eq!(Ok(8080), server.get_value("port").unwrap().get_i64());
+        assert_eq!(Ok(0.5), server.get_value("ratio").unwrap().get_f64());
+        assert_eq!(Ok(3), server.get_array("tags").unwrap().len());
+        assert_eq!(
+            Ok("first"),
+            json.get_array("users")
+                .unwrap()
+                .get_object(0)
+                .unwrap()
+                .get_value("name")
+                .unwrap()
+                .get_string()
+        );
+    }
+
+    #[test]
+    fn toml_roundtrip() {
+        let json = Json::<Object>::new(
+            "{\"name\": \"a\\\\b\", \"server\": {\"port\": 80, \"hosts\": [\"x\", \"y\"]}, \"mixed\": [1, \"two\"]}",
+        )
+        .unwrap();
+        let toml = json.to_toml_string().unwrap();
+        assert_eq!(json.data, Json::<Object>::from_toml(toml).unwrap().data);
+    }
+
+    #[test]
+    fn to_toml_unrepresentable() {
+        assert!(matches!(
+            Json::<Array>::new("[1, 2]").unwrap().to_toml_string(),
+            Err(JsonError::UnrepresentableInToml(_))
+        ));
+        assert_eq!(
+            Err(JsonError::UnrepresentableInToml(String::from(
+                "Toml has no null value, found null at `/a/1`"
+            ))),
+            Json::<Object>::new("{\"a\": [1, null]}")
+                .unwrap()
+                .to_toml_string()
+        );
+        assert_eq!(
+            Err(JsonError::UnrepresentableInToml(String::from(
+                "Toml has no null value, found null at `/a~1b/c~0`"
+            ))),
+            Json::<Object>::new("{\"a/b\": {\"c~\": null}}")
+                .unwrap()
+                .to_toml_string()
+        );
+    }
+
+    #[test]
+    fn invalid_toml() {
+        assert!(matches!(
+            Json::<Object>::from_toml("a = "),
+            Err(JsonError::InvalidToml(_))
+        ));
+        assert!(matches!(
+            Json::<Object>::from_toml("a = nan"),
+            Err(JsonError::InvalidToml(_))
+        ));
+    }
+}
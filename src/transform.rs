@@ -0,0 +1,203 @@
+use crate::escape::{escape_str, unescape_str};
+use crate::format::{event_error, io_error, write_data_events, EventWriter, FormatOptions};
+use crate::json::{AnyJson, Json, JsonError, Value};
+use crate::lexer::{Token, TokenKind};
+use crate::pointer::pointer_token;
+use crate::stream::{scalar_data, Event, Events, ReadChars};
+use std::io::{BufWriter, Read, Write};
+
+// What `transform_stream` shows its callback, the scalars parsed and the arrays and objects
+// before anything in them is read
+#[derive(Debug)]
+pub enum Item<'a> {
+    // The key of a member, unescaped, before its value
+    Key(&'a str),
+    Value(&'a Json<Value>),
+    StartObject,
+    StartArray,
+}
+
+// What `transform_stream` does with an item. `Drop` of a key drops the whole member and
+// `Replace` of a key replaces its value. `Rename` only renames keys and keeps other items.
+#[derive(Debug, Clone)]
+pub enum Action {
+    Keep,
+    Drop,
+    Rename(String),
+    Replace(AnyJson),
+}
+
+// An array or object that is being copied
+struct Open {
+    pointer: String,
+    // The index of the next item of an array, `None` for objects
+    next_index: Option<usize>,
+}
+
+// Copies the json read from `reader` into `writer` formatted with `options`, calling `f`
+// with the json pointer and the item of every key and value on the way. Like `reformat`
+// the whole document is never in memory, only the scalar that is being looked at. The
+// pointers are those of the input, renames don't change them.
+pub fn transform_stream<R, W, F>(
+    reader: R,
+    writer: W,
+    options: &FormatOptions,
+    mut f: F,
+) -> Result<(), JsonError>
+where
+    R: Read,
+    W: Write,
+    F: FnMut(&str, Item) -> Action,
+{
+    let chars = ReadChars::new(reader);
+    let read_error = chars.error();
+    let mut writer = BufWriter::new(writer);
+    let mut event_writer = EventWriter::new(options);
+    let mut open: Vec<Open> = Vec::new();
+    // The key of the value that comes next, written only once the value is kept
+    let mut key: Option<(String, Option<Token>)> = None;
+    // What the key asked to do with its value
+    let mut key_action = Action::Keep;
+    // The depth of the array or object that is being skipped, 0 when nothing is
+    let mut skipping = 0;
+    for event in Events::new(chars) {
+        let event = event.map_err(|error| event_error(error, read_error.get()))?;
+        if skipping > 0 {
+            match event {
+                Event::StartObject(_) | Event::StartArray(_) => skipping += 1,
+                Event::EndObject(_) | Event::EndArray(_) => skipping -= 1,
+                _ => {}
+            }
+            continue;
+        }
+        let pointer = match (&event, open.last_mut()) {
+            (Event::EndObject(_) | Event::EndArray(_), _) => {
+                open.pop();
+                event_writer.write(&mut writer, &event).map_err(io_error)?;
+                continue;
+            }
+            (Event::Key(token), Some(parent)) => {
+                let unescaped = unescape_str(&token.text[1..token.text.len() - 1]);
+                let pointer = format!("{}/{}", parent.pointer, pointer_token(&unescaped));
+                key_action = f(&pointer, Item::Key(&unescaped));
+                let written = match &key_action {
+                    Action::Drop => None,
+                    Action::Rename(name) => Some(Token {
+                        kind: TokenKind::Str,
+                        text: format!("\"{}\"", escape_str(name)),
+                        loc: token.loc,
+                    }),
+                    _ => Some(token.clone()),
+                };
+                key = Some((pointer, written));
+                continue;
+            }
+            (_, None) => String::new(),
+            (
+                _,
+                Some(Open {
+                    pointer,
+                    next_index: Some(index),
+                }),
+            ) => {
+                *index += 1;
+                format!("{}/{}", pointer, *index - 1)
+            }
+            (_, Some(_)) => key.as_ref().expect("THIS SHOULD NEVER PANIC").0.clone(),
+        };
+        let written_key = key.take().and_then(|(_, token)| token);
+        let action = match std::mem::replace(&mut key_action, Action::Keep) {
+            Action::Keep | Action::Rename(_) => match &event {
+                Event::Scalar(token) => {
+                    let data = scalar_data(token.clone())
+                        .map_err(|error| event_error(error, read_error.get()))?;
+                    let value = Json::from_data(data).at(pointer.clone());
+                    f(&pointer, Item::Value(&value))
+                }
+                Event::StartObject(_) => f(&pointer, Item::StartObject),
+                _ => f(&pointer, Item::StartArray),
+            },
+            action => action,
+        };
+        let is_start = matches!(event, Event::StartObject(_) | Event::StartArray(_));
+        if matches!(action, Action::Drop) {
+            skipping = usize::from(is_start);
+            continue;
+        }
+        if let Some(token) = written_key {
+            event_writer
+                .write(&mut writer, &Event::Key(token))
+                .map_err(io_error)?;
+        }
+        if let Action::Replace(json) = action {
+            write_data_events(&mut event_writer, &mut writer, json.data()).map_err(io_error)?;
+            skipping = usize::from(is_start);
+            continue;
+        }
+        if is_start {
+            let next_index = matches!(event, Event::StartArray(_)).then_some(0);
+            open.push(Open {
+                pointer,
+                next_index,
+            });
+        }
+        event_writer.write(&mut writer, &event).map_err(io_error)?;
+    }
+    if let Some(kind) = read_error.get() {
+        return Err(JsonError::FileError(kind));
+    }
+    writer.flush().map_err(io_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transform<F: FnMut(&str, Item) -> Action>(json: &str, f: F) -> Result<String, JsonError> {
+        let mut out = Vec::new();
+        transform_stream(json.as_bytes(), &mut out, &FormatOptions::minified(), f)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn drop_rename_and_replace() {
+        let json = r#"{"users": [{"name": "a", "password": "x", "tags": [1, 2]}, {"name": "b\n", "password": {"hash": "y"}}], "v": 1}"#;
+        let mut pointers = Vec::new();
+        let out = transform(json, |pointer, item| {
+            pointers.push(pointer.to_string());
+            match item {
+                Item::Key("password") => Action::Drop,
+                Item::Key("name") => Action::Rename(String::from("full\"name")),
+                Item::Key("v") => Action::Replace(AnyJson::from(2)),
+                Item::Value(value) if value.get_string().is_ok_and(|s| s == "b\n") => {
+                    Action::Replace(AnyJson::from("B"))
+                }
+                Item::StartArray if pointer.ends_with("/tags") => {
+                    Action::Replace(AnyJson::from(Json::<Value>::new("null").unwrap()))
+                }
+                _ => Action::Keep,
+            }
+        })
+        .unwrap();
+        assert_eq!(
+            r#"{"users":[{"full\"name":"a","tags":null},{"full\"name":"B"}],"v":2}"#,
+            out
+        );
+        assert!(pointers.contains(&String::from("/users/1/password")));
+        assert!(!pointers.contains(&String::from("/users/1/password/hash")));
+        assert!(!pointers.contains(&String::from("/users/0/tags/0")));
+        assert!(pointers.contains(&String::from("/users/1/name")));
+    }
+
+    #[test]
+    fn drop_array_items_and_root() {
+        let out = transform("[1, [2], {\"a\": 3}, 4]", |pointer, _| match pointer {
+            "/1" | "/2/a" | "/3" => Action::Drop,
+            _ => Action::Keep,
+        });
+        assert_eq!(Ok(String::from("[1,{}]")), out);
+        assert_eq!(Ok(String::new()), transform("[1]", |_, _| Action::Drop));
+        assert!(transform("[1,]", |_, _| Action::Keep).is_err());
+        assert!(transform("{\"a\": [}", |_, _| Action::Drop).is_err());
+    }
+}
@@ -0,0 +1,55 @@
+use crate::json::{Json, JsonError, JsonType, Value};
+use crate::parser::JsonData;
+use ::uuid::Uuid;
+
+impl Json<Value> {
+    // Parses a string like `"67e55044-10b1-426f-9247-bb680e5fe0c8"`, the simple, braced and
+    // urn forms are accepted too
+    pub fn get_uuid(&self) -> Result<Uuid, JsonError> {
+        match self.data.as_ref() {
+            JsonData::Str(s) => {
+                Uuid::parse_str(s).map_err(|e| JsonError::InvalidUuid(e.to_string()))
+            }
+            _ => Err(self.incorrect_type(&[JsonType::String])),
+        }
+    }
+}
+
+// Written in the hyphenated lowercase form
+impl From<Uuid> for Json<Value> {
+    fn from(uuid: Uuid) -> Self {
+        Json::from_data(JsonData::Str(uuid.hyphenated().to_string().into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::JsonType;
+    use crate::json::Object;
+
+    #[test]
+    fn uuid_accessor() {
+        let json: Json<Object> = Json::new(
+            "{\"id\": \"67E55044-10B1-426F-9247-BB680E5FE0C8\", \"bad\": \"67e55044\", \"n\": 1}",
+        )
+        .unwrap();
+        let id = json.get_value("id").unwrap().get_uuid().unwrap();
+        assert_eq!(
+            Ok("67e55044-10b1-426f-9247-bb680e5fe0c8"),
+            Json::<Value>::from(id).get_string()
+        );
+        assert!(matches!(
+            json.get_value("bad").unwrap().get_uuid(),
+            Err(JsonError::InvalidUuid(_))
+        ));
+        assert_eq!(
+            Err(JsonError::IncorrectType {
+                path: "/n".to_string(),
+                expected: vec![JsonType::String],
+                found: JsonType::Integer,
+            }),
+            json.get_value("n").unwrap().get_uuid()
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use crate::format::syntax_error;
+use crate::json::{Json, JsonError, JsonState};
+use crate::lenient::Diagnostic;
+use crate::lint::{Checks, LintOptions};
+use crate::parser::ParseError;
+use crate::stream::{DataBuilder, Events};
+
+impl<S: JsonState> Json<S> {
+    // Like `new` but also returns what is valid json yet likely a mistake: duplicate keys,
+    // numbers that lose precision as a float or don't fit in one and escapes like `\/`,
+    // `\u0041` or unpaired surrogates. These are the default rules of `lint`.
+    pub fn new_with_warnings<R: AsRef<str>>(json: R) -> Result<(Self, Vec<Diagnostic>), JsonError> {
+        let options = LintOptions::default();
+        let mut checks = Checks::new(&options);
+        let mut builder = DataBuilder::default();
+        let mut data = None;
+        for event in Events::new(json.as_ref().chars()) {
+            let event = event.map_err(syntax_error)?;
+            checks.event(&event);
+            if let Some(value) = builder.push(event).map_err(syntax_error)? {
+                data = Some(value);
+            }
+        }
+        let data = data.ok_or_else(|| syntax_error(ParseError::EmptyInput))?;
+        let warnings = checks.findings.iter().map(|f| f.diagnostic()).collect();
+        Ok((Self::checked(data)?, warnings))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{Array, Object};
+
+    fn messages(json: &str) -> Vec<String> {
+        let (_, warnings) = Json::<Array>::new_with_warnings(json).unwrap();
+        warnings.into_iter().map(|w| w.message).collect()
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        let (json, warnings) =
+            Json::<Object>::new_with_warnings("{\"a\": 1, \"b\": {\"a\": 2}, \"\\u0061\": 3}")
+                .unwrap();
+        assert_eq!(Json::<Object>::new(json.to_string()).unwrap(), json);
+        assert_eq!(2, warnings.len());
+        assert_eq!("Unnecessary escape `\\u0061` of `a`", warnings[0].message);
+        assert_eq!(26, warnings[0].loc.col);
+        assert_eq!(
+            "Duplicate key `a`, only the last value is kept",
+            warnings[1].message
+        );
+        assert_eq!(25, warnings[1].loc.col);
+    }
+
+    #[test]
+    fn lossy_numbers() {
+        assert!(messages("[1, 2.5, 9007199254740992, 1e10]").is_empty());
+        #[cfg(not(any(feature = "bigint", feature = "decimal")))]
+        assert_eq!(
+            vec![
+                "Integer `9007199254740993` loses precision when read as a float",
+                "Integer `99999999999999999999` does not fit in an i64 and is read as the float 100000000000000000000",
+                "Number `1e400` is out of the range of a float",
+            ],
+            messages("[9007199254740993, 99999999999999999999, 1e400]")
+        );
+        // the integer too large for an i64 is kept exactly as a decimal
+        #[cfg(all(feature = "decimal", not(feature = "bigint")))]
+        assert_eq!(
+            vec![
+                "Integer `9007199254740993` loses precision when read as a float",
+                "Number `1e400` is out of the range of a float",
+            ],
+            messages("[9007199254740993, 99999999999999999999, 1e400]")
+        );
+        #[cfg(not(feature = "decimal"))]
+        assert_eq!(
+            vec!["Number `0.123456789012345678` has more digits than a float holds"],
+            messages("[0.123456789012345678, 1.50000000000000000000]")
+        );
+    }
+
+    #[test]
+    fn unusual_escapes() {
+        assert_eq!(
+            vec![
+                "Unnecessary escape `\\/`",
+                "Unnecessary escape `\\u0041` of `A`",
+                "Unpaired surrogate `\\ud800`",
+                "Unpaired surrogate `\\udc00`",
+            ],
+            messages(r#"["a\/b", "\u0041\u00e9\n\u0022", "\ud800x", "\udc00", "\ud83d\ude00"]"#)
+        );
+        let (_, warnings) = Json::<Array>::new_with_warnings("[\"ab\\/\"]").unwrap();
+        assert_eq!(5, warnings[0].loc.col);
+    }
+}
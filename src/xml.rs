@@ -0,0 +1,523 @@
+use crate::json::{Json, JsonError, JsonState, MAX_DEPTH};
+use crate::parser::{remove_member, JsonData, Map, Shared};
+
+const ATTRIBUTE_PREFIX: &str = "@";
+const TEXT_KEY: &str = "#text";
+
+// The names and decoded values of the attributes of an element
+type Attributes<'a> = Vec<(&'a str, String)>;
+
+impl<S: JsonState> Json<S> {
+    // The root element becomes the only key of the returned object. Attributes are stored
+    // as `@name` keys, text next to child elements or attributes as `#text` and repeated
+    // child elements are collected into an array.
+    pub fn from_xml<R: AsRef<str>>(xml: R) -> Result<Self, JsonError> {
+        let mut parser = XmlParser {
+            xml: xml.as_ref(),
+            pos: 0,
+        };
+        parser.skip_misc()?;
+        if !parser.eat("<") {
+            return Err(parser.error("Expected a root element"));
+        }
+        let (name, value) = parser.parse_element(0)?;
+        parser.skip_misc()?;
+        if parser.pos != parser.xml.len() {
+            return Err(parser.error("Unexpected content after the root element"));
+        }
+        Self::checked(JsonData::object([(name, value)]))
+    }
+
+    pub fn to_xml(&self) -> Result<String, JsonError> {
+        match self.data.as_ref() {
+            JsonData::Object(m) if m.len() == 1 => {
+                let mut xml = String::new();
+                for (name, value) in m.iter() {
+                    write_element(name, value, &mut xml)?;
+                }
+                Ok(xml)
+            }
+            _ => Err(JsonError::UnrepresentableInXml(String::from(
+                "The root must be an object with exactly one key",
+            ))),
+        }
+    }
+}
+
+struct XmlParser<'a> {
+    xml: &'a str,
+    pos: usize,
+}
+
+impl<'a> XmlParser<'a> {
+    fn error(&self, msg: &str) -> JsonError {
+        let before = &self.xml[..self.pos];
+        let row = before.matches('\n').count() + 1;
+        let col = before.chars().rev().take_while(|c| c != &'\n').count() + 1;
+        JsonError::InvalidXml(format!("{} at {}:{}", msg, row, col))
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.xml[self.pos..]
+    }
+
+    fn eat(&mut self, expected: &str) -> bool {
+        if self.rest().starts_with(expected) {
+            self.pos += expected.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn take_until(&mut self, end: &str) -> Result<&'a str, JsonError> {
+        match self.rest().find(end) {
+            Some(len) => {
+                let taken = &self.rest()[..len];
+                self.pos += len + end.len();
+                Ok(taken)
+            }
+            None => Err(self.error(&format!("Expected `{}`", end))),
+        }
+    }
+
+    // Skips whitespace, comments, processing instructions and the doctype
+    fn skip_misc(&mut self) -> Result<(), JsonError> {
+        loop {
+            self.skip_whitespace();
+            if self.eat("<!--") {
+                self.take_until("-->")?;
+            } else if self.eat("<?") {
+                self.take_until("?>")?;
+            } else if self.eat("<!DOCTYPE") {
+                self.take_until(">")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn parse_name(&mut self) -> Result<&'a str, JsonError> {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| c.is_whitespace() || "/>=<\"'".contains(c))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error("Expected a name"));
+        }
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    // Parses the element after its `<`. `depth` counts the elements around it, past
+    // `MAX_DEPTH` it is an error so untrusted input can not overflow the stack.
+    fn parse_element(&mut self, depth: usize) -> Result<(&'a str, JsonData), JsonError> {
+        if depth >= MAX_DEPTH {
+            return Err(self.too_deep());
+        }
+        let name = self.parse_name()?;
+        let (attributes, has_content) = self.parse_attributes()?;
+        let mut children = Vec::new();
+        let mut text = String::new();
+        while has_content && self.parse_content(name, &mut text)? {
+            children.push(self.parse_element(depth + 1)?);
+        }
+        Ok((name, element_value(attributes, children, text)))
+    }
+
+    fn too_deep(&self) -> JsonError {
+        self.error(&format!("Elements nested deeper than {} levels", MAX_DEPTH))
+    }
+
+    // The attributes up to the end of a start tag and `false` when it ends with `/>`, so the
+    // element has no content
+    fn parse_attributes(&mut self) -> Result<(Attributes<'a>, bool), JsonError> {
+        let mut attributes = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.eat("/>") {
+                return Ok((attributes, false));
+            }
+            if self.eat(">") {
+                return Ok((attributes, true));
+            }
+            let key = self.parse_name()?;
+            self.skip_whitespace();
+            if !self.eat("=") {
+                return Err(self.error("Expected `=` after the attribute name"));
+            }
+            self.skip_whitespace();
+            let quote = if self.eat("\"") {
+                "\""
+            } else if self.eat("'") {
+                "'"
+            } else {
+                return Err(self.error("Expected a quoted attribute value"));
+            };
+            let value = self.take_until(quote)?;
+            attributes.push((key, self.decode_entities(value)?));
+        }
+    }
+
+    // Reads the content of the element `name` into `text` up to its end tag or the `<` of
+    // a child element, `true` for a child
+    fn parse_content(&mut self, name: &str, text: &mut String) -> Result<bool, JsonError> {
+        loop {
+            if self.eat("</") {
+                let end = self.parse_name()?;
+                if end != name {
+                    return Err(
+                        self.error(&format!("Expected `</{}>` but found `</{}>`", name, end))
+                    );
+                }
+                self.skip_whitespace();
+                if !self.eat(">") {
+                    return Err(self.error("Expected `>`"));
+                }
+                return Ok(false);
+            } else if self.eat("<!--") {
+                self.take_until("-->")?;
+            } else if self.eat("<![CDATA[") {
+                text.push_str(self.take_until("]]>")?);
+            } else if self.eat("<?") {
+                self.take_until("?>")?;
+            } else if self.eat("<") {
+                return Ok(true);
+            } else if self.rest().is_empty() {
+                return Err(self.error(&format!("Unclosed element `{}`", name)));
+            } else {
+                let len = self.rest().find('<').unwrap_or(self.rest().len());
+                let raw = &self.rest()[..len];
+                text.push_str(&self.decode_entities(raw)?);
+                self.pos += len;
+            }
+        }
+    }
+
+    fn decode_entities(&self, raw: &str) -> Result<String, JsonError> {
+        let mut decoded = String::with_capacity(raw.len());
+        let mut rest = raw;
+        while let Some(start) = rest.find('&') {
+            decoded.push_str(&rest[..start]);
+            rest = &rest[start + 1..];
+            let end = match rest.find(';') {
+                Some(end) => end,
+                None => return Err(self.error("Unterminated entity")),
+            };
+            let c = match &rest[..end] {
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "amp" => Some('&'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                entity => match entity.strip_prefix("#x") {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+                    None => entity
+                        .strip_prefix('#')
+                        .and_then(|dec| dec.parse::<u32>().ok())
+                        .and_then(char::from_u32),
+                },
+            };
+            match c {
+                Some(c) => decoded.push(c),
+                None => return Err(self.error(&format!("Unknown entity `&{};`", &rest[..end]))),
+            }
+            rest = &rest[end + 1..];
+        }
+        decoded.push_str(rest);
+        Ok(decoded)
+    }
+}
+
+fn element_value(
+    attributes: Attributes,
+    children: Vec<(&str, JsonData)>,
+    text: String,
+) -> JsonData {
+    let text = text.trim();
+    if attributes.is_empty() && children.is_empty() {
+        return if text.is_empty() {
+            JsonData::Null
+        } else {
+            JsonData::Str(text.into())
+        };
+    }
+    let mut map = Map::default();
+    for (key, value) in attributes {
+        map.insert(
+            format!("{}{}", ATTRIBUTE_PREFIX, key),
+            JsonData::Str(value.into()),
+        );
+    }
+    for (name, value) in children {
+        // An element value is never an array, so an array means the name was repeated
+        match remove_member(&mut map, name) {
+            Some(JsonData::Array(mut v)) => {
+                v.push(Shared::new(value));
+                map.insert(name.to_string(), JsonData::Array(v));
+            }
+            Some(first) => {
+                map.insert(name.to_string(), JsonData::array([first, value]));
+            }
+            None => {
+                map.insert(name.to_string(), value);
+            }
+        }
+    }
+    if !text.is_empty() {
+        map.insert(String::from(TEXT_KEY), JsonData::Str(text.into()));
+    }
+    JsonData::object(map)
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '.')
+        && !name.contains(|c: char| c.is_whitespace() || "/>=<\"'&!?".contains(c))
+}
+
+fn escape_xml(text: &str, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '&' => out.push_str("&amp;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+}
+
+fn scalar_text(data: &JsonData) -> Option<String> {
+    match data {
+        JsonData::Str(s) => Some(s.to_string()),
+        JsonData::Integer(i) => Some(i.to_string()),
+        #[cfg(feature = "bigint")]
+        JsonData::BigInt(i) => Some(i.to_string()),
+        JsonData::Float(f) => Some(f.to_string()),
+        #[cfg(feature = "decimal")]
+        JsonData::Decimal(d) => Some(d.to_string()),
+        #[cfg(feature = "raw_numbers")]
+        JsonData::RawNumber(raw) => Some(raw.text.to_string()),
+        JsonData::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+fn write_element(name: &str, value: &JsonData, xml: &mut String) -> Result<(), JsonError> {
+    if !is_valid_name(name) {
+        return Err(JsonError::UnrepresentableInXml(format!(
+            "`{}` is not a valid element name",
+            name
+        )));
+    }
+    match value {
+        JsonData::Array(v) => {
+            for item in v {
+                if let JsonData::Array(_) = item.as_ref() {
+                    return Err(JsonError::UnrepresentableInXml(format!(
+                        "Nested arrays in `{}` can't be written as elements",
+                        name
+                    )));
+                }
+                write_element(name, item, xml)?;
+            }
+        }
+        JsonData::Object(m) => {
+            xml.push('<');
+            xml.push_str(name);
+            let mut content = String::new();
+            for (key, value) in m.iter() {
+                if key == TEXT_KEY {
+                    match scalar_text(value) {
+                        Some(text) => escape_xml(&text, &mut content),
+                        None if value.as_ref() == &JsonData::Null => {}
+                        None => {
+                            return Err(JsonError::UnrepresentableInXml(format!(
+                                "`{}` of `{}` must be a scalar",
+                                TEXT_KEY, name
+                            )))
+                        }
+                    }
+                } else if let Some(attribute) = key.strip_prefix(ATTRIBUTE_PREFIX) {
+                    let text = match scalar_text(value) {
+                        Some(text) if is_valid_name(attribute) => text,
+                        _ => {
+                            return Err(JsonError::UnrepresentableInXml(format!(
+                                "Attribute `{}` of `{}` must be a scalar with a valid name",
+                                key, name
+                            )))
+                        }
+                    };
+                    xml.push(' ');
+                    xml.push_str(attribute);
+                    xml.push_str("=\"");
+                    escape_xml(&text, xml);
+                    xml.push('"');
+                } else {
+                    write_element(key, value, &mut content)?;
+                }
+            }
+            if content.is_empty() {
+                xml.push_str("/>");
+            } else {
+                xml.push('>');
+                xml.push_str(&content);
+                xml.push_str("</");
+                xml.push_str(name);
+                xml.push('>');
+            }
+        }
+        JsonData::Null => {
+            xml.push('<');
+            xml.push_str(name);
+            xml.push_str("/>");
+        }
+        scalar => {
+            xml.push('<');
+            xml.push_str(name);
+            xml.push('>');
+            escape_xml(&scalar_text(scalar).unwrap_or_default(), xml);
+            xml.push_str("</");
+            xml.push_str(name);
+            xml.push('>');
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json::{Array, Json, JsonError, Object, MAX_DEPTH};
+
+    #[test]
+    fn from_xml_soap_envelope() {
+        let json = Json::<Object>::from_xml(
+            "<?xml version=\"1.0\"?>\n\
+             <!-- envelope -->\n\
+             <soap:Envelope xmlns:soap=\"http://schemas.xmlsoap.org/soap/envelope/\">\n\
+               <soap:Body>\n\
+                 <order id='42' status=\"new\">\n\
+                   <item sku=\"a&amp;b\">first</item>\n\
+                   <item>second &lt;2&gt;</item>\n\
+                   <note><![CDATA[<raw> & text]]></note>\n\
+                   <empty/>\n\
+                   <link href=\"/next\"/>\n\
+                   total &#x3A; 3\n\
+                 </order>\n\
+               </soap:Body>\n\
+             </soap:Envelope>",
+        )
+        .unwrap();
+        let envelope = json.get_object("soap:Envelope").unwrap();
+        assert_eq!(
+            Ok("http://schemas.xmlsoap.org/soap/envelope/"),
+            envelope.get_value("@xmlns:soap").unwrap().get_string()
+        );
+        let order = envelope
+            .get_object("soap:Body")
+            .unwrap()
+            .get_object("order")
+            .unwrap();
+        assert_eq!(Ok("42"), order.get_value("@id").unwrap().get_string());
+        assert_eq!(
+            Ok("total : 3"),
+            order.get_value("#text").unwrap().get_string()
+        );
+        assert_eq!(
+            Ok("/next"),
+            order
+                .get_object("link")
+                .unwrap()
+                .get_value("@href")
+                .unwrap()
+                .get_string()
+        );
+        let items = order.get_array("item").unwrap();
+        assert_eq!(Ok(2), items.len());
+        let first = items.get_object(0).unwrap();
+        assert_eq!(Ok("a&b"), first.get_value("@sku").unwrap().get_string());
+        assert_eq!(Ok("first"), first.get_value("#text").unwrap().get_string());
+        assert_eq!(Ok("second <2>"), items.get_value(1).unwrap().get_string());
+        assert_eq!(
+            Ok("<raw> & text"),
+            order.get_value("note").unwrap().get_string()
+        );
+        assert!(order.get_value("empty").unwrap().is_null());
+    }
+
+    #[test]
+    fn xml_roundtrip() {
+        let json = Json::<Object>::new(
+            "{\"root\": {\"@version\": \"2\", \"entry\": [\"a \\\"quoted\\\" <b>\", {\"@k\": \"v\", \"#text\": \"t\"}, null], \"count\": \"3\"}}",
+        )
+        .unwrap();
+        let xml = json.to_xml().unwrap();
+        assert_eq!(json.data, Json::<Object>::from_xml(xml).unwrap().data);
+    }
+
+    #[test]
+    fn to_xml_scalars() {
+        assert_eq!(
+            Ok(String::from("<a>1</a>")),
+            Json::<Object>::new("{\"a\": 1}").unwrap().to_xml()
+        );
+        assert_eq!(
+            Ok(String::from("<a><b>true</b><b>1.5</b></a>")),
+            Json::<Object>::new("{\"a\": {\"b\": [true, 1.5]}}")
+                .unwrap()
+                .to_xml()
+        );
+    }
+
+    #[test]
+    fn to_xml_unrepresentable() {
+        assert!(matches!(
+            Json::<Array>::new("[1]").unwrap().to_xml(),
+            Err(JsonError::UnrepresentableInXml(_))
+        ));
+        assert!(matches!(
+            Json::<Object>::new("{\"a\": [[1]]}").unwrap().to_xml(),
+            Err(JsonError::UnrepresentableInXml(_))
+        ));
+        assert!(matches!(
+            Json::<Object>::new("{\"a b\": 1}").unwrap().to_xml(),
+            Err(JsonError::UnrepresentableInXml(_))
+        ));
+        assert!(matches!(
+            Json::<Object>::new("{\"a\": {\"@c\": [1]}}")
+                .unwrap()
+                .to_xml(),
+            Err(JsonError::UnrepresentableInXml(_))
+        ));
+    }
+
+    #[test]
+    fn invalid_xml() {
+        for xml in [
+            "",
+            "<a>",
+            "<a></b>",
+            "<a b=c/>",
+            "<a>&unknown;</a>",
+            "<a/><b/>",
+        ] {
+            assert!(
+                matches!(Json::<Object>::from_xml(xml), Err(JsonError::InvalidXml(_))),
+                "{xml}"
+            );
+        }
+        assert!(matches!(
+            Json::<Object>::from_xml("<a>".repeat(200_000)),
+            Err(JsonError::InvalidXml(msg)) if msg.starts_with("Elements nested deeper than 512 levels")
+        ));
+        let deepest = format!("{}{}", "<a>".repeat(MAX_DEPTH), "</a>".repeat(MAX_DEPTH));
+        assert!(Json::<Object>::from_xml(deepest).is_ok());
+    }
+}
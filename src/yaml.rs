@@ -0,0 +1,192 @@
+use crate::json::{Json, JsonState};
+use crate::parser::JsonData;
+use std::fmt::Write;
+
+impl<S: JsonState> Json<S> {
+    pub fn to_yaml_string(&self) -> String {
+        let mut yaml = String::new();
+        write_node(&self.data, 0, &mut yaml);
+        yaml
+    }
+}
+
+fn is_non_empty_container(data: &JsonData) -> bool {
+    match data {
+        JsonData::Array(v) => !v.is_empty(),
+        JsonData::Object(m) => !m.is_empty(),
+        _ => false,
+    }
+}
+
+// Writes `data` starting at the current position of the last line, every following line
+// is indented with `indent` spaces.
+fn write_node(data: &JsonData, indent: usize, yaml: &mut String) {
+    match data {
+        JsonData::Array(v) if !v.is_empty() => {
+            for (count, item) in v.iter().enumerate() {
+                if count > 0 {
+                    yaml.push_str(&" ".repeat(indent));
+                }
+                yaml.push_str("- ");
+                write_node(item, indent + 2, yaml);
+            }
+        }
+        JsonData::Object(m) if !m.is_empty() => {
+            for (count, (key, value)) in m.iter().enumerate() {
+                if count > 0 {
+                    yaml.push_str(&" ".repeat(indent));
+                }
+                yaml.push_str(&yaml_str(key));
+                yaml.push(':');
+                if is_non_empty_container(value) {
+                    yaml.push('\n');
+                    yaml.push_str(&" ".repeat(indent + 2));
+                    write_node(value, indent + 2, yaml);
+                } else {
+                    yaml.push(' ');
+                    write_node(value, indent, yaml);
+                }
+            }
+        }
+        JsonData::Array(_) => yaml.push_str("[]\n"),
+        JsonData::Object(_) => yaml.push_str("{}\n"),
+        JsonData::Null => yaml.push_str("null\n"),
+        JsonData::Bool(b) => writeln!(yaml, "{b}").expect("THIS SHOULD NEVER PANIC"),
+        JsonData::Integer(i) => writeln!(yaml, "{i}").expect("THIS SHOULD NEVER PANIC"),
+        #[cfg(feature = "bigint")]
+        JsonData::BigInt(i) => writeln!(yaml, "{i}").expect("THIS SHOULD NEVER PANIC"),
+        #[cfg(feature = "raw_numbers")]
+        JsonData::RawNumber(raw) => write_node(&raw.data, indent, yaml),
+        JsonData::Float(f) => {
+            let float = f.to_string();
+            // Without a `.` the float would be read back as an integer
+            if float.contains('.') {
+                writeln!(yaml, "{float}").expect("THIS SHOULD NEVER PANIC")
+            } else {
+                writeln!(yaml, "{float}.0").expect("THIS SHOULD NEVER PANIC")
+            }
+        }
+        #[cfg(feature = "decimal")]
+        JsonData::Decimal(d) => {
+            let decimal = d.to_string();
+            if decimal.contains('.') {
+                writeln!(yaml, "{decimal}").expect("THIS SHOULD NEVER PANIC")
+            } else {
+                writeln!(yaml, "{decimal}.0").expect("THIS SHOULD NEVER PANIC")
+            }
+        }
+        JsonData::Str(s) => {
+            yaml.push_str(&yaml_str(s));
+            yaml.push('\n');
+        }
+    }
+}
+
+fn yaml_str(s: &str) -> String {
+    if needs_quotes(s) {
+        let mut quoted = String::from('"');
+        for c in s.chars() {
+            match c {
+                '"' => quoted.push_str("\\\""),
+                '\\' => quoted.push_str("\\\\"),
+                '\n' => quoted.push_str("\\n"),
+                '\r' => quoted.push_str("\\r"),
+                '\t' => quoted.push_str("\\t"),
+                c if c.is_control() || c == '\u{feff}' => {
+                    write!(quoted, "\\u{:04x}", c as u32).expect("THIS SHOULD NEVER PANIC")
+                }
+                c => quoted.push(c),
+            }
+        }
+        quoted.push('"');
+        quoted
+    } else {
+        s.to_string()
+    }
+}
+
+fn needs_quotes(s: &str) -> bool {
+    const RESERVED: [&str; 13] = [
+        "null", "~", "true", "false", "yes", "no", "on", "off", "y", "n", ".inf", "-.inf", ".nan",
+    ];
+    let first = match s.chars().next() {
+        Some(c) => c,
+        None => return true,
+    };
+    RESERVED.contains(&s.to_lowercase().as_str())
+        || s.parse::<f64>().is_ok()
+        || s.starts_with("0x")
+        || s.starts_with("0o")
+        || "-?:,[]{}#&*!|>'\"%@`".contains(first)
+        || first.is_whitespace()
+        || s.ends_with(char::is_whitespace)
+        || s.ends_with(':')
+        || s.contains(": ")
+        || s.contains(" #")
+        || s.contains(|c: char| c.is_control() || c == '\u{feff}')
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json::{Array, Json, Object, Value};
+
+    #[test]
+    fn yaml_scalars() {
+        assert_eq!(
+            "null\n",
+            Json::<Value>::new("null").unwrap().to_yaml_string()
+        );
+        assert_eq!(
+            "true\n",
+            Json::<Value>::new("true").unwrap().to_yaml_string()
+        );
+        assert_eq!("-12\n", Json::<Value>::new("-12").unwrap().to_yaml_string());
+        assert_eq!(
+            "12.0\n",
+            Json::<Value>::new("12.0").unwrap().to_yaml_string()
+        );
+        assert_eq!("0.5\n", Json::<Value>::new("0.5").unwrap().to_yaml_string());
+    }
+
+    #[test]
+    fn yaml_string_quoting() {
+        let yaml = |s: &str| Json::<Value>::new(s).unwrap().to_yaml_string();
+        assert_eq!("hello world\n", yaml("\"hello world\""));
+        assert_eq!("\"\"\n", yaml("\"\""));
+        assert_eq!("\"true\"\n", yaml("\"true\""));
+        assert_eq!("\"No\"\n", yaml("\"No\""));
+        assert_eq!("\"123\"\n", yaml("\"123\""));
+        assert_eq!("\"1e3\"\n", yaml("\"1e3\""));
+        assert_eq!("\"- item\"\n", yaml("\"- item\""));
+        assert_eq!("\"a: b\"\n", yaml("\"a: b\""));
+        assert_eq!("\" padded\"\n", yaml("\" padded\""));
+        assert_eq!("\"line\\nbreak\"\n", yaml("\"line\\nbreak\""));
+        assert_eq!("say \"hi\"\n", yaml("\"say \\\"hi\\\"\""));
+        assert_eq!("\"tab\\there\"\n", yaml("\"tab\\u0009here\""));
+        assert_eq!("smörgåsbord\n", yaml("\"smörgåsbord\""));
+    }
+
+    #[test]
+    fn yaml_block_style() {
+        let json = Json::<Object>::new(
+            "{\"servers\": [{\"host\": \"a\"}, [1, []], {}], \"nested\": {\"inner\": {\"x\": null}}}",
+        )
+        .unwrap();
+        let yaml = json.to_yaml_string();
+        assert!(
+            yaml.contains("servers:\n  - host: a\n  - - 1\n    - []\n  - {}\n"),
+            "{yaml}"
+        );
+        assert!(yaml.contains("nested:\n  inner:\n    x: null\n"), "{yaml}");
+    }
+
+    #[test]
+    fn yaml_empty_containers() {
+        assert_eq!("[]\n", Json::<Array>::new("[]").unwrap().to_yaml_string());
+        assert_eq!("{}\n", Json::<Object>::new("{}").unwrap().to_yaml_string());
+        assert_eq!(
+            "\"\": []\n",
+            Json::<Object>::new("{\"\": []}").unwrap().to_yaml_string()
+        );
+    }
+}